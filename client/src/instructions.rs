@@ -0,0 +1,1442 @@
+//! Instruction builders for every instruction in the program.
+//!
+//! Account lists come from the program's own generated `accounts::*` structs,
+//! so a change to an instruction's accounts breaks this crate at compile time
+//! instead of at runtime. PDAs and associated token accounts are derived here.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::{instruction::Instruction, system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token, metadata::mpl_token_metadata, token, token_2022};
+use monkey_dao::state::{AllowlistProof, FeeTier, FlashSale, LeafArgs, LoyaltyTier};
+use monkey_dao::{accounts, instruction};
+use mpl_bubblegum::programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+
+use crate::pda;
+
+/// A coupon NFT mint together with the token program that owns it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coupon {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl Coupon {
+    pub fn new(mint: Pubkey, token_program: Pubkey) -> Self {
+        Self { mint, token_program }
+    }
+    
+    pub fn token(mint: Pubkey) -> Self {
+        Self::new(mint, token::ID)
+    }
+    
+    pub fn token_2022(mint: Pubkey) -> Self {
+        Self::new(mint, token_2022::ID)
+    }
+    
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        pda::associated_token(owner, &self.mint, &self.token_program)
+    }
+}
+
+/// A listing bought through `checkout_cart`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CartItem {
+    pub coupon: Coupon,
+    pub seller: Pubkey,
+    /// Authority of the merchant that listed the coupon
+    pub merchant: Pubkey,
+}
+
+impl CartItem {
+    fn metas(&self, buyer: &Pubkey) -> [AccountMeta; 7] {
+        let listing = pda::listing(&self.coupon.mint).0;
+        [
+            AccountMeta::new(listing, false),
+            AccountMeta::new(pda::merchant(&self.merchant).0, false),
+            AccountMeta::new_readonly(self.coupon.mint, false),
+            AccountMeta::new(self.coupon.ata(&listing), false),
+            AccountMeta::new(self.seller, false),
+            AccountMeta::new(self.coupon.ata(buyer), false),
+            AccountMeta::new_readonly(pda::listing_schedule(&listing).0, false),
+        ]
+    }
+}
+
+/// What a buyer shows to pass a gated listing's `ListingGate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatePass {
+    pub gate: Pubkey,
+    /// Required by gates with an allowlist
+    pub allowlist_proof: Option<AllowlistProof>,
+    /// Token account and mint of an NFT the buyer holds, for collection gates
+    pub holding: Option<(Pubkey, Pubkey)>,
+}
+
+impl GatePass {
+    pub fn new(gate: Pubkey) -> Self {
+        Self { gate, allowlist_proof: None, holding: None }
+    }
+    
+    fn holding_metas(&self) -> Vec<AccountMeta> {
+        self.holding
+            .iter()
+            .flat_map(|(token_account, mint)| {
+                [
+                    AccountMeta::new_readonly(*token_account, false),
+                    AccountMeta::new_readonly(pda::metadata(mint).0, false),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Platform-wide accounts a few instructions need, as stored in `PlatformConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Client {
+    pub platform_wallet: Pubkey,
+    pub monk_mint: Pubkey,
+    pub monk_token_program: Pubkey,
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: monkey_dao::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn with_remaining(mut ix: Instruction, remaining: impl IntoIterator<Item = AccountMeta>) -> Instruction {
+    ix.accounts.extend(remaining);
+    ix
+}
+
+/// Appends the transfer hook accounts of a coupon whose Token-2022 mint has a
+/// hook to any instruction that moves it, after the instruction's own
+/// remaining accounts. `hook_accounts` are the metas
+/// `spl_transfer_hook_interface::offchain::add_extra_account_metas_for_execute`
+/// appends to a transfer of the coupon: the extra accounts, the hook program,
+/// then the validation account. A cart takes those of every hooked coupon in it.
+pub fn with_transfer_hook(ix: Instruction, hook_accounts: &[AccountMeta]) -> Instruction {
+    with_remaining(ix, hook_accounts.iter().cloned())
+}
+
+fn attestation_metas(merchant: &Pubkey, verifiers: &[Pubkey]) -> Vec<AccountMeta> {
+    verifiers
+        .iter()
+        .map(|verifier| AccountMeta::new_readonly(pda::attestation(merchant, verifier).0, false))
+        .collect()
+}
+
+fn proof_metas(proof: &[Pubkey]) -> Vec<AccountMeta> {
+    proof.iter().map(|node| AccountMeta::new_readonly(*node, false)).collect()
+}
+
+/// `None` when the merchant approves their own redemption, otherwise the
+/// approver's delegate record.
+fn delegate_for(merchant_authority: &Pubkey, merchant: &Pubkey, approver: &Pubkey) -> Option<Pubkey> {
+    (approver != merchant_authority).then(|| pda::merchant_delegate(merchant, approver).0)
+}
+
+impl Client {
+    /// Client for a platform whose MONK mint is the program's `monk_mint` PDA.
+    pub fn new(platform_wallet: Pubkey, monk_token_program: Pubkey) -> Self {
+        Self {
+            platform_wallet,
+            monk_mint: pda::monk_mint().0,
+            monk_token_program,
+        }
+    }
+    
+    fn monk_ata(&self, owner: &Pubkey) -> Pubkey {
+        pda::associated_token(owner, &self.monk_mint, &self.monk_token_program)
+    }
+    
+    // ==================== MERCHANT INSTRUCTIONS ====================
+    pub fn register_merchant(
+        &self,
+        authority: Pubkey,
+        business_name: String,
+        business_type: String,
+        kyc_hash: [u8; 32],
+        kyc_uri: String,
+    ) -> Instruction {
+        build(
+            accounts::RegisterMerchant {
+                authority,
+                merchant: pda::merchant(&authority).0,
+                system_program: system_program::ID,
+            },
+            instruction::RegisterMerchant {
+                business_name,
+                business_type,
+                kyc_hash,
+                kyc_uri,
+            },
+        )
+    }
+    
+    /// `verifiers` whose attestations should be counted.
+    pub fn verify_merchant(&self, merchant_authority: Pubkey, verifiers: &[Pubkey]) -> Instruction {
+        let merchant = pda::merchant(&merchant_authority).0;
+        with_remaining(
+            build(
+                accounts::VerifyMerchant {
+                    verifier_registry: pda::verifier_registry().0,
+                    merchant,
+                },
+                instruction::VerifyMerchant {},
+            ),
+            attestation_metas(&merchant, verifiers),
+        )
+    }
+    
+    pub fn update_merchant_kyc(&self, authority: Pubkey, kyc_hash: [u8; 32], kyc_uri: String) -> Instruction {
+        build(
+            accounts::UpdateMerchantKyc {
+                authority,
+                merchant: pda::merchant(&authority).0,
+            },
+            instruction::UpdateMerchantKyc { kyc_hash, kyc_uri },
+        )
+    }
+    
+    pub fn migrate_merchant(&self, authority: Pubkey, kyc_hash: [u8; 32], kyc_uri: String) -> Instruction {
+        build(
+            accounts::MigrateMerchant {
+                authority,
+                merchant: pda::merchant(&authority).0,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateMerchant { kyc_hash, kyc_uri },
+        )
+    }
+    
+    pub fn add_delegate(&self, authority: Pubkey, delegate: Pubkey, valid_until: i64) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        build(
+            accounts::AddDelegate {
+                authority,
+                merchant,
+                merchant_delegate: pda::merchant_delegate(&merchant, &delegate).0,
+                system_program: system_program::ID,
+            },
+            instruction::AddDelegate { delegate, valid_until },
+        )
+    }
+    
+    pub fn revoke_delegate(&self, authority: Pubkey, delegate: Pubkey) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        build(
+            accounts::RevokeDelegate {
+                authority,
+                merchant,
+                merchant_delegate: pda::merchant_delegate(&merchant, &delegate).0,
+            },
+            instruction::RevokeDelegate {},
+        )
+    }
+    
+    // ==================== VERIFIER INSTRUCTIONS ====================
+    pub fn initialize_verifier_registry(
+        &self,
+        authority: Pubkey,
+        verifiers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Instruction {
+        build(
+            accounts::InitializeVerifierRegistry {
+                authority,
+                config: pda::config().0,
+                verifier_registry: pda::verifier_registry().0,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeVerifierRegistry { verifiers, threshold },
+        )
+    }
+    
+    fn update_verifier_registry(authority: Pubkey) -> accounts::UpdateVerifierRegistry {
+        accounts::UpdateVerifierRegistry {
+            authority,
+            verifier_registry: pda::verifier_registry().0,
+        }
+    }
+    
+    pub fn add_verifier(&self, authority: Pubkey, verifier: Pubkey) -> Instruction {
+        build(
+            Self::update_verifier_registry(authority),
+            instruction::AddVerifier { verifier },
+        )
+    }
+    
+    pub fn remove_verifier(&self, authority: Pubkey, verifier: Pubkey) -> Instruction {
+        build(
+            Self::update_verifier_registry(authority),
+            instruction::RemoveVerifier { verifier },
+        )
+    }
+    
+    pub fn set_attestation_threshold(&self, authority: Pubkey, threshold: u8) -> Instruction {
+        build(
+            Self::update_verifier_registry(authority),
+            instruction::SetAttestationThreshold { threshold },
+        )
+    }
+    
+    pub fn issue_attestation(
+        &self,
+        verifier: Pubkey,
+        merchant_authority: Pubkey,
+        kyc_hash: [u8; 32],
+        expires_at: i64,
+    ) -> Instruction {
+        let merchant = pda::merchant(&merchant_authority).0;
+        build(
+            accounts::IssueAttestation {
+                verifier,
+                verifier_registry: pda::verifier_registry().0,
+                merchant,
+                attestation: pda::attestation(&merchant, &verifier).0,
+                system_program: system_program::ID,
+            },
+            instruction::IssueAttestation { kyc_hash, expires_at },
+        )
+    }
+    
+    pub fn revoke_attestation(&self, verifier: Pubkey, merchant_authority: Pubkey) -> Instruction {
+        let merchant = pda::merchant(&merchant_authority).0;
+        build(
+            accounts::RevokeAttestation {
+                verifier,
+                attestation: pda::attestation(&merchant, &verifier).0,
+            },
+            instruction::RevokeAttestation {},
+        )
+    }
+    
+    // ==================== LISTING INSTRUCTIONS ====================
+    /// `verifiers` whose attestations prove the merchant is verified.
+    pub fn list_nft(
+        &self,
+        seller: Pubkey,
+        coupon: Coupon,
+        args: instruction::ListNft,
+        verifiers: &[Pubkey],
+    ) -> Instruction {
+        let merchant = pda::merchant(&seller).0;
+        let listing = pda::listing(&coupon.mint).0;
+        with_remaining(
+            build(
+                accounts::ListNFT {
+                    seller,
+                    config: pda::config().0,
+                    merchant,
+                    verifier_registry: pda::verifier_registry().0,
+                    listing,
+                    nft_mint: coupon.mint,
+                    seller_token_account: coupon.ata(&seller),
+                    vault: coupon.ata(&listing),
+                    token_program: coupon.token_program,
+                    associated_token_program: associated_token::ID,
+                    system_program: system_program::ID,
+                },
+                args,
+            ),
+            attestation_metas(&merchant, verifiers),
+        )
+    }
+    
+    pub fn relist_nft(&self, seller: Pubkey, coupon: Coupon, new_price: u64) -> Instruction {
+        let listing = pda::listing(&coupon.mint).0;
+        build(
+            accounts::RelistNFT {
+                seller,
+                config: pda::config().0,
+                listing,
+                nft_mint: coupon.mint,
+                seller_token_account: coupon.ata(&seller),
+                vault: coupon.ata(&listing),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::RelistNft { new_price },
+        )
+    }
+    
+    pub fn delist_nft(&self, seller: Pubkey, coupon: Coupon) -> Instruction {
+        let listing = pda::listing(&coupon.mint).0;
+        build(
+            accounts::DelistNFT {
+                seller,
+                listing,
+                nft_mint: coupon.mint,
+                seller_token_account: coupon.ata(&seller),
+                vault: coupon.ata(&listing),
+                token_program: coupon.token_program,
+            },
+            instruction::DelistNft {},
+        )
+    }
+    
+    pub fn close_listing(&self, authority: Pubkey, nft_mint: Pubkey) -> Instruction {
+        build(
+            accounts::CloseListing {
+                authority,
+                merchant: pda::merchant(&authority).0,
+                listing: pda::listing(&nft_mint).0,
+            },
+            instruction::CloseListing {},
+        )
+    }
+    
+    /// `owner` owns the associated token account holding the coupon now,
+    /// whether a wallet or a vault's stake account, gift or pool; `None` once
+    /// the coupon was burned.
+    pub fn sync_custody(&self, coupon: Coupon, owner: Option<Pubkey>) -> Instruction {
+        build(
+            accounts::SyncCustody {
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                token_account: owner.map(|owner| coupon.ata(&owner)),
+                custodian: owner,
+                token_program: coupon.token_program,
+            },
+            instruction::SyncCustody {},
+        )
+    }
+    
+    // ==================== TRADING INSTRUCTIONS ====================
+    /// `seller` is the listing's current `seller`.
+    /// `merchant` is the authority of the merchant that listed the coupon.
+    /// `referrer` is the authority of the buyer's registered referrer, if any.
+    pub fn buy_nft(
+        &self,
+        buyer: Pubkey,
+        seller: Pubkey,
+        merchant: Pubkey,
+        coupon: Coupon,
+        referrer: Option<Pubkey>,
+    ) -> Instruction {
+        self.buy_gated_nft(buyer, seller, merchant, coupon, referrer, None)
+    }
+    
+    /// `buy_nft` for a listing behind a `ListingGate`.
+    pub fn buy_gated_nft(
+        &self,
+        buyer: Pubkey,
+        seller: Pubkey,
+        merchant: Pubkey,
+        coupon: Coupon,
+        referrer: Option<Pubkey>,
+        pass: Option<&GatePass>,
+    ) -> Instruction {
+        let listing = pda::listing(&coupon.mint).0;
+        with_remaining(
+            build(
+                accounts::BuyNFT {
+                    buyer,
+                    listing,
+                    merchant: pda::merchant(&merchant).0,
+                    nft_mint: coupon.mint,
+                    seller,
+                    vault: coupon.ata(&listing),
+                    buyer_token_account: coupon.ata(&buyer),
+                    config: pda::config().0,
+                    fee_schedule: pda::fee_schedule().0,
+                    platform_wallet: self.platform_wallet,
+                    monk_mint: self.monk_mint,
+                    buyer_monk_account: self.monk_ata(&buyer),
+                    user_stats: pda::user_stats(&buyer).0,
+                    loyalty: pda::loyalty(&buyer).0,
+                    referrer: referrer.map(|authority| pda::referrer(&authority).0),
+                    gate: pass.map(|pass| pass.gate),
+                    gate_purchases: pass.map(|pass| pda::gate_purchases(&pass.gate, &buyer).0),
+                    schedule: pda::listing_schedule(&listing).0,
+                    token_program: coupon.token_program,
+                    monk_token_program: self.monk_token_program,
+                    associated_token_program: associated_token::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::BuyNft {
+                    allowlist_proof: pass.and_then(|pass| pass.allowlist_proof.clone()),
+                },
+            ),
+            pass.map(GatePass::holding_metas).unwrap_or_default(),
+        )
+    }
+    
+    /// Buys every item in one transaction. The coupons must share a token program.
+    pub fn checkout_cart(&self, buyer: Pubkey, items: &[CartItem]) -> Instruction {
+        let token_program = items.first().map_or(token::ID, |item| item.coupon.token_program);
+        with_remaining(
+            build(
+                accounts::CheckoutCart {
+                    buyer,
+                    config: pda::config().0,
+                    fee_schedule: pda::fee_schedule().0,
+                    platform_wallet: self.platform_wallet,
+                    monk_mint: self.monk_mint,
+                    buyer_monk_account: self.monk_ata(&buyer),
+                    user_stats: pda::user_stats(&buyer).0,
+                    loyalty: pda::loyalty(&buyer).0,
+                    token_program,
+                    monk_token_program: self.monk_token_program,
+                    associated_token_program: associated_token::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::CheckoutCart { items: items.len() as u8 },
+            ),
+            items.iter().flat_map(|item| item.metas(&buyer)),
+        )
+    }
+    
+    // ==================== POOL INSTRUCTIONS ====================
+    pub fn create_pool(&self, initiator: Pubkey, nft_mint: Pubkey, pool_size: u8) -> Instruction {
+        let listing = pda::listing(&nft_mint).0;
+        let pool = pda::pool(&listing, &initiator).0;
+        build(
+            accounts::CreatePool {
+                initiator,
+                config: pda::config().0,
+                listing,
+                pool,
+                escrow: pda::escrow(&pool).0,
+                system_program: system_program::ID,
+            },
+            instruction::CreatePool { pool_size },
+        )
+    }
+    
+    pub fn join_pool(
+        &self,
+        participant: Pubkey,
+        nft_mint: Pubkey,
+        initiator: Pubkey,
+        referrer: Option<Pubkey>,
+    ) -> Instruction {
+        self.join_gated_pool(participant, nft_mint, initiator, referrer, None)
+    }
+    
+    /// `join_pool` for a pool on a listing behind a `ListingGate`.
+    pub fn join_gated_pool(
+        &self,
+        participant: Pubkey,
+        nft_mint: Pubkey,
+        initiator: Pubkey,
+        referrer: Option<Pubkey>,
+        pass: Option<&GatePass>,
+    ) -> Instruction {
+        let listing = pda::listing(&nft_mint).0;
+        let pool = pda::pool(&listing, &initiator).0;
+        with_remaining(
+            build(
+                accounts::JoinPool {
+                    participant,
+                    config: pda::config().0,
+                    pool,
+                    pool_participant: pda::pool_participant(&pool, &participant).0,
+                    escrow: pda::escrow(&pool).0,
+                    referrer: referrer.map(|authority| pda::referrer(&authority).0),
+                    pool_referral: referrer.map(|_| pda::pool_referral(&pool, &participant).0),
+                    listing,
+                    gate: pass.map(|pass| pass.gate),
+                    gate_purchases: pass.map(|pass| pda::gate_purchases(&pass.gate, &participant).0),
+                    system_program: system_program::ID,
+                },
+                instruction::JoinPool {
+                    allowlist_proof: pass.and_then(|pass| pass.allowlist_proof.clone()),
+                },
+            ),
+            pass.map(GatePass::holding_metas).unwrap_or_default(),
+        )
+    }
+    
+    /// Settles a filled pool, paying `seller` (the merchant for a primary sale).
+    pub fn complete_pool(
+        &self,
+        initiator: Pubkey,
+        seller: Pubkey,
+        merchant: Pubkey,
+        coupon: Coupon,
+    ) -> Instruction {
+        self.complete_referred_pool(initiator, seller, merchant, coupon, &[])
+    }
+    
+    /// `complete_pool` for a pool some participants joined with a referrer,
+    /// `referrals` pairing each such participant with their referrer's authority.
+    pub fn complete_referred_pool(
+        &self,
+        initiator: Pubkey,
+        seller: Pubkey,
+        merchant: Pubkey,
+        coupon: Coupon,
+        referrals: &[(Pubkey, Pubkey)],
+    ) -> Instruction {
+        let listing = pda::listing(&coupon.mint).0;
+        let pool = pda::pool(&listing, &initiator).0;
+        with_remaining(
+            build(
+                accounts::CompletePool {
+                    initiator,
+                    config: pda::config().0,
+                    pool,
+                    listing,
+                    merchant: pda::merchant(&merchant).0,
+                    nft_mint: coupon.mint,
+                    vault: coupon.ata(&listing),
+                    pool_token_account: coupon.ata(&pool),
+                    escrow: pda::escrow(&pool).0,
+                    seller,
+                    token_program: coupon.token_program,
+                    associated_token_program: associated_token::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::CompletePool {},
+            ),
+            referrals.iter().flat_map(|(participant, referrer)| {
+                [
+                    AccountMeta::new_readonly(pda::pool_referral(&pool, participant).0, false),
+                    AccountMeta::new(pda::referrer(referrer).0, false),
+                ]
+            }),
+        )
+    }
+    
+    /// Moves a settled pool's coupon to its initiator.
+    pub fn claim_pool_coupon(&self, initiator: Pubkey, coupon: Coupon) -> Instruction {
+        let listing = pda::listing(&coupon.mint).0;
+        let pool = pda::pool(&listing, &initiator).0;
+        build(
+            accounts::ClaimPoolCoupon {
+                initiator,
+                pool,
+                listing,
+                nft_mint: coupon.mint,
+                pool_token_account: coupon.ata(&pool),
+                initiator_token_account: coupon.ata(&initiator),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimPoolCoupon {},
+        )
+    }
+    
+    pub fn cancel_pool(&self, initiator: Pubkey, nft_mint: Pubkey) -> Instruction {
+        let pool = pda::pool(&pda::listing(&nft_mint).0, &initiator).0;
+        build(
+            accounts::CancelPool {
+                initiator,
+                pool,
+                escrow: pda::escrow(&pool).0,
+                system_program: system_program::ID,
+            },
+            instruction::CancelPool {},
+        )
+    }
+    
+    pub fn refund_pool_deposit(
+        &self,
+        participant: Pubkey,
+        nft_mint: Pubkey,
+        initiator: Pubkey,
+    ) -> Instruction {
+        let listing = pda::listing(&nft_mint).0;
+        let pool = pda::pool(&listing, &initiator).0;
+        build(
+            accounts::RefundPoolDeposit {
+                participant,
+                pool,
+                pool_participant: pda::pool_participant(&pool, &participant).0,
+                escrow: pda::escrow(&pool).0,
+                pool_referral: pda::pool_referral(&pool, &participant).0,
+                listing,
+                system_program: system_program::ID,
+            },
+            instruction::RefundPoolDeposit {},
+        )
+    }
+    
+    pub fn close_pool(&self, initiator: Pubkey, coupon: Coupon) -> Instruction {
+        let pool = pda::pool(&pda::listing(&coupon.mint).0, &initiator).0;
+        build(
+            accounts::ClosePool {
+                initiator,
+                pool,
+                nft_mint: coupon.mint,
+                pool_token_account: coupon.ata(&pool),
+                token_program: coupon.token_program,
+            },
+            instruction::ClosePool {},
+        )
+    }
+    
+    pub fn close_pool_participant(
+        &self,
+        participant: Pubkey,
+        nft_mint: Pubkey,
+        initiator: Pubkey,
+    ) -> Instruction {
+        let pool = pda::pool(&pda::listing(&nft_mint).0, &initiator).0;
+        build(
+            accounts::ClosePoolParticipant {
+                participant,
+                pool,
+                pool_participant: pda::pool_participant(&pool, &participant).0,
+                pool_referral: pda::pool_referral(&pool, &participant).0,
+            },
+            instruction::ClosePoolParticipant {},
+        )
+    }
+    
+    // ==================== REVIEW INSTRUCTIONS ====================
+    pub fn add_review(&self, reviewer: Pubkey, nft_mint: Pubkey, rating: u8, comment: String) -> Instruction {
+        let listing = pda::listing(&nft_mint).0;
+        build(
+            accounts::AddReview {
+                reviewer,
+                config: pda::config().0,
+                listing,
+                review: pda::review(&listing, &reviewer).0,
+                system_program: system_program::ID,
+            },
+            instruction::AddReview { rating, comment },
+        )
+    }
+    
+    // ==================== STAKING INSTRUCTIONS ====================
+    pub fn stake_nft(&self, owner: Pubkey, coupon: Coupon) -> Instruction {
+        let stake_account = pda::stake(&coupon.mint).0;
+        build(
+            accounts::StakeNFT {
+                owner,
+                config: pda::config().0,
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                owner_token_account: coupon.ata(&owner),
+                stake_account,
+                stake_vault: coupon.ata(&stake_account),
+                user_stats: pda::user_stats(&owner).0,
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::StakeNft {},
+        )
+    }
+    
+    pub fn unstake_nft(&self, owner: Pubkey, coupon: Coupon) -> Instruction {
+        let stake_account = pda::stake(&coupon.mint).0;
+        build(
+            accounts::UnstakeNFT {
+                owner,
+                nft_mint: coupon.mint,
+                listing: pda::listing(&coupon.mint).0,
+                stake_account,
+                stake_vault: coupon.ata(&stake_account),
+                owner_token_account: coupon.ata(&owner),
+                config: pda::config().0,
+                monk_mint: self.monk_mint,
+                owner_monk_account: self.monk_ata(&owner),
+                user_stats: pda::user_stats(&owner).0,
+                token_program: coupon.token_program,
+                monk_token_program: self.monk_token_program,
+                system_program: system_program::ID,
+            },
+            instruction::UnstakeNft {},
+        )
+    }
+    
+    pub fn claim_staking_rewards(&self, owner: Pubkey, nft_mint: Pubkey) -> Instruction {
+        build(
+            accounts::ClaimStakingRewards {
+                owner,
+                nft_mint,
+                stake_account: pda::stake(&nft_mint).0,
+                config: pda::config().0,
+                monk_mint: self.monk_mint,
+                owner_monk_account: self.monk_ata(&owner),
+                user_stats: pda::user_stats(&owner).0,
+                monk_token_program: self.monk_token_program,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimStakingRewards {},
+        )
+    }
+    
+    pub fn close_stake_account(&self, owner: Pubkey, nft_mint: Pubkey) -> Instruction {
+        build(
+            accounts::CloseStakeAccount {
+                owner,
+                stake_account: pda::stake(&nft_mint).0,
+            },
+            instruction::CloseStakeAccount {},
+        )
+    }
+    
+    // ==================== REDEMPTION INSTRUCTIONS ====================
+    /// `spent` is the uses or value of the coupon already spent, its
+    /// listing's `uses.spent()`.
+    pub fn redeem_nft(
+        &self,
+        redeemer: Pubkey,
+        merchant_authority: Pubkey,
+        approver: Pubkey,
+        spent: u64,
+        coupon: Coupon,
+        signature: Vec<u8>,
+        amount: u64,
+    ) -> Instruction {
+        let merchant = pda::merchant(&merchant_authority).0;
+        let listing = pda::listing(&coupon.mint).0;
+        build(
+            accounts::RedeemNFT {
+                redeemer,
+                config: pda::config().0,
+                merchant,
+                approver,
+                merchant_delegate: delegate_for(&merchant_authority, &merchant, &approver),
+                listing,
+                receipt: pda::receipt(&listing, spent).0,
+                nft_mint: coupon.mint,
+                redeemer_token_account: coupon.ata(&redeemer),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::RedeemNft { signature, amount },
+        )
+    }
+    
+    // ==================== COMPRESSED COUPON INSTRUCTIONS ====================
+    /// `verifiers` whose attestations prove the merchant is verified.
+    pub fn create_coupon_tree(
+        &self,
+        authority: Pubkey,
+        merkle_tree: Pubkey,
+        max_depth: u32,
+        max_buffer_size: u32,
+        original_price: u64,
+        expiry_date: i64,
+        verifiers: &[Pubkey],
+    ) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        with_remaining(
+            build(
+                accounts::CreateCouponTree {
+                    authority,
+                    merchant,
+                    verifier_registry: pda::verifier_registry().0,
+                    coupon_tree: pda::coupon_tree(&merkle_tree).0,
+                    tree_config: pda::tree_config(&merkle_tree).0,
+                    merkle_tree,
+                    bubblegum_program: mpl_bubblegum::ID,
+                    log_wrapper: SPL_NOOP_ID,
+                    compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                    system_program: system_program::ID,
+                },
+                instruction::CreateCouponTree {
+                    max_depth,
+                    max_buffer_size,
+                    original_price,
+                    expiry_date,
+                },
+            ),
+            attestation_metas(&merchant, verifiers),
+        )
+    }
+    
+    /// `verifiers` whose attestations prove the merchant is still verified.
+    pub fn mint_compressed_coupon(
+        &self,
+        authority: Pubkey,
+        merkle_tree: Pubkey,
+        recipient: Pubkey,
+        name: String,
+        uri: String,
+        verifiers: &[Pubkey],
+    ) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        with_remaining(
+            build(
+                accounts::MintCompressedCoupon {
+                    authority,
+                    config: pda::config().0,
+                    merchant,
+                    verifier_registry: pda::verifier_registry().0,
+                    coupon_tree: pda::coupon_tree(&merkle_tree).0,
+                    recipient,
+                    tree_config: pda::tree_config(&merkle_tree).0,
+                    merkle_tree,
+                    bubblegum_program: mpl_bubblegum::ID,
+                    log_wrapper: SPL_NOOP_ID,
+                    compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                    system_program: system_program::ID,
+                },
+                instruction::MintCompressedCoupon { name, uri },
+            ),
+            attestation_metas(&merchant, verifiers),
+        )
+    }
+    
+    /// `proof` is the leaf's sibling path as returned by the DAS `getAssetProof`.
+    pub fn list_compressed_coupon(
+        &self,
+        seller: Pubkey,
+        merkle_tree: Pubkey,
+        price: u64,
+        leaf: LeafArgs,
+        proof: &[Pubkey],
+    ) -> Instruction {
+        with_remaining(
+            build(
+                accounts::ListCompressedCoupon {
+                    seller,
+                    config: pda::config().0,
+                    coupon_tree: pda::coupon_tree(&merkle_tree).0,
+                    listing: pda::compressed_listing(&merkle_tree, leaf.nonce).0,
+                    tree_config: pda::tree_config(&merkle_tree).0,
+                    merkle_tree,
+                    bubblegum_program: mpl_bubblegum::ID,
+                    log_wrapper: SPL_NOOP_ID,
+                    compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                    system_program: system_program::ID,
+                },
+                instruction::ListCompressedCoupon { price, leaf },
+            ),
+            proof_metas(proof),
+        )
+    }
+    
+    pub fn buy_compressed_coupon(
+        &self,
+        buyer: Pubkey,
+        seller: Pubkey,
+        merchant: Pubkey,
+        merkle_tree: Pubkey,
+        leaf: LeafArgs,
+        proof: &[Pubkey],
+        referrer: Option<Pubkey>,
+    ) -> Instruction {
+        with_remaining(
+            build(
+                accounts::BuyCompressedCoupon {
+                    buyer,
+                    listing: pda::compressed_listing(&merkle_tree, leaf.nonce).0,
+                    seller,
+                    coupon_tree: pda::coupon_tree(&merkle_tree).0,
+                    merchant: pda::merchant(&merchant).0,
+                    config: pda::config().0,
+                    fee_schedule: pda::fee_schedule().0,
+                    monk_mint: self.monk_mint,
+                    buyer_monk_account: self.monk_ata(&buyer),
+                    user_stats: pda::user_stats(&buyer).0,
+                    buyer_loyalty: pda::loyalty(&buyer).0,
+                    referrer: referrer.map(|authority| pda::referrer(&authority).0),
+                    platform_wallet: self.platform_wallet,
+                    tree_config: pda::tree_config(&merkle_tree).0,
+                    merkle_tree,
+                    bubblegum_program: mpl_bubblegum::ID,
+                    log_wrapper: SPL_NOOP_ID,
+                    compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                    monk_token_program: self.monk_token_program,
+                    associated_token_program: associated_token::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::BuyCompressedCoupon { leaf },
+            ),
+            proof_metas(proof),
+        )
+    }
+    
+    pub fn delist_compressed_coupon(
+        &self,
+        seller: Pubkey,
+        merkle_tree: Pubkey,
+        leaf: LeafArgs,
+        proof: &[Pubkey],
+    ) -> Instruction {
+        with_remaining(
+            build(
+                accounts::DelistCompressedCoupon {
+                    seller,
+                    listing: pda::compressed_listing(&merkle_tree, leaf.nonce).0,
+                    tree_config: pda::tree_config(&merkle_tree).0,
+                    merkle_tree,
+                    bubblegum_program: mpl_bubblegum::ID,
+                    log_wrapper: SPL_NOOP_ID,
+                    compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                    system_program: system_program::ID,
+                },
+                instruction::DelistCompressedCoupon { leaf },
+            ),
+            proof_metas(proof),
+        )
+    }
+    
+    pub fn redeem_compressed_coupon(
+        &self,
+        redeemer: Pubkey,
+        merchant_authority: Pubkey,
+        approver: Pubkey,
+        merkle_tree: Pubkey,
+        signature: Vec<u8>,
+        leaf: LeafArgs,
+        proof: &[Pubkey],
+    ) -> Instruction {
+        let merchant = pda::merchant(&merchant_authority).0;
+        let coupon_tree = pda::coupon_tree(&merkle_tree).0;
+        with_remaining(
+            build(
+                accounts::RedeemCompressedCoupon {
+                    redeemer,
+                    config: pda::config().0,
+                    merchant,
+                    approver,
+                    merchant_delegate: delegate_for(&merchant_authority, &merchant, &approver),
+                    coupon_tree,
+                    receipt: pda::receipt(&coupon_tree, leaf.nonce).0,
+                    tree_config: pda::tree_config(&merkle_tree).0,
+                    merkle_tree,
+                    bubblegum_program: mpl_bubblegum::ID,
+                    log_wrapper: SPL_NOOP_ID,
+                    compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                    system_program: system_program::ID,
+                },
+                instruction::RedeemCompressedCoupon { signature, leaf },
+            ),
+            proof_metas(proof),
+        )
+    }
+    
+    // ==================== EXPIRY INSTRUCTIONS ====================
+    /// `seller` is the listing's current `seller`; `payer` may be any crank.
+    pub fn reclaim_expired_listing(&self, payer: Pubkey, seller: Pubkey, coupon: Coupon) -> Instruction {
+        let listing = pda::listing(&coupon.mint).0;
+        build(
+            accounts::ReclaimExpiredListing {
+                payer,
+                listing,
+                seller,
+                nft_mint: coupon.mint,
+                vault: coupon.ata(&listing),
+                seller_token_account: coupon.ata(&seller),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ReclaimExpiredListing {},
+        )
+    }
+    
+    pub fn set_refund_policy(&self, authority: Pubkey, refund_bps: u64, claim_window: i64) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        build(
+            accounts::SetRefundPolicy {
+                authority,
+                merchant,
+                refund_policy: pda::refund_policy(&merchant).0,
+                system_program: system_program::ID,
+            },
+            instruction::SetRefundPolicy { refund_bps, claim_window },
+        )
+    }
+    
+    fn manage_refund_escrow(authority: Pubkey) -> accounts::ManageRefundEscrow {
+        let merchant = pda::merchant(&authority).0;
+        accounts::ManageRefundEscrow {
+            authority,
+            merchant,
+            refund_escrow: pda::refund_escrow(&merchant).0,
+            system_program: system_program::ID,
+        }
+    }
+    
+    pub fn fund_refund_escrow(&self, authority: Pubkey, amount: u64) -> Instruction {
+        build(
+            Self::manage_refund_escrow(authority),
+            instruction::FundRefundEscrow { amount },
+        )
+    }
+    
+    pub fn withdraw_refund_escrow(&self, authority: Pubkey, amount: u64) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        build(
+            accounts::WithdrawRefundEscrow {
+                authority,
+                merchant,
+                refund_policy: pda::refund_policy(&merchant).0,
+                refund_escrow: pda::refund_escrow(&merchant).0,
+                system_program: system_program::ID,
+            },
+            instruction::WithdrawRefundEscrow { amount },
+        )
+    }
+    
+    pub fn claim_expired_refund(&self, holder: Pubkey, merchant_authority: Pubkey, coupon: Coupon) -> Instruction {
+        let merchant = pda::merchant(&merchant_authority).0;
+        build(
+            accounts::ClaimExpiredRefund {
+                holder,
+                merchant,
+                refund_policy: pda::refund_policy(&merchant).0,
+                refund_escrow: pda::refund_escrow(&merchant).0,
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                holder_token_account: coupon.ata(&holder),
+                token_program: coupon.token_program,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimExpiredRefund {},
+        )
+    }
+    
+    // ==================== MONK TOKEN INSTRUCTIONS ====================
+    pub fn initialize_monk_mint(&self, authority: Pubkey) -> Instruction {
+        build(
+            accounts::InitializeMonkMint {
+                authority,
+                config: pda::config().0,
+                monk_mint: self.monk_mint,
+                metadata_account: pda::monk_metadata().0,
+                platform_wallet: self.platform_wallet,
+                token_program: token::ID,
+                token_metadata_program: mpl_token_metadata::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::InitializeMonkMint {},
+        )
+    }
+    
+    pub fn initialize_monk_mint_2022(
+        &self,
+        authority: Pubkey,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Instruction {
+        build(
+            accounts::InitializeMonkMint2022 {
+                authority,
+                config: pda::config().0,
+                monk_mint: self.monk_mint,
+                platform_wallet: self.platform_wallet,
+                token_program: token_2022::ID,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeMonkMint2022 { name, symbol, uri },
+        )
+    }
+    
+    // ==================== PAUSE INSTRUCTIONS ====================
+    /// `signer` is the platform authority or the guardian; `paused` is a set of `PAUSE_*` bits.
+    pub fn set_paused(&self, signer: Pubkey, paused: u8) -> Instruction {
+        build(
+            accounts::SetPaused {
+                signer,
+                config: pda::config().0,
+            },
+            instruction::SetPaused { paused },
+        )
+    }
+    
+    pub fn set_guardian(&self, authority: Pubkey, guardian: Pubkey) -> Instruction {
+        build(
+            accounts::SetGuardian {
+                authority,
+                config: pda::config().0,
+            },
+            instruction::SetGuardian { guardian },
+        )
+    }
+    
+    // ==================== FEE INSTRUCTIONS ====================
+    pub fn set_fee_schedule(
+        &self,
+        authority: Pubkey,
+        primary_tiers: Vec<FeeTier>,
+        secondary_fee_bps: u16,
+        staker_discount_bps: u16,
+        buyer_pays_fee: bool,
+    ) -> Instruction {
+        build(
+            accounts::SetFeeSchedule {
+                authority,
+                config: pda::config().0,
+                fee_schedule: pda::fee_schedule().0,
+                system_program: system_program::ID,
+            },
+            instruction::SetFeeSchedule {
+                primary_tiers,
+                secondary_fee_bps,
+                staker_discount_bps,
+                buyer_pays_fee,
+            },
+        )
+    }
+    
+    // ==================== REFERRAL INSTRUCTIONS ====================
+    pub fn register_referrer(&self, authority: Pubkey) -> Instruction {
+        build(
+            accounts::RegisterReferrer {
+                authority,
+                referrer: pda::referrer(&authority).0,
+                system_program: system_program::ID,
+            },
+            instruction::RegisterReferrer {},
+        )
+    }
+    
+    pub fn claim_referral_rewards(&self, authority: Pubkey) -> Instruction {
+        build(
+            accounts::ClaimReferralRewards {
+                authority,
+                referrer: pda::referrer(&authority).0,
+                config: pda::config().0,
+                monk_mint: self.monk_mint,
+                authority_monk_account: self.monk_ata(&authority),
+                user_stats: pda::user_stats(&authority).0,
+                monk_token_program: self.monk_token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimReferralRewards {},
+        )
+    }
+    
+    pub fn set_referral_rewards(&self, authority: Pubkey, fee_share_bps: u16, monk_bps: u16) -> Instruction {
+        build(
+            accounts::SetReferralRewards {
+                authority,
+                config: pda::config().0,
+            },
+            instruction::SetReferralRewards { fee_share_bps, monk_bps },
+        )
+    }
+    
+    // ==================== LOYALTY INSTRUCTIONS ====================
+    pub fn refresh_tier(&self, user: Pubkey) -> Instruction {
+        build(
+            accounts::RefreshTier {
+                user,
+                user_stats: pda::user_stats(&user).0,
+                loyalty: pda::loyalty(&user).0,
+                system_program: system_program::ID,
+            },
+            instruction::RefreshTier {},
+        )
+    }
+    
+    pub fn set_listing_access(
+        &self,
+        authority: Pubkey,
+        nft_mint: Pubkey,
+        min_tier: LoyaltyTier,
+        early_access_until: i64,
+    ) -> Instruction {
+        build(
+            accounts::SetListingAccess {
+                authority,
+                merchant: pda::merchant(&authority).0,
+                listing: pda::listing(&nft_mint).0,
+            },
+            instruction::SetListingAccess { min_tier, early_access_until },
+        )
+    }
+    
+    // ==================== GATE INSTRUCTIONS ====================
+    pub fn set_gate(
+        &self,
+        authority: Pubkey,
+        gate_id: u64,
+        collection: Option<Pubkey>,
+        allowlist_root: Option<[u8; 32]>,
+        max_per_wallet: u16,
+    ) -> Instruction {
+        let merchant = pda::merchant(&authority).0;
+        build(
+            accounts::SetGate {
+                authority,
+                merchant,
+                gate: pda::listing_gate(&merchant, gate_id).0,
+                system_program: system_program::ID,
+            },
+            instruction::SetGate { gate_id, collection, allowlist_root, max_per_wallet },
+        )
+    }
+    
+    /// `gate` is a `ListingGate` address, `None` ungates the listing.
+    pub fn set_listing_gate(&self, authority: Pubkey, nft_mint: Pubkey, gate: Option<Pubkey>) -> Instruction {
+        build(
+            accounts::SetListingGate {
+                authority,
+                merchant: pda::merchant(&authority).0,
+                listing: pda::listing(&nft_mint).0,
+                gate,
+            },
+            instruction::SetListingGate {},
+        )
+    }
+    
+    // ==================== SCHEDULE INSTRUCTIONS ====================
+    pub fn set_listing_schedule(
+        &self,
+        authority: Pubkey,
+        nft_mint: Pubkey,
+        start_time: i64,
+        flash_sales: Vec<FlashSale>,
+    ) -> Instruction {
+        let listing = pda::listing(&nft_mint).0;
+        build(
+            accounts::SetListingSchedule {
+                authority,
+                merchant: pda::merchant(&authority).0,
+                listing,
+                schedule: pda::listing_schedule(&listing).0,
+                system_program: system_program::ID,
+            },
+            instruction::SetListingSchedule { start_time, flash_sales },
+        )
+    }
+    
+    // ==================== GIFT INSTRUCTIONS ====================
+    pub fn gift_coupon(&self, sender: Pubkey, coupon: Coupon, recipient: Pubkey, message: &str) -> Instruction {
+        build(
+            accounts::GiftCoupon {
+                sender,
+                config: pda::config().0,
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                sender_token_account: coupon.ata(&sender),
+                recipient,
+                recipient_token_account: coupon.ata(&recipient),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::GiftCoupon { message: message.to_string() },
+        )
+    }
+    
+    /// `claim_key` is the pubkey of a keypair handed to the recipient, who
+    /// claims with it as `claim_gift`'s `claim_signer`.
+    pub fn send_gift(
+        &self,
+        sender: Pubkey,
+        coupon: Coupon,
+        recipient: Option<Pubkey>,
+        claim_key: Option<Pubkey>,
+        message: &str,
+    ) -> Instruction {
+        let gift = pda::gift(&coupon.mint).0;
+        build(
+            accounts::SendGift {
+                sender,
+                config: pda::config().0,
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                sender_token_account: coupon.ata(&sender),
+                gift,
+                gift_vault: coupon.ata(&gift),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::SendGift { recipient, claim_key, message: message.to_string() },
+        )
+    }
+    
+    pub fn claim_gift(
+        &self,
+        claimer: Pubkey,
+        sender: Pubkey,
+        coupon: Coupon,
+        claim_signer: Option<Pubkey>,
+    ) -> Instruction {
+        let gift = pda::gift(&coupon.mint).0;
+        build(
+            accounts::ClaimGift {
+                claimer,
+                claim_signer,
+                gift,
+                sender,
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                gift_vault: coupon.ata(&gift),
+                claimer_token_account: coupon.ata(&claimer),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimGift {},
+        )
+    }
+    
+    pub fn return_gift(&self, payer: Pubkey, sender: Pubkey, coupon: Coupon) -> Instruction {
+        let gift = pda::gift(&coupon.mint).0;
+        build(
+            accounts::ReturnGift {
+                payer,
+                gift,
+                sender,
+                listing: pda::listing(&coupon.mint).0,
+                nft_mint: coupon.mint,
+                gift_vault: coupon.ata(&gift),
+                sender_token_account: coupon.ata(&sender),
+                token_program: coupon.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ReturnGift {},
+        )
+    }
+    
+    // ==================== MIGRATION INSTRUCTIONS ====================
+    // These take the account's address, as found by scanning the program's accounts
+    pub fn migrate_listing(&self, payer: Pubkey, listing: Pubkey) -> Instruction {
+        build(
+            accounts::MigrateListing {
+                payer,
+                listing,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateListing {},
+        )
+    }
+    
+    pub fn migrate_pool(&self, payer: Pubkey, pool: Pubkey) -> Instruction {
+        build(
+            accounts::MigratePool {
+                payer,
+                pool,
+                system_program: system_program::ID,
+            },
+            instruction::MigratePool {},
+        )
+    }
+    
+    pub fn migrate_stake_account(&self, payer: Pubkey, stake_account: Pubkey) -> Instruction {
+        build(
+            accounts::MigrateStakeAccount {
+                payer,
+                stake_account,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateStakeAccount {},
+        )
+    }
+    
+    pub fn migrate_config(&self, payer: Pubkey) -> Instruction {
+        build(
+            accounts::MigrateConfig {
+                payer,
+                config: pda::config().0,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateConfig {},
+        )
+    }
+}
@@ -0,0 +1,12 @@
+//! Client helpers for the coupon marketplace program: typed PDA derivation
+//! and instruction builders with associated token accounts filled in.
+
+#![allow(clippy::too_many_arguments)]
+
+pub mod instructions;
+pub mod pda;
+
+pub use instructions::{with_transfer_hook, CartItem, Client, Coupon, GatePass};
+pub use monkey_dao::instruction::ListNft as ListNftArgs;
+pub use monkey_dao::state::{AllowlistProof, FeeTier, FlashSale, LeafArgs, LoyaltyTier, UseMethod};
+pub use monkey_dao::ID as PROGRAM_ID;
@@ -0,0 +1,137 @@
+//! Program derived addresses, one helper per seed family used by the program.
+//! Each returns the address together with its canonical bump.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::metadata::mpl_token_metadata;
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &monkey_dao::ID)
+}
+
+pub fn config() -> (Pubkey, u8) {
+    find(&[b"config"])
+}
+
+pub fn fee_schedule() -> (Pubkey, u8) {
+    find(&[b"fee_schedule"])
+}
+
+pub fn referrer(authority: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"referrer", authority.as_ref()])
+}
+
+pub fn loyalty(user: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"loyalty", user.as_ref()])
+}
+
+pub fn listing_gate(merchant: &Pubkey, gate_id: u64) -> (Pubkey, u8) {
+    find(&[b"gate", merchant.as_ref(), &gate_id.to_le_bytes()])
+}
+
+pub fn listing_schedule(listing: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"schedule", listing.as_ref()])
+}
+
+pub fn gift(nft_mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"gift", nft_mint.as_ref()])
+}
+
+pub fn gate_purchases(gate: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"gate_purchases", gate.as_ref(), wallet.as_ref()])
+}
+
+pub fn monk_mint() -> (Pubkey, u8) {
+    find(&[b"monk_mint"])
+}
+
+/// Metaplex metadata account of `mint`.
+pub fn metadata(mint: &Pubkey) -> (Pubkey, u8) {
+    let program = mpl_token_metadata::ID;
+    Pubkey::find_program_address(&[b"metadata", program.as_ref(), mint.as_ref()], &program)
+}
+
+/// Metaplex metadata account of the legacy MONK mint.
+pub fn monk_metadata() -> (Pubkey, u8) {
+    metadata(&monk_mint().0)
+}
+
+pub fn verifier_registry() -> (Pubkey, u8) {
+    find(&[b"verifier_registry"])
+}
+
+pub fn merchant(authority: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"merchant", authority.as_ref()])
+}
+
+pub fn attestation(merchant: &Pubkey, verifier: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"attestation", merchant.as_ref(), verifier.as_ref()])
+}
+
+pub fn merchant_delegate(merchant: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"delegate", merchant.as_ref(), delegate.as_ref()])
+}
+
+pub fn listing(nft_mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"listing", nft_mint.as_ref()])
+}
+
+pub fn pool(listing: &Pubkey, initiator: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"pool", listing.as_ref(), initiator.as_ref()])
+}
+
+pub fn escrow(pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"escrow", pool.as_ref()])
+}
+
+pub fn pool_participant(pool: &Pubkey, participant: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"pool_participant", pool.as_ref(), participant.as_ref()])
+}
+
+pub fn pool_referral(pool: &Pubkey, participant: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"pool_referral", pool.as_ref(), participant.as_ref()])
+}
+
+pub fn review(listing: &Pubkey, reviewer: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"review", listing.as_ref(), reviewer.as_ref()])
+}
+
+pub fn stake(nft_mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"stake", nft_mint.as_ref()])
+}
+
+pub fn user_stats(user: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"user_stats", user.as_ref()])
+}
+
+/// Receipt of a coupon's redemption: for a listing, the one made once
+/// `spent` uses or value of it were spent (`CouponUses::spent`); for a
+/// coupon tree, the one of the leaf with nonce `spent`.
+pub fn receipt(listing: &Pubkey, spent: u64) -> (Pubkey, u8) {
+    find(&[b"receipt", listing.as_ref(), &spent.to_le_bytes()])
+}
+
+pub fn refund_policy(merchant: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"refund_policy", merchant.as_ref()])
+}
+
+pub fn refund_escrow(merchant: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"refund_escrow", merchant.as_ref()])
+}
+
+pub fn coupon_tree(merkle_tree: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"coupon_tree", merkle_tree.as_ref()])
+}
+
+pub fn compressed_listing(merkle_tree: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    find(&[b"cnft_listing", merkle_tree.as_ref(), &nonce.to_le_bytes()])
+}
+
+/// Bubblegum tree config, owned by the Bubblegum program.
+pub fn tree_config(merkle_tree: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &mpl_bubblegum::ID)
+}
+
+pub fn associated_token(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
@@ -0,0 +1,217 @@
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::hash::hash;
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, token, token_2022};
+use monkey_dao_client::{pda, with_transfer_hook, AllowlistProof, CartItem, Client, Coupon, GatePass, ListNftArgs, UseMethod, PROGRAM_ID};
+
+fn client() -> Client {
+    Client::new(Pubkey::new_unique(), token::ID)
+}
+
+fn sighash(name: &str) -> Vec<u8> {
+    hash(format!("global:{name}").as_bytes()).to_bytes()[..8].to_vec()
+}
+
+#[test]
+fn buy_nft_fills_in_every_account() {
+    let client = client();
+    let (buyer, seller, merchant, mint) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let ix = client.buy_nft(buyer, seller, merchant, Coupon::token(mint), None);
+    
+    let listing = Pubkey::find_program_address(&[b"listing", mint.as_ref()], &PROGRAM_ID).0;
+    let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    
+    assert_eq!(ix.program_id, PROGRAM_ID);
+    // No allowlist proof
+    assert_eq!(ix.data, [sighash("buy_nft"), vec![0]].concat());
+    assert_eq!(keys.len(), 22);
+    assert_eq!(keys[0], buyer);
+    assert!(ix.accounts[0].is_signer && ix.accounts[0].is_writable);
+    assert_eq!(keys[1], listing);
+    assert_eq!(keys[2], pda::merchant(&merchant).0);
+    assert!(ix.accounts[2].is_writable);
+    assert_eq!(keys[5], get_associated_token_address_with_program_id(&listing, &mint, &token::ID));
+    assert_eq!(keys[6], get_associated_token_address_with_program_id(&buyer, &mint, &token::ID));
+    assert_eq!(keys[8], pda::fee_schedule().0);
+    assert_eq!(keys[9], client.platform_wallet);
+    assert_eq!(keys[11], get_associated_token_address_with_program_id(&buyer, &client.monk_mint, &token::ID));
+    assert_eq!(keys[12], pda::user_stats(&buyer).0);
+    assert_eq!(keys[13], pda::loyalty(&buyer).0);
+    // Without a referrer or gate the optional accounts are the program id
+    assert_eq!(keys[14], PROGRAM_ID);
+    assert_eq!(keys[15], PROGRAM_ID);
+    assert_eq!(keys[16], PROGRAM_ID);
+    assert_eq!(keys[17], pda::listing_schedule(&listing).0);
+    
+    let referrer = Pubkey::new_unique();
+    let referred = client.buy_nft(buyer, seller, merchant, Coupon::token(mint), Some(referrer));
+    assert_eq!(referred.accounts[14].pubkey, pda::referrer(&referrer).0);
+    assert!(referred.accounts[14].is_writable);
+}
+
+#[test]
+fn gated_buy_adds_the_gate_counter_and_holding() {
+    let client = client();
+    let (buyer, seller, merchant, mint) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let (gate, held_account, held_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let pass = GatePass {
+        allowlist_proof: Some(AllowlistProof { index: 1, path: vec![[3; 32]] }),
+        holding: Some((held_account, held_mint)),
+        ..GatePass::new(gate)
+    };
+    let ix = client.buy_gated_nft(buyer, seller, merchant, Coupon::token(mint), None, Some(&pass));
+    
+    assert_eq!(ix.accounts.len(), 24);
+    assert_eq!(ix.accounts[15].pubkey, gate);
+    assert_eq!(ix.accounts[16].pubkey, pda::gate_purchases(&gate, &buyer).0);
+    assert!(ix.accounts[16].is_writable);
+    assert_eq!(ix.accounts[22].pubkey, held_account);
+    assert_eq!(ix.accounts[23].pubkey, pda::metadata(&held_mint).0);
+    // Some, index 1, one node
+    let mut data = sighash("buy_nft");
+    data.extend([1, 1, 0, 0, 0, 1, 0, 0, 0]);
+    data.extend([3; 32]);
+    assert_eq!(ix.data, data);
+}
+
+#[test]
+fn checkout_cart_appends_each_item() {
+    let client = client();
+    let buyer = Pubkey::new_unique();
+    let items = [(); 2].map(|_| CartItem {
+        coupon: Coupon::token(Pubkey::new_unique()),
+        seller: Pubkey::new_unique(),
+        merchant: Pubkey::new_unique(),
+    });
+    let ix = client.checkout_cart(buyer, &items);
+    
+    // Item count
+    assert_eq!(ix.data, [sighash("checkout_cart"), vec![2]].concat());
+    assert_eq!(ix.accounts.len(), 12 + 2 * 7);
+    let second = &ix.accounts[12 + 7..];
+    let listing = pda::listing(&items[1].coupon.mint).0;
+    assert_eq!(second[0].pubkey, listing);
+    assert_eq!(second[1].pubkey, pda::merchant(&items[1].merchant).0);
+    assert_eq!(second[2].pubkey, items[1].coupon.mint);
+    assert_eq!(second[3].pubkey, get_associated_token_address_with_program_id(&listing, &items[1].coupon.mint, &token::ID));
+    assert_eq!(second[4].pubkey, items[1].seller);
+    assert_eq!(second[5].pubkey, get_associated_token_address_with_program_id(&buyer, &items[1].coupon.mint, &token::ID));
+    assert_eq!(second[6].pubkey, pda::listing_schedule(&listing).0);
+    assert!(second[..6].iter().enumerate().all(|(i, meta)| meta.is_writable == (i != 2)));
+}
+
+#[test]
+fn token_2022_coupons_use_token_2022_accounts() {
+    let client = client();
+    let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let legacy = client.stake_nft(owner, Coupon::token(mint));
+    let extended = client.stake_nft(owner, Coupon::token_2022(mint));
+    
+    assert_ne!(legacy.accounts[4].pubkey, extended.accounts[4].pubkey);
+    assert_eq!(
+        extended.accounts[4].pubkey,
+        get_associated_token_address_with_program_id(&owner, &mint, &token_2022::ID)
+    );
+    assert_eq!(extended.accounts[8].pubkey, token_2022::ID);
+}
+
+#[test]
+fn pda_helpers_match_program_seeds() {
+    let (authority, listing, initiator) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let find = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &PROGRAM_ID).0;
+    
+    assert_eq!(pda::config().0, find(&[b"config"]));
+    assert_eq!(pda::fee_schedule().0, find(&[b"fee_schedule"]));
+    assert_eq!(pda::merchant(&authority).0, find(&[b"merchant", authority.as_ref()]));
+    assert_eq!(pda::referrer(&authority).0, find(&[b"referrer", authority.as_ref()]));
+    assert_eq!(pda::loyalty(&authority).0, find(&[b"loyalty", authority.as_ref()]));
+    let pool = pda::pool(&listing, &initiator).0;
+    assert_eq!(pool, find(&[b"pool", listing.as_ref(), initiator.as_ref()]));
+    assert_eq!(pda::escrow(&pool).0, find(&[b"escrow", pool.as_ref()]));
+    assert_eq!(
+        pda::pool_participant(&pool, &authority).0,
+        find(&[b"pool_participant", pool.as_ref(), authority.as_ref()])
+    );
+    assert_eq!(pda::receipt(&listing, 7).0, find(&[b"receipt", listing.as_ref(), &7u64.to_le_bytes()]));
+}
+
+#[test]
+fn redeem_nft_only_passes_a_delegate_for_delegated_approvers() {
+    let client = client();
+    let (redeemer, authority, staff, mint) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let merchant = pda::merchant(&authority).0;
+    
+    let own = client.redeem_nft(redeemer, authority, authority, 3, Coupon::token(mint), vec![0; 64], 1);
+    // Anchor encodes a missing optional account as the program id
+    assert_eq!(own.accounts[4].pubkey, PROGRAM_ID);
+    assert_eq!(own.accounts[6].pubkey, pda::receipt(&pda::listing(&mint).0, 3).0);
+    
+    let delegated = client.redeem_nft(redeemer, authority, staff, 3, Coupon::token(mint), vec![0; 64], 1);
+    assert_eq!(delegated.accounts[4].pubkey, pda::merchant_delegate(&merchant, &staff).0);
+    assert!(delegated.accounts[3].is_signer);
+}
+
+#[test]
+fn list_nft_appends_attestations() {
+    let client = client();
+    let (seller, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let verifiers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let args = ListNftArgs {
+        price: 1_000,
+        is_group_deal: false,
+        deal_price_2: None,
+        deal_price_4: None,
+        deal_price_6: None,
+        coupon_description: "Two for one".to_string(),
+        expiry_date: 1_900_000_000,
+        use_method: UseMethod::Single,
+        total_uses: 1,
+    };
+    let ix = client.list_nft(seller, Coupon::token(mint), args, &verifiers);
+    
+    let merchant = pda::merchant(&seller).0;
+    assert_eq!(ix.accounts.len(), 13);
+    assert_eq!(ix.accounts[11].pubkey, pda::attestation(&merchant, &verifiers[0]).0);
+    assert_eq!(ix.accounts[12].pubkey, pda::attestation(&merchant, &verifiers[1]).0);
+    assert!(!ix.accounts[12].is_writable);
+    assert_eq!(&ix.data[..8], sighash("list_nft").as_slice());
+}
+
+#[test]
+fn transfer_hook_accounts_follow_the_instruction_accounts() {
+    let client = client();
+    let (seller, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let hook_accounts = [
+        AccountMeta::new(Pubkey::new_unique(), false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+    ];
+    let ix = client.delist_nft(seller, Coupon::token_2022(mint));
+    let accounts = ix.accounts.clone();
+    
+    let ix = with_transfer_hook(ix, &hook_accounts);
+    
+    assert_eq!(ix.accounts[..accounts.len()], accounts);
+    assert_eq!(ix.accounts[accounts.len()..], hook_accounts);
+    assert_eq!(ix.data, sighash("delist_nft"));
+}
+
+#[test]
+fn pool_builders_agree_on_the_pool_address() {
+    let client = client();
+    let (initiator, participant, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    
+    let create = client.create_pool(initiator, mint, 2);
+    let join = client.join_pool(participant, mint, initiator, None);
+    let cancel = client.cancel_pool(initiator, mint);
+    
+    assert_eq!(create.accounts[3].pubkey, join.accounts[2].pubkey);
+    assert_eq!(create.accounts[3].pubkey, cancel.accounts[1].pubkey);
+    assert_eq!(create.accounts[4].pubkey, join.accounts[4].pubkey);
+}
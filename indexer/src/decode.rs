@@ -0,0 +1,297 @@
+//! Decodes raw program accounts and `emit!` logs using the program's own types.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, Space};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use monkey_dao::events::*;
+use monkey_dao::state::*;
+
+use crate::error::{IndexerError, Result};
+
+const DISCRIMINATOR_LEN: usize = 8;
+
+/// Every account type the program owns.
+pub enum DecodedAccount {
+    PlatformConfig(PlatformConfig),
+    UserStats(UserStats),
+    Merchant(Merchant),
+    /// Merchant account that has not been through `migrate_merchant` yet
+    LegacyMerchant(LegacyMerchant),
+    Listing(Listing),
+    Pool(Pool),
+    PoolParticipant(PoolParticipant),
+    Review(Review),
+    StakeAccount(StakeAccount),
+    VerifierRegistry(VerifierRegistry),
+    Attestation(Attestation),
+    MerchantDelegate(MerchantDelegate),
+    RedemptionReceipt(RedemptionReceipt),
+    RefundPolicy(RefundPolicy),
+    CouponTree(CouponTree),
+    CompressedListing(CompressedListing),
+    FeeSchedule(FeeSchedule),
+    Referrer(Referrer),
+    Loyalty(Loyalty),
+    ListingGate(ListingGate),
+    GatePurchases(GatePurchases),
+    ListingSchedule(ListingSchedule),
+    Gift(Gift),
+}
+
+impl DecodedAccount {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DecodedAccount::PlatformConfig(_) => "PlatformConfig",
+            DecodedAccount::UserStats(_) => "UserStats",
+            DecodedAccount::Merchant(_) => "Merchant",
+            DecodedAccount::LegacyMerchant(_) => "LegacyMerchant",
+            DecodedAccount::Listing(_) => "Listing",
+            DecodedAccount::Pool(_) => "Pool",
+            DecodedAccount::PoolParticipant(_) => "PoolParticipant",
+            DecodedAccount::Review(_) => "Review",
+            DecodedAccount::StakeAccount(_) => "StakeAccount",
+            DecodedAccount::VerifierRegistry(_) => "VerifierRegistry",
+            DecodedAccount::Attestation(_) => "Attestation",
+            DecodedAccount::MerchantDelegate(_) => "MerchantDelegate",
+            DecodedAccount::RedemptionReceipt(_) => "RedemptionReceipt",
+            DecodedAccount::RefundPolicy(_) => "RefundPolicy",
+            DecodedAccount::CouponTree(_) => "CouponTree",
+            DecodedAccount::CompressedListing(_) => "CompressedListing",
+            DecodedAccount::FeeSchedule(_) => "FeeSchedule",
+            DecodedAccount::Referrer(_) => "Referrer",
+            DecodedAccount::Loyalty(_) => "Loyalty",
+            DecodedAccount::ListingGate(_) => "ListingGate",
+            DecodedAccount::GatePurchases(_) => "GatePurchases",
+            DecodedAccount::ListingSchedule(_) => "ListingSchedule",
+            DecodedAccount::Gift(_) => "Gift",
+        }
+    }
+}
+
+/// Every event the program emits.
+pub enum DecodedEvent {
+    MerchantRegistered(MerchantRegistered),
+    MerchantVerificationUpdated(MerchantVerificationUpdated),
+    MerchantKycUpdated(MerchantKycUpdated),
+    DelegateAdded(DelegateAdded),
+    DelegateRevoked(DelegateRevoked),
+    AttestationIssued(AttestationIssued),
+    AttestationRevoked(AttestationRevoked),
+    CouponListed(CouponListed),
+    CouponRelisted(CouponRelisted),
+    CouponDelisted(CouponDelisted),
+    ExpiredListingReclaimed(ExpiredListingReclaimed),
+    CouponPurchased(CouponPurchased),
+    CartCheckedOut(CartCheckedOut),
+    PoolCreated(PoolCreated),
+    PoolJoined(PoolJoined),
+    PoolCompleted(PoolCompleted),
+    PoolSettled(PoolSettled),
+    PoolCouponClaimed(PoolCouponClaimed),
+    PoolCancelled(PoolCancelled),
+    PoolDepositRefunded(PoolDepositRefunded),
+    ReviewAdded(ReviewAdded),
+    CouponStaked(CouponStaked),
+    CouponUnstaked(CouponUnstaked),
+    StakingRewardsClaimed(StakingRewardsClaimed),
+    CouponRedeemed(CouponRedeemed),
+    ExpiredRefundClaimed(ExpiredRefundClaimed),
+    CompressedCouponMinted(CompressedCouponMinted),
+    CompressedCouponListed(CompressedCouponListed),
+    CompressedCouponPurchased(CompressedCouponPurchased),
+    CompressedCouponDelisted(CompressedCouponDelisted),
+    PauseUpdated(PauseUpdated),
+    GuardianUpdated(GuardianUpdated),
+    FeeScheduleUpdated(FeeScheduleUpdated),
+    ReferrerRegistered(ReferrerRegistered),
+    ReferralRewarded(ReferralRewarded),
+    ReferralRewardsClaimed(ReferralRewardsClaimed),
+    ReferralRewardsUpdated(ReferralRewardsUpdated),
+    TierRefreshed(TierRefreshed),
+    ListingAccessUpdated(ListingAccessUpdated),
+    GateUpdated(GateUpdated),
+    ListingGateUpdated(ListingGateUpdated),
+    ListingScheduled(ListingScheduled),
+    CustodySynced(CustodySynced),
+    CouponGifted(CouponGifted),
+    GiftSent(GiftSent),
+    GiftClaimed(GiftClaimed),
+    GiftReturned(GiftReturned),
+}
+
+macro_rules! decode_by_discriminator {
+    ($disc:expr, $data:expr, $out:ident, $decode:ident, $unknown:expr, [$($ty:ident),* $(,)?]) => {{
+        $(
+            if $disc == $ty::DISCRIMINATOR {
+                return $decode::<$ty>($data, stringify!($ty)).map($out::$ty);
+            }
+        )*
+        Err($unknown)
+    }};
+}
+
+/// Decodes accounts still in an older layout, which share the discriminator
+/// of the current type but not its size, upgrading them in memory.
+macro_rules! decode_old_layout {
+    ($disc:expr, $data:expr, [$($ty:ident => $old:ident),* $(,)?]) => {
+        $(
+            if $disc == $ty::DISCRIMINATOR && $data.len() == DISCRIMINATOR_LEN + $old::INIT_SPACE {
+                return payload::<$old>($data, stringify!($old)).map(|old| DecodedAccount::$ty(old.into()));
+            }
+        )*
+    };
+}
+
+fn split_discriminator(data: &[u8]) -> Result<[u8; 8]> {
+    if data.len() < DISCRIMINATOR_LEN {
+        return Err(IndexerError::AccountTooShort);
+    }
+    let mut disc = [0u8; 8];
+    disc.copy_from_slice(&data[..DISCRIMINATOR_LEN]);
+    Ok(disc)
+}
+
+fn account<T: AccountDeserialize>(data: &[u8], kind: &'static str) -> Result<T> {
+    let mut buf = data;
+    T::try_deserialize(&mut buf).map_err(|e| IndexerError::Decode {
+        kind,
+        reason: e.to_string(),
+    })
+}
+
+fn payload<T: AnchorDeserialize>(data: &[u8], kind: &'static str) -> Result<T> {
+    T::deserialize(&mut &data[DISCRIMINATOR_LEN..]).map_err(|e| IndexerError::Decode {
+        kind,
+        reason: e.to_string(),
+    })
+}
+
+/// Decodes account data as returned by `getAccountInfo` / `getProgramAccounts`.
+pub fn decode_account(data: &[u8]) -> Result<DecodedAccount> {
+    let disc = split_discriminator(data)?;
+    
+    // Legacy merchants share the discriminator but have the old, larger layout
+    if disc == Merchant::DISCRIMINATOR
+        && data.len() == DISCRIMINATOR_LEN + LegacyMerchant::INIT_SPACE
+    {
+        return payload::<LegacyMerchant>(data, "LegacyMerchant").map(DecodedAccount::LegacyMerchant);
+    }
+    
+    decode_old_layout!(disc, data, [
+        PlatformConfig => PlatformConfigV0,
+        Merchant => MerchantV0,
+        Listing => ListingV0,
+        Listing => ListingV1,
+        Pool => PoolV0,
+        StakeAccount => StakeAccountV0,
+    ]);
+    
+    decode_by_discriminator!(disc, data, DecodedAccount, account, IndexerError::UnknownAccount(disc), [
+        PlatformConfig,
+        UserStats,
+        Merchant,
+        Listing,
+        Pool,
+        PoolParticipant,
+        Review,
+        StakeAccount,
+        VerifierRegistry,
+        Attestation,
+        MerchantDelegate,
+        RedemptionReceipt,
+        RefundPolicy,
+        CouponTree,
+        CompressedListing,
+        FeeSchedule,
+        Referrer,
+        Loyalty,
+        ListingGate,
+        GatePurchases,
+        ListingSchedule,
+        Gift,
+    ])
+}
+
+/// Decodes the payload of a single `Program data:` log line.
+pub fn decode_event(data: &[u8]) -> Result<DecodedEvent> {
+    let disc = split_discriminator(data)?;
+    
+    decode_by_discriminator!(disc, data, DecodedEvent, payload, IndexerError::UnknownEvent(disc), [
+        MerchantRegistered,
+        MerchantVerificationUpdated,
+        MerchantKycUpdated,
+        DelegateAdded,
+        DelegateRevoked,
+        AttestationIssued,
+        AttestationRevoked,
+        CouponListed,
+        CouponRelisted,
+        CouponDelisted,
+        ExpiredListingReclaimed,
+        CouponPurchased,
+        CartCheckedOut,
+        PoolCreated,
+        PoolJoined,
+        PoolCompleted,
+        PoolSettled,
+        PoolCouponClaimed,
+        PoolCancelled,
+        PoolDepositRefunded,
+        ReviewAdded,
+        CouponStaked,
+        CouponUnstaked,
+        StakingRewardsClaimed,
+        CouponRedeemed,
+        ExpiredRefundClaimed,
+        CompressedCouponMinted,
+        CompressedCouponListed,
+        CompressedCouponPurchased,
+        CompressedCouponDelisted,
+        PauseUpdated,
+        GuardianUpdated,
+        FeeScheduleUpdated,
+        ReferrerRegistered,
+        ReferralRewarded,
+        ReferralRewardsClaimed,
+        ReferralRewardsUpdated,
+        TierRefreshed,
+        ListingAccessUpdated,
+        GateUpdated,
+        ListingGateUpdated,
+        ListingScheduled,
+        CustodySynced,
+        CouponGifted,
+        GiftSent,
+        GiftClaimed,
+        GiftReturned,
+    ])
+}
+
+/// Extracts the events emitted by `program_id` from a transaction's log messages.
+/// Tracks the invoke stack so `Program data:` lines from other programs,
+/// including ones we CPI into, are skipped.
+pub fn events_from_logs(program_id: &Pubkey, logs: &[String]) -> Result<Vec<DecodedEvent>> {
+    let program = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if stack.last() == Some(&program.as_str()) {
+                let bytes = STANDARD.decode(data)?;
+                events.push(decode_event(&bytes)?);
+            }
+        } else if let Some((id, status)) = rest.split_once(' ') {
+            if status.starts_with("invoke [") {
+                stack.push(id);
+            } else if status == "success" || status.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    
+    Ok(events)
+}
@@ -0,0 +1,33 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("account data is shorter than a discriminator")]
+    AccountTooShort,
+    
+    #[error("unknown account discriminator {0:?}")]
+    UnknownAccount([u8; 8]),
+    
+    #[error("unknown event discriminator {0:?}")]
+    UnknownEvent([u8; 8]),
+    
+    #[error("failed to decode {kind}: {reason}")]
+    Decode { kind: &'static str, reason: String },
+    
+    #[error("invalid pubkey: {0}")]
+    InvalidPubkey(String),
+    
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
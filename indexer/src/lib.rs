@@ -0,0 +1,71 @@
+//! Off-chain indexer for the coupon marketplace program.
+//!
+//! Account snapshots and transaction logs are decoded with the program's own
+//! state and event types, then materialized into SQLite so the backend can
+//! query listings by merchant, pools by listing and reviews by listing.
+
+pub mod decode;
+pub mod error;
+pub mod record;
+pub mod store;
+
+use anchor_lang::prelude::Pubkey;
+
+pub use decode::{decode_account, decode_event, events_from_logs, DecodedAccount, DecodedEvent};
+pub use error::{IndexerError, Result};
+pub use record::{AccountRecord, Recording, TransactionRecord};
+pub use store::{ListingRow, MerchantRow, PoolRow, ReviewRow, Store};
+
+/// Replays recorded accounts and transactions into a [`Store`].
+pub struct Indexer {
+    program_id: Pubkey,
+    store: Store,
+}
+
+impl Indexer {
+    pub fn new(store: Store) -> Self {
+        Self {
+            program_id: monkey_dao::ID,
+            store,
+        }
+    }
+    
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+    
+    pub fn ingest_account(&mut self, record: &AccountRecord) -> Result<()> {
+        let data = record.data()?;
+        let account = decode_account(&data)?;
+        self.store.upsert_account(&record.pubkey()?, record.slot, &data, &account)
+    }
+    
+    pub fn ingest_transaction(&mut self, record: &TransactionRecord) -> Result<()> {
+        let events = events_from_logs(&self.program_id, &record.logs)?;
+        for event in &events {
+            self.store.apply_event(&record.signature, record.slot, event)?;
+        }
+        Ok(())
+    }
+    
+    /// Applies a recording in slot order. Within a slot, transactions go
+    /// first since account snapshots reflect the state after them.
+    pub fn replay(&mut self, recording: &Recording) -> Result<()> {
+        let mut transactions: Vec<&TransactionRecord> = recording.transactions.iter().collect();
+        transactions.sort_by_key(|tx| tx.slot);
+        let mut accounts: Vec<&AccountRecord> = recording.accounts.iter().collect();
+        accounts.sort_by_key(|account| account.slot);
+        
+        let mut accounts = accounts.into_iter().peekable();
+        for tx in transactions {
+            while let Some(account) = accounts.next_if(|account| account.slot < tx.slot) {
+                self.ingest_account(account)?;
+            }
+            self.ingest_transaction(tx)?;
+        }
+        for account in accounts {
+            self.ingest_account(account)?;
+        }
+        Ok(())
+    }
+}
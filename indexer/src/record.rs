@@ -0,0 +1,55 @@
+//! Serialized forms of what the indexer consumes, matching the shapes the
+//! RPC returns for `getProgramAccounts` and `getTransaction` log messages.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{IndexerError, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecord {
+    pub pubkey: String,
+    pub slot: u64,
+    /// Base64 account data
+    pub data: String,
+}
+
+impl AccountRecord {
+    pub fn pubkey(&self) -> Result<Pubkey> {
+        parse_pubkey(&self.pubkey)
+    }
+    
+    pub fn data(&self) -> Result<Vec<u8>> {
+        Ok(STANDARD.decode(&self.data)?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    #[serde(default)]
+    pub accounts: Vec<AccountRecord>,
+    #[serde(default)]
+    pub transactions: Vec<TransactionRecord>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+pub fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|_| IndexerError::InvalidPubkey(value.to_string()))
+}
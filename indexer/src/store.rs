@@ -0,0 +1,795 @@
+//! SQLite materialization of program state.
+//!
+//! Account snapshots overwrite rows wholesale; events patch the fields they
+//! carry. Every write is guarded by slot so replaying out of order never
+//! rolls a row back.
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Discriminator;
+use monkey_dao::state::Custody;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::decode::{DecodedAccount, DecodedEvent};
+use crate::error::Result;
+use crate::record::parse_pubkey;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS accounts (
+    pubkey TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS merchants (
+    pubkey TEXT PRIMARY KEY,
+    authority TEXT NOT NULL,
+    business_name TEXT NOT NULL,
+    business_type TEXT NOT NULL,
+    kyc_uri TEXT NOT NULL DEFAULT '',
+    is_verified INTEGER NOT NULL DEFAULT 0,
+    total_listings INTEGER NOT NULL DEFAULT 0,
+    total_redemptions INTEGER NOT NULL DEFAULT 0,
+    registration_date INTEGER NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS listings (
+    pubkey TEXT PRIMARY KEY,
+    nft_mint TEXT NOT NULL,
+    merchant TEXT NOT NULL,
+    seller TEXT NOT NULL,
+    original_price INTEGER NOT NULL,
+    current_price INTEGER NOT NULL,
+    is_group_deal INTEGER NOT NULL,
+    is_active INTEGER NOT NULL,
+    is_used INTEGER NOT NULL DEFAULT 0,
+    remaining_uses INTEGER NOT NULL,
+    total_sales INTEGER NOT NULL DEFAULT 0,
+    coupon_description TEXT NOT NULL DEFAULT '',
+    expiry_date INTEGER NOT NULL,
+    average_rating INTEGER NOT NULL DEFAULT 0,
+    total_reviews INTEGER NOT NULL DEFAULT 0,
+    holder TEXT NOT NULL,
+    custody TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS listings_by_merchant ON listings (merchant);
+CREATE INDEX IF NOT EXISTS listings_by_holder ON listings (holder);
+CREATE TABLE IF NOT EXISTS pools (
+    pubkey TEXT PRIMARY KEY,
+    listing TEXT NOT NULL,
+    initiator TEXT NOT NULL,
+    pool_size INTEGER NOT NULL,
+    current_participants INTEGER NOT NULL DEFAULT 0,
+    price_per_person INTEGER NOT NULL,
+    total_deposited INTEGER NOT NULL DEFAULT 0,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    is_completed INTEGER NOT NULL DEFAULT 0,
+    slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS pools_by_listing ON pools (listing);
+CREATE TABLE IF NOT EXISTS reviews (
+    pubkey TEXT PRIMARY KEY,
+    listing TEXT NOT NULL,
+    reviewer TEXT NOT NULL,
+    rating INTEGER NOT NULL,
+    comment TEXT,
+    slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS reviews_by_listing ON reviews (listing);
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    discriminator BLOB NOT NULL
+);
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerchantRow {
+    pub pubkey: Pubkey,
+    pub authority: Pubkey,
+    pub business_name: String,
+    pub business_type: String,
+    pub is_verified: bool,
+    pub total_listings: u64,
+    pub total_redemptions: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingRow {
+    pub pubkey: Pubkey,
+    pub nft_mint: Pubkey,
+    pub merchant: Pubkey,
+    pub seller: Pubkey,
+    pub original_price: u64,
+    pub current_price: u64,
+    pub is_group_deal: bool,
+    pub is_active: bool,
+    pub is_used: bool,
+    pub remaining_uses: u64,
+    pub total_sales: u64,
+    pub coupon_description: String,
+    pub expiry_date: i64,
+    pub average_rating: u8,
+    pub total_reviews: u64,
+    pub holder: Pubkey,
+    pub custody: Custody,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolRow {
+    pub pubkey: Pubkey,
+    pub listing: Pubkey,
+    pub initiator: Pubkey,
+    pub pool_size: u8,
+    pub current_participants: u8,
+    pub price_per_person: u64,
+    pub total_deposited: u64,
+    pub is_active: bool,
+    pub is_completed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewRow {
+    pub pubkey: Pubkey,
+    pub listing: Pubkey,
+    pub reviewer: Pubkey,
+    pub rating: u8,
+    /// Only known once the review account itself has been indexed
+    pub comment: Option<String>,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+fn pubkey_at(row: &Row, idx: usize) -> rusqlite::Result<Pubkey> {
+    let value: String = row.get(idx)?;
+    parse_pubkey(&value).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+const CUSTODIES: [Custody; 6] = [
+    Custody::Wallet,
+    Custody::ListingVault,
+    Custody::StakeVault,
+    Custody::Pool,
+    Custody::GiftEscrow,
+    Custody::Redeemed,
+];
+
+/// Custody is stored by variant name, which is what event patches write.
+fn custody_name(custody: Custody) -> String {
+    format!("{custody:?}")
+}
+
+fn custody_at(row: &Row, idx: usize) -> rusqlite::Result<Custody> {
+    let value: String = row.get(idx)?;
+    CUSTODIES
+        .into_iter()
+        .find(|custody| custody_name(*custody) == value)
+        .ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                idx,
+                rusqlite::types::Type::Text,
+                format!("unknown custody {value}").into(),
+            )
+        })
+}
+
+fn listing_row(row: &Row) -> rusqlite::Result<ListingRow> {
+    Ok(ListingRow {
+        pubkey: pubkey_at(row, 0)?,
+        nft_mint: pubkey_at(row, 1)?,
+        merchant: pubkey_at(row, 2)?,
+        seller: pubkey_at(row, 3)?,
+        original_price: row.get(4)?,
+        current_price: row.get(5)?,
+        is_group_deal: row.get(6)?,
+        is_active: row.get(7)?,
+        is_used: row.get(8)?,
+        remaining_uses: row.get(9)?,
+        total_sales: row.get(10)?,
+        coupon_description: row.get(11)?,
+        expiry_date: row.get(12)?,
+        average_rating: row.get(13)?,
+        total_reviews: row.get(14)?,
+        holder: pubkey_at(row, 15)?,
+        custody: custody_at(row, 16)?,
+    })
+}
+
+const LISTING_COLUMNS: &str = "pubkey, nft_mint, merchant, seller, original_price, current_price, \
+    is_group_deal, is_active, is_used, remaining_uses, total_sales, coupon_description, \
+    expiry_date, average_rating, total_reviews, holder, custody";
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+    
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+    
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+    
+    /// Stores the raw account and, for the types the marketplace views
+    /// need, its materialized row.
+    pub fn upsert_account(
+        &mut self,
+        pubkey: &Pubkey,
+        slot: u64,
+        data: &[u8],
+        account: &DecodedAccount,
+    ) -> Result<()> {
+        let key = pubkey.to_string();
+        let tx = self.conn.transaction()?;
+        
+        tx.execute(
+            "INSERT INTO accounts (pubkey, kind, slot, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (pubkey) DO UPDATE SET kind = excluded.kind, slot = excluded.slot, data = excluded.data
+             WHERE excluded.slot >= accounts.slot",
+            params![key, account.kind(), slot, data],
+        )?;
+        
+        match account {
+            DecodedAccount::Merchant(merchant) => {
+                tx.execute(
+                    "INSERT INTO merchants (pubkey, authority, business_name, business_type, kyc_uri,
+                        is_verified, total_listings, total_redemptions, registration_date, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                     ON CONFLICT (pubkey) DO UPDATE SET authority = excluded.authority,
+                        business_name = excluded.business_name, business_type = excluded.business_type,
+                        kyc_uri = excluded.kyc_uri, is_verified = excluded.is_verified,
+                        total_listings = excluded.total_listings,
+                        total_redemptions = excluded.total_redemptions,
+                        registration_date = excluded.registration_date, slot = excluded.slot
+                     WHERE excluded.slot >= merchants.slot",
+                    params![
+                        key,
+                        merchant.authority.to_string(),
+                        merchant.business_name,
+                        merchant.business_type,
+                        merchant.kyc_uri,
+                        merchant.is_verified,
+                        merchant.total_listings,
+                        merchant.total_redemptions,
+                        merchant.registration_date,
+                        slot,
+                    ],
+                )?;
+            }
+            DecodedAccount::Listing(listing) => {
+                tx.execute(
+                    "INSERT INTO listings (pubkey, nft_mint, merchant, seller, original_price,
+                        current_price, is_group_deal, is_active, is_used, remaining_uses, total_sales,
+                        coupon_description, expiry_date, average_rating, total_reviews, holder, custody,
+                        slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                        ?18)
+                     ON CONFLICT (pubkey) DO UPDATE SET nft_mint = excluded.nft_mint,
+                        merchant = excluded.merchant, seller = excluded.seller,
+                        original_price = excluded.original_price, current_price = excluded.current_price,
+                        is_group_deal = excluded.is_group_deal, is_active = excluded.is_active,
+                        is_used = excluded.is_used, remaining_uses = excluded.remaining_uses,
+                        total_sales = excluded.total_sales,
+                        coupon_description = excluded.coupon_description,
+                        expiry_date = excluded.expiry_date, average_rating = excluded.average_rating,
+                        total_reviews = excluded.total_reviews, holder = excluded.holder,
+                        custody = excluded.custody, slot = excluded.slot
+                     WHERE excluded.slot >= listings.slot",
+                    params![
+                        key,
+                        listing.nft_mint.to_string(),
+                        listing.merchant.to_string(),
+                        listing.seller.to_string(),
+                        listing.original_price,
+                        listing.current_price,
+                        listing.is_group_deal,
+                        listing.is_active,
+                        listing.is_used,
+                        listing.uses.remaining,
+                        listing.total_sales,
+                        listing.coupon_description,
+                        listing.expiry_date,
+                        listing.average_rating,
+                        listing.total_reviews,
+                        listing.holder.to_string(),
+                        custody_name(listing.custody),
+                        slot,
+                    ],
+                )?;
+            }
+            DecodedAccount::Pool(pool) => {
+                tx.execute(
+                    "INSERT INTO pools (pubkey, listing, initiator, pool_size, current_participants,
+                        price_per_person, total_deposited, is_active, is_completed, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                     ON CONFLICT (pubkey) DO UPDATE SET listing = excluded.listing,
+                        initiator = excluded.initiator, pool_size = excluded.pool_size,
+                        current_participants = excluded.current_participants,
+                        price_per_person = excluded.price_per_person,
+                        total_deposited = excluded.total_deposited, is_active = excluded.is_active,
+                        is_completed = excluded.is_completed, slot = excluded.slot
+                     WHERE excluded.slot >= pools.slot",
+                    params![
+                        key,
+                        pool.listing.to_string(),
+                        pool.initiator.to_string(),
+                        pool.pool_size,
+                        pool.current_participants,
+                        pool.price_per_person,
+                        pool.total_deposited,
+                        pool.is_active,
+                        pool.is_completed,
+                        slot,
+                    ],
+                )?;
+            }
+            DecodedAccount::Review(review) => {
+                tx.execute(
+                    "INSERT INTO reviews (pubkey, listing, reviewer, rating, comment, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT (pubkey) DO UPDATE SET listing = excluded.listing,
+                        reviewer = excluded.reviewer, rating = excluded.rating,
+                        comment = excluded.comment, slot = excluded.slot
+                     WHERE excluded.slot >= reviews.slot",
+                    params![
+                        key,
+                        review.listing.to_string(),
+                        review.reviewer.to_string(),
+                        review.rating,
+                        review.comment,
+                        slot,
+                    ],
+                )?;
+            }
+            _ => {}
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Records the event and patches the materialized rows it describes.
+    pub fn apply_event(&mut self, signature: &str, slot: u64, event: &DecodedEvent) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        let (name, discriminator) = event_name(event);
+        tx.execute(
+            "INSERT INTO events (signature, slot, name, discriminator) VALUES (?1, ?2, ?3, ?4)",
+            params![signature, slot, name, discriminator.as_slice()],
+        )?;
+        
+        match event {
+            DecodedEvent::MerchantRegistered(e) => {
+                tx.execute(
+                    "INSERT INTO merchants (pubkey, authority, business_name, business_type,
+                        registration_date, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT (pubkey) DO NOTHING",
+                    params![
+                        e.merchant.to_string(),
+                        e.authority.to_string(),
+                        e.business_name,
+                        e.business_type,
+                        e.registered_at,
+                        slot,
+                    ],
+                )?;
+            }
+            DecodedEvent::MerchantVerificationUpdated(e) => {
+                tx.execute(
+                    "UPDATE merchants SET is_verified = ?2, slot = ?3 WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.merchant.to_string(), e.is_verified, slot],
+                )?;
+            }
+            DecodedEvent::MerchantKycUpdated(e) => {
+                tx.execute(
+                    "UPDATE merchants SET kyc_uri = ?2, is_verified = 0, slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.merchant.to_string(), e.kyc_uri, slot],
+                )?;
+            }
+            DecodedEvent::CouponListed(e) => {
+                tx.execute(
+                    "INSERT INTO listings (pubkey, nft_mint, merchant, seller, original_price,
+                        current_price, is_group_deal, is_active, remaining_uses, expiry_date, holder,
+                        custody, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, 1, ?7, ?8, ?4, 'ListingVault', ?9)
+                     ON CONFLICT (pubkey) DO UPDATE SET seller = excluded.seller,
+                        holder = excluded.holder, custody = excluded.custody,
+                        original_price = excluded.original_price, current_price = excluded.current_price,
+                        is_group_deal = excluded.is_group_deal, is_active = 1,
+                        remaining_uses = excluded.remaining_uses, expiry_date = excluded.expiry_date,
+                        slot = excluded.slot
+                     WHERE excluded.slot >= listings.slot",
+                    params![
+                        e.listing.to_string(),
+                        e.nft_mint.to_string(),
+                        e.merchant.to_string(),
+                        e.seller.to_string(),
+                        e.price,
+                        e.is_group_deal,
+                        e.remaining_uses,
+                        e.expiry_date,
+                        slot,
+                    ],
+                )?;
+            }
+            DecodedEvent::CouponRelisted(e) => {
+                tx.execute(
+                    "UPDATE listings SET seller = ?2, current_price = ?3, is_active = 1, holder = ?2,
+                        custody = 'ListingVault', slot = ?4
+                     WHERE pubkey = ?1 AND slot <= ?4",
+                    params![e.listing.to_string(), e.seller.to_string(), e.price, slot],
+                )?;
+            }
+            DecodedEvent::CouponDelisted(e) => {
+                tx.execute(
+                    "UPDATE listings SET is_active = 0, custody = 'Wallet', slot = ?2
+                     WHERE pubkey = ?1 AND slot <= ?2",
+                    params![e.listing.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::ExpiredListingReclaimed(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Wallet', is_active = 0, slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.seller.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::CouponPurchased(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Wallet', is_active = 0,
+                        total_sales = total_sales + 1, slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.buyer.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::PoolCreated(e) => {
+                tx.execute(
+                    "INSERT INTO pools (pubkey, listing, initiator, pool_size, price_per_person, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT (pubkey) DO NOTHING",
+                    params![
+                        e.pool.to_string(),
+                        e.listing.to_string(),
+                        e.initiator.to_string(),
+                        e.pool_size,
+                        e.price_per_person,
+                        slot,
+                    ],
+                )?;
+            }
+            DecodedEvent::PoolJoined(e) => {
+                tx.execute(
+                    "UPDATE pools SET current_participants = ?2,
+                        total_deposited = total_deposited + ?3, slot = ?4
+                     WHERE pubkey = ?1 AND slot <= ?4",
+                    params![e.pool.to_string(), e.current_participants, e.amount, slot],
+                )?;
+            }
+            DecodedEvent::PoolCompleted(e) => {
+                tx.execute(
+                    "UPDATE pools SET is_completed = 1, total_deposited = ?2, slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.pool.to_string(), e.total_deposited, slot],
+                )?;
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Pool', slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.pool.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::PoolSettled(e) => {
+                tx.execute(
+                    "UPDATE pools SET is_active = 0, is_completed = 1, slot = ?2
+                     WHERE pubkey = ?1 AND slot <= ?2",
+                    params![e.pool.to_string(), slot],
+                )?;
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Pool', is_active = 0,
+                        total_sales = total_sales + 1, slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.pool.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::PoolCouponClaimed(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Wallet', slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.initiator.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::PoolCancelled(e) => {
+                tx.execute(
+                    "UPDATE pools SET is_active = 0, slot = ?2 WHERE pubkey = ?1 AND slot <= ?2",
+                    params![e.pool.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::PoolDepositRefunded(e) => {
+                // Refunds only happen once the pool can no longer settle
+                tx.execute(
+                    "UPDATE pools SET is_active = 0, current_participants = ?2,
+                        total_deposited = total_deposited - ?3, slot = ?4
+                     WHERE pubkey = ?1 AND slot <= ?4",
+                    params![e.pool.to_string(), e.current_participants, e.amount, slot],
+                )?;
+            }
+            DecodedEvent::ReviewAdded(e) => {
+                tx.execute(
+                    "INSERT INTO reviews (pubkey, listing, reviewer, rating, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT (pubkey) DO NOTHING",
+                    params![
+                        e.review.to_string(),
+                        e.listing.to_string(),
+                        e.reviewer.to_string(),
+                        e.rating,
+                        slot,
+                    ],
+                )?;
+                tx.execute(
+                    "UPDATE listings SET average_rating = ?2, total_reviews = ?3, slot = ?4
+                     WHERE pubkey = ?1 AND slot <= ?4",
+                    params![e.listing.to_string(), e.average_rating, e.total_reviews, slot],
+                )?;
+            }
+            DecodedEvent::CouponRedeemed(e) => {
+                tx.execute(
+                    "UPDATE listings SET remaining_uses = ?2, is_used = ?3, holder = ?4,
+                        custody = CASE WHEN ?3 THEN 'Redeemed' ELSE 'Wallet' END, slot = ?5
+                     WHERE pubkey = ?1 AND slot <= ?5",
+                    params![e.listing.to_string(), e.remaining, e.remaining == 0, e.redeemer.to_string(), slot],
+                )?;
+                tx.execute(
+                    "UPDATE merchants SET total_redemptions = total_redemptions + 1, slot = ?2
+                     WHERE pubkey = ?1 AND slot <= ?2",
+                    params![e.merchant.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::ExpiredRefundClaimed(e) => {
+                tx.execute(
+                    "UPDATE listings SET is_used = 1, remaining_uses = 0, holder = ?2,
+                        custody = 'Redeemed', slot = ?3
+                     WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.holder.to_string(), slot],
+                )?;
+            }
+            // Coupons that leave their listing's vault are found by mint
+            DecodedEvent::CouponStaked(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'StakeVault', slot = ?3
+                     WHERE nft_mint = ?1 AND slot <= ?3",
+                    params![e.nft_mint.to_string(), e.owner.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::CouponUnstaked(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Wallet', slot = ?3
+                     WHERE nft_mint = ?1 AND slot <= ?3",
+                    params![e.nft_mint.to_string(), e.owner.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::CouponGifted(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, slot = ?3 WHERE pubkey = ?1 AND slot <= ?3",
+                    params![e.listing.to_string(), e.recipient.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::GiftSent(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'GiftEscrow', slot = ?3
+                     WHERE nft_mint = ?1 AND slot <= ?3",
+                    params![e.nft_mint.to_string(), e.sender.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::GiftClaimed(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Wallet', slot = ?3
+                     WHERE nft_mint = ?1 AND slot <= ?3",
+                    params![e.nft_mint.to_string(), e.recipient.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::GiftReturned(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = 'Wallet', slot = ?3
+                     WHERE nft_mint = ?1 AND slot <= ?3",
+                    params![e.nft_mint.to_string(), e.sender.to_string(), slot],
+                )?;
+            }
+            DecodedEvent::CustodySynced(e) => {
+                tx.execute(
+                    "UPDATE listings SET holder = ?2, custody = ?3, slot = ?4
+                     WHERE pubkey = ?1 AND slot <= ?4",
+                    params![e.listing.to_string(), e.holder.to_string(), custody_name(e.custody), slot],
+                )?;
+            }
+            // Kept in the event log only
+            _ => {}
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    pub fn merchant(&self, pubkey: &Pubkey) -> Result<Option<MerchantRow>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT pubkey, authority, business_name, business_type, is_verified,
+                    total_listings, total_redemptions
+                 FROM merchants WHERE pubkey = ?1",
+                params![pubkey.to_string()],
+                |row| {
+                    Ok(MerchantRow {
+                        pubkey: pubkey_at(row, 0)?,
+                        authority: pubkey_at(row, 1)?,
+                        business_name: row.get(2)?,
+                        business_type: row.get(3)?,
+                        is_verified: row.get(4)?,
+                        total_listings: row.get(5)?,
+                        total_redemptions: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+    
+    pub fn listing(&self, pubkey: &Pubkey) -> Result<Option<ListingRow>> {
+        Ok(self
+            .conn
+            .query_row(
+                &format!("SELECT {LISTING_COLUMNS} FROM listings WHERE pubkey = ?1"),
+                params![pubkey.to_string()],
+                listing_row,
+            )
+            .optional()?)
+    }
+    
+    pub fn listings_by_merchant(&self, merchant: &Pubkey) -> Result<Vec<ListingRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {LISTING_COLUMNS} FROM listings WHERE merchant = ?1 ORDER BY pubkey"
+        ))?;
+        let rows = stmt.query_map(params![merchant.to_string()], listing_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+    
+    /// Marketplace front page: listings currently for sale, cheapest first.
+    pub fn active_listings(&self) -> Result<Vec<ListingRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {LISTING_COLUMNS} FROM listings WHERE is_active = 1
+             ORDER BY current_price, pubkey"
+        ))?;
+        let rows = stmt.query_map([], listing_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+    
+    pub fn pools_by_listing(&self, listing: &Pubkey) -> Result<Vec<PoolRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pubkey, listing, initiator, pool_size, current_participants, price_per_person,
+                total_deposited, is_active, is_completed
+             FROM pools WHERE listing = ?1 ORDER BY pubkey",
+        )?;
+        let rows = stmt.query_map(params![listing.to_string()], |row| {
+            Ok(PoolRow {
+                pubkey: pubkey_at(row, 0)?,
+                listing: pubkey_at(row, 1)?,
+                initiator: pubkey_at(row, 2)?,
+                pool_size: row.get(3)?,
+                current_participants: row.get(4)?,
+                price_per_person: row.get(5)?,
+                total_deposited: row.get(6)?,
+                is_active: row.get(7)?,
+                is_completed: row.get(8)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+    
+    pub fn reviews_by_listing(&self, listing: &Pubkey) -> Result<Vec<ReviewRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pubkey, listing, reviewer, rating, comment
+             FROM reviews WHERE listing = ?1 ORDER BY pubkey",
+        )?;
+        let rows = stmt.query_map(params![listing.to_string()], |row| {
+            Ok(ReviewRow {
+                pubkey: pubkey_at(row, 0)?,
+                listing: pubkey_at(row, 1)?,
+                reviewer: pubkey_at(row, 2)?,
+                rating: row.get(3)?,
+                comment: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+    
+    /// Names of the events recorded for a transaction, in emission order.
+    pub fn event_names(&self, signature: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM events WHERE signature = ?1 ORDER BY id")?;
+        let rows = stmt.query_map(params![signature], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+    
+    /// Kind of the last snapshot stored for an account, if any.
+    pub fn account_kind(&self, pubkey: &Pubkey) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT kind FROM accounts WHERE pubkey = ?1",
+                params![pubkey.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+}
+
+macro_rules! event_names {
+    ($event:expr, [$($ty:ident),* $(,)?]) => {
+        match $event {
+            $(DecodedEvent::$ty(_) => (stringify!($ty), monkey_dao::events::$ty::DISCRIMINATOR),)*
+        }
+    };
+}
+
+fn event_name(event: &DecodedEvent) -> (&'static str, [u8; 8]) {
+    event_names!(event, [
+        MerchantRegistered,
+        MerchantVerificationUpdated,
+        MerchantKycUpdated,
+        DelegateAdded,
+        DelegateRevoked,
+        AttestationIssued,
+        AttestationRevoked,
+        CouponListed,
+        CouponRelisted,
+        CouponDelisted,
+        ExpiredListingReclaimed,
+        CouponPurchased,
+        CartCheckedOut,
+        PoolCreated,
+        PoolJoined,
+        PoolCompleted,
+        PoolSettled,
+        PoolCouponClaimed,
+        PoolCancelled,
+        PoolDepositRefunded,
+        ReviewAdded,
+        CouponStaked,
+        CouponUnstaked,
+        StakingRewardsClaimed,
+        CouponRedeemed,
+        ExpiredRefundClaimed,
+        CompressedCouponMinted,
+        CompressedCouponListed,
+        CompressedCouponPurchased,
+        CompressedCouponDelisted,
+        PauseUpdated,
+        GuardianUpdated,
+        FeeScheduleUpdated,
+        ReferrerRegistered,
+        ReferralRewarded,
+        ReferralRewardsClaimed,
+        ReferralRewardsUpdated,
+        TierRefreshed,
+        ListingAccessUpdated,
+        GateUpdated,
+        ListingGateUpdated,
+        ListingScheduled,
+        CustodySynced,
+        CouponGifted,
+        GiftSent,
+        GiftClaimed,
+        GiftReturned,
+    ])
+}
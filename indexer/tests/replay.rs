@@ -0,0 +1,172 @@
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::Pubkey;
+use monkey_dao::state::Custody;
+use monkey_dao_indexer::{decode_account, Indexer, Recording, Store, TransactionRecord};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/marketplace.json");
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+fn replayed() -> Indexer {
+    let recording = Recording::load(FIXTURE).unwrap();
+    let mut indexer = Indexer::new(Store::in_memory().unwrap());
+    indexer.replay(&recording).unwrap();
+    indexer
+}
+
+#[test]
+fn listings_by_merchant() {
+    let indexer = replayed();
+    let store = indexer.store();
+    
+    let listings = store.listings_by_merchant(&key(2)).unwrap();
+    let keys: Vec<Pubkey> = listings.iter().map(|l| l.pubkey).collect();
+    assert_eq!(keys, vec![key(11), key(13)]);
+    
+    // Bought, redeemed, then snapshotted: the account overrides the event-built row
+    let sold = &listings[0];
+    assert_eq!(sold.seller, key(21));
+    assert!(!sold.is_active);
+    assert!(sold.is_used);
+    assert_eq!(sold.total_sales, 1);
+    assert_eq!(sold.holder, key(21));
+    assert_eq!(sold.custody, Custody::Redeemed);
+    assert_eq!(sold.coupon_description, "Free coffee with any pastry");
+    
+    // Only ever seen through events
+    let group = &listings[1];
+    assert!(group.is_active);
+    assert!(group.is_group_deal);
+    assert_eq!(group.current_price, 2_000_000_000);
+    // Its pool filled, so the coupon is held for the pool
+    assert_eq!(group.holder, key(30));
+    assert_eq!(group.custody, Custody::Pool);
+    
+    let other = store.listings_by_merchant(&key(4)).unwrap();
+    assert_eq!(other.len(), 1);
+    assert!(!other[0].is_active);
+}
+
+#[test]
+fn active_listings_view() {
+    let indexer = replayed();
+    let active: Vec<Pubkey> = indexer
+        .store()
+        .active_listings()
+        .unwrap()
+        .iter()
+        .map(|l| l.pubkey)
+        .collect();
+    assert_eq!(active, vec![key(13)]);
+}
+
+#[test]
+fn pools_by_listing() {
+    let indexer = replayed();
+    let pools = indexer.store().pools_by_listing(&key(13)).unwrap();
+    
+    assert_eq!(pools.len(), 1);
+    let pool = &pools[0];
+    assert_eq!(pool.pubkey, key(30));
+    assert_eq!(pool.initiator, key(22));
+    assert_eq!(pool.current_participants, 2);
+    assert_eq!(pool.total_deposited, 1_800_000_000);
+    assert!(pool.is_completed);
+    
+    assert!(indexer.store().pools_by_listing(&key(11)).unwrap().is_empty());
+}
+
+#[test]
+fn reviews_by_listing() {
+    let indexer = replayed();
+    let store = indexer.store();
+    let reviews = store.reviews_by_listing(&key(11)).unwrap();
+    
+    assert_eq!(reviews.len(), 2);
+    assert_eq!(reviews[0].rating, 5);
+    assert_eq!(reviews[0].comment.as_deref(), Some("Great coffee"));
+    // Second review was only seen through its event
+    assert_eq!(reviews[1].reviewer, key(22));
+    assert_eq!(reviews[1].comment, None);
+    
+    let listing = store.listing(&key(11)).unwrap().unwrap();
+    assert_eq!(listing.total_reviews, 2);
+    assert_eq!(listing.average_rating, 80);
+}
+
+#[test]
+fn merchants_follow_registration_and_verification() {
+    let indexer = replayed();
+    let store = indexer.store();
+    
+    let merchant = store.merchant(&key(2)).unwrap().unwrap();
+    assert_eq!(merchant.business_name, "Monkey Cafe");
+    assert!(merchant.is_verified);
+    assert_eq!(merchant.total_redemptions, 1);
+    
+    let unverified = store.merchant(&key(4)).unwrap().unwrap();
+    assert!(!unverified.is_verified);
+}
+
+#[test]
+fn decodes_every_recorded_account() {
+    let recording = Recording::load(FIXTURE).unwrap();
+    let kinds: BTreeSet<&str> = recording
+        .accounts
+        .iter()
+        .map(|record| decode_account(&record.data().unwrap()).unwrap().kind())
+        .collect();
+    
+    let expected: BTreeSet<&str> = [
+        "PlatformConfig",
+        "Pool",
+        "Merchant",
+        "LegacyMerchant",
+        "Listing",
+        "Review",
+        "StakeAccount",
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(kinds, expected);
+    
+    let indexer = replayed();
+    assert_eq!(
+        indexer.store().account_kind(&key(5)).unwrap().as_deref(),
+        Some("LegacyMerchant")
+    );
+}
+
+#[test]
+fn ignores_program_data_from_other_programs() {
+    let indexer = replayed();
+    assert_eq!(
+        indexer.store().event_names("sigBuy").unwrap(),
+        vec!["CouponPurchased"]
+    );
+    assert_eq!(
+        indexer.store().event_names("sigJoin2").unwrap(),
+        vec!["PoolJoined", "PoolCompleted"]
+    );
+}
+
+#[test]
+fn stale_events_do_not_roll_back_snapshots() {
+    let recording = Recording::load(FIXTURE).unwrap();
+    let mut indexer = replayed();
+    
+    // Re-deliver the original listing event after the slot 80 snapshot
+    let late: &TransactionRecord = recording
+        .transactions
+        .iter()
+        .find(|tx| tx.signature == "sigList1")
+        .unwrap();
+    indexer.ingest_transaction(late).unwrap();
+    
+    let listing = indexer.store().listing(&key(11)).unwrap().unwrap();
+    assert!(!listing.is_active);
+    assert_eq!(listing.seller, key(21));
+}
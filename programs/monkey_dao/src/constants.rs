@@ -1,7 +1,33 @@
 pub const ANCHOR_DISCRIMINATOR: usize = 8;
 pub const PLATFORM_FEE_BPS: u64 = 250; // 2.5% platform fee
+pub const MAX_FEE_BPS: u16 = 1000; // 10% cap on any fee in the fee schedule
+pub const MAX_FEE_TIERS: usize = 5;
+pub const MAX_FLASH_SALES: usize = 4;
+pub const MAX_CART_ITEMS: usize = 5;
 pub const MONK_DECIMALS: u8 = 9;
 pub const STAKING_REWARD_RATE: u64 = 100_000_000_000; // 100 MONK tokens per day (with 9 decimals)
 pub const MIN_POOL_TIMEOUT: i64 = 86400; // 24 hours
 pub const MAX_RATING: u8 = 5;
-pub const PURCHASE_REWARD_BPS: u64 = 1000; // 10% of purchase price in MONK tokens
\ No newline at end of file
+pub const PURCHASE_REWARD_BPS: u64 = 1000; // 10% of purchase price in MONK tokens
+pub const MAX_REFERRAL_MONK_BPS: u16 = 1000; // referrer bonus capped at 10% of the amount in MONK tokens
+pub const TIER_VALIDITY: i64 = 30 * 86400; // a loyalty tier lapses to Bronze unless refreshed within 30 days
+pub const GIFT_CLAIM_PERIOD: i64 = 30 * 86400; // unclaimed gifts may be returned to the sender after 30 days
+pub const MAX_GIFT_MESSAGE_LEN: usize = 200;
+pub const MAX_VERIFIERS: usize = 10;
+pub const COMPRESSED_COUPON_SYMBOL: &str = "COUPON";
+
+// Bits of `PlatformConfig::paused`, one per subsystem that can be stopped
+pub const PAUSE_LISTING: u8 = 1 << 0;
+pub const PAUSE_TRADING: u8 = 1 << 1;
+pub const PAUSE_POOLS: u8 = 1 << 2;
+pub const PAUSE_STAKING: u8 = 1 << 3;
+pub const PAUSE_REDEMPTION: u8 = 1 << 4;
+pub const PAUSE_REVIEWS: u8 = 1 << 5;
+pub const PAUSE_ALL: u8 = PAUSE_LISTING | PAUSE_TRADING | PAUSE_POOLS | PAUSE_STAKING | PAUSE_REDEMPTION | PAUSE_REVIEWS;
+
+// Loyalty tier thresholds: purchases made, MONK earned and coupons staked
+pub const SILVER_MIN_PURCHASES: u64 = 5;
+pub const SILVER_MIN_MONK: u64 = 100_000_000_000; // 100 MONK
+pub const GOLD_MIN_PURCHASES: u64 = 20;
+pub const GOLD_MIN_MONK: u64 = 1_000_000_000_000; // 1,000 MONK
+pub const GOLD_MIN_NFTS_STAKED: u64 = 1;
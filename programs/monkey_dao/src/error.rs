@@ -67,4 +67,139 @@ pub enum ErrorCode {
     
     #[msg("Only platform authority can perform this action")]
     NotPlatformAuthority,
-}
\ No newline at end of file
+    
+    #[msg("KYC document hash does not match the merchant's commitment")]
+    KycHashMismatch,
+    
+    #[msg("Account is not in the legacy layout")]
+    AccountAlreadyMigrated,
+    
+    #[msg("Signer is not an accredited verifier")]
+    NotAccreditedVerifier,
+    
+    #[msg("Merchant does not have enough valid attestations")]
+    InsufficientAttestations,
+    
+    #[msg("Invalid attestation threshold")]
+    InvalidThreshold,
+    
+    #[msg("Verifier is already registered")]
+    VerifierAlreadyRegistered,
+    
+    #[msg("Verifier registry is full")]
+    VerifierRegistryFull,
+    
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    
+    #[msg("Delegate is expired or not allowed to approve redemptions")]
+    DelegateNotAuthorized,
+    
+    #[msg("Invalid number of uses or redemption amount")]
+    InvalidUses,
+    
+    #[msg("Redemption amount exceeds the coupon's remaining balance")]
+    InsufficientUses,
+    
+    #[msg("Coupon has not expired yet")]
+    CouponNotExpired,
+    
+    #[msg("Refund claim window has closed")]
+    RefundWindowClosed,
+    
+    #[msg("Invalid refund policy")]
+    InvalidRefundPolicy,
+    
+    #[msg("Refund escrow cannot cover this refund")]
+    InsufficientRefundEscrow,
+    
+    #[msg("Coupon has not been fully used")]
+    CouponNotUsed,
+    
+    #[msg("Pool is still active")]
+    PoolStillActive,
+    
+    #[msg("Listing is still active")]
+    ListingStillActive,
+    
+    #[msg("Merkle proof does not match the compressed coupon")]
+    InvalidMerkleProof,
+    
+    #[msg("This part of the platform is paused")]
+    Paused,
+    
+    #[msg("Unknown pause flags")]
+    InvalidPauseFlags,
+    
+    #[msg("Invalid fee schedule")]
+    InvalidFeeSchedule,
+    
+    #[msg("Buyers cannot refer themselves")]
+    SelfReferral,
+    
+    #[msg("Invalid referral rewards")]
+    InvalidReferralRewards,
+    
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    
+    #[msg("Loyalty tier too low for this listing")]
+    TierTooLow,
+    
+    #[msg("Listing is in early access for Silver and Gold members")]
+    EarlyAccessOnly,
+    
+    #[msg("Pass the gate this listing points at")]
+    GateRequired,
+    
+    #[msg("Wallet is not on the allowlist")]
+    NotOnAllowlist,
+    
+    #[msg("Buyer holds no NFT of the gate's collection")]
+    CollectionNotHeld,
+    
+    #[msg("Wallet reached the gate's purchase limit")]
+    WalletLimitReached,
+    
+    #[msg("Listing is not on sale yet")]
+    ListingNotStarted,
+    
+    #[msg("Invalid listing schedule")]
+    InvalidSchedule,
+    
+    #[msg("Cart is empty, too large or its accounts do not match")]
+    InvalidCart,
+    
+    #[msg("A gift needs a recipient or a secret, and a message of at most 200 bytes")]
+    InvalidGift,
+    
+    #[msg("Only the gift's recipient, or whoever knows its secret, may claim it")]
+    NotGiftRecipient,
+    
+    #[msg("Gift has expired")]
+    GiftExpired,
+    
+    #[msg("Gift can still be claimed")]
+    GiftNotExpired,
+    
+    #[msg("Coupon is not in the custody this action needs")]
+    InvalidCustody,
+    
+    #[msg("Refund policy cannot change once a covered coupon has sold")]
+    RefundPolicyFrozen,
+    
+    #[msg("Refund escrow is locked until covered coupons expire and their claim window closes")]
+    RefundEscrowLocked,
+    
+    #[msg("Coupon mint has a transfer hook whose accounts were not passed")]
+    TransferHookAccountsMissing,
+    
+    #[msg("Pool still holds its coupon")]
+    PoolHoldsCoupon,
+    
+    #[msg("Pool can still settle, so its deposits are not refundable")]
+    PoolStillOpen,
+    
+    #[msg("Pool referral records and their referrers must be passed together")]
+    PoolReferralsMissing,
+}
@@ -0,0 +1,415 @@
+use anchor_lang::prelude::*;
+use crate::state::{Custody, FeeTier, FlashSale, LoyaltyTier};
+
+// ==================== MERCHANT EVENTS ====================
+
+#[event]
+pub struct MerchantRegistered {
+    pub merchant: Pubkey,
+    pub authority: Pubkey,
+    pub business_name: String,
+    pub business_type: String,
+    pub kyc_hash: [u8; 32],
+    pub registered_at: i64,
+}
+
+#[event]
+pub struct MerchantVerificationUpdated {
+    pub merchant: Pubkey,
+    pub valid_attestations: u8,
+    pub threshold: u8,
+    pub is_verified: bool,
+}
+
+#[event]
+pub struct MerchantKycUpdated {
+    pub merchant: Pubkey,
+    pub kyc_hash: [u8; 32],
+    pub kyc_uri: String,
+}
+
+#[event]
+pub struct DelegateAdded {
+    pub merchant: Pubkey,
+    pub delegate: Pubkey,
+    pub valid_until: i64,
+}
+
+#[event]
+pub struct DelegateRevoked {
+    pub merchant: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct AttestationIssued {
+    pub attestation: Pubkey,
+    pub merchant: Pubkey,
+    pub verifier: Pubkey,
+    pub kyc_hash: [u8; 32],
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct AttestationRevoked {
+    pub attestation: Pubkey,
+    pub merchant: Pubkey,
+    pub verifier: Pubkey,
+}
+
+// ==================== LISTING EVENTS ====================
+
+#[event]
+pub struct CouponListed {
+    pub listing: Pubkey,
+    pub merchant: Pubkey,
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub is_group_deal: bool,
+    pub expiry_date: i64,
+    pub remaining_uses: u64,
+}
+
+#[event]
+pub struct CouponRelisted {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct CouponDelisted {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct ExpiredListingReclaimed {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct CustodySynced {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub holder: Pubkey,
+    pub custody: Custody,
+}
+
+// ==================== TRADING EVENTS ====================
+
+#[event]
+pub struct CouponPurchased {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub platform_fee: u64,
+    pub monk_reward: u64,
+    pub purchased_at: i64,
+    pub seller_amount: u64,
+}
+
+#[event]
+pub struct CartCheckedOut {
+    pub buyer: Pubkey,
+    pub items: u8,
+    pub total_price: u64,
+    pub platform_fee: u64,
+    pub monk_reward: u64,
+}
+
+// ==================== POOL EVENTS ====================
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub listing: Pubkey,
+    pub initiator: Pubkey,
+    pub pool_size: u8,
+    pub price_per_person: u64,
+}
+
+#[event]
+pub struct PoolJoined {
+    pub pool: Pubkey,
+    pub participant: Pubkey,
+    pub amount: u64,
+    pub current_participants: u8,
+    pub pool_size: u8,
+}
+
+#[event]
+pub struct PoolCompleted {
+    pub pool: Pubkey,
+    pub listing: Pubkey,
+    pub total_deposited: u64,
+}
+
+#[event]
+pub struct PoolSettled {
+    pub pool: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PoolCouponClaimed {
+    pub pool: Pubkey,
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub initiator: Pubkey,
+}
+
+#[event]
+pub struct PoolCancelled {
+    pub pool: Pubkey,
+    pub listing: Pubkey,
+}
+
+#[event]
+pub struct PoolDepositRefunded {
+    pub pool: Pubkey,
+    pub participant: Pubkey,
+    pub amount: u64,
+    pub current_participants: u8,
+}
+
+// ==================== REVIEW EVENTS ====================
+
+#[event]
+pub struct ReviewAdded {
+    pub review: Pubkey,
+    pub listing: Pubkey,
+    pub reviewer: Pubkey,
+    pub rating: u8,
+    pub average_rating: u8,
+    pub total_reviews: u64,
+}
+
+// ==================== STAKING EVENTS ====================
+
+#[event]
+pub struct CouponStaked {
+    pub stake_account: Pubkey,
+    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    pub reward_weight_bps: u64,
+    pub staked_at: i64,
+}
+
+#[event]
+pub struct CouponUnstaked {
+    pub stake_account: Pubkey,
+    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    pub rewards: u64,
+    pub total_rewards_claimed: u64,
+}
+
+#[event]
+pub struct StakingRewardsClaimed {
+    pub stake_account: Pubkey,
+    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    pub rewards: u64,
+    pub total_rewards_claimed: u64,
+}
+
+// ==================== REDEMPTION EVENTS ====================
+
+#[event]
+pub struct CouponRedeemed {
+    pub receipt: Pubkey,
+    pub merchant: Pubkey,
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub redeemer: Pubkey,
+    pub approver: Pubkey,
+    pub index: u64,
+    pub amount: u64,
+    pub value: u64,
+    pub remaining: u64,
+    pub redeemed_at: i64,
+}
+
+#[event]
+pub struct ExpiredRefundClaimed {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub holder: Pubkey,
+    pub refund: u64,
+}
+
+// ==================== COMPRESSED COUPON EVENTS ====================
+
+#[event]
+pub struct CompressedCouponMinted {
+    pub coupon_tree: Pubkey,
+    pub asset_id: Pubkey,
+    pub recipient: Pubkey,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct CompressedCouponListed {
+    pub listing: Pubkey,
+    pub asset_id: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct CompressedCouponPurchased {
+    pub listing: Pubkey,
+    pub asset_id: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub platform_fee: u64,
+    pub seller_amount: u64,
+}
+
+#[event]
+pub struct CompressedCouponDelisted {
+    pub listing: Pubkey,
+    pub asset_id: Pubkey,
+    pub seller: Pubkey,
+}
+
+// ==================== PLATFORM EVENTS ====================
+
+#[event]
+pub struct PauseUpdated {
+    pub paused: u8,
+    pub updated_by: Pubkey,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct FeeScheduleUpdated {
+    pub primary_tiers: Vec<FeeTier>,
+    pub secondary_fee_bps: u16,
+    pub staker_discount_bps: u16,
+    pub buyer_pays_fee: bool,
+}
+
+// ==================== REFERRAL EVENTS ====================
+
+#[event]
+pub struct ReferrerRegistered {
+    pub referrer: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct ReferralRewarded {
+    pub referrer: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub fee_share: u64,
+    pub monk_reward: u64,
+}
+
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub referrer: Pubkey,
+    pub authority: Pubkey,
+    pub lamports: u64,
+    pub monk: u64,
+}
+
+#[event]
+pub struct ReferralRewardsUpdated {
+    pub fee_share_bps: u16,
+    pub monk_bps: u16,
+}
+
+// ==================== LOYALTY EVENTS ====================
+
+#[event]
+pub struct TierRefreshed {
+    pub user: Pubkey,
+    pub previous: LoyaltyTier,
+    pub tier: LoyaltyTier,
+}
+
+#[event]
+pub struct ListingAccessUpdated {
+    pub listing: Pubkey,
+    pub min_tier: LoyaltyTier,
+    pub early_access_until: i64,
+}
+
+// ==================== GATE EVENTS ====================
+
+#[event]
+pub struct GateUpdated {
+    pub gate: Pubkey,
+    pub merchant: Pubkey,
+    pub collection: Option<Pubkey>,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub max_per_wallet: u16,
+}
+
+#[event]
+pub struct ListingGateUpdated {
+    pub listing: Pubkey,
+    pub gate: Option<Pubkey>,
+}
+
+// ==================== SCHEDULE EVENTS ====================
+
+#[event]
+pub struct ListingScheduled {
+    pub listing: Pubkey,
+    pub start_time: i64,
+    pub flash_sales: Vec<FlashSale>,
+}
+
+// ==================== GIFT EVENTS ====================
+
+#[event]
+pub struct CouponGifted {
+    pub listing: Pubkey,
+    pub nft_mint: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub message: String,
+}
+
+#[event]
+pub struct GiftSent {
+    pub gift: Pubkey,
+    pub nft_mint: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Option<Pubkey>,
+    pub message: String,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct GiftClaimed {
+    pub gift: Pubkey,
+    pub nft_mint: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+}
+
+#[event]
+pub struct GiftReturned {
+    pub gift: Pubkey,
+    pub nft_mint: Pubkey,
+    pub sender: Pubkey,
+}
@@ -0,0 +1,296 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create},
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, MintTo, mint_to, CloseAccount, close_account},
+};
+use crate::state::*;
+use crate::constants::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{CartCheckedOut, CouponPurchased};
+use crate::instructions::fee::{fee_schedule_or_flat, sale_amounts, SaleAmounts};
+use crate::instructions::loyalty::{buyer_tier, check_listing_access};
+use crate::instructions::schedule::scheduled_price;
+use crate::instructions::trading::purchase_reward;
+use crate::instructions::transfer_hook::transfer_checked_with_hook;
+
+/// Accounts per cart item in `remaining_accounts`: listing, merchant, NFT
+/// mint, vault, seller, buyer's token account and the listing's schedule.
+pub const CART_ITEM_ACCOUNTS: usize = 7;
+
+#[derive(Accounts)]
+pub struct CheckoutCart<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_TRADING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    /// CHECK: Platform's `FeeSchedule`, read only if it exists; until one is set every sale pays `config.platform_fee_bps`
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump,
+    )]
+    pub fee_schedule: UncheckedAccount<'info>,
+    
+    /// CHECK: Platform wallet to receive fees
+    #[account(
+        mut,
+        constraint = platform_wallet.key() == config.platform_wallet @ ErrorCode::Unauthorized
+    )]
+    pub platform_wallet: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = monk_mint.key() == config.monk_mint @ ErrorCode::Unauthorized
+    )]
+    pub monk_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = monk_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = monk_token_program,
+    )]
+    pub buyer_monk_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = ANCHOR_DISCRIMINATOR + UserStats::INIT_SPACE,
+        seeds = [b"user_stats", buyer.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+    
+    /// CHECK: Buyer's `Loyalty`, read only if it exists; buyers without one are Bronze
+    #[account(
+        seeds = [b"loyalty", buyer.key().as_ref()],
+        bump,
+    )]
+    pub loyalty: UncheckedAccount<'info>,
+    
+    /// Token program of every coupon in the cart
+    pub token_program: Interface<'info, TokenInterface>,
+    pub monk_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: `CART_ITEM_ACCOUNTS` accounts per item, in the order listed there,
+    // then the transfer hook accounts of every hooked coupon in the cart
+}
+
+/// Buys every listing in the cart, or none of them. Each seller is paid as in
+/// `buy_nft`, while the platform fee and the MONK reward are paid once for the
+/// whole cart. Gated listings need `buy_nft`, and referrers are not credited.
+pub fn checkout_cart<'info>(ctx: Context<'_, '_, 'info, 'info, CheckoutCart<'info>>, items: u8) -> Result<()> {
+    let item_accounts = (items as usize) * CART_ITEM_ACCOUNTS;
+    require!(
+        items > 0
+            && (items as usize) <= MAX_CART_ITEMS
+            && ctx.remaining_accounts.len() >= item_accounts,
+        ErrorCode::InvalidCart
+    );
+    let (items, hook_accounts) = ctx.remaining_accounts.split_at(item_accounts);
+    let clock = Clock::get()?;
+    let buyer = ctx.accounts.buyer.key();
+    let token_program = ctx.accounts.token_program.key();
+    let tier = buyer_tier(&ctx.accounts.loyalty, clock.unix_timestamp);
+    let is_staker = ctx.accounts.user_stats.nfts_staked > 0;
+    
+    let fee_schedule = fee_schedule_or_flat(&ctx.accounts.fee_schedule, &ctx.accounts.config)?;
+    let mut total_price: u64 = 0;
+    let mut total_fee: u64 = 0;
+    let mut monk_reward: u64 = 0;
+    
+    for item in items.chunks(CART_ITEM_ACCOUNTS) {
+        let [listing_info, merchant_info, mint_info, vault, seller, buyer_token_account, schedule] = item else {
+            return err!(ErrorCode::InvalidCart);
+        };
+        
+        let mut listing = Account::<Listing>::try_from(listing_info)?;
+        let mut merchant = Account::<Merchant>::try_from(merchant_info)?;
+        let nft_mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+        // An item bought earlier in the cart is no longer active
+        require!(listing.is_active, ErrorCode::ListingNotActive);
+        require_keys_eq!(listing.nft_mint, nft_mint.key(), ErrorCode::InvalidCart);
+        require_keys_eq!(merchant.key(), listing.merchant, ErrorCode::Unauthorized);
+        require_keys_eq!(seller.key(), listing.seller, ErrorCode::Unauthorized);
+        require_keys_eq!(*nft_mint.to_account_info().owner, token_program, ErrorCode::InvalidCart);
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address_with_program_id(&listing.key(), &nft_mint.key(), &token_program),
+            ErrorCode::InvalidCart
+        );
+        require_keys_eq!(
+            buyer_token_account.key(),
+            get_associated_token_address_with_program_id(&buyer, &nft_mint.key(), &token_program),
+            ErrorCode::InvalidCart
+        );
+        require_keys_eq!(
+            schedule.key(),
+            Pubkey::find_program_address(&[b"schedule", listing.key().as_ref()], &crate::ID).0,
+            ErrorCode::InvalidCart
+        );
+        
+        // Same checks as `buy_nft`
+        require!(!listing.is_used, ErrorCode::CouponAlreadyUsed);
+        require!(listing.expiry_date > clock.unix_timestamp, ErrorCode::CouponExpired);
+        check_listing_access(&listing, tier, clock.unix_timestamp)?;
+        require!(listing.gate.is_none(), ErrorCode::GateRequired);
+        require!(listing.custody == Custody::ListingVault, ErrorCode::InvalidCustody);
+        let price = scheduled_price(schedule, &listing, clock.unix_timestamp)?;
+        let is_primary = listing.seller == merchant.authority;
+        let SaleAmounts { platform_fee, seller_amount } = sale_amounts(
+            &fee_schedule,
+            price,
+            is_primary,
+            merchant.total_sales_volume,
+            is_staker,
+            tier,
+        )?;
+        let item_reward = purchase_reward(price, tier)?;
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: seller.clone(),
+                },
+            ),
+            seller_amount,
+        )?;
+        
+        create_idempotent(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.buyer.to_account_info(),
+                associated_token: buyer_token_account.clone(),
+                authority: ctx.accounts.buyer.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+        
+        let seeds = &[
+            b"listing",
+            listing.nft_mint.as_ref(),
+            &[listing.bump],
+        ];
+        let signer = &[&seeds[..]];
+        transfer_checked_with_hook(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.clone(),
+                    mint: mint_info.clone(),
+                    to: buyer_token_account.clone(),
+                    authority: listing_info.clone(),
+                },
+                signer,
+            )
+            .with_remaining_accounts(hook_accounts.to_vec()),
+            1,
+            nft_mint.decimals,
+        )?;
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                destination: seller.clone(),
+                authority: listing_info.clone(),
+            },
+            signer,
+        ))?;
+        
+        listing.is_active = false;
+        listing.holder = buyer;
+        listing.custody = Custody::Wallet;
+        listing.min_tier = LoyaltyTier::Bronze;
+        listing.early_access_until = 0;
+        listing.total_sales = listing.total_sales.checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        // Written back now, so a listing or merchant that comes up again in
+        // the cart is read with this purchase applied
+        listing.exit(&crate::ID)?;
+        if is_primary {
+            merchant.total_sales_volume = merchant.total_sales_volume.checked_add(price)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            merchant.exit(&crate::ID)?;
+        }
+        
+        total_price = total_price.checked_add(price).ok_or(ErrorCode::ArithmeticOverflow)?;
+        total_fee = total_fee.checked_add(platform_fee).ok_or(ErrorCode::ArithmeticOverflow)?;
+        monk_reward = monk_reward.checked_add(item_reward).ok_or(ErrorCode::ArithmeticOverflow)?;
+        
+        emit!(CouponPurchased {
+            listing: listing.key(),
+            nft_mint: listing.nft_mint,
+            seller: seller.key(),
+            buyer,
+            price,
+            platform_fee,
+            seller_amount,
+            monk_reward: item_reward,
+            purchased_at: clock.unix_timestamp,
+        });
+    }
+    
+    // One platform fee transfer and one MONK mint for the whole cart
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.platform_wallet.to_account_info(),
+            },
+        ),
+        total_fee,
+    )?;
+    
+    let binding = [ctx.accounts.config.bump];
+    let config_signer = &[&[b"config".as_ref(), &binding][..]];
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.monk_token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.monk_mint.to_account_info(),
+                to: ctx.accounts.buyer_monk_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            config_signer,
+        ),
+        monk_reward,
+    )?;
+    
+    let item_count = (items.len() / CART_ITEM_ACCOUNTS) as u64;
+    let user_stats = &mut ctx.accounts.user_stats;
+    if user_stats.user == Pubkey::default() {
+        user_stats.user = buyer;
+        user_stats.bump = ctx.bumps.user_stats;
+    }
+    user_stats.total_purchases = user_stats.total_purchases.checked_add(item_count)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    user_stats.total_monk_earned = user_stats.total_monk_earned.checked_add(monk_reward)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(CartCheckedOut {
+        buyer,
+        items: item_count as u8,
+        total_price,
+        platform_fee: total_fee,
+        monk_reward,
+    });
+    
+    msg!("Cart checked out: {} coupons for {} lamports", item_count, total_price);
+    msg!("Platform fee: {} lamports", total_fee);
+    msg!("MONK tokens rewarded: {}", monk_reward);
+    Ok(())
+}
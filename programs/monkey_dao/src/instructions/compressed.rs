@@ -0,0 +1,960 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenInterface, TokenAccount, MintTo, mint_to},
+};
+use mpl_bubblegum::{
+    instructions::{
+        BurnCpi, BurnCpiAccounts, BurnInstructionArgs,
+        CreateTreeConfigCpi, CreateTreeConfigCpiAccounts, CreateTreeConfigInstructionArgs,
+        MintV1Cpi, MintV1CpiAccounts, MintV1InstructionArgs,
+        TransferCpi, TransferCpiAccounts, TransferInstructionArgs,
+    },
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    types::{MetadataArgs, TokenProgramVersion, TokenStandard},
+    utils::get_asset_id,
+};
+use crate::state::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::{CompressedCouponMinted, CompressedCouponListed, CompressedCouponPurchased, CompressedCouponDelisted};
+use crate::instructions::redemption::{authorize_redemption, record_redemption};
+use crate::instructions::fee::{fee_schedule_or_flat, sale_amounts, SaleAmounts};
+use crate::instructions::referral::reward_referrer;
+use crate::instructions::loyalty::buyer_tier;
+use crate::instructions::trading::purchase_reward;
+use crate::instructions::verifier::count_valid_attestations;
+
+#[derive(Accounts)]
+pub struct CreateCouponTree<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + CouponTree::INIT_SPACE,
+        seeds = [b"coupon_tree", merkle_tree.key().as_ref()],
+        bump
+    )]
+    pub coupon_tree: Account<'info, CouponTree>,
+    
+    /// CHECK: Bubblegum tree config, created by the CPI
+    #[account(
+        mut,
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key(),
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Pre-allocated concurrent Merkle tree, owned by the compression program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the merchant's `Attestation`s
+}
+
+#[derive(Accounts)]
+pub struct MintCompressedCoupon<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_LISTING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    #[account(
+        mut,
+        seeds = [b"coupon_tree", merkle_tree.key().as_ref()],
+        bump = coupon_tree.bump,
+        constraint = coupon_tree.merchant == merchant.key() @ ErrorCode::Unauthorized,
+    )]
+    pub coupon_tree: Account<'info, CouponTree>,
+    
+    /// CHECK: Receives the compressed coupon
+    pub recipient: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum tree config
+    #[account(
+        mut,
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key(),
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Concurrent Merkle tree, checked by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the merchant's `Attestation`s
+}
+
+#[derive(Accounts)]
+#[instruction(price: u64, leaf: LeafArgs)]
+pub struct ListCompressedCoupon<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_LISTING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        seeds = [b"coupon_tree", merkle_tree.key().as_ref()],
+        bump = coupon_tree.bump,
+    )]
+    pub coupon_tree: Account<'info, CouponTree>,
+    
+    #[account(
+        init,
+        payer = seller,
+        space = ANCHOR_DISCRIMINATOR + CompressedListing::INIT_SPACE,
+        seeds = [b"cnft_listing", merkle_tree.key().as_ref(), leaf.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, CompressedListing>,
+    
+    /// CHECK: Bubblegum tree config
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key(),
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Concurrent Merkle tree, checked by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: Merkle proof path for the leaf
+}
+
+#[derive(Accounts)]
+pub struct BuyCompressedCoupon<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"cnft_listing", merkle_tree.key().as_ref(), listing.nonce.to_le_bytes().as_ref()],
+        bump = listing.bump,
+        constraint = listing.coupon_tree == coupon_tree.key() @ ErrorCode::Unauthorized,
+    )]
+    pub listing: Account<'info, CompressedListing>,
+    
+    /// CHECK: Seller account to receive payment and the listing rent
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ ErrorCode::Unauthorized
+    )]
+    pub seller: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"coupon_tree", merkle_tree.key().as_ref()],
+        bump = coupon_tree.bump,
+    )]
+    pub coupon_tree: Account<'info, CouponTree>,
+    
+    #[account(
+        mut,
+        address = coupon_tree.merchant @ ErrorCode::Unauthorized,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_TRADING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    /// CHECK: Platform's `FeeSchedule`, read only if it exists; until one is set every sale pays `config.platform_fee_bps`
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump,
+    )]
+    pub fee_schedule: UncheckedAccount<'info>,
+    
+    // MONK Token accounts
+    #[account(
+        mut,
+        constraint = monk_mint.key() == config.monk_mint @ ErrorCode::Unauthorized
+    )]
+    pub monk_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = monk_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = monk_token_program,
+    )]
+    pub buyer_monk_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = ANCHOR_DISCRIMINATOR + UserStats::INIT_SPACE,
+        seeds = [b"user_stats", buyer.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+    
+    /// CHECK: Buyer's `Loyalty`, read only if it exists; buyers without one are Bronze
+    #[account(
+        seeds = [b"loyalty", buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_loyalty: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"referrer", referrer.authority.as_ref()],
+        bump = referrer.bump,
+        constraint = referrer.authority != buyer.key() @ ErrorCode::SelfReferral,
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// CHECK: Platform wallet to receive fees
+    #[account(
+        mut,
+        constraint = platform_wallet.key() == config.platform_wallet @ ErrorCode::Unauthorized
+    )]
+    pub platform_wallet: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum tree config
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key(),
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Concurrent Merkle tree, checked by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    pub monk_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: Merkle proof path for the leaf
+}
+
+#[derive(Accounts)]
+pub struct DelistCompressedCoupon<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"cnft_listing", merkle_tree.key().as_ref(), listing.nonce.to_le_bytes().as_ref()],
+        bump = listing.bump,
+        constraint = listing.seller == seller.key() @ ErrorCode::Unauthorized,
+    )]
+    pub listing: Account<'info, CompressedListing>,
+    
+    /// CHECK: Bubblegum tree config
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key(),
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Concurrent Merkle tree, checked by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: Merkle proof path for the leaf
+}
+
+#[derive(Accounts)]
+#[instruction(signature: Vec<u8>, leaf: LeafArgs)]
+pub struct RedeemCompressedCoupon<'info> {
+    #[account(mut)]
+    pub redeemer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_REDEMPTION) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant.authority.as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    /// Merchant authority or one of its redemption delegates
+    pub approver: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegate", merchant.key().as_ref(), approver.key().as_ref()],
+        bump = merchant_delegate.bump,
+    )]
+    pub merchant_delegate: Option<Account<'info, MerchantDelegate>>,
+    
+    #[account(
+        mut,
+        seeds = [b"coupon_tree", merkle_tree.key().as_ref()],
+        bump = coupon_tree.bump,
+        constraint = coupon_tree.merchant == merchant.key() @ ErrorCode::Unauthorized,
+    )]
+    pub coupon_tree: Account<'info, CouponTree>,
+    
+    /// One per leaf, which is burned by its only redemption
+    #[account(
+        init,
+        payer = redeemer,
+        space = ANCHOR_DISCRIMINATOR + RedemptionReceipt::INIT_SPACE,
+        seeds = [b"receipt", coupon_tree.key().as_ref(), leaf.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, RedemptionReceipt>,
+    
+    /// CHECK: Bubblegum tree config
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key(),
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Concurrent Merkle tree, checked by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: Merkle proof path for the leaf
+}
+
+fn proof_nodes(remaining_accounts: &[AccountInfo]) -> Vec<[u8; 32]> {
+    remaining_accounts.iter().map(|node| node.key.to_bytes()).collect()
+}
+
+/// Moves a compressed coupon leaf from `leaf_owner` to `new_leaf_owner`.
+/// `signer_seeds` is set when the current owner is one of our PDAs.
+fn transfer_leaf<'info>(
+    bubblegum_program: &AccountInfo<'info>,
+    tree_config: &AccountInfo<'info>,
+    leaf_owner: &AccountInfo<'info>,
+    new_leaf_owner: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    log_wrapper: &AccountInfo<'info>,
+    compression_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    proof: &[AccountInfo<'info>],
+    leaf: LeafArgs,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let proof_accounts: Vec<(&AccountInfo<'info>, bool, bool)> =
+        proof.iter().map(|node| (node, false, false)).collect();
+    
+    TransferCpi::new(
+        bubblegum_program,
+        TransferCpiAccounts {
+            tree_config,
+            leaf_owner: (leaf_owner, true),
+            leaf_delegate: (leaf_owner, false),
+            new_leaf_owner,
+            merkle_tree,
+            log_wrapper,
+            compression_program,
+            system_program,
+        },
+        TransferInstructionArgs {
+            root: leaf.root,
+            data_hash: leaf.data_hash,
+            creator_hash: leaf.creator_hash,
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+    )
+    .invoke_signed_with_remaining_accounts(signer_seeds, &proof_accounts)?;
+    
+    Ok(())
+}
+
+pub fn create_coupon_tree(
+    ctx: Context<CreateCouponTree>,
+    max_depth: u32,
+    max_buffer_size: u32,
+    original_price: u64,
+    expiry_date: i64,
+) -> Result<()> {
+    require!(original_price > 0, ErrorCode::InvalidPrice);
+    let clock = Clock::get()?;
+    require!(expiry_date > clock.unix_timestamp, ErrorCode::CouponExpired);
+    
+    // Merchant must hold M-of-N unexpired attestations, as when listing
+    let valid_attestations = count_valid_attestations(
+        &ctx.accounts.verifier_registry,
+        &ctx.accounts.merchant,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+    require!(
+        valid_attestations >= ctx.accounts.verifier_registry.threshold,
+        ErrorCode::MerchantNotVerified
+    );
+    
+    let coupon_tree = &mut ctx.accounts.coupon_tree;
+    coupon_tree.merchant = ctx.accounts.merchant.key();
+    coupon_tree.merkle_tree = ctx.accounts.merkle_tree.key();
+    coupon_tree.original_price = original_price;
+    coupon_tree.expiry_date = expiry_date;
+    coupon_tree.num_minted = 0;
+    coupon_tree.num_redeemed = 0;
+    coupon_tree.created_at = clock.unix_timestamp;
+    coupon_tree.bump = ctx.bumps.coupon_tree;
+    
+    // The coupon tree PDA is the tree creator, so only this program can mint into it
+    let merkle_tree_key = ctx.accounts.merkle_tree.key();
+    let seeds = &[
+        b"coupon_tree",
+        merkle_tree_key.as_ref(),
+        &[coupon_tree.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    CreateTreeConfigCpi::new(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        CreateTreeConfigCpiAccounts {
+            tree_config: &ctx.accounts.tree_config.to_account_info(),
+            merkle_tree: &ctx.accounts.merkle_tree.to_account_info(),
+            payer: &ctx.accounts.authority.to_account_info(),
+            tree_creator: &coupon_tree.to_account_info(),
+            log_wrapper: &ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: &ctx.accounts.compression_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        },
+        CreateTreeConfigInstructionArgs {
+            max_depth,
+            max_buffer_size,
+            public: Some(false),
+        },
+    )
+    .invoke_signed(signer)?;
+    
+    msg!("Coupon tree created: depth {}, buffer {}", max_depth, max_buffer_size);
+    Ok(())
+}
+
+pub fn mint_compressed_coupon(
+    ctx: Context<MintCompressedCoupon>,
+    name: String,
+    uri: String,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    // Attestations expire, so every mint checks them again as listing does
+    let valid_attestations = count_valid_attestations(
+        &ctx.accounts.verifier_registry,
+        &ctx.accounts.merchant,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+    require!(
+        valid_attestations >= ctx.accounts.verifier_registry.threshold,
+        ErrorCode::MerchantNotVerified
+    );
+    
+    let coupon_tree = &mut ctx.accounts.coupon_tree;
+    require!(coupon_tree.expiry_date > clock.unix_timestamp, ErrorCode::CouponExpired);
+    
+    let merkle_tree_key = ctx.accounts.merkle_tree.key();
+    let seeds = &[
+        b"coupon_tree",
+        merkle_tree_key.as_ref(),
+        &[coupon_tree.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let recipient = ctx.accounts.recipient.to_account_info();
+    MintV1Cpi::new(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        MintV1CpiAccounts {
+            tree_config: &ctx.accounts.tree_config.to_account_info(),
+            leaf_owner: &recipient,
+            leaf_delegate: &recipient,
+            merkle_tree: &ctx.accounts.merkle_tree.to_account_info(),
+            payer: &ctx.accounts.authority.to_account_info(),
+            tree_creator_or_delegate: &coupon_tree.to_account_info(),
+            log_wrapper: &ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: &ctx.accounts.compression_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        },
+        MintV1InstructionArgs {
+            metadata: MetadataArgs {
+                name,
+                symbol: COMPRESSED_COUPON_SYMBOL.to_string(),
+                uri,
+                seller_fee_basis_points: 0,
+                primary_sale_happened: false,
+                is_mutable: false,
+                edition_nonce: None,
+                token_standard: Some(TokenStandard::NonFungible),
+                collection: None,
+                uses: None,
+                token_program_version: TokenProgramVersion::Original,
+                creators: vec![],
+            },
+        },
+    )
+    .invoke_signed(signer)?;
+    
+    // Our PDA is the only minter, so our count tracks the Bubblegum nonce
+    let asset_id = get_asset_id(&merkle_tree_key, coupon_tree.num_minted);
+    coupon_tree.num_minted = coupon_tree.num_minted.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(CompressedCouponMinted {
+        coupon_tree: coupon_tree.key(),
+        asset_id,
+        recipient: ctx.accounts.recipient.key(),
+        nonce: coupon_tree.num_minted - 1,
+    });
+    
+    msg!("Compressed coupon minted: {}", asset_id);
+    Ok(())
+}
+
+pub fn list_compressed_coupon<'info>(
+    ctx: Context<'_, '_, '_, 'info, ListCompressedCoupon<'info>>,
+    price: u64,
+    leaf: LeafArgs,
+) -> Result<()> {
+    require!(price > 0, ErrorCode::InvalidPrice);
+    let clock = Clock::get()?;
+    require!(
+        ctx.accounts.coupon_tree.expiry_date > clock.unix_timestamp,
+        ErrorCode::CouponExpired
+    );
+    
+    let merkle_tree_key = ctx.accounts.merkle_tree.key();
+    let listing = &mut ctx.accounts.listing;
+    listing.coupon_tree = ctx.accounts.coupon_tree.key();
+    listing.asset_id = get_asset_id(&merkle_tree_key, leaf.nonce);
+    listing.nonce = leaf.nonce;
+    listing.seller = ctx.accounts.seller.key();
+    listing.price = price;
+    listing.created_at = clock.unix_timestamp;
+    listing.bump = ctx.bumps.listing;
+    
+    // Escrow the leaf with the listing PDA
+    transfer_leaf(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        &ctx.accounts.tree_config.to_account_info(),
+        &ctx.accounts.seller.to_account_info(),
+        &listing.to_account_info(),
+        &ctx.accounts.merkle_tree.to_account_info(),
+        &ctx.accounts.log_wrapper.to_account_info(),
+        &ctx.accounts.compression_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+        leaf,
+        &[],
+    )?;
+    
+    emit!(CompressedCouponListed {
+        listing: listing.key(),
+        asset_id: listing.asset_id,
+        seller: listing.seller,
+        price,
+    });
+    
+    msg!("Compressed coupon listed at price: {} lamports", price);
+    Ok(())
+}
+
+pub fn buy_compressed_coupon<'info>(
+    ctx: Context<'_, '_, '_, 'info, BuyCompressedCoupon<'info>>,
+    leaf: LeafArgs,
+) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    let clock = Clock::get()?;
+    
+    require!(leaf.nonce == listing.nonce, ErrorCode::InvalidMerkleProof);
+    require!(
+        ctx.accounts.coupon_tree.expiry_date > clock.unix_timestamp,
+        ErrorCode::CouponExpired
+    );
+    
+    let price = listing.price;
+    let is_primary = listing.seller == ctx.accounts.merchant.authority;
+    let tier = buyer_tier(&ctx.accounts.buyer_loyalty, clock.unix_timestamp);
+    let fee_schedule = fee_schedule_or_flat(&ctx.accounts.fee_schedule, &ctx.accounts.config)?;
+    let SaleAmounts { platform_fee, seller_amount } = sale_amounts(
+        &fee_schedule,
+        price,
+        is_primary,
+        ctx.accounts.merchant.total_sales_volume,
+        ctx.accounts.user_stats.nfts_staked > 0,
+        tier,
+    )?;
+    // A referrer takes its share out of the platform fee
+    let referral_fee = match ctx.accounts.referrer.as_mut() {
+        Some(referrer) => reward_referrer(
+            &ctx.accounts.config,
+            referrer,
+            ctx.accounts.buyer.key(),
+            price,
+            platform_fee,
+        )?,
+        None => 0,
+    };
+    
+    // Transfer SOL to seller
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.seller.to_account_info(),
+            },
+        ),
+        seller_amount,
+    )?;
+    
+    // Transfer platform fee
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.platform_wallet.to_account_info(),
+            },
+        ),
+        platform_fee - referral_fee,
+    )?;
+    
+    if let Some(referrer) = &ctx.accounts.referrer {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: referrer.to_account_info(),
+                },
+            ),
+            referral_fee,
+        )?;
+    }
+    
+    // Release the leaf from the listing PDA to the buyer
+    let merkle_tree_key = ctx.accounts.merkle_tree.key();
+    let nonce_bytes = listing.nonce.to_le_bytes();
+    let seeds = &[
+        b"cnft_listing",
+        merkle_tree_key.as_ref(),
+        nonce_bytes.as_ref(),
+        &[listing.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    transfer_leaf(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        &ctx.accounts.tree_config.to_account_info(),
+        &listing.to_account_info(),
+        &ctx.accounts.buyer.to_account_info(),
+        &ctx.accounts.merkle_tree.to_account_info(),
+        &ctx.accounts.log_wrapper.to_account_info(),
+        &ctx.accounts.compression_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+        leaf,
+        signer,
+    )?;
+    
+    // MONK reward, as for any other purchase
+    let monk_reward = purchase_reward(price, tier)?;
+    let config_bump = [ctx.accounts.config.bump];
+    let config_signer = &[&[b"config".as_ref(), &config_bump][..]];
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.monk_token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.monk_mint.to_account_info(),
+                to: ctx.accounts.buyer_monk_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            config_signer,
+        ),
+        monk_reward,
+    )?;
+    
+    if is_primary {
+        let merchant = &mut ctx.accounts.merchant;
+        merchant.total_sales_volume = merchant.total_sales_volume.checked_add(price)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    
+    let user_stats = &mut ctx.accounts.user_stats;
+    if user_stats.user == Pubkey::default() {
+        user_stats.user = ctx.accounts.buyer.key();
+        user_stats.bump = ctx.bumps.user_stats;
+    }
+    user_stats.total_purchases = user_stats.total_purchases.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    user_stats.total_monk_earned = user_stats.total_monk_earned.checked_add(monk_reward)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(CompressedCouponPurchased {
+        listing: listing.key(),
+        asset_id: listing.asset_id,
+        seller: listing.seller,
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        platform_fee,
+        seller_amount,
+    });
+    
+    msg!("Compressed coupon purchased: {}", listing.asset_id);
+    msg!("Price paid: {} lamports", price);
+    msg!("Platform fee: {} lamports", platform_fee);
+    Ok(())
+}
+
+pub fn delist_compressed_coupon<'info>(
+    ctx: Context<'_, '_, '_, 'info, DelistCompressedCoupon<'info>>,
+    leaf: LeafArgs,
+) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(leaf.nonce == listing.nonce, ErrorCode::InvalidMerkleProof);
+    
+    let merkle_tree_key = ctx.accounts.merkle_tree.key();
+    let nonce_bytes = listing.nonce.to_le_bytes();
+    let seeds = &[
+        b"cnft_listing",
+        merkle_tree_key.as_ref(),
+        nonce_bytes.as_ref(),
+        &[listing.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    transfer_leaf(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        &ctx.accounts.tree_config.to_account_info(),
+        &listing.to_account_info(),
+        &ctx.accounts.seller.to_account_info(),
+        &ctx.accounts.merkle_tree.to_account_info(),
+        &ctx.accounts.log_wrapper.to_account_info(),
+        &ctx.accounts.compression_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+        leaf,
+        signer,
+    )?;
+    
+    emit!(CompressedCouponDelisted {
+        listing: listing.key(),
+        asset_id: listing.asset_id,
+        seller: listing.seller,
+    });
+    
+    msg!("Compressed coupon delisted: {}", listing.asset_id);
+    Ok(())
+}
+
+pub fn redeem_compressed_coupon<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemCompressedCoupon<'info>>,
+    signature: Vec<u8>,
+    leaf: LeafArgs,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    // Verify merchant authority or an active delegate
+    let approver = ctx.accounts.approver.key();
+    authorize_redemption(
+        &ctx.accounts.merchant,
+        approver,
+        ctx.accounts.merchant_delegate.as_mut(),
+        clock.unix_timestamp,
+    )?;
+    
+    require!(
+        ctx.accounts.coupon_tree.expiry_date > clock.unix_timestamp,
+        ErrorCode::CouponExpired
+    );
+    
+    // Simple length check for signature (replace with actual verification if required)
+    require!(signature.len() == 64, ErrorCode::InvalidSignature);
+    
+    // Check the redeemer's leaf against the supplied root before burning,
+    // Bubblegum then checks the root against the tree's changelog
+    let merkle_tree_key = ctx.accounts.merkle_tree.key();
+    let redeemer_key = ctx.accounts.redeemer.key();
+    let asset_id = get_asset_id(&merkle_tree_key, leaf.nonce);
+    let leaf_hash = leaf.leaf_hash(asset_id, redeemer_key, redeemer_key);
+    require!(
+        verify_merkle_proof(leaf.root, leaf_hash, leaf.index, &proof_nodes(ctx.remaining_accounts)),
+        ErrorCode::InvalidMerkleProof
+    );
+    
+    let proof_accounts: Vec<(&AccountInfo, bool, bool)> =
+        ctx.remaining_accounts.iter().map(|node| (node, false, false)).collect();
+    let redeemer = ctx.accounts.redeemer.to_account_info();
+    BurnCpi::new(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        BurnCpiAccounts {
+            tree_config: &ctx.accounts.tree_config.to_account_info(),
+            leaf_owner: (&redeemer, true),
+            leaf_delegate: (&redeemer, false),
+            merkle_tree: &ctx.accounts.merkle_tree.to_account_info(),
+            log_wrapper: &ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: &ctx.accounts.compression_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        },
+        BurnInstructionArgs {
+            root: leaf.root,
+            data_hash: leaf.data_hash,
+            creator_hash: leaf.creator_hash,
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+    )
+    .invoke_with_remaining_accounts(&proof_accounts)?;
+    
+    let coupon_tree = &mut ctx.accounts.coupon_tree;
+    coupon_tree.num_redeemed = coupon_tree.num_redeemed.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    // Record the redemption
+    let record = RedemptionReceipt {
+        merchant: ctx.accounts.merchant.key(),
+        listing: coupon_tree.key(),
+        nft_mint: asset_id,
+        redeemer: redeemer_key,
+        approver,
+        index: ctx.accounts.merchant.total_redemptions,
+        amount: 1,
+        value: coupon_tree.original_price,
+        redeemed_at: clock.unix_timestamp,
+        bump: ctx.bumps.receipt,
+    };
+    record_redemption(
+        &mut ctx.accounts.merchant,
+        &mut ctx.accounts.receipt,
+        record,
+        0,
+    )?;
+    
+    msg!("Compressed coupon redeemed and burned: {}", asset_id);
+    msg!("Approved by: {}", approver);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{DelegateAdded, DelegateRevoked};
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct AddDelegate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + MerchantDelegate::INIT_SPACE,
+        seeds = [b"delegate", merchant.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub merchant_delegate: Account<'info, MerchantDelegate>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"delegate", merchant.key().as_ref(), merchant_delegate.delegate.as_ref()],
+        bump = merchant_delegate.bump,
+    )]
+    pub merchant_delegate: Account<'info, MerchantDelegate>,
+}
+
+pub fn add_delegate(
+    ctx: Context<AddDelegate>,
+    delegate: Pubkey,
+    valid_until: i64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(valid_until > clock.unix_timestamp, ErrorCode::InvalidExpiry);
+    
+    let merchant_delegate = &mut ctx.accounts.merchant_delegate;
+    merchant_delegate.merchant = ctx.accounts.merchant.key();
+    merchant_delegate.delegate = delegate;
+    merchant_delegate.can_redeem = true;
+    merchant_delegate.valid_until = valid_until;
+    merchant_delegate.total_redemptions = 0;
+    merchant_delegate.created_at = clock.unix_timestamp;
+    merchant_delegate.bump = ctx.bumps.merchant_delegate;
+    
+    emit!(DelegateAdded {
+        merchant: merchant_delegate.merchant,
+        delegate,
+        valid_until,
+    });
+    
+    msg!("Redemption delegate added: {} until {}", delegate, valid_until);
+    Ok(())
+}
+
+pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+    emit!(DelegateRevoked {
+        merchant: ctx.accounts.merchant_delegate.merchant,
+        delegate: ctx.accounts.merchant_delegate.delegate,
+    });
+    
+    msg!("Redemption delegate revoked: {}", ctx.accounts.merchant_delegate.delegate);
+    Ok(())
+}
@@ -0,0 +1,399 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount, close_account, Burn, burn},
+};
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{ExpiredListingReclaimed, ExpiredRefundClaimed};
+use crate::instructions::transfer_hook::transfer_checked_with_hook;
+
+#[derive(Accounts)]
+pub struct ReclaimExpiredListing<'info> {
+    /// Seller or any crank; pays for the seller's token account if it was closed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.is_active @ ErrorCode::ListingNotActive,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// CHECK: Receives the coupon and the vault's rent
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ ErrorCode::Unauthorized
+    )]
+    pub seller: UncheckedAccount<'info>,
+    
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program,
+    )]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
+}
+
+#[derive(Accounts)]
+pub struct SetRefundPolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + RefundPolicy::INIT_SPACE,
+        seeds = [b"refund_policy", merchant.key().as_ref()],
+        bump
+    )]
+    pub refund_policy: Account<'info, RefundPolicy>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageRefundEscrow<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    /// CHECK: Escrow account holding the merchant's refund funds
+    #[account(
+        mut,
+        seeds = [b"refund_escrow", merchant.key().as_ref()],
+        bump,
+    )]
+    pub refund_escrow: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRefundEscrow<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    /// CHECK: Merchant's `RefundPolicy`, read only if it exists; without one no coupon is covered
+    #[account(
+        seeds = [b"refund_policy", merchant.key().as_ref()],
+        bump,
+    )]
+    pub refund_policy: UncheckedAccount<'info>,
+    
+    /// CHECK: Escrow account holding the merchant's refund funds
+    #[account(
+        mut,
+        seeds = [b"refund_escrow", merchant.key().as_ref()],
+        bump,
+    )]
+    pub refund_escrow: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimExpiredRefund<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", merchant.authority.as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        mut,
+        seeds = [b"refund_policy", merchant.key().as_ref()],
+        bump = refund_policy.bump,
+    )]
+    pub refund_policy: Account<'info, RefundPolicy>,
+    
+    /// CHECK: Escrow account holding the merchant's refund funds
+    #[account(
+        mut,
+        seeds = [b"refund_escrow", merchant.key().as_ref()],
+        bump,
+    )]
+    pub refund_escrow: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.merchant == merchant.key() @ ErrorCode::Unauthorized,
+        constraint = !listing.is_used @ ErrorCode::CouponAlreadyUsed,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(mut)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::Unauthorized,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Returns an expired, unsold coupon to its seller. The listing stays, as
+/// delisting leaves it, so the coupon's refund claim and history outlive it.
+pub fn reclaim_expired_listing<'info>(ctx: Context<'_, '_, '_, 'info, ReclaimExpiredListing<'info>>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    let clock = Clock::get()?;
+    
+    require!(listing.expiry_date <= clock.unix_timestamp, ErrorCode::CouponNotExpired);
+    
+    let seeds = &[
+        b"listing",
+        listing.nft_mint.as_ref(),
+        &[listing.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    // Return the coupon to the seller
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.seller_token_account.to_account_info(),
+            authority: listing.to_account_info(),
+        },
+        signer,
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+    
+    // Close the empty vault, rent goes to the seller
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.vault.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: listing.to_account_info(),
+        },
+        signer,
+    );
+    close_account(close_ctx)?;
+    
+    let listing = &mut ctx.accounts.listing;
+    listing.is_active = false;
+    listing.custody = Custody::Wallet;
+    
+    emit!(ExpiredListingReclaimed {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        seller: listing.seller,
+    });
+    
+    msg!("Expired listing reclaimed for seller: {}", listing.seller);
+    Ok(())
+}
+
+pub fn set_refund_policy(
+    ctx: Context<SetRefundPolicy>,
+    refund_bps: u64,
+    claim_window: i64,
+) -> Result<()> {
+    require!(refund_bps <= 10_000, ErrorCode::InvalidRefundPolicy);
+    require!(claim_window > 0, ErrorCode::InvalidRefundPolicy);
+    
+    let merchant = &ctx.accounts.merchant;
+    let refund_policy = &mut ctx.accounts.refund_policy;
+    // A fresh policy has no merchant yet; an existing one binds sold coupons
+    if refund_policy.merchant != Pubkey::default() {
+        require!(!refund_policy.is_frozen(merchant), ErrorCode::RefundPolicyFrozen);
+    }
+    
+    refund_policy.merchant = merchant.key();
+    refund_policy.refund_bps = refund_bps;
+    refund_policy.claim_window = claim_window;
+    refund_policy.sales_volume_at_set = merchant.total_sales_volume;
+    refund_policy.bump = ctx.bumps.refund_policy;
+    
+    msg!("Refund policy set: {} bps for {} seconds after expiry", refund_bps, claim_window);
+    Ok(())
+}
+
+pub fn fund_refund_escrow(ctx: Context<ManageRefundEscrow>, amount: u64) -> Result<()> {
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.refund_escrow.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    
+    msg!("Refund escrow funded with {} lamports", amount);
+    Ok(())
+}
+
+pub fn withdraw_refund_escrow(ctx: Context<WithdrawRefundEscrow>, amount: u64) -> Result<()> {
+    let policy_info = ctx.accounts.refund_policy.to_account_info();
+    if policy_info.owner == &crate::ID {
+        let refund_policy = RefundPolicy::try_deserialize(&mut &policy_info.try_borrow_data()?[..])?;
+        require!(
+            refund_policy.escrow_unlocked(&ctx.accounts.merchant, Clock::get()?.unix_timestamp),
+            ErrorCode::RefundEscrowLocked
+        );
+    }
+    
+    let merchant_key = ctx.accounts.merchant.key();
+    let seeds = &[
+        b"refund_escrow",
+        merchant_key.as_ref(),
+        &[ctx.bumps.refund_escrow],
+    ];
+    let signer = &[&seeds[..]];
+    
+    anchor_lang::system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.refund_escrow.to_account_info(),
+                to: ctx.accounts.authority.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+    
+    msg!("Withdrew {} lamports from refund escrow", amount);
+    Ok(())
+}
+
+pub fn claim_expired_refund(ctx: Context<ClaimExpiredRefund>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let refund_policy = &mut ctx.accounts.refund_policy;
+    let clock = Clock::get()?;
+    
+    require!(listing.expiry_date <= clock.unix_timestamp, ErrorCode::CouponNotExpired);
+    require!(
+        refund_policy.can_claim(listing.expiry_date, clock.unix_timestamp),
+        ErrorCode::RefundWindowClosed
+    );
+    
+    // Refund a share of whatever value was left on the coupon
+    let remaining_value = listing.uses.remaining_share(listing.original_price)?;
+    let refund = (remaining_value as u128)
+        .checked_mul(refund_policy.refund_bps as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / 10_000;
+    let refund = refund as u64;
+    // The escrow's rent-exempt reserve is not refundable
+    let escrow = &ctx.accounts.refund_escrow;
+    let available = escrow
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(escrow.data_len()));
+    require!(available >= refund, ErrorCode::InsufficientRefundEscrow);
+    
+    // Burn the expired coupon so it cannot be refunded twice
+    let burn_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            from: ctx.accounts.holder_token_account.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        },
+    );
+    burn(burn_ctx, 1)?;
+    
+    let close_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.holder_token_account.to_account_info(),
+            destination: ctx.accounts.holder.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        },
+    );
+    close_account(close_ctx)?;
+    
+    listing.is_used = true;
+    listing.uses.remaining = 0;
+    listing.holder = ctx.accounts.holder.key();
+    listing.custody = Custody::Redeemed;
+    
+    if refund > 0 {
+        let merchant_key = ctx.accounts.merchant.key();
+        let seeds = &[
+            b"refund_escrow",
+            merchant_key.as_ref(),
+            &[ctx.bumps.refund_escrow],
+        ];
+        let signer = &[&seeds[..]];
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.refund_escrow.to_account_info(),
+                    to: ctx.accounts.holder.to_account_info(),
+                },
+                signer,
+            ),
+            refund,
+        )?;
+    }
+    
+    refund_policy.total_refunded = refund_policy.total_refunded.checked_add(refund)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(ExpiredRefundClaimed {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        holder: ctx.accounts.holder.key(),
+        refund,
+    });
+    
+    msg!("Expired coupon burned, refunded {} lamports", refund);
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::FeeScheduleUpdated;
+use crate::{MAX_FEE_BPS, MAX_FEE_TIERS};
+
+#[derive(Accounts)]
+pub struct SetFeeSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ ErrorCode::NotPlatformAuthority
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + FeeSchedule::INIT_SPACE,
+        seeds = [b"fee_schedule"],
+        bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// The platform's `FeeSchedule`, or until one is set, a flat schedule at the
+/// config's `platform_fee_bps`.
+pub fn fee_schedule_or_flat(fee_schedule: &AccountInfo, config: &PlatformConfig) -> Result<FeeSchedule> {
+    if fee_schedule.owner != &crate::ID {
+        return Ok(FeeSchedule::flat(config.platform_fee_bps));
+    }
+    FeeSchedule::try_deserialize(&mut &fee_schedule.try_borrow_data()?[..])
+}
+
+/// Splits a sale at `price` according to the fee schedule, less the buyer's
+/// loyalty tier discount.
+pub struct SaleAmounts {
+    pub platform_fee: u64,
+    pub seller_amount: u64,
+}
+
+pub fn sale_amounts(
+    fee_schedule: &FeeSchedule,
+    price: u64,
+    is_primary: bool,
+    merchant_volume: u64,
+    is_staker: bool,
+    tier: LoyaltyTier,
+) -> Result<SaleAmounts> {
+    let fee_bps = fee_schedule.fee_bps(is_primary, merchant_volume, is_staker);
+    let fee_bps = fee_bps - fee_bps * tier.fee_discount_bps() / 10000;
+    let platform_fee = (price as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / 10000;
+    let platform_fee = platform_fee as u64;
+    
+    // A buyer-paid fee comes on top of the price, so the seller keeps all of it
+    let seller_amount = if fee_schedule.buyer_pays_fee {
+        price
+    } else {
+        price.checked_sub(platform_fee).ok_or(ErrorCode::ArithmeticOverflow)?
+    };
+    
+    Ok(SaleAmounts { platform_fee, seller_amount })
+}
+
+pub fn set_fee_schedule(
+    ctx: Context<SetFeeSchedule>,
+    primary_tiers: Vec<FeeTier>,
+    secondary_fee_bps: u16,
+    staker_discount_bps: u16,
+    buyer_pays_fee: bool,
+) -> Result<()> {
+    require!(
+        !primary_tiers.is_empty() && primary_tiers.len() <= MAX_FEE_TIERS,
+        ErrorCode::InvalidFeeSchedule
+    );
+    require!(primary_tiers[0].min_volume == 0, ErrorCode::InvalidFeeSchedule);
+    require!(
+        primary_tiers.windows(2).all(|pair| pair[0].min_volume < pair[1].min_volume),
+        ErrorCode::InvalidFeeSchedule
+    );
+    require!(
+        primary_tiers.iter().all(|tier| tier.fee_bps <= MAX_FEE_BPS) && secondary_fee_bps <= MAX_FEE_BPS,
+        ErrorCode::InvalidFeeSchedule
+    );
+    require!(staker_discount_bps <= 10000, ErrorCode::InvalidFeeSchedule);
+    
+    let fee_schedule = &mut ctx.accounts.fee_schedule;
+    fee_schedule.primary_tiers = primary_tiers;
+    fee_schedule.secondary_fee_bps = secondary_fee_bps;
+    fee_schedule.staker_discount_bps = staker_discount_bps;
+    fee_schedule.buyer_pays_fee = buyer_pays_fee;
+    fee_schedule.bump = ctx.bumps.fee_schedule;
+    fee_schedule.version = FeeSchedule::VERSION;
+    
+    emit!(FeeScheduleUpdated {
+        primary_tiers: fee_schedule.primary_tiers.clone(),
+        secondary_fee_bps,
+        staker_discount_bps,
+        buyer_pays_fee,
+    });
+    
+    msg!("Fee schedule updated: {} primary tiers", fee_schedule.primary_tiers.len());
+    Ok(())
+}
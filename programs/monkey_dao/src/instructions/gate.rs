@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::mpl_token_metadata::accounts::Metadata,
+    token::ID as TOKEN_PROGRAM_ID,
+    token_2022::ID as TOKEN_2022_PROGRAM_ID,
+    token_interface::TokenAccount,
+};
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{GateUpdated, ListingGateUpdated};
+
+#[derive(Accounts)]
+#[instruction(gate_id: u64)]
+pub struct SetGate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + ListingGate::INIT_SPACE,
+        seeds = [b"gate", merchant.key().as_ref(), gate_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub gate: Account<'info, ListingGate>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetListingGate<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    /// Only the merchant's own listings, while it still sells them
+    #[account(
+        mut,
+        seeds = [b"listing", listing.nft_mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.merchant == merchant.key() @ ErrorCode::Unauthorized,
+        constraint = listing.seller == authority.key() @ ErrorCode::Unauthorized,
+        constraint = listing.is_active @ ErrorCode::ListingNotActive,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// None ungates the listing
+    #[account(
+        constraint = gate.merchant == merchant.key() @ ErrorCode::Unauthorized,
+    )]
+    pub gate: Option<Account<'info, ListingGate>>,
+}
+
+/// Checks `buyer` against the gate `listing` points at, if any, and counts
+/// the purchase toward the gate's wallet limit. A collection gate reads the
+/// buyer's NFT token account and its metadata from `holding`.
+pub fn enforce_gate(
+    listing: &Listing,
+    gate: Option<&Account<ListingGate>>,
+    gate_purchases: Option<&mut Account<GatePurchases>>,
+    gate_purchases_bump: Option<u8>,
+    buyer: Pubkey,
+    allowlist_proof: Option<&AllowlistProof>,
+    holding: &[AccountInfo],
+) -> Result<()> {
+    if listing.gate.is_none() {
+        return Ok(());
+    }
+    let gate = gate.ok_or(ErrorCode::GateRequired)?;
+    let gate_purchases = gate_purchases.ok_or(ErrorCode::GateRequired)?;
+    
+    require!(gate.is_allowlisted(&buyer, allowlist_proof), ErrorCode::NotOnAllowlist);
+    if let Some(collection) = gate.collection {
+        require!(holds_collection(&collection, &buyer, holding), ErrorCode::CollectionNotHeld);
+    }
+    
+    if gate_purchases.wallet == Pubkey::default() {
+        gate_purchases.gate = gate.key();
+        gate_purchases.wallet = buyer;
+        gate_purchases.bump = gate_purchases_bump.ok_or(ErrorCode::GateRequired)?;
+    }
+    require!(
+        gate.max_per_wallet == 0 || gate_purchases.purchases < gate.max_per_wallet,
+        ErrorCode::WalletLimitReached
+    );
+    gate_purchases.purchases = gate_purchases.purchases.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// `holding` is a token account of `owner` holding an NFT, followed by that
+/// NFT's metadata, which names `collection` as its verified collection.
+fn holds_collection(collection: &Pubkey, owner: &Pubkey, holding: &[AccountInfo]) -> bool {
+    let [token_account, metadata, ..] = holding else {
+        return false;
+    };
+    if token_account.owner != &TOKEN_PROGRAM_ID && token_account.owner != &TOKEN_2022_PROGRAM_ID {
+        return false;
+    }
+    let Some(token) = token_account
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| TokenAccount::try_deserialize(&mut &data[..]).ok())
+    else {
+        return false;
+    };
+    if token.owner != *owner || token.amount == 0 {
+        return false;
+    }
+    
+    if metadata.owner != &anchor_spl::metadata::ID || metadata.key() != Metadata::find_pda(&token.mint).0 {
+        return false;
+    }
+    metadata
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| Metadata::from_bytes(&data).ok())
+        .and_then(|metadata| metadata.collection)
+        .is_some_and(|nft_collection| nft_collection.verified && nft_collection.key == *collection)
+}
+
+pub fn set_gate(
+    ctx: Context<SetGate>,
+    gate_id: u64,
+    collection: Option<Pubkey>,
+    allowlist_root: Option<[u8; 32]>,
+    max_per_wallet: u16,
+) -> Result<()> {
+    let gate = &mut ctx.accounts.gate;
+    gate.merchant = ctx.accounts.merchant.key();
+    gate.gate_id = gate_id;
+    gate.collection = collection;
+    gate.allowlist_root = allowlist_root;
+    gate.max_per_wallet = max_per_wallet;
+    gate.bump = ctx.bumps.gate;
+    gate.version = ListingGate::VERSION;
+    
+    emit!(GateUpdated {
+        gate: gate.key(),
+        merchant: gate.merchant,
+        collection,
+        allowlist_root,
+        max_per_wallet,
+    });
+    
+    msg!("Gate {} updated", gate_id);
+    Ok(())
+}
+
+pub fn set_listing_gate(ctx: Context<SetListingGate>) -> Result<()> {
+    let gate = ctx.accounts.gate.as_ref().map(|gate| gate.key());
+    let listing = &mut ctx.accounts.listing;
+    listing.gate = gate;
+    
+    emit!(ListingGateUpdated {
+        listing: listing.key(),
+        gate,
+    });
+    
+    msg!("Listing gate updated");
+    Ok(())
+}
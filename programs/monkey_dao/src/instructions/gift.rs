@@ -0,0 +1,420 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount, close_account},
+};
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{CouponGifted, GiftSent, GiftClaimed, GiftReturned};
+use crate::instructions::transfer_hook::transfer_checked_with_hook;
+use crate::{GIFT_CLAIM_PERIOD, MAX_GIFT_MESSAGE_LEN, PAUSE_TRADING};
+
+#[derive(Accounts)]
+pub struct GiftCoupon<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_TRADING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = !listing.is_active @ ErrorCode::ListingStillActive,
+        constraint = !listing.is_used @ ErrorCode::CouponAlreadyUsed,
+        constraint = listing.custody == Custody::Wallet @ ErrorCode::InvalidCustody,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+        constraint = sender_token_account.amount == 1 @ ErrorCode::Unauthorized
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Only receives the coupon
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = nft_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
+}
+
+#[derive(Accounts)]
+pub struct SendGift<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_TRADING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = !listing.is_active @ ErrorCode::ListingStillActive,
+        constraint = !listing.is_used @ ErrorCode::CouponAlreadyUsed,
+        constraint = listing.custody == Custody::Wallet @ ErrorCode::InvalidCustody,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+        constraint = sender_token_account.amount == 1 @ ErrorCode::Unauthorized
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = sender,
+        space = ANCHOR_DISCRIMINATOR + Gift::INIT_SPACE,
+        seeds = [b"gift", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub gift: Account<'info, Gift>,
+    
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = nft_mint,
+        associated_token::authority = gift,
+        associated_token::token_program = token_program,
+    )]
+    pub gift_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
+}
+
+#[derive(Accounts)]
+pub struct ClaimGift<'info> {
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    
+    /// The gift's claim key, co-signing for a claimer who isn't its recipient
+    pub claim_signer: Option<Signer<'info>>,
+    
+    #[account(
+        mut,
+        close = sender,
+        seeds = [b"gift", nft_mint.key().as_ref()],
+        bump = gift.bump,
+    )]
+    pub gift: Account<'info, Gift>,
+    
+    /// CHECK: Gets the gift's rent back
+    #[account(
+        mut,
+        address = gift.sender @ ErrorCode::Unauthorized,
+    )]
+    pub sender: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = gift,
+        associated_token::token_program = token_program,
+    )]
+    pub gift_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = claimer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = claimer,
+        associated_token::token_program = token_program,
+    )]
+    pub claimer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
+}
+
+#[derive(Accounts)]
+pub struct ReturnGift<'info> {
+    /// Sender or any crank; pays for the sender's token account if it was closed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = sender,
+        seeds = [b"gift", nft_mint.key().as_ref()],
+        bump = gift.bump,
+    )]
+    pub gift: Account<'info, Gift>,
+    
+    /// CHECK: Receives the coupon and the gift's rent
+    #[account(
+        mut,
+        address = gift.sender @ ErrorCode::Unauthorized,
+    )]
+    pub sender: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = gift,
+        associated_token::token_program = token_program,
+    )]
+    pub gift_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program,
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
+}
+
+/// Moves the escrowed coupon to `to` and closes the gift vault, its rent
+/// going back to the sender.
+fn release_gift<'info>(
+    gift: &Account<'info, Gift>,
+    gift_vault: &InterfaceAccount<'info, TokenAccount>,
+    nft_mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    sender: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let seeds = &[
+        b"gift",
+        gift.nft_mint.as_ref(),
+        &[gift.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.clone(),
+        TransferChecked {
+            from: gift_vault.to_account_info(),
+            mint: nft_mint.to_account_info(),
+            to,
+            authority: gift.to_account_info(),
+        },
+        signer,
+    )
+    .with_remaining_accounts(hook_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, nft_mint.decimals)?;
+    
+    let close_ctx = CpiContext::new_with_signer(
+        token_program,
+        CloseAccount {
+            account: gift_vault.to_account_info(),
+            destination: sender,
+            authority: gift.to_account_info(),
+        },
+        signer,
+    );
+    close_account(close_ctx)
+}
+
+/// Hands the coupon straight to `recipient`, who becomes its holder.
+pub fn gift_coupon<'info>(ctx: Context<'_, '_, '_, 'info, GiftCoupon<'info>>, message: String) -> Result<()> {
+    require!(message.len() <= MAX_GIFT_MESSAGE_LEN, ErrorCode::InvalidGift);
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.sender_token_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.recipient_token_account.to_account_info(),
+            authority: ctx.accounts.sender.to_account_info(),
+        },
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+    
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = ctx.accounts.recipient.key();
+    
+    emit!(CouponGifted {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        sender: ctx.accounts.sender.key(),
+        recipient: listing.holder,
+        message,
+    });
+    
+    msg!("Coupon gifted to {}", listing.holder);
+    Ok(())
+}
+
+/// Escrows the coupon until `recipient`, or a claimer co-signing with
+/// `claim_key`, claims it. The claim key's keypair is handed to the
+/// recipient off-chain, for gifts sent before they have a wallet.
+pub fn send_gift<'info>(
+    ctx: Context<'_, '_, '_, 'info, SendGift<'info>>,
+    recipient: Option<Pubkey>,
+    claim_key: Option<Pubkey>,
+    message: String,
+) -> Result<()> {
+    require!(recipient.is_some() || claim_key.is_some(), ErrorCode::InvalidGift);
+    require!(message.len() <= MAX_GIFT_MESSAGE_LEN, ErrorCode::InvalidGift);
+    let clock = Clock::get()?;
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.sender_token_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.gift_vault.to_account_info(),
+            authority: ctx.accounts.sender.to_account_info(),
+        },
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+    
+    let gift = &mut ctx.accounts.gift;
+    gift.sender = ctx.accounts.sender.key();
+    gift.nft_mint = ctx.accounts.nft_mint.key();
+    gift.recipient = recipient;
+    gift.claim_key = claim_key;
+    gift.message = message;
+    gift.sent_at = clock.unix_timestamp;
+    gift.expires_at = clock.unix_timestamp.checked_add(GIFT_CLAIM_PERIOD)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    gift.bump = ctx.bumps.gift;
+    gift.version = Gift::VERSION;
+    
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = gift.sender;
+    listing.custody = Custody::GiftEscrow;
+    
+    emit!(GiftSent {
+        gift: gift.key(),
+        nft_mint: gift.nft_mint,
+        sender: gift.sender,
+        recipient,
+        message: gift.message.clone(),
+        expires_at: gift.expires_at,
+    });
+    
+    msg!("Gift sent, claimable until {}", gift.expires_at);
+    Ok(())
+}
+
+pub fn claim_gift<'info>(ctx: Context<'_, '_, '_, 'info, ClaimGift<'info>>) -> Result<()> {
+    let gift = &ctx.accounts.gift;
+    let claimer = ctx.accounts.claimer.key();
+    let claim_signer = ctx.accounts.claim_signer.as_ref().map(|signer| signer.key());
+    require!(gift.can_claim(&claimer, claim_signer.as_ref()), ErrorCode::NotGiftRecipient);
+    require!(Clock::get()?.unix_timestamp < gift.expires_at, ErrorCode::GiftExpired);
+    
+    release_gift(
+        gift,
+        &ctx.accounts.gift_vault,
+        &ctx.accounts.nft_mint,
+        ctx.accounts.claimer_token_account.to_account_info(),
+        ctx.accounts.sender.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+    )?;
+    
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = claimer;
+    listing.custody = Custody::Wallet;
+    
+    emit!(GiftClaimed {
+        gift: gift.key(),
+        nft_mint: gift.nft_mint,
+        sender: gift.sender,
+        recipient: claimer,
+    });
+    
+    msg!("Gift claimed by {}", claimer);
+    Ok(())
+}
+
+/// Gives an unclaimed gift back to its sender once it expired.
+pub fn return_gift<'info>(ctx: Context<'_, '_, '_, 'info, ReturnGift<'info>>) -> Result<()> {
+    let gift = &ctx.accounts.gift;
+    require!(Clock::get()?.unix_timestamp >= gift.expires_at, ErrorCode::GiftNotExpired);
+    
+    release_gift(
+        gift,
+        &ctx.accounts.gift_vault,
+        &ctx.accounts.nft_mint,
+        ctx.accounts.sender_token_account.to_account_info(),
+        ctx.accounts.sender.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+    )?;
+    
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = gift.sender;
+    listing.custody = Custody::Wallet;
+    
+    emit!(GiftReturned {
+        gift: gift.key(),
+        nft_mint: gift.nft_mint,
+        sender: gift.sender,
+    });
+    
+    msg!("Gift returned to {}", gift.sender);
+    Ok(())
+}
@@ -1,25 +1,40 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount, Transfer, transfer},
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount, close_account},
 };
-use crate::{state::*, error::ErrorCode, ANCHOR_DISCRIMINATOR};
+use crate::{state::*, error::ErrorCode, ANCHOR_DISCRIMINATOR, PAUSE_LISTING};
+use crate::instructions::verifier::count_valid_attestations;
+use crate::instructions::transfer_hook::{split_hook_accounts, transfer_checked_with_hook};
+use crate::events::{CouponListed, CouponRelisted, CouponDelisted, CustodySynced};
 
 #[derive(Accounts)]
 pub struct ListNFT<'info> {
     #[account(mut)]
     pub seller: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_LISTING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         mut,
         seeds = [b"merchant", seller.key().as_ref()],
         bump = merchant.bump,
-        constraint = merchant.is_verified @ ErrorCode::MerchantNotVerified
     )]
     pub merchant: Account<'info, Merchant>,
     
     #[account(
-        init,
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    #[account(
+        init_if_needed,
         payer = seller,
         space = ANCHOR_DISCRIMINATOR + Listing::INIT_SPACE,
         seeds = [b"listing", nft_mint.key().as_ref()],
@@ -27,27 +42,31 @@ pub struct ListNFT<'info> {
     )]
     pub listing: Account<'info, Listing>,
     
-    pub nft_mint: Account<'info, Mint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = seller,
+        associated_token::token_program = token_program,
         constraint = seller_token_account.amount == 1 @ ErrorCode::Unauthorized
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
-        init,
+        init_if_needed,
         payer = seller,
         associated_token::mint = nft_mint,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub vault: Account<'info, TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    // remaining_accounts: the merchant's `Attestation` accounts, then the
+    // coupon's transfer hook accounts if its mint has a hook
 }
 
 #[derive(Accounts)]
@@ -55,32 +74,46 @@ pub struct RelistNFT<'info> {
     #[account(mut)]
     pub seller: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_LISTING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         mut,
         seeds = [b"listing", nft_mint.key().as_ref()],
         bump = listing.bump,
-        constraint = listing.seller == seller.key() @ ErrorCode::Unauthorized
     )]
     pub listing: Account<'info, Listing>,
     
-    pub nft_mint: Account<'info, Mint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
+    /// Whoever holds the coupon may relist it, however it reached them
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = seller,
+        associated_token::token_program = token_program,
         constraint = seller_token_account.amount == 1 @ ErrorCode::Unauthorized
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
-        mut,
+        init_if_needed,
+        payer = seller,
         associated_token::mint = nft_mint,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub vault: Account<'info, TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
 }
 
 #[derive(Accounts)]
@@ -92,31 +125,82 @@ pub struct DelistNFT<'info> {
         mut,
         seeds = [b"listing", nft_mint.key().as_ref()],
         bump = listing.bump,
-        constraint = listing.seller == seller.key() @ ErrorCode::Unauthorized
+        constraint = listing.holder == seller.key() @ ErrorCode::Unauthorized
     )]
     pub listing: Account<'info, Listing>,
     
-    pub nft_mint: Account<'info, Mint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = seller,
+        associated_token::token_program = token_program,
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
+}
+
+#[derive(Accounts)]
+pub struct CloseListing<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"listing", listing.nft_mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.merchant == merchant.key() @ ErrorCode::Unauthorized,
+        constraint = listing.is_used @ ErrorCode::CouponNotUsed,
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+#[derive(Accounts)]
+pub struct SyncCustody<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Any token account now holding the coupon; none once it was burned
+    #[account(
+        token::mint = nft_mint,
+        token::token_program = token_program,
+        constraint = token_account.amount == 1 @ ErrorCode::InvalidCustody,
     )]
-    pub vault: Account<'info, TokenAccount>,
+    pub token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Owner of `token_account`; a vault's stake account, gift or pool is read for its holder
+    pub custodian: Option<UncheckedAccount<'info>>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn list_nft(
-    ctx: Context<ListNFT>,
+pub fn list_nft<'info>(
+    ctx: Context<'_, '_, '_, 'info, ListNFT<'info>>,
     price: u64,
     is_group_deal: bool,
     deal_price_2: Option<u64>,
@@ -124,15 +208,56 @@ pub fn list_nft(
     deal_price_6: Option<u64>,
     coupon_description: String,
     expiry_date: i64,
+    use_method: UseMethod,
+    total_uses: u64,
 ) -> Result<()> {
     require!(price > 0, ErrorCode::InvalidPrice);
+    require!(total_uses > 0, ErrorCode::InvalidUses);
     let clock = Clock::get()?;
     require!(expiry_date > clock.unix_timestamp, ErrorCode::CouponExpired);
     
+    let (attestations, hook_accounts) =
+        split_hook_accounts(&ctx.accounts.nft_mint.to_account_info(), ctx.remaining_accounts)?;
+    // Merchant must hold M-of-N unexpired attestations at listing time
+    let valid_attestations = count_valid_attestations(
+        &ctx.accounts.verifier_registry,
+        &ctx.accounts.merchant,
+        attestations,
+        clock.unix_timestamp,
+    )?;
+    require!(
+        valid_attestations >= ctx.accounts.verifier_registry.threshold,
+        ErrorCode::MerchantNotVerified
+    );
+    
     let listing = &mut ctx.accounts.listing;
-    listing.nft_mint = ctx.accounts.nft_mint.key();
+    let is_new_listing = listing.nft_mint == Pubkey::default();
+    
+    if is_new_listing {
+        listing.nft_mint = ctx.accounts.nft_mint.key();
+        listing.merchant = ctx.accounts.merchant.key();
+        listing.is_used = false;
+        listing.uses = CouponUses::new(use_method, total_uses);
+        listing.total_sales = 0;
+        listing.created_at = clock.unix_timestamp;
+        listing.average_rating = 0;
+        listing.total_reviews = 0;
+        listing.bump = ctx.bumps.listing;
+        listing.version = Listing::VERSION;
+    } else {
+        // Merchant listing its own coupon again after a delist or buy-back.
+        // Uses, sales and reviews carry over; only the sale terms are replaced.
+        require!(listing.merchant == ctx.accounts.merchant.key(), ErrorCode::Unauthorized);
+        require!(!listing.is_active, ErrorCode::ListingStillActive);
+        require!(!listing.is_used, ErrorCode::CouponAlreadyUsed);
+        // Uses were fixed when the coupon was first listed and cannot be changed
+        let uses = CouponUses::new(use_method, total_uses);
+        require!(
+            uses.use_method == listing.uses.use_method && uses.total == listing.uses.total,
+            ErrorCode::InvalidUses
+        );
+    }
     listing.seller = ctx.accounts.seller.key();
-    listing.merchant = ctx.accounts.merchant.key();
     listing.original_price = price;
     listing.current_price = price;
     listing.is_group_deal = is_group_deal;
@@ -140,64 +265,97 @@ pub fn list_nft(
     listing.deal_price_4 = deal_price_4;
     listing.deal_price_6 = deal_price_6;
     listing.is_active = true;
-    listing.is_used = false;
-    listing.total_sales = 0;
     listing.coupon_description = coupon_description;
     listing.expiry_date = expiry_date;
-    listing.created_at = clock.unix_timestamp;
-    listing.average_rating = 0;
-    listing.total_reviews = 0;
-    listing.bump = ctx.bumps.listing;
+    listing.holder = listing.seller;
+    listing.custody = Custody::ListingVault;
 
     // Transfer NFT to vault
     let transfer_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.seller_token_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
             to: ctx.accounts.vault.to_account_info(),
             authority: ctx.accounts.seller.to_account_info(),
         },
-    );
-    transfer(transfer_ctx, 1)?;
+    )
+    .with_remaining_accounts(hook_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
 
     let merchant = &mut ctx.accounts.merchant;
-    merchant.total_listings = merchant.total_listings.checked_add(1)
-        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    if is_new_listing {
+        merchant.total_listings = merchant.total_listings.checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    merchant.latest_expiry = merchant.latest_expiry.max(expiry_date);
+
+    let listing = &ctx.accounts.listing;
+    emit!(CouponListed {
+        listing: listing.key(),
+        merchant: listing.merchant,
+        nft_mint: listing.nft_mint,
+        seller: listing.seller,
+        price,
+        is_group_deal,
+        expiry_date,
+        remaining_uses: listing.uses.remaining,
+    });
 
     msg!("NFT listed successfully at price: {} lamports", price);
     Ok(())
 }
 
-pub fn relist_nft(ctx: Context<RelistNFT>, new_price: u64) -> Result<()> {
+pub fn relist_nft<'info>(ctx: Context<'_, '_, '_, 'info, RelistNFT<'info>>, new_price: u64) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     
     require!(!listing.is_active, ErrorCode::ListingNotActive);
     require!(new_price > 0, ErrorCode::InvalidPrice);
     require!(new_price < listing.original_price, ErrorCode::PriceTooHigh);
     require!(!listing.is_used, ErrorCode::CannotStakeUsedCoupon);
+    // A partly spent coupon cannot be resold for more than what is left on it
+    require!(
+        new_price <= listing.uses.remaining_share(listing.original_price)?,
+        ErrorCode::PriceTooHigh
+    );
+    require!(listing.custody == Custody::Wallet, ErrorCode::InvalidCustody);
 
+    listing.seller = ctx.accounts.seller.key();
     listing.current_price = new_price;
     listing.is_active = true;
+    listing.holder = listing.seller;
+    listing.custody = Custody::ListingVault;
     
     // Transfer NFT to vault
     let transfer_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.seller_token_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
             to: ctx.accounts.vault.to_account_info(),
             authority: ctx.accounts.seller.to_account_info(),
         },
-    );
-    transfer(transfer_ctx, 1)?;
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+
+    emit!(CouponRelisted {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        seller: ctx.accounts.seller.key(),
+        price: new_price,
+    });
 
     msg!("NFT relisted at new price: {} lamports", new_price);
     Ok(())
 }
 
-pub fn delist_nft(ctx: Context<DelistNFT>) -> Result<()> {
+pub fn delist_nft<'info>(ctx: Context<'_, '_, '_, 'info, DelistNFT<'info>>) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     
     require!(listing.is_active, ErrorCode::ListingNotActive);
+    // A coupon bought by a filled pool is no longer the seller's to take back
+    require!(listing.custody == Custody::ListingVault, ErrorCode::InvalidCustody);
 
     // Transfer NFT back to seller using PDA signer
     let binding = ctx.accounts.nft_mint.key();
@@ -210,17 +368,110 @@ pub fn delist_nft(ctx: Context<DelistNFT>) -> Result<()> {
 
     let transfer_ctx = CpiContext::new_with_signer(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
             to: ctx.accounts.seller_token_account.to_account_info(),
             authority: listing.to_account_info(),
         },
         signer,
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+
+    // Close the now empty vault, relisting opens a fresh one
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.vault.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: listing.to_account_info(),
+        },
+        signer,
     );
-    transfer(transfer_ctx, 1)?;
+    close_account(close_ctx)?;
 
     listing.is_active = false;
+    listing.custody = Custody::Wallet;
+
+    emit!(CouponDelisted {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        seller: ctx.accounts.seller.key(),
+    });
 
     msg!("NFT delisted successfully");
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn close_listing(_ctx: Context<CloseListing>) -> Result<()> {
+    // Listing rent was paid by the merchant when the coupon was first listed
+    msg!("Listing closed, rent returned to merchant");
+    Ok(())
+}
+
+/// Reads the program account that owns a vault holding the coupon.
+fn read_custodian<T: AccountDeserialize>(custodian: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*custodian.owner, crate::ID, ErrorCode::InvalidCustody);
+    T::try_deserialize(&mut &custodian.try_borrow_data()?[..])
+}
+
+/// Points `holder` and `custody` at wherever the coupon is now, after it
+/// moved by a plain token transfer or burn, or for listings migrated before
+/// custody was tracked. Anyone may call it, as it only records where the
+/// token is; a listing already up to date is left as is.
+pub fn sync_custody(ctx: Context<SyncCustody>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let nft_mint = ctx.accounts.nft_mint.key();
+    
+    let (holder, custody) = match &ctx.accounts.token_account {
+        None => {
+            require!(ctx.accounts.nft_mint.supply == 0, ErrorCode::InvalidCustody);
+            (listing.holder, Custody::Redeemed)
+        }
+        Some(token_account) => {
+            let custodian = ctx.accounts.custodian.as_ref().ok_or(ErrorCode::InvalidCustody)?;
+            require_keys_eq!(custodian.key(), token_account.owner, ErrorCode::InvalidCustody);
+            let owner = token_account.owner;
+            
+            if owner == listing.key() {
+                // A filled pool's coupon waits in the listing vault until settled
+                if listing.custody == Custody::Pool {
+                    (listing.holder, Custody::Pool)
+                } else {
+                    (listing.seller, Custody::ListingVault)
+                }
+            } else if owner == Pubkey::find_program_address(&[b"stake", nft_mint.as_ref()], &crate::ID).0 {
+                let stake_account: StakeAccount = read_custodian(custodian)?;
+                (stake_account.owner, Custody::StakeVault)
+            } else if owner == Pubkey::find_program_address(&[b"gift", nft_mint.as_ref()], &crate::ID).0 {
+                let gift: Gift = read_custodian(custodian)?;
+                (gift.sender, Custody::GiftEscrow)
+            } else if custodian.owner == &crate::ID {
+                let pool: Pool = read_custodian(custodian)?;
+                require_keys_eq!(pool.listing, listing.key(), ErrorCode::InvalidCustody);
+                (owner, Custody::Pool)
+            } else {
+                (owner, Custody::Wallet)
+            }
+        }
+    };
+    // Already up to date, so a sync racing another is harmless
+    if holder == listing.holder && custody == listing.custody {
+        msg!("Coupon custody already synced");
+        return Ok(());
+    }
+    
+    listing.holder = holder;
+    listing.custody = custody;
+    
+    emit!(CustodySynced {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        holder,
+        custody,
+    });
+    
+    msg!("Coupon custody synced: {:?} by {}", custody, holder);
+    Ok(())
+}
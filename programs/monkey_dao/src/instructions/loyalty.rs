@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{TierRefreshed, ListingAccessUpdated};
+
+#[derive(Accounts)]
+pub struct RefreshTier<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump = user_stats.bump,
+    )]
+    pub user_stats: Account<'info, UserStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR + Loyalty::INIT_SPACE,
+        seeds = [b"loyalty", user.key().as_ref()],
+        bump
+    )]
+    pub loyalty: Account<'info, Loyalty>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetListingAccess<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    /// Only the merchant's own listings, while it still sells them
+    #[account(
+        mut,
+        seeds = [b"listing", listing.nft_mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.merchant == merchant.key() @ ErrorCode::Unauthorized,
+        constraint = listing.seller == authority.key() @ ErrorCode::Unauthorized,
+        constraint = listing.is_active @ ErrorCode::ListingNotActive,
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+/// Tier of the buyer whose `Loyalty` PDA is `loyalty`. Buyers that never
+/// refreshed a tier have no account there and are Bronze.
+pub fn buyer_tier(loyalty: &AccountInfo, current_time: i64) -> LoyaltyTier {
+    if loyalty.owner != &crate::ID {
+        return LoyaltyTier::Bronze;
+    }
+    loyalty
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| Loyalty::try_deserialize(&mut &data[..]).ok())
+        .map_or(LoyaltyTier::Bronze, |loyalty| loyalty.current_tier(current_time))
+}
+
+/// Turns away buyers below the listing's tier, and everyone below Silver
+/// during its early access window.
+pub fn check_listing_access(listing: &Listing, tier: LoyaltyTier, current_time: i64) -> Result<()> {
+    require!(tier >= listing.min_tier, ErrorCode::TierTooLow);
+    require!(
+        current_time >= listing.early_access_until || tier >= LoyaltyTier::Silver,
+        ErrorCode::EarlyAccessOnly
+    );
+    Ok(())
+}
+
+/// Sets the user's tier from their own `UserStats`. The MONK threshold is
+/// met by MONK the wallet earned, which unlike a token balance can't be
+/// passed along to the next wallet to refresh with.
+pub fn refresh_tier(ctx: Context<RefreshTier>) -> Result<()> {
+    let clock = Clock::get()?;
+    let tier = LoyaltyTier::from_stats(&ctx.accounts.user_stats);
+    
+    let loyalty = &mut ctx.accounts.loyalty;
+    let previous = if loyalty.version == 0 {
+        LoyaltyTier::Bronze
+    } else {
+        loyalty.current_tier(clock.unix_timestamp)
+    };
+    loyalty.user = ctx.accounts.user.key();
+    loyalty.tier = tier;
+    loyalty.refreshed_at = clock.unix_timestamp;
+    loyalty.bump = ctx.bumps.loyalty;
+    loyalty.version = Loyalty::VERSION;
+    
+    emit!(TierRefreshed {
+        user: loyalty.user,
+        previous,
+        tier,
+    });
+    
+    msg!("Loyalty tier refreshed: {:?}", tier);
+    Ok(())
+}
+
+pub fn set_listing_access(
+    ctx: Context<SetListingAccess>,
+    min_tier: LoyaltyTier,
+    early_access_until: i64,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    listing.min_tier = min_tier;
+    listing.early_access_until = early_access_until;
+    
+    emit!(ListingAccessUpdated {
+        listing: listing.key(),
+        min_tier,
+        early_access_until,
+    });
+    
+    msg!("Listing access updated: {:?} and above", min_tier);
+    Ok(())
+}
@@ -2,6 +2,9 @@ use anchor_lang::prelude::*;
 use crate::state::*;
 use crate:: ANCHOR_DISCRIMINATOR;
 use crate::error::ErrorCode;
+use crate::events::{MerchantRegistered, MerchantVerificationUpdated, MerchantKycUpdated};
+use crate::instructions::verifier::count_valid_attestations;
+use crate::instructions::migrate::{read_layout, write_migrated};
 
 #[derive(Accounts)]
 pub struct RegisterMerchant<'info> {
@@ -22,28 +25,56 @@ pub struct RegisterMerchant<'info> {
 
 #[derive(Accounts)]
 pub struct VerifyMerchant<'info> {
-    #[account(mut)]
-    pub platform_authority: Signer<'info>,
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
     
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
-        constraint = config.authority == platform_authority.key() @ ErrorCode::NotPlatformAuthority
+        mut,
+        seeds = [b"merchant", merchant.authority.as_ref()],
+        bump = merchant.bump,
     )]
-    pub config: Account<'info, PlatformConfig>,
+    pub merchant: Account<'info, Merchant>,
+    // remaining_accounts: the merchant's `Attestation` accounts
+}
+
+#[derive(Accounts)]
+pub struct UpdateMerchantKyc<'info> {
+    pub authority: Signer<'info>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
     pub merchant: Account<'info, Merchant>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateMerchant<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Still in an older layout, decoded by hand in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump,
+    )]
+    pub merchant: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 pub fn register_merchant(
     ctx: Context<RegisterMerchant>,
     business_name: String,
     business_type: String,
-    contact_email: String,
-    phone: String,
-    business_address: String,
-    tax_id: String,
+    kyc_hash: [u8; 32],
+    kyc_uri: String,
 ) -> Result<()> {
     let merchant = &mut ctx.accounts.merchant;
     let clock = Clock::get()?;
@@ -51,23 +82,135 @@ pub fn register_merchant(
     merchant.authority = ctx.accounts.authority.key();
     merchant.business_name = business_name;
     merchant.business_type = business_type;
-    merchant.contact_email = contact_email;
-    merchant.phone = phone;
-    merchant.business_address = business_address;
-    merchant.tax_id = tax_id;
+    merchant.kyc_hash = kyc_hash;
+    merchant.kyc_uri = kyc_uri;
     merchant.is_verified = false;
     merchant.total_listings = 0;
+    merchant.total_redemptions = 0;
+    merchant.total_value_honored = 0;
+    merchant.total_sales_volume = 0;
+    merchant.latest_expiry = 0;
     merchant.registration_date = clock.unix_timestamp;
     merchant.bump = ctx.bumps.merchant;
+    merchant.version = Merchant::VERSION;
+    
+    emit!(MerchantRegistered {
+        merchant: merchant.key(),
+        authority: merchant.authority,
+        business_name: merchant.business_name.clone(),
+        business_type: merchant.business_type.clone(),
+        kyc_hash,
+        registered_at: clock.unix_timestamp,
+    });
     
     msg!("Merchant registered successfully: {}", merchant.business_name);
     Ok(())
 }
 
 pub fn verify_merchant(ctx: Context<VerifyMerchant>) -> Result<()> {
+    let clock = Clock::get()?;
+    let registry = &ctx.accounts.verifier_registry;
+    
+    let valid = count_valid_attestations(
+        registry,
+        &ctx.accounts.merchant,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+    
+    // Permissionless refresh of the cached flag; listing re-checks attestations itself
+    let merchant = &mut ctx.accounts.merchant;
+    merchant.is_verified = valid >= registry.threshold;
+    
+    emit!(MerchantVerificationUpdated {
+        merchant: merchant.key(),
+        valid_attestations: valid,
+        threshold: registry.threshold,
+        is_verified: merchant.is_verified,
+    });
+    
+    msg!(
+        "Merchant {} has {}/{} valid attestations",
+        merchant.business_name,
+        valid,
+        registry.threshold
+    );
+    Ok(())
+}
+
+pub fn update_merchant_kyc(
+    ctx: Context<UpdateMerchantKyc>,
+    kyc_hash: [u8; 32],
+    kyc_uri: String,
+) -> Result<()> {
     let merchant = &mut ctx.accounts.merchant;
-    merchant.is_verified = true;
     
-    msg!("Merchant verified: {}", merchant.business_name);
+    merchant.kyc_hash = kyc_hash;
+    merchant.kyc_uri = kyc_uri;
+    // New documents have to be reviewed again
+    merchant.is_verified = false;
+    
+    emit!(MerchantKycUpdated {
+        merchant: merchant.key(),
+        kyc_hash,
+        kyc_uri: merchant.kyc_uri.clone(),
+    });
+    
+    msg!("Merchant KYC commitment updated: {}", merchant.business_name);
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn migrate_merchant(
+    ctx: Context<MigrateMerchant>,
+    kyc_hash: [u8; 32],
+    kyc_uri: String,
+) -> Result<()> {
+    let merchant_info = ctx.accounts.merchant.to_account_info();
+    let authority = ctx.accounts.authority.key();
+    
+    let merchant = if let Some(legacy) = read_layout::<Merchant, LegacyMerchant>(&merchant_info)? {
+        require!(legacy.authority == authority, ErrorCode::Unauthorized);
+        
+        // Contact details are dropped; verification must be redone against the commitment
+        Merchant {
+            authority: legacy.authority,
+            business_name: legacy.business_name,
+            business_type: legacy.business_type,
+            kyc_hash,
+            kyc_uri,
+            is_verified: false,
+            total_listings: legacy.total_listings,
+            total_redemptions: 0,
+            total_value_honored: 0,
+            registration_date: legacy.registration_date,
+            bump: legacy.bump,
+            version: Merchant::VERSION,
+            total_sales_volume: 0,
+            latest_expiry: 0,
+            reserved: [0; 48],
+        }
+    } else if let Some(unversioned) = read_layout::<Merchant, MerchantV0>(&merchant_info)? {
+        require!(unversioned.authority == authority, ErrorCode::Unauthorized);
+        
+        // Verification only carries over if the commitment is unchanged
+        let is_verified = unversioned.is_verified && unversioned.kyc_hash == kyc_hash;
+        Merchant {
+            kyc_hash,
+            kyc_uri,
+            is_verified,
+            ..unversioned.into()
+        }
+    } else {
+        return err!(ErrorCode::AccountAlreadyMigrated);
+    };
+    
+    write_migrated(
+        &merchant_info,
+        &merchant,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    msg!("Merchant migrated to version {}: {}", Merchant::VERSION, merchant.business_name);
+    Ok(())
+}
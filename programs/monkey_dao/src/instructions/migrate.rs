@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct MigrateListing<'info> {
+    /// Anyone may migrate; the payer covers rent for the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Still in an older layout, decoded by hand in the handler
+    #[account(mut, owner = crate::ID)]
+    pub listing: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// Anyone may migrate; the payer covers rent for the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Still in the unversioned layout, decoded by hand in the handler
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStakeAccount<'info> {
+    /// Anyone may migrate; the payer covers rent for the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Still in the unversioned layout, decoded by hand in the handler
+    #[account(mut, owner = crate::ID)]
+    pub stake_account: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// Anyone may migrate; the payer covers rent for the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Still in the unversioned layout, decoded by hand in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Decodes `info` in the older layout `Old` of account type `A`. Returns
+/// `None` unless the data carries `A`'s discriminator and is exactly the
+/// size `Old` was allocated at.
+pub fn read_layout<A: Discriminator, Old: AnchorDeserialize + Space>(
+    info: &AccountInfo,
+) -> Result<Option<Old>> {
+    let data = info.try_borrow_data()?;
+    if data.len() != ANCHOR_DISCRIMINATOR + Old::INIT_SPACE
+        || data[..ANCHOR_DISCRIMINATOR] != A::DISCRIMINATOR
+    {
+        return Ok(None);
+    }
+    Ok(Some(Old::deserialize(&mut &data[ANCHOR_DISCRIMINATOR..])?))
+}
+
+/// Rewrites `info` as `account`, resized to the current layout. Rent for a
+/// larger layout is paid by `payer`; rent freed by a smaller one goes back to it.
+pub fn write_migrated<'info, A: AccountSerialize + Space>(
+    info: &AccountInfo<'info>,
+    account: &A,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let new_len = ANCHOR_DISCRIMINATOR + A::INIT_SPACE;
+    let required = Rent::get()?.minimum_balance(new_len);
+    let current = info.lamports();
+    
+    if required > current {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            required - current,
+        )?;
+    }
+    
+    info.realloc(new_len, false)?;
+    {
+        let mut data = info.try_borrow_mut_data()?;
+        data.fill(0);
+        let mut writer: &mut [u8] = &mut data;
+        account.try_serialize(&mut writer)?;
+    }
+    
+    if current > required {
+        let excess = current - required;
+        **info.try_borrow_mut_lamports()? -= excess;
+        **payer.try_borrow_mut_lamports()? += excess;
+    }
+    Ok(())
+}
+
+pub fn migrate_listing(ctx: Context<MigrateListing>) -> Result<()> {
+    let listing_info = ctx.accounts.listing.to_account_info();
+    
+    let listing: Listing = match read_layout::<Listing, ListingV0>(&listing_info)? {
+        Some(old) => old.into(),
+        None => read_layout::<Listing, ListingV1>(&listing_info)?
+            .ok_or(ErrorCode::AccountAlreadyMigrated)?
+            .into(),
+    };
+    write_migrated(
+        &listing_info,
+        &listing,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    msg!("Listing migrated to version {}: {}", Listing::VERSION, listing.nft_mint);
+    Ok(())
+}
+
+pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+    let pool_info = ctx.accounts.pool.to_account_info();
+    
+    let pool: Pool = read_layout::<Pool, PoolV0>(&pool_info)?
+        .ok_or(ErrorCode::AccountAlreadyMigrated)?
+        .into();
+    write_migrated(
+        &pool_info,
+        &pool,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    msg!("Pool migrated to version {}: {}", Pool::VERSION, pool_info.key());
+    Ok(())
+}
+
+pub fn migrate_stake_account(ctx: Context<MigrateStakeAccount>) -> Result<()> {
+    let stake_info = ctx.accounts.stake_account.to_account_info();
+    
+    let stake_account: StakeAccount = read_layout::<StakeAccount, StakeAccountV0>(&stake_info)?
+        .ok_or(ErrorCode::AccountAlreadyMigrated)?
+        .into();
+    write_migrated(
+        &stake_info,
+        &stake_account,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    msg!(
+        "Stake account migrated to version {}: {}",
+        StakeAccount::VERSION,
+        stake_account.nft_mint
+    );
+    Ok(())
+}
+
+pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+    let config_info = ctx.accounts.config.to_account_info();
+    
+    let config: PlatformConfig = read_layout::<PlatformConfig, PlatformConfigV0>(&config_info)?
+        .ok_or(ErrorCode::AccountAlreadyMigrated)?
+        .into();
+    write_migrated(
+        &config_info,
+        &config,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    msg!("Platform config migrated to version {}", PlatformConfig::VERSION);
+    Ok(())
+}
@@ -6,6 +6,20 @@ pub mod review;
 pub mod staking;
 pub mod redemption;
 pub mod monk_token;
+pub mod verifier;
+pub mod delegate;
+pub mod expiry;
+pub mod compressed;
+pub mod migrate;
+pub mod pause;
+pub mod fee;
+pub mod referral;
+pub mod loyalty;
+pub mod gate;
+pub mod schedule;
+pub mod cart;
+pub mod gift;
+pub mod transfer_hook;
 
 pub use merchant::*;
 pub use listing::*;
@@ -14,4 +28,18 @@ pub use pool::*;
 pub use review::*;
 pub use staking::*;
 pub use redemption::*;
-pub use monk_token::*;
\ No newline at end of file
+pub use monk_token::*;
+pub use verifier::*;
+pub use delegate::*;
+pub use expiry::*;
+pub use compressed::*;
+pub use migrate::*;
+pub use pause::*;
+pub use fee::*;
+pub use referral::*;
+pub use loyalty::*;
+pub use gate::*;
+pub use schedule::*;
+pub use cart::*;
+pub use gift::*;
+pub use transfer_hook::*;
\ No newline at end of file
@@ -1,6 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{Mint, Token},
+    token::Token,
+    token_2022::Token2022,
+    token_interface::Mint,
+    token_2022_extensions::{
+        token_metadata_initialize, TokenMetadataInitialize,
+        spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_metadata_interface::state::TokenMetadata,
+    },
     metadata::{
         create_metadata_accounts_v3, CreateMetadataAccountsV3,
         mpl_token_metadata::types::DataV2, Metadata,
@@ -30,10 +37,11 @@ pub struct InitializeMonkMint<'info> {
         mint::decimals = MONK_DECIMALS,
         mint::authority = config,
         mint::freeze_authority = config,
+        mint::token_program = token_program,
         seeds = [b"monk_mint"],
         bump,
     )]
-    pub monk_mint: Account<'info, Mint>,
+    pub monk_mint: InterfaceAccount<'info, Mint>,
     
     /// CHECK: Metadata account derived via PDA
     #[account(
@@ -57,15 +65,67 @@ pub struct InitializeMonkMint<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn initialize_monk_mint(ctx: Context<InitializeMonkMint>) -> Result<()> {
-    let config = &mut ctx.accounts.config;
+#[derive(Accounts)]
+pub struct InitializeMonkMint2022<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + PlatformConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, PlatformConfig>,
     
-    config.authority = ctx.accounts.authority.key();
-    config.monk_mint = ctx.accounts.monk_mint.key();
-    config.platform_wallet = ctx.accounts.platform_wallet.key();
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = MONK_DECIMALS,
+        mint::authority = config,
+        mint::freeze_authority = config,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = config,
+        extensions::metadata_pointer::metadata_address = monk_mint,
+        seeds = [b"monk_mint"],
+        bump,
+    )]
+    pub monk_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Platform wallet to receive fees
+    pub platform_wallet: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+fn init_config(
+    config: &mut PlatformConfig,
+    authority: Pubkey,
+    monk_mint: Pubkey,
+    platform_wallet: Pubkey,
+    bump: u8,
+) {
+    config.authority = authority;
+    config.monk_mint = monk_mint;
+    config.platform_wallet = platform_wallet;
     config.platform_fee_bps = PLATFORM_FEE_BPS;
     config.staking_reward_rate = STAKING_REWARD_RATE;
-    config.bump = ctx.bumps.config;
+    config.bump = bump;
+    config.version = PlatformConfig::VERSION;
+}
+
+pub fn initialize_monk_mint(ctx: Context<InitializeMonkMint>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    
+    init_config(
+        config,
+        ctx.accounts.authority.key(),
+        ctx.accounts.monk_mint.key(),
+        ctx.accounts.platform_wallet.key(),
+        ctx.bumps.config,
+    );
     
     // PDA seeds for config signer
     let config_seeds: &[&[u8]] = &[
@@ -113,3 +173,75 @@ pub fn initialize_monk_mint(ctx: Context<InitializeMonkMint>) -> Result<()> {
     
     Ok(())
 }
+
+pub fn initialize_monk_mint_2022(
+    ctx: Context<InitializeMonkMint2022>,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Result<()> {
+    init_config(
+        &mut ctx.accounts.config,
+        ctx.accounts.authority.key(),
+        ctx.accounts.monk_mint.key(),
+        ctx.accounts.platform_wallet.key(),
+        ctx.bumps.config,
+    );
+    
+    // The metadata lives in the mint itself; top up rent for the TLV entry
+    // token-2022 appends when it is initialized
+    let metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey(ctx.accounts.config.key()),
+        mint: ctx.accounts.monk_mint.key(),
+        name: name.clone(),
+        symbol: symbol.clone(),
+        uri: uri.clone(),
+        additional_metadata: vec![],
+    };
+    let mint_info = ctx.accounts.monk_mint.to_account_info();
+    let new_len = mint_info.data_len() + metadata.tlv_size_of()?;
+    let top_up = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(mint_info.lamports());
+    if top_up > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: mint_info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    
+    let config_seeds: &[&[u8]] = &[
+        b"config",
+        &[ctx.accounts.config.bump],
+    ];
+    let config_signer = &[config_seeds];
+    
+    token_metadata_initialize(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TokenMetadataInitialize {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                metadata: mint_info.clone(),
+                update_authority: ctx.accounts.config.to_account_info(),
+                mint_authority: ctx.accounts.config.to_account_info(),
+                mint: mint_info,
+            },
+            config_signer,
+        ),
+        name,
+        symbol,
+        uri,
+    )?;
+    
+    msg!("✅ MONK token-2022 mint initialized successfully");
+    msg!("Mint address: {}", ctx.accounts.monk_mint.key());
+    msg!("Decimals: {}", MONK_DECIMALS);
+    
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::events::{PauseUpdated, GuardianUpdated};
+use crate::{PAUSE_ALL, PAUSE_STAKING};
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// Platform authority or guardian
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = signer.key() == config.authority || signer.key() == config.guardian @ ErrorCode::NotPlatformAuthority
+    )]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ ErrorCode::NotPlatformAuthority
+    )]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+pub fn set_paused(ctx: Context<SetPaused>, paused: u8) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let signer = ctx.accounts.signer.key();
+    
+    require!(paused & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    // The guardian is a hot key for emergencies: it can stop things, not restart them
+    if signer != config.authority {
+        require!(paused & config.paused == config.paused, ErrorCode::NotPlatformAuthority);
+    }
+    
+    // Stakes earn nothing while staking is paused, so keep count of that time
+    let now = Clock::get()?.unix_timestamp;
+    let staking_paused = paused & PAUSE_STAKING != 0;
+    if staking_paused && !config.is_paused(PAUSE_STAKING) {
+        config.staking_paused_at = now;
+    } else if !staking_paused && config.is_paused(PAUSE_STAKING) {
+        config.staking_paused_secs = config.staking_paused_total(now);
+        config.staking_paused_at = 0;
+    }
+    config.paused = paused;
+    
+    emit!(PauseUpdated {
+        paused,
+        updated_by: signer,
+    });
+    
+    msg!("Pause flags set to {:#08b}", paused);
+    Ok(())
+}
+
+pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+    ctx.accounts.config.guardian = guardian;
+    
+    emit!(GuardianUpdated { guardian });
+    
+    msg!("Guardian set to {}", guardian);
+    Ok(())
+}
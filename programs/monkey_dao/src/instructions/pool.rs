@@ -1,11 +1,16 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, CloseAccount, close_account},
 };
 use crate::state::*;
 use crate:: ANCHOR_DISCRIMINATOR;
 use crate::error::ErrorCode;
+use crate::events::{PoolCreated, PoolJoined, PoolCompleted, PoolSettled, PoolCouponClaimed, PoolCancelled, PoolDepositRefunded};
+use crate::instructions::referral::reward_referrer;
+use crate::instructions::gate::enforce_gate;
+use crate::instructions::transfer_hook::transfer_checked_with_hook;
+use crate::PAUSE_POOLS;
 
 #[derive(Accounts)]
 #[instruction(pool_size: u8)]
@@ -13,11 +18,19 @@ pub struct CreatePool<'info> {
     #[account(mut)]
     pub initiator: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_POOLS) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         seeds = [b"listing", listing.nft_mint.as_ref()],
         bump = listing.bump,
         constraint = listing.is_group_deal @ ErrorCode::NotGroupDeal,
         constraint = listing.is_active @ ErrorCode::ListingNotActive,
+        constraint = listing.custody == Custody::ListingVault @ ErrorCode::InvalidCustody,
     )]
     pub listing: Account<'info, Listing>,
     
@@ -46,6 +59,13 @@ pub struct JoinPool<'info> {
     #[account(mut)]
     pub participant: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_POOLS) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         mut,
         seeds = [b"pool", pool.listing.as_ref(), pool.initiator.as_ref()],
@@ -72,7 +92,50 @@ pub struct JoinPool<'info> {
     )]
     pub escrow: UncheckedAccount<'info>,
     
+    #[account(
+        seeds = [b"referrer", referrer.authority.as_ref()],
+        bump = referrer.bump,
+        constraint = referrer.authority != participant.key() @ ErrorCode::SelfReferral,
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// Required with a referrer, who is credited once the pool settles
+    #[account(
+        init,
+        payer = participant,
+        space = ANCHOR_DISCRIMINATOR + PoolReferral::INIT_SPACE,
+        seeds = [b"pool_referral", pool.key().as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub pool_referral: Option<Account<'info, PoolReferral>>,
+    
+    /// Taken by the pool once it fills
+    #[account(
+        mut,
+        address = pool.listing,
+        constraint = listing.custody == Custody::ListingVault @ ErrorCode::InvalidCustody,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// Required when the listing is gated
+    #[account(
+        constraint = listing.gate == Some(gate.key()) @ ErrorCode::GateRequired,
+    )]
+    pub gate: Option<Account<'info, ListingGate>>,
+    
+    #[account(
+        init_if_needed,
+        payer = participant,
+        space = ANCHOR_DISCRIMINATOR + GatePurchases::INIT_SPACE,
+        seeds = [b"gate_purchases", gate.as_ref().map(|gate| gate.key()).unwrap_or_default().as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub gate_purchases: Option<Account<'info, GatePurchases>>,
+    
     pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: for a collection gate, the participant's token
+    // account of an NFT in the collection, then that NFT's metadata
 }
 
 #[derive(Accounts)]
@@ -80,6 +143,12 @@ pub struct CompletePool<'info> {
     #[account(mut)]
     pub initiator: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         mut,
         seeds = [b"pool", pool.listing.as_ref(), initiator.key().as_ref()],
@@ -92,27 +161,36 @@ pub struct CompletePool<'info> {
     
     #[account(
         mut,
-        seeds = [b"listing", listing.nft_mint.as_ref()],
-        bump = listing.bump,
+        address = pool.listing,
+        constraint = listing.custody == Custody::Pool && listing.holder == pool.key() @ ErrorCode::InvalidCustody,
     )]
     pub listing: Account<'info, Listing>,
     
-    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        address = listing.merchant @ ErrorCode::Unauthorized,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(address = listing.nft_mint)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub vault: Account<'info, TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         init_if_needed,
         payer = initiator,
         associated_token::mint = nft_mint,
-        associated_token::authority = initiator,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub initiator_token_account: Account<'info, TokenAccount>,
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
     
     /// CHECK: Escrow account
     #[account(
@@ -122,23 +200,66 @@ pub struct CompletePool<'info> {
     )]
     pub escrow: UncheckedAccount<'info>,
     
-    /// CHECK: Seller to receive payment
-    #[account(mut)]
+    /// CHECK: Seller to receive payment and the vault's rent
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ ErrorCode::Unauthorized
+    )]
     pub seller: UncheckedAccount<'info>,
     
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: each of the pool's `PoolReferral`s followed by its
+    // `Referrer`, then the coupon's transfer hook accounts, if its mint has a hook
+}
+
+#[derive(Accounts)]
+pub struct ClaimPoolCoupon<'info> {
+    #[account(mut)]
+    pub initiator: Signer<'info>,
+    
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
+        seeds = [b"pool", pool.listing.as_ref(), initiator.key().as_ref()],
+        bump = pool.bump,
+        constraint = pool.initiator == initiator.key() @ ErrorCode::NotPoolInitiator,
+        constraint = pool.is_completed @ ErrorCode::PoolNotComplete,
     )]
-    pub config: Account<'info, PlatformConfig>,
+    pub pool: Account<'info, Pool>,
     
-    /// CHECK: Platform wallet
-    #[account(mut)]
-    pub platform_wallet: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = pool.listing,
+        constraint = listing.custody == Custody::Pool && listing.holder == pool.key() @ ErrorCode::InvalidCustody,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(address = listing.nft_mint)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = initiator,
+        associated_token::mint = nft_mint,
+        associated_token::authority = initiator,
+        associated_token::token_program = token_program,
+    )]
+    pub initiator_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
 }
 
 #[derive(Accounts)]
@@ -166,6 +287,110 @@ pub struct CancelPool<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct RefundPoolDeposit<'info> {
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", pool.listing.as_ref(), pool.initiator.as_ref()],
+        bump = pool.bump,
+        constraint = !pool.is_completed @ ErrorCode::PoolStillOpen,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        close = participant,
+        seeds = [b"pool_participant", pool.key().as_ref(), participant.key().as_ref()],
+        bump = pool_participant.bump,
+    )]
+    pub pool_participant: Account<'info, PoolParticipant>,
+    
+    /// CHECK: Escrow account
+    #[account(
+        mut,
+        seeds = [b"escrow", pool.key().as_ref()],
+        bump,
+    )]
+    pub escrow: UncheckedAccount<'info>,
+    
+    /// CHECK: The participant's `PoolReferral`, closed with their record if they joined with a referrer
+    #[account(
+        mut,
+        seeds = [b"pool_referral", pool.key().as_ref(), participant.key().as_ref()],
+        bump,
+    )]
+    pub pool_referral: UncheckedAccount<'info>,
+    
+    /// CHECK: The pool's listing, read only if it exists
+    #[account(address = pool.listing)]
+    pub listing: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut)]
+    pub initiator: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = initiator,
+        seeds = [b"pool", pool.listing.as_ref(), initiator.key().as_ref()],
+        bump = pool.bump,
+        constraint = pool.initiator == initiator.key() @ ErrorCode::NotPoolInitiator,
+        constraint = pool.is_closable() @ ErrorCode::PoolStillActive,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    /// CHECK: Mint of the pool's coupon, matched to the pool's listing by its seeds
+    #[account(
+        constraint = Pubkey::find_program_address(&[b"listing", nft_mint.key().as_ref()], &crate::ID).0
+            == pool.listing @ ErrorCode::InvalidCustody,
+    )]
+    pub nft_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: The pool's token account for the coupon, closed along with the
+    /// pool if it exists; it must be empty
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(&pool.key(), &nft_mint.key(), &token_program.key()),
+    )]
+    pub pool_token_account: UncheckedAccount<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePoolParticipant<'info> {
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    
+    /// CHECK: The record's pool, read only if it exists; the initiator may
+    /// already have closed it
+    #[account(address = pool_participant.pool)]
+    pub pool: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        close = participant,
+        seeds = [b"pool_participant", pool_participant.pool.as_ref(), participant.key().as_ref()],
+        bump = pool_participant.bump,
+    )]
+    pub pool_participant: Account<'info, PoolParticipant>,
+    
+    /// CHECK: The participant's `PoolReferral`, closed with their record if they joined with a referrer
+    #[account(
+        mut,
+        seeds = [b"pool_referral", pool_participant.pool.as_ref(), participant.key().as_ref()],
+        bump,
+    )]
+    pub pool_referral: UncheckedAccount<'info>,
+}
+
 pub fn create_pool(ctx: Context<CreatePool>, pool_size: u8) -> Result<()> {
     require!(
         pool_size == 2 || pool_size == 4 || pool_size == 6,
@@ -181,6 +406,7 @@ pub fn create_pool(ctx: Context<CreatePool>, pool_size: u8) -> Result<()> {
         6 => listing.deal_price_6.ok_or(ErrorCode::DealNotAvailable)?,
         _ => return Err(ErrorCode::InvalidPoolSize.into()),
     };
+    let price_per_person = listing.uses.remaining_share(price_per_person)?;
 
     let pool = &mut ctx.accounts.pool;
     pool.listing = listing.key();
@@ -194,15 +420,39 @@ pub fn create_pool(ctx: Context<CreatePool>, pool_size: u8) -> Result<()> {
     pool.participants = Vec::new();
     pool.created_at = clock.unix_timestamp;
     pool.bump = ctx.bumps.pool;
+    pool.version = Pool::VERSION;
+
+    emit!(PoolCreated {
+        pool: pool.key(),
+        listing: pool.listing,
+        initiator: pool.initiator,
+        pool_size,
+        price_per_person,
+    });
 
     msg!("Pool created for {} participants at {} lamports per person", pool_size, price_per_person);
     Ok(())
 }
 
-pub fn join_pool(ctx: Context<JoinPool>) -> Result<()> {
+pub fn join_pool<'info>(
+    ctx: Context<'_, '_, '_, 'info, JoinPool<'info>>,
+    allowlist_proof: Option<AllowlistProof>,
+) -> Result<()> {
+    enforce_gate(
+        &ctx.accounts.listing,
+        ctx.accounts.gate.as_ref(),
+        ctx.accounts.gate_purchases.as_mut(),
+        ctx.bumps.gate_purchases,
+        ctx.accounts.participant.key(),
+        allowlist_proof.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    
     let pool = &mut ctx.accounts.pool;
     let clock = Clock::get()?;
     
+    require!(ctx.accounts.listing.expiry_date > clock.unix_timestamp, ErrorCode::CouponExpired);
+    
     // Transfer funds to escrow
     anchor_lang::system_program::transfer(
         CpiContext::new(
@@ -215,6 +465,21 @@ pub fn join_pool(ctx: Context<JoinPool>) -> Result<()> {
         pool.price_per_person,
     )?;
 
+    // The referrer is credited if the pool settles, not for the deposit
+    match (&ctx.accounts.referrer, ctx.accounts.pool_referral.as_mut()) {
+        (Some(referrer), Some(pool_referral)) => {
+            pool_referral.pool = pool.key();
+            pool_referral.participant = ctx.accounts.participant.key();
+            pool_referral.referrer = referrer.key();
+            pool_referral.bump = ctx.bumps.pool_referral.ok_or(ErrorCode::PoolReferralsMissing)?;
+            pool_referral.version = PoolReferral::VERSION;
+            pool.referrals = pool.referrals.checked_add(1)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+        (None, None) => {}
+        _ => return err!(ErrorCode::PoolReferralsMissing),
+    }
+
     // Record participant
     let pool_participant = &mut ctx.accounts.pool_participant;
     pool_participant.pool = pool.key();
@@ -230,23 +495,349 @@ pub fn join_pool(ctx: Context<JoinPool>) -> Result<()> {
     pool.total_deposited = pool.total_deposited.checked_add(pool.price_per_person)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+    emit!(PoolJoined {
+        pool: pool.key(),
+        participant: ctx.accounts.participant.key(),
+        amount: pool.price_per_person,
+        current_participants: pool.current_participants,
+        pool_size: pool.pool_size,
+    });
+
     msg!("Participant joined pool: {}/{}", pool.current_participants, pool.pool_size);
     
     // Check if pool is complete
     if pool.current_participants == pool.pool_size {
+        // The coupon stays in the listing vault, but is the pool's now
+        let listing = &mut ctx.accounts.listing;
+        listing.holder = pool.key();
+        listing.custody = Custody::Pool;
+        
+        emit!(PoolCompleted {
+            pool: pool.key(),
+            listing: pool.listing,
+            total_deposited: pool.total_deposited,
+        });
         msg!("Pool is now complete!");
     }
 
     Ok(())
 }
 
+/// Settles a filled pool: the seller is paid from the escrow and the coupon
+/// moves from the listing vault into the pool's own token account. Pools pay
+/// no platform fee.
+pub fn complete_pool<'info>(ctx: Context<'_, '_, 'info, 'info, CompletePool<'info>>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let listing = &mut ctx.accounts.listing;
+    let pool_key = pool.key();
+    
+    // Every participant's referrer is credited now, with the MONK bonus
+    // only as pools pay no platform fee
+    let referral_count = 2 * pool.referrals as usize;
+    require!(ctx.remaining_accounts.len() >= referral_count, ErrorCode::PoolReferralsMissing);
+    let (referral_accounts, hook_accounts) = ctx.remaining_accounts.split_at(referral_count);
+    let mut referred = Vec::with_capacity(pool.referrals as usize);
+    for pair in referral_accounts.chunks(2) {
+        let (pool_referral, referrer_info) = (&pair[0], &pair[1]);
+        require!(pool_referral.owner == &crate::ID, ErrorCode::PoolReferralsMissing);
+        let pool_referral = PoolReferral::try_deserialize(&mut &pool_referral.try_borrow_data()?[..])?;
+        require!(
+            pool_referral.pool == pool_key
+                && pool_referral.referrer == referrer_info.key()
+                && !referred.contains(&pool_referral.participant),
+            ErrorCode::PoolReferralsMissing
+        );
+        referred.push(pool_referral.participant);
+        
+        let mut referrer = Account::<Referrer>::try_from(referrer_info)?;
+        reward_referrer(
+            &ctx.accounts.config,
+            &mut referrer,
+            pool_referral.participant,
+            pool.price_per_person,
+            0,
+        )?;
+        referrer.exit(&crate::ID)?;
+    }
+    
+    // Pay the seller everything the participants deposited
+    let escrow_seeds = &[
+        b"escrow",
+        pool_key.as_ref(),
+        &[ctx.bumps.escrow],
+    ];
+    let escrow_signer = &[&escrow_seeds[..]];
+    
+    anchor_lang::system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.seller.to_account_info(),
+            },
+            escrow_signer,
+        ),
+        pool.total_deposited,
+    )?;
+    
+    // Move the coupon from the listing vault to the pool
+    let seeds = &[
+        b"listing",
+        listing.nft_mint.as_ref(),
+        &[listing.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.pool_token_account.to_account_info(),
+            authority: listing.to_account_info(),
+        },
+        signer,
+    )
+    .with_remaining_accounts(hook_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+    
+    // Close the now empty vault, rent goes back to the seller who opened it
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.vault.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: listing.to_account_info(),
+        },
+        signer,
+    );
+    close_account(close_ctx)?;
+    
+    listing.is_active = false;
+    listing.holder = pool_key;
+    listing.custody = Custody::Pool;
+    listing.min_tier = LoyaltyTier::Bronze;
+    listing.early_access_until = 0;
+    listing.gate = None;
+    listing.total_sales = listing.total_sales.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    // Sales by the merchant itself are primary and count toward its fee tier
+    let merchant = &mut ctx.accounts.merchant;
+    if listing.seller == merchant.authority {
+        merchant.total_sales_volume = merchant.total_sales_volume.checked_add(pool.total_deposited)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    
+    pool.is_active = false;
+    pool.is_completed = true;
+    
+    emit!(PoolSettled {
+        pool: pool_key,
+        listing: listing.key(),
+        seller: listing.seller,
+        amount: pool.total_deposited,
+    });
+    
+    msg!("Pool settled, seller paid {} lamports", pool.total_deposited);
+    Ok(())
+}
+
+/// Hands a settled pool's coupon to its initiator, who holds it for the
+/// group and redeems it for everyone. The pool's token account is closed,
+/// its rent going back to the initiator who opened it in `complete_pool`.
+pub fn claim_pool_coupon<'info>(ctx: Context<'_, '_, '_, 'info, ClaimPoolCoupon<'info>>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let seeds = &[
+        b"pool",
+        pool.listing.as_ref(),
+        pool.initiator.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.pool_token_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.initiator_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        },
+        signer,
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+    
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.pool_token_account.to_account_info(),
+            destination: ctx.accounts.initiator.to_account_info(),
+            authority: pool.to_account_info(),
+        },
+        signer,
+    );
+    close_account(close_ctx)?;
+    
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = pool.initiator;
+    listing.custody = Custody::Wallet;
+    
+    emit!(PoolCouponClaimed {
+        pool: pool.key(),
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        initiator: pool.initiator,
+    });
+    
+    msg!("Pool coupon claimed by {}", pool.initiator);
+    Ok(())
+}
+
 pub fn cancel_pool(ctx: Context<CancelPool>) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
     
-    require!(pool.current_participants == 0, ErrorCode::PoolNotActive);
+    // A filled pool holds the listing's coupon and is settled instead;
+    // anyone already in an unfilled one takes their deposit back
+    require!(pool.current_participants < pool.pool_size, ErrorCode::PoolFull);
 
     pool.is_active = false;
     
+    emit!(PoolCancelled {
+        pool: pool.key(),
+        listing: pool.listing,
+    });
+    
     msg!("Pool cancelled");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Returns a participant's deposit once their pool can no longer settle:
+/// it was cancelled, or its listing was sold elsewhere, delisted or expired
+/// before the pool filled. Refunds stay open while pools are paused.
+pub fn refund_pool_deposit(ctx: Context<RefundPoolDeposit>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let pool_key = pool.key();
+    let listing_info = ctx.accounts.listing.to_account_info();
+    
+    if pool.is_active {
+        let listing_open = listing_info.owner == &crate::ID && {
+            let listing = Listing::try_deserialize(&mut &listing_info.try_borrow_data()?[..])?;
+            let held_for_pool = listing.custody == Custody::Pool && listing.holder == pool_key;
+            // Once filled, the coupon is the pool's and the initiator settles it
+            held_for_pool
+                || (listing.is_active
+                    && listing.custody == Custody::ListingVault
+                    && listing.expiry_date > Clock::get()?.unix_timestamp)
+        };
+        require!(!listing_open, ErrorCode::PoolStillOpen);
+        // Nothing can join or settle the pool from here on
+        pool.is_active = false;
+    }
+    
+    let participant = ctx.accounts.participant.key();
+    let amount = ctx.accounts.pool_participant.amount_deposited;
+    
+    let escrow_seeds = &[
+        b"escrow",
+        pool_key.as_ref(),
+        &[ctx.bumps.escrow],
+    ];
+    let escrow_signer = &[&escrow_seeds[..]];
+    
+    anchor_lang::system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.participant.to_account_info(),
+            },
+            escrow_signer,
+        ),
+        amount,
+    )?;
+    
+    if close_pool_referral(&ctx.accounts.pool_referral, &ctx.accounts.participant)? {
+        pool.referrals = pool.referrals.checked_sub(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    
+    pool.participants.retain(|key| key != &participant);
+    pool.current_participants = pool.current_participants.checked_sub(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    pool.total_deposited = pool.total_deposited.checked_sub(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(PoolDepositRefunded {
+        pool: pool_key,
+        participant,
+        amount,
+        current_participants: pool.current_participants,
+    });
+    
+    msg!("Refunded {} lamports from pool", amount);
+    Ok(())
+}
+
+/// Closes a settled or cancelled pool. A pool's token account left empty,
+/// or opened again by someone else after the coupon was claimed, is closed
+/// with it, since nothing could sign for it once the pool is gone.
+pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+    let pool_token_account = ctx.accounts.pool_token_account.to_account_info();
+    if !pool_token_account.data_is_empty() {
+        let token_account = TokenAccount::try_deserialize(&mut &pool_token_account.try_borrow_data()?[..])?;
+        require!(token_account.amount == 0, ErrorCode::PoolHoldsCoupon);
+        
+        let pool = &ctx.accounts.pool;
+        let seeds = &[
+            b"pool",
+            pool.listing.as_ref(),
+            pool.initiator.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: pool_token_account,
+                destination: ctx.accounts.initiator.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer,
+        ))?;
+    }
+    
+    msg!("Pool closed");
+    Ok(())
+}
+
+pub fn close_pool_participant(ctx: Context<ClosePoolParticipant>) -> Result<()> {
+    let pool_info = ctx.accounts.pool.to_account_info();
+    if pool_info.owner == &crate::ID {
+        let pool = Pool::try_deserialize(&mut &pool_info.try_borrow_data()?[..])?;
+        require!(pool.is_closable(), ErrorCode::PoolStillActive);
+    }
+    close_pool_referral(&ctx.accounts.pool_referral, &ctx.accounts.participant)?;
+    
+    msg!("Pool participant record closed");
+    Ok(())
+}
+
+/// Closes a participant's `PoolReferral` to them, if they joined with a
+/// referrer. Returns whether there was one.
+fn close_pool_referral<'info>(
+    pool_referral: &UncheckedAccount<'info>,
+    participant: &Signer<'info>,
+) -> Result<bool> {
+    if pool_referral.owner != &crate::ID {
+        return Ok(false);
+    }
+    
+    let lamports = pool_referral.lamports();
+    **pool_referral.try_borrow_mut_lamports()? -= lamports;
+    **participant.try_borrow_mut_lamports()? += lamports;
+    pool_referral.assign(&anchor_lang::system_program::ID);
+    pool_referral.realloc(0, false)?;
+    Ok(true)
+}
@@ -1,16 +1,25 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{ Mint as SplMint, Token as SplTokenProgram, TokenAccount as SplTokenAccount, Burn, burn},
+    token_interface::{Mint, TokenInterface, TokenAccount, Burn, burn, CloseAccount, close_account},
 };
 use crate::{state::*};
 use crate::error::ErrorCode;
+use crate::events::CouponRedeemed;
+use crate::{ANCHOR_DISCRIMINATOR, PAUSE_REDEMPTION};
 
 #[derive(Accounts)]
 pub struct RedeemNFT<'info> {
     #[account(mut)]
     pub redeemer: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_REDEMPTION) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         mut,
         seeds = [b"merchant", merchant.authority.as_ref()],
@@ -18,8 +27,15 @@ pub struct RedeemNFT<'info> {
     )]
     pub merchant: Account<'info, Merchant>,
     
-    #[account(mut)]
-    pub merchant_authority: Signer<'info>,
+    /// Merchant authority or one of its redemption delegates
+    pub approver: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegate", merchant.key().as_ref(), approver.key().as_ref()],
+        bump = merchant_delegate.bump,
+    )]
+    pub merchant_delegate: Option<Account<'info, MerchantDelegate>>,
     
     #[account(
         mut,
@@ -30,34 +46,104 @@ pub struct RedeemNFT<'info> {
     )]
     pub listing: Account<'info, Listing>,
     
+    /// One per redemption of the coupon, so terminals redeeming other
+    /// coupons of the merchant at the same time don't race for it
+    #[account(
+        init,
+        payer = redeemer,
+        space = ANCHOR_DISCRIMINATOR + RedemptionReceipt::INIT_SPACE,
+        seeds = [b"receipt", listing.key().as_ref(), listing.uses.spent().to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, RedemptionReceipt>,
+    
     #[account(mut)]
-    pub nft_mint: Account<'info, SplMint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = redeemer,
+        associated_token::token_program = token_program,
         constraint = redeemer_token_account.amount == 1 @ ErrorCode::Unauthorized,
     )]
-    pub redeemer_token_account: Account<'info, SplTokenAccount>,
+    pub redeemer_token_account: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, SplTokenProgram>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// Checks that `approver` is the merchant authority or one of its active
+/// redemption delegates, counting the approval against the delegate.
+pub fn authorize_redemption(
+    merchant: &Merchant,
+    approver: Pubkey,
+    merchant_delegate: Option<&mut Account<MerchantDelegate>>,
+    current_time: i64,
+) -> Result<()> {
+    if merchant.authority == approver {
+        return Ok(());
+    }
+    
+    let merchant_delegate = merchant_delegate.ok_or(ErrorCode::Unauthorized)?;
+    require!(
+        merchant_delegate.can_approve_redemption(current_time),
+        ErrorCode::DelegateNotAuthorized
+    );
+    merchant_delegate.total_redemptions = merchant_delegate.total_redemptions
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Writes `record` into the receipt PDA, advances the merchant's settlement
+/// counters and emits `CouponRedeemed`.
+pub fn record_redemption(
+    merchant: &mut Account<Merchant>,
+    receipt: &mut Account<RedemptionReceipt>,
+    record: RedemptionReceipt,
+    remaining: u64,
+) -> Result<()> {
+    merchant.total_redemptions = merchant.total_redemptions.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    merchant.total_value_honored = merchant.total_value_honored.checked_add(record.value)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(CouponRedeemed {
+        receipt: receipt.key(),
+        merchant: record.merchant,
+        listing: record.listing,
+        nft_mint: record.nft_mint,
+        redeemer: record.redeemer,
+        approver: record.approver,
+        index: record.index,
+        amount: record.amount,
+        value: record.value,
+        remaining,
+        redeemed_at: record.redeemed_at,
+    });
+    
+    receipt.set_inner(record);
+    Ok(())
+}
+
 pub fn redeem_nft(
     ctx: Context<RedeemNFT>,
     signature: Vec<u8>,
+    amount: u64,
 ) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     let clock = Clock::get()?;
     
-    // Verify merchant authority
-    require!(
-        ctx.accounts.merchant.authority == ctx.accounts.merchant_authority.key(),
-        ErrorCode::Unauthorized
-    );
+    // Verify merchant authority or an active delegate
+    let approver = ctx.accounts.approver.key();
+    authorize_redemption(
+        &ctx.accounts.merchant,
+        approver,
+        ctx.accounts.merchant_delegate.as_mut(),
+        clock.unix_timestamp,
+    )?;
     
     // Check expiry
     require!(
@@ -68,23 +154,77 @@ pub fn redeem_nft(
     // Simple length check for signature (replace with actual verification if required)
     require!(signature.len() == 64, ErrorCode::InvalidSignature);
     
-    // Mark coupon as used
-    listing.is_used = true;
-    
-    // Burn the NFT (1 token)
-    let burn_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Burn {
-            mint: ctx.accounts.nft_mint.to_account_info(),
-            from: ctx.accounts.redeemer_token_account.to_account_info(),
-            authority: ctx.accounts.redeemer.to_account_info(),
-        },
-    );
-    burn(burn_ctx, 1)?;
+    // Single-use coupons always spend their one use
+    let amount = match listing.uses.use_method {
+        UseMethod::Single => 1,
+        _ => amount,
+    };
+    require!(amount > 0, ErrorCode::InvalidUses);
+    require!(amount <= listing.uses.remaining, ErrorCode::InsufficientUses);
+    
+    // Value honored is the redeemed share of the original price
+    let value = listing.uses.share_of(amount, listing.original_price)?;
+    listing.uses.remaining -= amount;
+    // The redeemer showed the coupon in its own wallet
+    listing.holder = ctx.accounts.redeemer.key();
+    listing.custody = Custody::Wallet;
+    
+    if listing.uses.remaining == 0 {
+        // Mark coupon as used
+        listing.is_used = true;
+        listing.custody = Custody::Redeemed;
+        
+        // Burn the NFT (1 token)
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                from: ctx.accounts.redeemer_token_account.to_account_info(),
+                authority: ctx.accounts.redeemer.to_account_info(),
+            },
+        );
+        burn(burn_ctx, 1)?;
+        
+        // Return the rent of the emptied token account to the redeemer
+        let close_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.redeemer_token_account.to_account_info(),
+                destination: ctx.accounts.redeemer.to_account_info(),
+                authority: ctx.accounts.redeemer.to_account_info(),
+            },
+        );
+        close_account(close_ctx)?;
+        
+        msg!("NFT coupon redeemed and burned successfully");
+    } else {
+        msg!("NFT coupon redeemed: {}/{} remaining", listing.uses.remaining, listing.uses.total);
+    }
+    
+    // Record the redemption
+    let record = RedemptionReceipt {
+        merchant: ctx.accounts.merchant.key(),
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        redeemer: ctx.accounts.redeemer.key(),
+        approver,
+        index: ctx.accounts.merchant.total_redemptions,
+        amount,
+        value,
+        redeemed_at: clock.unix_timestamp,
+        bump: ctx.bumps.receipt,
+    };
+    record_redemption(
+        &mut ctx.accounts.merchant,
+        &mut ctx.accounts.receipt,
+        record,
+        listing.uses.remaining,
+    )?;
     
-    msg!("NFT coupon redeemed and burned successfully");
+    msg!("Amount redeemed: {}", amount);
     msg!("Redeemer: {}", ctx.accounts.redeemer.key());
     msg!("Merchant: {}", ctx.accounts.merchant.business_name);
+    msg!("Approved by: {}", approver);
     
     Ok(())
 }
@@ -0,0 +1,224 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenInterface, TokenAccount, MintTo, mint_to},
+};
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{ReferrerRegistered, ReferralRewarded, ReferralRewardsClaimed, ReferralRewardsUpdated};
+use crate::MAX_REFERRAL_MONK_BPS;
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + Referrer::INIT_SPACE,
+        seeds = [b"referrer", authority.key().as_ref()],
+        bump
+    )]
+    pub referrer: Account<'info, Referrer>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"referrer", authority.key().as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Account<'info, Referrer>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
+        constraint = monk_mint.key() == config.monk_mint @ ErrorCode::Unauthorized
+    )]
+    pub monk_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = monk_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = monk_token_program,
+    )]
+    pub authority_monk_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + UserStats::INIT_SPACE,
+        seeds = [b"user_stats", authority.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+    
+    pub monk_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferralRewards<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ ErrorCode::NotPlatformAuthority
+    )]
+    pub config: Account<'info, PlatformConfig>,
+}
+
+/// Credits `referrer` for a referred purchase, or a share of a settled pool,
+/// of `amount` that paid `platform_fee`. Returns the referrer's share of that
+/// fee, which the caller pays into the referrer account instead of the
+/// platform wallet.
+pub fn reward_referrer(
+    config: &PlatformConfig,
+    referrer: &mut Account<Referrer>,
+    buyer: Pubkey,
+    amount: u64,
+    platform_fee: u64,
+) -> Result<u64> {
+    let fee_share = (platform_fee as u128)
+        .checked_mul(config.referral_fee_share_bps as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / 10000;
+    let fee_share = fee_share as u64;
+    let monk_reward = (amount as u128)
+        .checked_mul(config.referral_monk_bps as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / 10000;
+    let monk_reward = monk_reward as u64;
+    
+    referrer.total_referrals = referrer.total_referrals.checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    referrer.total_referred_volume = referrer.total_referred_volume.checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    referrer.total_fees_earned = referrer.total_fees_earned.checked_add(fee_share)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    referrer.total_monk_earned = referrer.total_monk_earned.checked_add(monk_reward)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    referrer.pending_monk = referrer.pending_monk.checked_add(monk_reward)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    
+    emit!(ReferralRewarded {
+        referrer: referrer.key(),
+        buyer,
+        amount,
+        fee_share,
+        monk_reward,
+    });
+    
+    Ok(fee_share)
+}
+
+pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+    let referrer = &mut ctx.accounts.referrer;
+    referrer.authority = ctx.accounts.authority.key();
+    referrer.registered_at = Clock::get()?.unix_timestamp;
+    referrer.bump = ctx.bumps.referrer;
+    referrer.version = Referrer::VERSION;
+    
+    emit!(ReferrerRegistered {
+        referrer: referrer.key(),
+        authority: referrer.authority,
+    });
+    
+    msg!("Referrer registered: {}", referrer.authority);
+    Ok(())
+}
+
+pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+    let referrer_info = ctx.accounts.referrer.to_account_info();
+    
+    // Fee shares were paid into the referrer account on top of its rent
+    let rent = Rent::get()?.minimum_balance(referrer_info.data_len());
+    let lamports = referrer_info.lamports().saturating_sub(rent);
+    let monk = ctx.accounts.referrer.pending_monk;
+    require!(lamports > 0 || monk > 0, ErrorCode::NoReferralRewards);
+    
+    if lamports > 0 {
+        **referrer_info.try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += lamports;
+    }
+    
+    if monk > 0 {
+        let config_seeds: &[&[u8]] = &[
+            b"config",
+            &[ctx.accounts.config.bump],
+        ];
+        let config_signer = &[config_seeds];
+        
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.monk_token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.monk_mint.to_account_info(),
+                to: ctx.accounts.authority_monk_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            config_signer,
+        );
+        mint_to(mint_ctx, monk)?;
+        ctx.accounts.referrer.pending_monk = 0;
+        
+        // Referral MONK counts toward the referrer's earnings like any other
+        let user_stats = &mut ctx.accounts.user_stats;
+        if user_stats.user == Pubkey::default() {
+            user_stats.user = ctx.accounts.authority.key();
+            user_stats.bump = ctx.bumps.user_stats;
+        }
+        user_stats.total_monk_earned = user_stats.total_monk_earned.checked_add(monk)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    
+    emit!(ReferralRewardsClaimed {
+        referrer: ctx.accounts.referrer.key(),
+        authority: ctx.accounts.authority.key(),
+        lamports,
+        monk,
+    });
+    
+    msg!("Referral rewards claimed: {} lamports, {} MONK", lamports, monk);
+    Ok(())
+}
+
+pub fn set_referral_rewards(
+    ctx: Context<SetReferralRewards>,
+    fee_share_bps: u16,
+    monk_bps: u16,
+) -> Result<()> {
+    require!(
+        fee_share_bps <= 10000 && monk_bps <= MAX_REFERRAL_MONK_BPS,
+        ErrorCode::InvalidReferralRewards
+    );
+    
+    let config = &mut ctx.accounts.config;
+    config.referral_fee_share_bps = fee_share_bps;
+    config.referral_monk_bps = monk_bps;
+    
+    emit!(ReferralRewardsUpdated {
+        fee_share_bps,
+        monk_bps,
+    });
+    
+    msg!("Referral rewards updated: {} bps of fees, {} bps in MONK", fee_share_bps, monk_bps);
+    Ok(())
+}
@@ -2,13 +2,21 @@ use anchor_lang::prelude::*;
 use crate::state::*;
 use crate:: ANCHOR_DISCRIMINATOR;
 use crate::error::ErrorCode;
-use crate::MAX_RATING;
+use crate::events::ReviewAdded;
+use crate::{MAX_RATING, PAUSE_REVIEWS};
 
 #[derive(Accounts)]
 pub struct AddReview<'info> {
     #[account(mut)]
     pub reviewer: Signer<'info>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_REVIEWS) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
     #[account(
         mut,
         seeds = [b"listing", listing.nft_mint.as_ref()],
@@ -33,7 +41,7 @@ pub fn add_review(
     rating: u8,
     comment: String,
 ) -> Result<()> {
-    require!(rating >= 1 && rating <= MAX_RATING, ErrorCode::InvalidRating);
+    require!((1..=MAX_RATING).contains(&rating), ErrorCode::InvalidRating);
     require!(comment.len() <= 500, ErrorCode::InvalidRating);
     
     let clock = Clock::get()?;
@@ -49,14 +57,25 @@ pub fn add_review(
     // Update listing average rating
     let listing = &mut ctx.accounts.listing;
     let total_reviews = listing.total_reviews;
-    let current_total = (listing.average_rating as u64) * total_reviews;
-    let new_total = current_total + (rating as u64 * 20); // Convert 1-5 to 20-100
+    let current_total = (listing.average_rating as u64).checked_mul(total_reviews)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    let new_total = current_total.checked_add(rating as u64 * 20) // Convert 1-5 to 20-100
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
     let new_count = total_reviews.checked_add(1)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
     
     listing.average_rating = (new_total / new_count) as u8;
     listing.total_reviews = new_count;
 
+    emit!(ReviewAdded {
+        review: review.key(),
+        listing: listing.key(),
+        reviewer: review.reviewer,
+        rating,
+        average_rating: listing.average_rating,
+        total_reviews: listing.total_reviews,
+    });
+
     msg!("Review added with rating: {}/5", rating);
     msg!("New average rating: {}", listing.average_rating as f32 / 20.0);
     
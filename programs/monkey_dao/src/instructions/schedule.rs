@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::ListingScheduled;
+use crate::MAX_FLASH_SALES;
+
+#[derive(Accounts)]
+pub struct SetListingSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"merchant", authority.key().as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    /// Only the merchant's own listings, while it still sells them
+    #[account(
+        seeds = [b"listing", listing.nft_mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.merchant == merchant.key() @ ErrorCode::Unauthorized,
+        constraint = listing.seller == authority.key() @ ErrorCode::Unauthorized,
+        constraint = listing.is_active @ ErrorCode::ListingNotActive,
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + ListingSchedule::INIT_SPACE,
+        seeds = [b"schedule", listing.key().as_ref()],
+        bump
+    )]
+    pub schedule: Account<'info, ListingSchedule>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Price `listing` sells at now, from its `ListingSchedule` PDA `schedule`
+/// if it has one. Before the schedule's start time it does not sell at all.
+pub fn scheduled_price(schedule: &AccountInfo, listing: &Listing, current_time: i64) -> Result<u64> {
+    if schedule.owner != &crate::ID {
+        return Ok(listing.current_price);
+    }
+    let Some(schedule) = schedule
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| ListingSchedule::try_deserialize(&mut &data[..]).ok())
+        .filter(|schedule| schedule.seller == listing.seller)
+    else {
+        return Ok(listing.current_price);
+    };
+    
+    require!(current_time >= schedule.start_time, ErrorCode::ListingNotStarted);
+    Ok(schedule.effective_price(listing.current_price, current_time))
+}
+
+pub fn set_listing_schedule(
+    ctx: Context<SetListingSchedule>,
+    start_time: i64,
+    flash_sales: Vec<FlashSale>,
+) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(flash_sales.len() <= MAX_FLASH_SALES, ErrorCode::InvalidSchedule);
+    require!(start_time < listing.expiry_date, ErrorCode::InvalidSchedule);
+    require!(
+        flash_sales.iter().all(|sale| {
+            start_time <= sale.starts_at
+                && sale.starts_at < sale.ends_at
+                && sale.ends_at <= listing.expiry_date
+                && sale.price > 0
+                && sale.price < listing.current_price
+        }),
+        ErrorCode::InvalidSchedule
+    );
+    require!(
+        flash_sales.windows(2).all(|pair| pair[0].ends_at <= pair[1].starts_at),
+        ErrorCode::InvalidSchedule
+    );
+    
+    let schedule = &mut ctx.accounts.schedule;
+    schedule.listing = listing.key();
+    schedule.seller = listing.seller;
+    schedule.start_time = start_time;
+    schedule.flash_sales = flash_sales;
+    schedule.bump = ctx.bumps.schedule;
+    schedule.version = ListingSchedule::VERSION;
+    
+    emit!(ListingScheduled {
+        listing: schedule.listing,
+        start_time,
+        flash_sales: schedule.flash_sales.clone(),
+    });
+    
+    msg!("Listing scheduled with {} flash sales", schedule.flash_sales.len());
+    Ok(())
+}
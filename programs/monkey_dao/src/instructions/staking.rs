@@ -1,11 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount, Transfer, transfer, MintTo, mint_to},
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, MintTo, mint_to, CloseAccount, close_account},
 };
 use crate::state::*;
 use crate:: ANCHOR_DISCRIMINATOR;
 use crate::error::ErrorCode;
+use crate::events::{CouponStaked, CouponUnstaked, StakingRewardsClaimed};
+use crate::instructions::transfer_hook::transfer_checked_with_hook;
+use crate::PAUSE_STAKING;
 
 #[derive(Accounts)]
 pub struct StakeNFT<'info> {
@@ -13,24 +16,34 @@ pub struct StakeNFT<'info> {
     pub owner: Signer<'info>,
     
     #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.is_paused(PAUSE_STAKING) @ ErrorCode::Paused,
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        mut,
         seeds = [b"listing", nft_mint.key().as_ref()],
         bump = listing.bump,
         constraint = !listing.is_used @ ErrorCode::CannotStakeUsedCoupon,
+        constraint = listing.custody == Custody::Wallet @ ErrorCode::InvalidCustody,
     )]
     pub listing: Account<'info, Listing>,
     
-    pub nft_mint: Account<'info, Mint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = owner,
+        associated_token::token_program = token_program,
         constraint = owner_token_account.amount == 1 @ ErrorCode::Unauthorized,
     )]
-    pub owner_token_account: Account<'info, TokenAccount>,
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
-        init,
+        init_if_needed,
         payer = owner,
         space = ANCHOR_DISCRIMINATOR + StakeAccount::INIT_SPACE,
         seeds = [b"stake", nft_mint.key().as_ref()],
@@ -39,12 +52,13 @@ pub struct StakeNFT<'info> {
     pub stake_account: Account<'info, StakeAccount>,
     
     #[account(
-        init,
+        init_if_needed,
         payer = owner,
         associated_token::mint = nft_mint,
         associated_token::authority = stake_account,
+        associated_token::token_program = token_program,
     )]
-    pub stake_vault: Account<'info, TokenAccount>,
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
@@ -53,9 +67,11 @@ pub struct StakeNFT<'info> {
     )]
     pub user_stats: Account<'info, UserStats>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
 }
 
 #[derive(Accounts)]
@@ -63,10 +79,18 @@ pub struct UnstakeNFT<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
-    pub nft_mint: Account<'info, Mint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, Listing>,
     
     #[account(
         mut,
+        close = owner,
         seeds = [b"stake", nft_mint.key().as_ref()],
         bump = stake_account.bump,
         constraint = stake_account.owner == owner.key() @ ErrorCode::Unauthorized,
@@ -78,15 +102,17 @@ pub struct UnstakeNFT<'info> {
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = stake_account,
+        associated_token::token_program = token_program,
     )]
-    pub stake_vault: Account<'info, TokenAccount>,
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = owner,
+        associated_token::token_program = token_program,
     )]
-    pub owner_token_account: Account<'info, TokenAccount>,
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         seeds = [b"config"],
@@ -98,14 +124,15 @@ pub struct UnstakeNFT<'info> {
         mut,
         constraint = monk_mint.key() == config.monk_mint @ ErrorCode::Unauthorized
     )]
-    pub monk_mint: Account<'info, Mint>,
+    pub monk_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = monk_mint,
         associated_token::authority = owner,
+        associated_token::token_program = monk_token_program,
     )]
-    pub owner_monk_account: Account<'info, TokenAccount>,
+    pub owner_monk_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
@@ -114,8 +141,11 @@ pub struct UnstakeNFT<'info> {
     )]
     pub user_stats: Account<'info, UserStats>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub monk_token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: the coupon's transfer hook accounts, if its mint has a hook
 }
 
 #[derive(Accounts)]
@@ -123,7 +153,7 @@ pub struct ClaimStakingRewards<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
-    pub nft_mint: Account<'info, Mint>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
@@ -137,6 +167,7 @@ pub struct ClaimStakingRewards<'info> {
     #[account(
         seeds = [b"config"],
         bump = config.bump,
+        constraint = !config.is_paused(PAUSE_STAKING) @ ErrorCode::Paused,
     )]
     pub config: Account<'info, PlatformConfig>,
     
@@ -144,14 +175,15 @@ pub struct ClaimStakingRewards<'info> {
         mut,
         constraint = monk_mint.key() == config.monk_mint @ ErrorCode::Unauthorized
     )]
-    pub monk_mint: Account<'info, Mint>,
+    pub monk_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
         associated_token::mint = monk_mint,
         associated_token::authority = owner,
+        associated_token::token_program = monk_token_program,
     )]
-    pub owner_monk_account: Account<'info, TokenAccount>,
+    pub owner_monk_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
@@ -160,50 +192,89 @@ pub struct ClaimStakingRewards<'info> {
     )]
     pub user_stats: Account<'info, UserStats>,
     
-    pub token_program: Program<'info, Token>,
+    pub monk_token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn stake_nft(ctx: Context<StakeNFT>) -> Result<()> {
+#[derive(Accounts)]
+pub struct CloseStakeAccount<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"stake", stake_account.nft_mint.as_ref()],
+        bump = stake_account.bump,
+        constraint = stake_account.owner == owner.key() @ ErrorCode::Unauthorized,
+        constraint = !stake_account.is_active @ ErrorCode::AlreadyStaked,
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+}
+
+pub fn stake_nft<'info>(ctx: Context<'_, '_, '_, 'info, StakeNFT<'info>>) -> Result<()> {
     let clock = Clock::get()?;
     let stake_account = &mut ctx.accounts.stake_account;
     
+    // An inactive stake account left over from an earlier stake is reset in full
+    require!(!stake_account.is_active, ErrorCode::AlreadyStaked);
+    
     stake_account.nft_mint = ctx.accounts.nft_mint.key();
     stake_account.owner = ctx.accounts.owner.key();
     stake_account.staked_at = clock.unix_timestamp;
     stake_account.last_claim = clock.unix_timestamp;
+    stake_account.paused_secs_at_claim = ctx.accounts.config.staking_paused_total(clock.unix_timestamp);
     stake_account.total_rewards_claimed = 0;
     stake_account.is_active = true;
+    stake_account.reward_weight_bps = ctx.accounts.listing.uses.remaining_bps()?;
     stake_account.bump = ctx.bumps.stake_account;
+    stake_account.version = StakeAccount::VERSION;
 
     // Transfer NFT to stake vault
     let transfer_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.owner_token_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
             to: ctx.accounts.stake_vault.to_account_info(),
             authority: ctx.accounts.owner.to_account_info(),
         },
-    );
-    transfer(transfer_ctx, 1)?;
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = stake_account.owner;
+    listing.custody = Custody::StakeVault;
 
     // Update user stats
     let user_stats = &mut ctx.accounts.user_stats;
     user_stats.nfts_staked = user_stats.nfts_staked.checked_add(1)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+    emit!(CouponStaked {
+        stake_account: stake_account.key(),
+        nft_mint: stake_account.nft_mint,
+        owner: stake_account.owner,
+        reward_weight_bps: stake_account.reward_weight_bps,
+        staked_at: stake_account.staked_at,
+    });
+
     msg!("NFT staked successfully");
     Ok(())
 }
 
-pub fn unstake_nft(ctx: Context<UnstakeNFT>) -> Result<()> {
+pub fn unstake_nft<'info>(ctx: Context<'_, '_, '_, 'info, UnstakeNFT<'info>>) -> Result<()> {
     let stake_account = &mut ctx.accounts.stake_account;
     let clock = Clock::get()?;
     
-    // Calculate and mint pending rewards
+    // Calculate and mint pending rewards. Nothing accrues while staking is
+    // paused, but the coupon can always be taken back with what it earned
+    // before the pause.
     let rewards = stake_account.calculate_rewards(
         clock.unix_timestamp,
         ctx.accounts.config.staking_reward_rate,
+        ctx.accounts.config.staking_paused_total(clock.unix_timestamp),
     )?;
 
     if rewards > 0 {
@@ -214,7 +285,7 @@ pub fn unstake_nft(ctx: Context<UnstakeNFT>) -> Result<()> {
         let config_signer = &[&config_seeds[..]];
 
         let mint_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.monk_token_program.to_account_info(),
             MintTo {
                 mint: ctx.accounts.monk_mint.to_account_info(),
                 to: ctx.accounts.owner_monk_account.to_account_info(),
@@ -246,22 +317,48 @@ pub fn unstake_nft(ctx: Context<UnstakeNFT>) -> Result<()> {
 
     let transfer_ctx = CpiContext::new_with_signer(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
             to: ctx.accounts.owner_token_account.to_account_info(),
             authority: stake_account.to_account_info(),
         },
         signer,
+    )
+    .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+
+    // Close the empty stake vault; the stake account itself is closed by Anchor
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.stake_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: stake_account.to_account_info(),
+        },
+        signer,
     );
-    transfer(transfer_ctx, 1)?;
+    close_account(close_ctx)?;
 
     stake_account.is_active = false;
 
+    let listing = &mut ctx.accounts.listing;
+    listing.holder = stake_account.owner;
+    listing.custody = Custody::Wallet;
+
     // Update user stats
     let user_stats = &mut ctx.accounts.user_stats;
     user_stats.nfts_staked = user_stats.nfts_staked.checked_sub(1)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+    emit!(CouponUnstaked {
+        stake_account: stake_account.key(),
+        nft_mint: stake_account.nft_mint,
+        owner: stake_account.owner,
+        rewards,
+        total_rewards_claimed: stake_account.total_rewards_claimed,
+    });
+
     msg!("NFT unstaked successfully. Total rewards claimed: {}", rewards);
     Ok(())
 }
@@ -270,9 +367,11 @@ pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
     let stake_account = &mut ctx.accounts.stake_account;
     let clock = Clock::get()?;
     
+    let paused_total = ctx.accounts.config.staking_paused_total(clock.unix_timestamp);
     let rewards = stake_account.calculate_rewards(
         clock.unix_timestamp,
         ctx.accounts.config.staking_reward_rate,
+        paused_total,
     )?;
 
     require!(rewards > 0, ErrorCode::InsufficientTimeElapsed);
@@ -285,7 +384,7 @@ pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
     let config_signer = &[config_seeds];
 
     let mint_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.monk_token_program.to_account_info(),
         MintTo {
             mint: ctx.accounts.monk_mint.to_account_info(),
             to: ctx.accounts.owner_monk_account.to_account_info(),
@@ -297,6 +396,7 @@ pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
 
     // Update stake account
     stake_account.last_claim = clock.unix_timestamp;
+    stake_account.paused_secs_at_claim = paused_total;
     stake_account.total_rewards_claimed = stake_account.total_rewards_claimed
         .checked_add(rewards)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
@@ -307,6 +407,20 @@ pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
         .checked_add(rewards)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+    emit!(StakingRewardsClaimed {
+        stake_account: stake_account.key(),
+        nft_mint: stake_account.nft_mint,
+        owner: stake_account.owner,
+        rewards,
+        total_rewards_claimed: stake_account.total_rewards_claimed,
+    });
+
     msg!("Staking rewards claimed: {}", rewards);
     Ok(())
+}
+
+pub fn close_stake_account(_ctx: Context<CloseStakeAccount>) -> Result<()> {
+    // Stake accounts left inactive by earlier unstakes that did not close them
+    msg!("Inactive stake account closed");
+    Ok(())
 }
\ No newline at end of file
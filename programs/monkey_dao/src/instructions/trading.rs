@@ -1,12 +1,19 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount, Transfer, transfer, MintTo, mint_to},
+    token_interface::{Mint, TokenInterface, TokenAccount, TransferChecked, MintTo, mint_to, CloseAccount, close_account},
 };
 use crate::state::*;
 use crate::constants::*;
 use crate:: ANCHOR_DISCRIMINATOR;
 use crate::error::ErrorCode;
+use crate::events::CouponPurchased;
+use crate::instructions::fee::{fee_schedule_or_flat, sale_amounts, SaleAmounts};
+use crate::instructions::referral::reward_referrer;
+use crate::instructions::loyalty::{buyer_tier, check_listing_access};
+use crate::instructions::gate::enforce_gate;
+use crate::instructions::transfer_hook::{split_hook_accounts, transfer_checked_with_hook};
+use crate::instructions::schedule::scheduled_price;
 
 #[derive(Accounts)]
 pub struct BuyNFT<'info> {
@@ -20,33 +27,52 @@ pub struct BuyNFT<'info> {
     )]
     pub listing: Account<'info, Listing>,
     
-    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        address = listing.merchant @ ErrorCode::Unauthorized,
+    )]
+    pub merchant: Account<'info, Merchant>,
     
-    /// CHECK: Seller account to receive payment
-    #[account(mut)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Seller account to receive payment and the vault rent
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ ErrorCode::Unauthorized
+    )]
     pub seller: UncheckedAccount<'info>,
     
     #[account(
         mut,
         associated_token::mint = nft_mint,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub vault: Account<'info, TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         init_if_needed,
         payer = buyer,
         associated_token::mint = nft_mint,
         associated_token::authority = buyer,
+        associated_token::token_program = token_program,
     )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         seeds = [b"config"],
         bump = config.bump,
+        constraint = !config.is_paused(PAUSE_TRADING) @ ErrorCode::Paused,
     )]
     pub config: Account<'info, PlatformConfig>,
     
+    /// CHECK: Platform's `FeeSchedule`, read only if it exists; until one is set every sale pays `config.platform_fee_bps`
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump,
+    )]
+    pub fee_schedule: UncheckedAccount<'info>,
+    
     /// CHECK: Platform wallet to receive fees
     #[account(
         mut,
@@ -59,15 +85,16 @@ pub struct BuyNFT<'info> {
         mut,
         constraint = monk_mint.key() == config.monk_mint @ ErrorCode::Unauthorized
     )]
-    pub monk_mint: Account<'info, Mint>,
+    pub monk_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         init_if_needed,
         payer = buyer,
         associated_token::mint = monk_mint,
         associated_token::authority = buyer,
+        associated_token::token_program = monk_token_program,
     )]
-    pub buyer_monk_account: Account<'info, TokenAccount>,
+    pub buyer_monk_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         init_if_needed,
@@ -78,12 +105,68 @@ pub struct BuyNFT<'info> {
     )]
     pub user_stats: Account<'info, UserStats>,
     
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Buyer's `Loyalty`, read only if it exists; buyers without one are Bronze
+    #[account(
+        seeds = [b"loyalty", buyer.key().as_ref()],
+        bump,
+    )]
+    pub loyalty: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"referrer", referrer.authority.as_ref()],
+        bump = referrer.bump,
+        constraint = referrer.authority != buyer.key() @ ErrorCode::SelfReferral,
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// Required when the listing is gated
+    #[account(
+        constraint = listing.gate == Some(gate.key()) @ ErrorCode::GateRequired,
+    )]
+    pub gate: Option<Account<'info, ListingGate>>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = ANCHOR_DISCRIMINATOR + GatePurchases::INIT_SPACE,
+        seeds = [b"gate_purchases", gate.as_ref().map(|gate| gate.key()).unwrap_or_default().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub gate_purchases: Option<Account<'info, GatePurchases>>,
+    
+    /// CHECK: Listing's `ListingSchedule`, read only if it exists; unscheduled listings sell at their price
+    #[account(
+        seeds = [b"schedule", listing.key().as_ref()],
+        bump,
+    )]
+    pub schedule: UncheckedAccount<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub monk_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    
+    // remaining_accounts: for a collection gate, the buyer's token account
+    // of an NFT in the collection, then that NFT's metadata; then the
+    // coupon's transfer hook accounts if its mint has a hook
 }
 
-pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
+/// MONK rewarded for a purchase at `price`: 10% of it, more for loyal buyers.
+pub fn purchase_reward(price: u64, tier: LoyaltyTier) -> Result<u64> {
+    let monk_reward = (price as u128)
+        .checked_mul(PURCHASE_REWARD_BPS as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        .checked_mul(tier.reward_multiplier_bps() as u128)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / 100_000_000;
+    Ok(monk_reward as u64)
+}
+
+pub fn buy_nft<'info>(
+    ctx: Context<'_, '_, '_, 'info, BuyNFT<'info>>,
+    allowlist_proof: Option<AllowlistProof>,
+) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     let clock = Clock::get()?;
     
@@ -91,11 +174,45 @@ pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
     require!(listing.is_active, ErrorCode::ListingNotActive);
     require!(!listing.is_used, ErrorCode::CouponAlreadyUsed);
     require!(listing.expiry_date > clock.unix_timestamp, ErrorCode::CouponExpired);
+    // Still active, but already bought by a filled pool
+    require!(listing.custody == Custody::ListingVault, ErrorCode::InvalidCustody);
+    let tier = buyer_tier(&ctx.accounts.loyalty, clock.unix_timestamp);
+    check_listing_access(listing, tier, clock.unix_timestamp)?;
+    let (holding, hook_accounts) =
+        split_hook_accounts(&ctx.accounts.nft_mint.to_account_info(), ctx.remaining_accounts)?;
+    enforce_gate(
+        listing,
+        ctx.accounts.gate.as_ref(),
+        ctx.accounts.gate_purchases.as_mut(),
+        ctx.bumps.gate_purchases,
+        ctx.accounts.buyer.key(),
+        allowlist_proof.as_ref(),
+        holding,
+    )?;
 
-    let price = listing.current_price;
-    let platform_fee = (price * ctx.accounts.config.platform_fee_bps) / 10000;
-    let seller_amount = price.checked_sub(platform_fee)
-        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    let price = scheduled_price(&ctx.accounts.schedule, listing, clock.unix_timestamp)?;
+    // Sales by the merchant itself are primary; resales between users are secondary
+    let is_primary = listing.seller == ctx.accounts.merchant.authority;
+    let fee_schedule = fee_schedule_or_flat(&ctx.accounts.fee_schedule, &ctx.accounts.config)?;
+    let SaleAmounts { platform_fee, seller_amount } = sale_amounts(
+        &fee_schedule,
+        price,
+        is_primary,
+        ctx.accounts.merchant.total_sales_volume,
+        ctx.accounts.user_stats.nfts_staked > 0,
+        tier,
+    )?;
+    // A referrer takes its share out of the platform fee
+    let referral_fee = match ctx.accounts.referrer.as_mut() {
+        Some(referrer) => reward_referrer(
+            &ctx.accounts.config,
+            referrer,
+            ctx.accounts.buyer.key(),
+            price,
+            platform_fee,
+        )?,
+        None => 0,
+    };
 
     // Transfer SOL to seller
     anchor_lang::system_program::transfer(
@@ -118,8 +235,21 @@ pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
                 to: ctx.accounts.platform_wallet.to_account_info(),
             },
         ),
-        platform_fee,
+        platform_fee - referral_fee,
     )?;
+    
+    if let Some(referrer) = &ctx.accounts.referrer {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: referrer.to_account_info(),
+                },
+            ),
+            referral_fee,
+        )?;
+    }
 
     // Transfer NFT from vault to buyer
     let seeds = &[
@@ -131,24 +261,37 @@ pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
 
     let transfer_ctx = CpiContext::new_with_signer(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
             to: ctx.accounts.buyer_token_account.to_account_info(),
             authority: listing.to_account_info(),
         },
         signer,
+    )
+    .with_remaining_accounts(hook_accounts.to_vec());
+    transfer_checked_with_hook(transfer_ctx, 1, ctx.accounts.nft_mint.decimals)?;
+
+    // Close the now empty vault, rent goes back to the seller who opened it
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.vault.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: listing.to_account_info(),
+        },
+        signer,
     );
-    transfer(transfer_ctx, 1)?;
+    close_account(close_ctx)?;
 
-    // Calculate MONK token rewards (10% of price)
-    let monk_reward = (price * PURCHASE_REWARD_BPS) / 10000;
+    let monk_reward = purchase_reward(price, tier)?;
     
     
     let binding = [ctx.accounts.config.bump];
     let config_signer = &[&[b"config".as_ref(), &binding][..]];
 
     let mint_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.monk_token_program.to_account_info(),
         MintTo {
             mint: ctx.accounts.monk_mint.to_account_info(),
             to: ctx.accounts.buyer_monk_account.to_account_info(),
@@ -160,10 +303,21 @@ pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
 
     // Update listing
     listing.is_active = false;
-    listing.seller = ctx.accounts.buyer.key();
+    listing.holder = ctx.accounts.buyer.key();
+    listing.custody = Custody::Wallet;
+    // Tier gates are for the merchant's own drop, resales are open to everyone
+    listing.min_tier = LoyaltyTier::Bronze;
+    listing.early_access_until = 0;
+    listing.gate = None;
     listing.total_sales = listing.total_sales.checked_add(1)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+    if is_primary {
+        let merchant = &mut ctx.accounts.merchant;
+        merchant.total_sales_volume = merchant.total_sales_volume.checked_add(price)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+
     // Update user stats
     let user_stats = &mut ctx.accounts.user_stats;
     if user_stats.user == Pubkey::default() {
@@ -175,6 +329,18 @@ pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
     user_stats.total_monk_earned = user_stats.total_monk_earned.checked_add(monk_reward)
         .ok_or(ErrorCode::ArithmeticOverflow)?;
 
+    emit!(CouponPurchased {
+        listing: listing.key(),
+        nft_mint: listing.nft_mint,
+        seller: ctx.accounts.seller.key(),
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        platform_fee,
+        seller_amount,
+        monk_reward,
+        purchased_at: clock.unix_timestamp,
+    });
+
     msg!("NFT purchased successfully");
     msg!("Price paid: {} lamports", price);
     msg!("Platform fee: {} lamports", platform_fee);
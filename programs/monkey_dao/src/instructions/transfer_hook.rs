@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{transfer_hook, StateWithExtensions},
+    state::Mint,
+};
+use anchor_spl::token_interface::TransferChecked;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address,
+    instruction::ExecuteInstruction,
+    onchain::add_extra_accounts_for_execute_cpi,
+};
+use spl_type_length_value::state::TlvStateBorrowed;
+use crate::error::ErrorCode;
+
+/// Transfer hook program of a Token-2022 `mint`, `None` for legacy mints and
+/// mints without the extension.
+pub fn transfer_hook_program(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(None);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(transfer_hook::get_program_id(&mint))
+}
+
+/// Splits `accounts` into the instruction's own remaining accounts and the
+/// transfer hook accounts of `mint` that end them, laid out as
+/// `add_extra_account_metas_for_execute` appends them: the extra accounts
+/// the hook's validation account lists, the hook program, then the
+/// validation account.
+pub fn split_hook_accounts<'a, 'info>(
+    mint: &AccountInfo,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+    let Some(program_id) = transfer_hook_program(mint)? else {
+        return Ok((accounts, &[]));
+    };
+    let validation = get_extra_account_metas_address(mint.key, &program_id);
+    let Some(validation_info) = accounts.last().filter(|info| *info.key == validation) else {
+        return err!(ErrorCode::TransferHookAccountsMissing);
+    };
+    let data = validation_info.try_borrow_data()?;
+    let extra_accounts = ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(
+        &TlvStateBorrowed::unpack(&data)?,
+    )?
+    .data()
+    .len();
+    let hook_accounts = extra_accounts + 2;
+    require!(accounts.len() >= hook_accounts, ErrorCode::TransferHookAccountsMissing);
+    Ok(accounts.split_at(accounts.len() - hook_accounts))
+}
+
+/// `transfer_checked` that also moves coupons whose mint has a transfer hook.
+/// The hook's validation account, its program and the extra accounts it
+/// lists are looked up in `ctx.remaining_accounts` and passed on to the
+/// token program, which calls the hook with them.
+pub fn transfer_checked_with_hook<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    let TransferChecked { from, mint, to, authority } = ctx.accounts;
+    let mut instruction = spl_token_2022::instruction::transfer_checked(
+        ctx.program.key,
+        from.key,
+        mint.key,
+        to.key,
+        authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    let mut account_infos = vec![from.clone(), mint.clone(), to.clone(), authority.clone()];
+    
+    if let Some(program_id) = transfer_hook_program(&mint)? {
+        let hook_accounts = &ctx.remaining_accounts;
+        let validation = get_extra_account_metas_address(mint.key, &program_id);
+        require!(
+            hook_accounts.iter().any(|info| *info.key == validation)
+                && hook_accounts.iter().any(|info| *info.key == program_id),
+            ErrorCode::TransferHookAccountsMissing
+        );
+        add_extra_accounts_for_execute_cpi(
+            &mut instruction,
+            &mut account_infos,
+            &program_id,
+            from,
+            mint,
+            to,
+            authority,
+            amount,
+            hook_accounts,
+        )?;
+    }
+    
+    invoke_signed(&instruction, &account_infos, ctx.signer_seeds)?;
+    Ok(())
+}
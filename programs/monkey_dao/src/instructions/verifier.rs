@@ -0,0 +1,225 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate:: ANCHOR_DISCRIMINATOR;
+use crate::error::ErrorCode;
+use crate::events::{AttestationIssued, AttestationRevoked};
+use crate::MAX_VERIFIERS;
+
+#[derive(Accounts)]
+pub struct InitializeVerifierRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == authority.key() @ ErrorCode::NotPlatformAuthority
+    )]
+    pub config: Account<'info, PlatformConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ANCHOR_DISCRIMINATOR + VerifierRegistry::INIT_SPACE,
+        seeds = [b"verifier_registry"],
+        bump
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVerifierRegistry<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+        constraint = verifier_registry.authority == authority.key() @ ErrorCode::NotPlatformAuthority
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct IssueAttestation<'info> {
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+    
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+        constraint = verifier_registry.is_verifier(&verifier.key()) @ ErrorCode::NotAccreditedVerifier
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+    
+    #[account(
+        seeds = [b"merchant", merchant.authority.as_ref()],
+        bump = merchant.bump,
+    )]
+    pub merchant: Account<'info, Merchant>,
+    
+    #[account(
+        init_if_needed,
+        payer = verifier,
+        space = ANCHOR_DISCRIMINATOR + Attestation::INIT_SPACE,
+        seeds = [b"attestation", merchant.key().as_ref(), verifier.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestation<'info> {
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = verifier,
+        seeds = [b"attestation", attestation.merchant.as_ref(), verifier.key().as_ref()],
+        bump = attestation.bump,
+    )]
+    pub attestation: Account<'info, Attestation>,
+}
+
+/// Counts the distinct, unexpired attestations in `attestations` that were
+/// issued by a registered verifier for the merchant's current KYC commitment.
+pub fn count_valid_attestations(
+    registry: &VerifierRegistry,
+    merchant: &Account<Merchant>,
+    attestations: &[AccountInfo],
+    now: i64,
+) -> Result<u8> {
+    let mut seen: Vec<Pubkey> = Vec::new();
+    
+    for info in attestations {
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::Unauthorized);
+        let attestation = Attestation::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"attestation", merchant.key().as_ref(), attestation.verifier.as_ref()],
+            &crate::ID,
+        );
+        
+        if info.key() != expected
+            || attestation.merchant != merchant.key()
+            || attestation.kyc_hash != merchant.kyc_hash
+            || attestation.expires_at <= now
+            || !registry.is_verifier(&attestation.verifier)
+            || seen.contains(&attestation.verifier)
+        {
+            continue;
+        }
+        seen.push(attestation.verifier);
+    }
+    
+    Ok(seen.len() as u8)
+}
+
+pub fn initialize_verifier_registry(
+    ctx: Context<InitializeVerifierRegistry>,
+    verifiers: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    require!(verifiers.len() <= MAX_VERIFIERS, ErrorCode::VerifierRegistryFull);
+    for (i, verifier) in verifiers.iter().enumerate() {
+        require!(!verifiers[..i].contains(verifier), ErrorCode::VerifierAlreadyRegistered);
+    }
+    
+    let registry = &mut ctx.accounts.verifier_registry;
+    registry.authority = ctx.accounts.authority.key();
+    registry.verifiers = verifiers;
+    require!(registry.is_valid_threshold(threshold), ErrorCode::InvalidThreshold);
+    registry.threshold = threshold;
+    registry.bump = ctx.bumps.verifier_registry;
+    
+    msg!(
+        "Verifier registry initialized with {} verifiers, threshold {}",
+        registry.verifiers.len(),
+        threshold
+    );
+    Ok(())
+}
+
+pub fn add_verifier(ctx: Context<UpdateVerifierRegistry>, verifier: Pubkey) -> Result<()> {
+    let registry = &mut ctx.accounts.verifier_registry;
+    
+    require!(!registry.is_verifier(&verifier), ErrorCode::VerifierAlreadyRegistered);
+    require!(registry.verifiers.len() < MAX_VERIFIERS, ErrorCode::VerifierRegistryFull);
+    
+    registry.verifiers.push(verifier);
+    
+    msg!("Verifier added: {} ({} registered)", verifier, registry.verifiers.len());
+    Ok(())
+}
+
+pub fn remove_verifier(ctx: Context<UpdateVerifierRegistry>, verifier: Pubkey) -> Result<()> {
+    let registry = &mut ctx.accounts.verifier_registry;
+    
+    require!(registry.is_verifier(&verifier), ErrorCode::NotAccreditedVerifier);
+    
+    // Attestations from a removed verifier stop counting immediately
+    registry.verifiers.retain(|v| *v != verifier);
+    // Lower the threshold first rather than leave it out of reach
+    require!(registry.is_valid_threshold(registry.threshold), ErrorCode::InvalidThreshold);
+    
+    msg!("Verifier removed: {} ({} registered)", verifier, registry.verifiers.len());
+    Ok(())
+}
+
+pub fn set_attestation_threshold(ctx: Context<UpdateVerifierRegistry>, threshold: u8) -> Result<()> {
+    let registry = &mut ctx.accounts.verifier_registry;
+    
+    require!(registry.is_valid_threshold(threshold), ErrorCode::InvalidThreshold);
+    
+    registry.threshold = threshold;
+    
+    msg!("Attestation threshold set to {}", threshold);
+    Ok(())
+}
+
+pub fn issue_attestation(
+    ctx: Context<IssueAttestation>,
+    kyc_hash: [u8; 32],
+    expires_at: i64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    require!(expires_at > clock.unix_timestamp, ErrorCode::InvalidExpiry);
+    // The verifier passes the hash of the bundle they actually reviewed
+    require!(ctx.accounts.merchant.kyc_hash == kyc_hash, ErrorCode::KycHashMismatch);
+    
+    let attestation = &mut ctx.accounts.attestation;
+    attestation.merchant = ctx.accounts.merchant.key();
+    attestation.verifier = ctx.accounts.verifier.key();
+    attestation.kyc_hash = kyc_hash;
+    attestation.issued_at = clock.unix_timestamp;
+    attestation.expires_at = expires_at;
+    attestation.bump = ctx.bumps.attestation;
+    
+    emit!(AttestationIssued {
+        attestation: attestation.key(),
+        merchant: attestation.merchant,
+        verifier: attestation.verifier,
+        kyc_hash,
+        expires_at,
+    });
+    
+    msg!("Attestation issued for merchant: {}", ctx.accounts.merchant.business_name);
+    Ok(())
+}
+
+pub fn revoke_attestation(ctx: Context<RevokeAttestation>) -> Result<()> {
+    let attestation = &ctx.accounts.attestation;
+    emit!(AttestationRevoked {
+        attestation: attestation.key(),
+        merchant: attestation.merchant,
+        verifier: attestation.verifier,
+    });
+    
+    msg!("Attestation revoked");
+    Ok(())
+}
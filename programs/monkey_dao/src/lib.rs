@@ -1,5 +1,6 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
+#![allow(clippy::too_many_arguments)]
 use anchor_lang::prelude::*;
 
 declare_id!("4hmqotpqtTjt3fDoyX1HR7QLqcxdPSb2V6ZctRnkiCfY");
@@ -8,8 +9,10 @@ pub mod state;
 pub mod instructions;
 pub mod error;
 pub mod constants;
+pub mod events;
 
 use instructions::*;
+use state::{UseMethod, LeafArgs, FeeTier, LoyaltyTier, AllowlistProof, FlashSale};
 pub use constants::*;
 
 #[program]
@@ -21,19 +24,15 @@ pub mod nft_coupon_platform {
         ctx: Context<RegisterMerchant>,
         business_name: String,
         business_type: String,
-        contact_email: String,
-        phone: String,
-        business_address: String,
-        tax_id: String,
+        kyc_hash: [u8; 32],
+        kyc_uri: String,
     ) -> Result<()> {
         instructions::merchant::register_merchant(
             ctx,
             business_name,
             business_type,
-            contact_email,
-            phone,
-            business_address,
-            tax_id,
+            kyc_hash,
+            kyc_uri,
         )
     }
 
@@ -41,9 +40,73 @@ pub mod nft_coupon_platform {
         instructions::merchant::verify_merchant(ctx)
     }
 
+    pub fn update_merchant_kyc(
+        ctx: Context<UpdateMerchantKyc>,
+        kyc_hash: [u8; 32],
+        kyc_uri: String,
+    ) -> Result<()> {
+        instructions::merchant::update_merchant_kyc(ctx, kyc_hash, kyc_uri)
+    }
+
+    pub fn migrate_merchant(
+        ctx: Context<MigrateMerchant>,
+        kyc_hash: [u8; 32],
+        kyc_uri: String,
+    ) -> Result<()> {
+        instructions::merchant::migrate_merchant(ctx, kyc_hash, kyc_uri)
+    }
+
+    pub fn add_delegate(
+        ctx: Context<AddDelegate>,
+        delegate: Pubkey,
+        valid_until: i64,
+    ) -> Result<()> {
+        instructions::delegate::add_delegate(ctx, delegate, valid_until)
+    }
+
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+        instructions::delegate::revoke_delegate(ctx)
+    }
+
+    // ==================== VERIFIER INSTRUCTIONS ====================
+    pub fn initialize_verifier_registry(
+        ctx: Context<InitializeVerifierRegistry>,
+        verifiers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::verifier::initialize_verifier_registry(ctx, verifiers, threshold)
+    }
+
+    pub fn add_verifier(ctx: Context<UpdateVerifierRegistry>, verifier: Pubkey) -> Result<()> {
+        instructions::verifier::add_verifier(ctx, verifier)
+    }
+
+    pub fn remove_verifier(ctx: Context<UpdateVerifierRegistry>, verifier: Pubkey) -> Result<()> {
+        instructions::verifier::remove_verifier(ctx, verifier)
+    }
+
+    pub fn set_attestation_threshold(
+        ctx: Context<UpdateVerifierRegistry>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::verifier::set_attestation_threshold(ctx, threshold)
+    }
+
+    pub fn issue_attestation(
+        ctx: Context<IssueAttestation>,
+        kyc_hash: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::verifier::issue_attestation(ctx, kyc_hash, expires_at)
+    }
+
+    pub fn revoke_attestation(ctx: Context<RevokeAttestation>) -> Result<()> {
+        instructions::verifier::revoke_attestation(ctx)
+    }
+
     // ==================== LISTING INSTRUCTIONS ====================
-    pub fn list_nft(
-        ctx: Context<ListNFT>,
+    pub fn list_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, ListNFT<'info>>,
         price: u64,
         is_group_deal: bool,
         deal_price_2: Option<u64>,
@@ -51,6 +114,8 @@ pub mod nft_coupon_platform {
         deal_price_6: Option<u64>,
         coupon_description: String,
         expiry_date: i64,
+        use_method: UseMethod,
+        total_uses: u64,
     ) -> Result<()> {
         instructions::listing::list_nft(
             ctx,
@@ -61,20 +126,37 @@ pub mod nft_coupon_platform {
             deal_price_6,
             coupon_description,
             expiry_date,
+            use_method,
+            total_uses,
         )
     }
 
-    pub fn relist_nft(ctx: Context<RelistNFT>, new_price: u64) -> Result<()> {
+    pub fn relist_nft<'info>(ctx: Context<'_, '_, '_, 'info, RelistNFT<'info>>, new_price: u64) -> Result<()> {
         instructions::listing::relist_nft(ctx, new_price)
     }
 
-    pub fn delist_nft(ctx: Context<DelistNFT>) -> Result<()> {
+    pub fn delist_nft<'info>(ctx: Context<'_, '_, '_, 'info, DelistNFT<'info>>) -> Result<()> {
         instructions::listing::delist_nft(ctx)
     }
 
+    pub fn close_listing(ctx: Context<CloseListing>) -> Result<()> {
+        instructions::listing::close_listing(ctx)
+    }
+
+    pub fn sync_custody(ctx: Context<SyncCustody>) -> Result<()> {
+        instructions::listing::sync_custody(ctx)
+    }
+
     // ==================== TRADING INSTRUCTIONS ====================
-    pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
-        instructions::trading::buy_nft(ctx)
+    pub fn buy_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyNFT<'info>>,
+        allowlist_proof: Option<AllowlistProof>,
+    ) -> Result<()> {
+        instructions::trading::buy_nft(ctx, allowlist_proof)
+    }
+
+    pub fn checkout_cart<'info>(ctx: Context<'_, '_, 'info, 'info, CheckoutCart<'info>>, items: u8) -> Result<()> {
+        instructions::cart::checkout_cart(ctx, items)
     }
 
     // ==================== POOL INSTRUCTIONS ====================
@@ -82,14 +164,37 @@ pub mod nft_coupon_platform {
         instructions::pool::create_pool(ctx, pool_size)
     }
 
-    pub fn join_pool(ctx: Context<JoinPool>) -> Result<()> {
-        instructions::pool::join_pool(ctx)
+    pub fn join_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, JoinPool<'info>>,
+        allowlist_proof: Option<AllowlistProof>,
+    ) -> Result<()> {
+        instructions::pool::join_pool(ctx, allowlist_proof)
+    }
+
+    pub fn complete_pool<'info>(ctx: Context<'_, '_, 'info, 'info, CompletePool<'info>>) -> Result<()> {
+        instructions::pool::complete_pool(ctx)
+    }
+
+    pub fn claim_pool_coupon<'info>(ctx: Context<'_, '_, '_, 'info, ClaimPoolCoupon<'info>>) -> Result<()> {
+        instructions::pool::claim_pool_coupon(ctx)
     }
 
     pub fn cancel_pool(ctx: Context<CancelPool>) -> Result<()> {
         instructions::pool::cancel_pool(ctx)
     }
 
+    pub fn refund_pool_deposit(ctx: Context<RefundPoolDeposit>) -> Result<()> {
+        instructions::pool::refund_pool_deposit(ctx)
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::pool::close_pool(ctx)
+    }
+
+    pub fn close_pool_participant(ctx: Context<ClosePoolParticipant>) -> Result<()> {
+        instructions::pool::close_pool_participant(ctx)
+    }
+
     // ==================== REVIEW INSTRUCTIONS ====================
     pub fn add_review(
         ctx: Context<AddReview>,
@@ -100,11 +205,11 @@ pub mod nft_coupon_platform {
     }
 
     // ==================== STAKING INSTRUCTIONS ====================
-    pub fn stake_nft(ctx: Context<StakeNFT>) -> Result<()> {
+    pub fn stake_nft<'info>(ctx: Context<'_, '_, '_, 'info, StakeNFT<'info>>) -> Result<()> {
         instructions::staking::stake_nft(ctx)
     }
 
-    pub fn unstake_nft(ctx: Context<UnstakeNFT>) -> Result<()> {
+    pub fn unstake_nft<'info>(ctx: Context<'_, '_, '_, 'info, UnstakeNFT<'info>>) -> Result<()> {
         instructions::staking::unstake_nft(ctx)
     }
 
@@ -112,16 +217,219 @@ pub mod nft_coupon_platform {
         instructions::staking::claim_staking_rewards(ctx)
     }
 
+    pub fn close_stake_account(ctx: Context<CloseStakeAccount>) -> Result<()> {
+        instructions::staking::close_stake_account(ctx)
+    }
+
     // ==================== REDEMPTION INSTRUCTIONS ====================
     pub fn redeem_nft(
         ctx: Context<RedeemNFT>,
         signature: Vec<u8>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::redemption::redeem_nft(ctx, signature, amount)
+    }
+
+    // ==================== COMPRESSED COUPON INSTRUCTIONS ====================
+    pub fn create_coupon_tree(
+        ctx: Context<CreateCouponTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+        original_price: u64,
+        expiry_date: i64,
+    ) -> Result<()> {
+        instructions::compressed::create_coupon_tree(
+            ctx,
+            max_depth,
+            max_buffer_size,
+            original_price,
+            expiry_date,
+        )
+    }
+
+    pub fn mint_compressed_coupon(
+        ctx: Context<MintCompressedCoupon>,
+        name: String,
+        uri: String,
     ) -> Result<()> {
-        instructions::redemption::redeem_nft(ctx, signature)
+        instructions::compressed::mint_compressed_coupon(ctx, name, uri)
+    }
+
+    pub fn list_compressed_coupon<'info>(
+        ctx: Context<'_, '_, '_, 'info, ListCompressedCoupon<'info>>,
+        price: u64,
+        leaf: LeafArgs,
+    ) -> Result<()> {
+        instructions::compressed::list_compressed_coupon(ctx, price, leaf)
+    }
+
+    pub fn buy_compressed_coupon<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyCompressedCoupon<'info>>,
+        leaf: LeafArgs,
+    ) -> Result<()> {
+        instructions::compressed::buy_compressed_coupon(ctx, leaf)
+    }
+
+    pub fn delist_compressed_coupon<'info>(
+        ctx: Context<'_, '_, '_, 'info, DelistCompressedCoupon<'info>>,
+        leaf: LeafArgs,
+    ) -> Result<()> {
+        instructions::compressed::delist_compressed_coupon(ctx, leaf)
+    }
+
+    pub fn redeem_compressed_coupon<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemCompressedCoupon<'info>>,
+        signature: Vec<u8>,
+        leaf: LeafArgs,
+    ) -> Result<()> {
+        instructions::compressed::redeem_compressed_coupon(ctx, signature, leaf)
+    }
+
+    // ==================== EXPIRY INSTRUCTIONS ====================
+    pub fn reclaim_expired_listing<'info>(ctx: Context<'_, '_, '_, 'info, ReclaimExpiredListing<'info>>) -> Result<()> {
+        instructions::expiry::reclaim_expired_listing(ctx)
+    }
+
+    pub fn set_refund_policy(
+        ctx: Context<SetRefundPolicy>,
+        refund_bps: u64,
+        claim_window: i64,
+    ) -> Result<()> {
+        instructions::expiry::set_refund_policy(ctx, refund_bps, claim_window)
+    }
+
+    pub fn fund_refund_escrow(ctx: Context<ManageRefundEscrow>, amount: u64) -> Result<()> {
+        instructions::expiry::fund_refund_escrow(ctx, amount)
+    }
+
+    pub fn withdraw_refund_escrow(ctx: Context<WithdrawRefundEscrow>, amount: u64) -> Result<()> {
+        instructions::expiry::withdraw_refund_escrow(ctx, amount)
+    }
+
+    pub fn claim_expired_refund(ctx: Context<ClaimExpiredRefund>) -> Result<()> {
+        instructions::expiry::claim_expired_refund(ctx)
     }
 
     // ==================== MONK TOKEN INSTRUCTIONS ====================
     pub fn initialize_monk_mint(ctx: Context<InitializeMonkMint>) -> Result<()> {
         instructions::monk_token::initialize_monk_mint(ctx)
     }
+
+    pub fn initialize_monk_mint_2022(
+        ctx: Context<InitializeMonkMint2022>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        instructions::monk_token::initialize_monk_mint_2022(ctx, name, symbol, uri)
+    }
+
+    // ==================== PAUSE INSTRUCTIONS ====================
+    pub fn set_paused(ctx: Context<SetPaused>, paused: u8) -> Result<()> {
+        instructions::pause::set_paused(ctx, paused)
+    }
+
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        instructions::pause::set_guardian(ctx, guardian)
+    }
+
+    // ==================== FEE INSTRUCTIONS ====================
+    pub fn set_fee_schedule(
+        ctx: Context<SetFeeSchedule>,
+        primary_tiers: Vec<FeeTier>,
+        secondary_fee_bps: u16,
+        staker_discount_bps: u16,
+        buyer_pays_fee: bool,
+    ) -> Result<()> {
+        instructions::fee::set_fee_schedule(ctx, primary_tiers, secondary_fee_bps, staker_discount_bps, buyer_pays_fee)
+    }
+
+    // ==================== REFERRAL INSTRUCTIONS ====================
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        instructions::referral::register_referrer(ctx)
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        instructions::referral::claim_referral_rewards(ctx)
+    }
+
+    pub fn set_referral_rewards(ctx: Context<SetReferralRewards>, fee_share_bps: u16, monk_bps: u16) -> Result<()> {
+        instructions::referral::set_referral_rewards(ctx, fee_share_bps, monk_bps)
+    }
+
+    // ==================== LOYALTY INSTRUCTIONS ====================
+    pub fn refresh_tier(ctx: Context<RefreshTier>) -> Result<()> {
+        instructions::loyalty::refresh_tier(ctx)
+    }
+
+    pub fn set_listing_access(
+        ctx: Context<SetListingAccess>,
+        min_tier: LoyaltyTier,
+        early_access_until: i64,
+    ) -> Result<()> {
+        instructions::loyalty::set_listing_access(ctx, min_tier, early_access_until)
+    }
+
+    // ==================== GATE INSTRUCTIONS ====================
+    pub fn set_gate(
+        ctx: Context<SetGate>,
+        gate_id: u64,
+        collection: Option<Pubkey>,
+        allowlist_root: Option<[u8; 32]>,
+        max_per_wallet: u16,
+    ) -> Result<()> {
+        instructions::gate::set_gate(ctx, gate_id, collection, allowlist_root, max_per_wallet)
+    }
+
+    pub fn set_listing_gate(ctx: Context<SetListingGate>) -> Result<()> {
+        instructions::gate::set_listing_gate(ctx)
+    }
+
+    // ==================== SCHEDULE INSTRUCTIONS ====================
+    pub fn set_listing_schedule(
+        ctx: Context<SetListingSchedule>,
+        start_time: i64,
+        flash_sales: Vec<FlashSale>,
+    ) -> Result<()> {
+        instructions::schedule::set_listing_schedule(ctx, start_time, flash_sales)
+    }
+
+    // ==================== GIFT INSTRUCTIONS ====================
+    pub fn gift_coupon<'info>(ctx: Context<'_, '_, '_, 'info, GiftCoupon<'info>>, message: String) -> Result<()> {
+        instructions::gift::gift_coupon(ctx, message)
+    }
+
+    pub fn send_gift<'info>(
+        ctx: Context<'_, '_, '_, 'info, SendGift<'info>>,
+        recipient: Option<Pubkey>,
+        claim_key: Option<Pubkey>,
+        message: String,
+    ) -> Result<()> {
+        instructions::gift::send_gift(ctx, recipient, claim_key, message)
+    }
+
+    pub fn claim_gift<'info>(ctx: Context<'_, '_, '_, 'info, ClaimGift<'info>>) -> Result<()> {
+        instructions::gift::claim_gift(ctx)
+    }
+
+    pub fn return_gift<'info>(ctx: Context<'_, '_, '_, 'info, ReturnGift<'info>>) -> Result<()> {
+        instructions::gift::return_gift(ctx)
+    }
+
+    // ==================== MIGRATION INSTRUCTIONS ====================
+    pub fn migrate_listing(ctx: Context<MigrateListing>) -> Result<()> {
+        instructions::migrate::migrate_listing(ctx)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate::migrate_pool(ctx)
+    }
+
+    pub fn migrate_stake_account(ctx: Context<MigrateStakeAccount>) -> Result<()> {
+        instructions::migrate::migrate_stake_account(ctx)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        instructions::migrate::migrate_config(ctx)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use mpl_bubblegum::types::LeafSchema;
+
+#[account]
+#[derive(InitSpace)]
+pub struct CouponTree {
+    pub merchant: Pubkey,
+    pub merkle_tree: Pubkey,
+    pub original_price: u64,
+    pub expiry_date: i64,
+    pub num_minted: u64,
+    pub num_redeemed: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CompressedListing {
+    pub coupon_tree: Pubkey,
+    pub asset_id: Pubkey,
+    pub nonce: u64, // leaf nonce, part of the listing seeds
+    pub seller: Pubkey,
+    pub price: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Leaf fields a client reads from the indexer to prove a compressed coupon.
+/// The proof path itself is passed as remaining accounts, as Bubblegum expects.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct LeafArgs {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+impl LeafArgs {
+    /// Hash of the Bubblegum V1 leaf for this asset held by `owner`.
+    pub fn leaf_hash(&self, asset_id: Pubkey, owner: Pubkey, delegate: Pubkey) -> [u8; 32] {
+        LeafSchema::V1 {
+            id: asset_id,
+            owner,
+            delegate,
+            nonce: self.nonce,
+            data_hash: self.data_hash,
+            creator_hash: self.creator_hash,
+        }
+        .hash()
+    }
+}
+
+/// Recomputes the root from `leaf` and its sibling path, using the same node
+/// ordering as spl-account-compression.
+pub fn compute_merkle_root(leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+    let mut node = leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        node = if (index >> level) & 1 == 0 {
+            keccak::hashv(&[&node, sibling]).to_bytes()
+        } else {
+            keccak::hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node
+}
+
+pub fn verify_merkle_proof(root: [u8; 32], leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> bool {
+    compute_merkle_root(leaf, index, proof) == root
+}
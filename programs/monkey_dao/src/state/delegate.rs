@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantDelegate {
+    pub merchant: Pubkey,
+    pub delegate: Pubkey, // staff or point-of-sale key
+    pub can_redeem: bool,
+    pub valid_until: i64,
+    pub total_redemptions: u64, // approvals given; each names this key on its `RedemptionReceipt`
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl MerchantDelegate {
+    pub fn can_approve_redemption(&self, current_time: i64) -> bool {
+        self.can_redeem && self.valid_until > current_time
+    }
+}
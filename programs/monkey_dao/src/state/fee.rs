@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::MAX_FEE_TIERS;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, PartialEq, Eq, Debug)]
+pub struct FeeTier {
+    pub min_volume: u64, // merchant's primary sales volume, in lamports, from which the tier applies
+    pub fee_bps: u16,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FeeSchedule {
+    #[max_len(MAX_FEE_TIERS)]
+    pub primary_tiers: Vec<FeeTier>, // ascending by min_volume, the first starting at 0
+    pub secondary_fee_bps: u16, // resales between users
+    pub staker_discount_bps: u16, // share of the fee waived for buyers with coupons staked
+    pub buyer_pays_fee: bool, // fee charged on top of the price instead of out of the proceeds
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl FeeSchedule {
+    pub const VERSION: u8 = 1;
+    
+    /// Charges `fee_bps` on every sale, out of the proceeds. Stands in for
+    /// the schedule until the authority sets one.
+    pub fn flat(fee_bps: u64) -> Self {
+        let fee_bps = fee_bps.min(10_000) as u16;
+        Self {
+            primary_tiers: vec![FeeTier { min_volume: 0, fee_bps }],
+            secondary_fee_bps: fee_bps,
+            staker_discount_bps: 0,
+            buyer_pays_fee: false,
+            bump: 0,
+            version: Self::VERSION,
+            reserved: [0; 64],
+        }
+    }
+    
+    /// Fee rate for a sale. Primary sales by the merchant are tiered by its
+    /// sales volume so far; resales pay the flat secondary rate.
+    pub fn fee_bps(&self, is_primary: bool, merchant_volume: u64, is_staker: bool) -> u64 {
+        let base = if is_primary {
+            self.primary_tiers
+                .iter()
+                .rev()
+                .find(|tier| tier.min_volume <= merchant_volume)
+                .map_or(0, |tier| tier.fee_bps)
+        } else {
+            self.secondary_fee_bps
+        } as u64;
+        
+        if is_staker {
+            base - base * self.staker_discount_bps as u64 / 10000
+        } else {
+            base
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use crate::state::verify_merkle_proof;
+
+/// Who may buy the listings that point at this gate. A merchant shares one
+/// gate between the listings of a drop, so the wallet limit spans all of them.
+#[account]
+#[derive(InitSpace)]
+pub struct ListingGate {
+    pub merchant: Pubkey,
+    pub gate_id: u64,
+    pub collection: Option<Pubkey>, // buyers must hold an NFT of this verified collection
+    pub allowlist_root: Option<[u8; 32]>, // buyers must prove their wallet is a leaf of this tree
+    pub max_per_wallet: u16, // 0 = no limit
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl ListingGate {
+    pub const VERSION: u8 = 1;
+    
+    pub fn is_allowlisted(&self, wallet: &Pubkey, proof: Option<&AllowlistProof>) -> bool {
+        match (self.allowlist_root, proof) {
+            (None, _) => true,
+            (Some(root), Some(proof)) => {
+                verify_merkle_proof(root, allowlist_leaf(wallet), proof.index, &proof.path)
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Purchases a wallet made through a gate, against its `max_per_wallet`.
+#[account]
+#[derive(InitSpace)]
+pub struct GatePurchases {
+    pub gate: Pubkey,
+    pub wallet: Pubkey,
+    pub purchases: u16,
+    pub bump: u8,
+}
+
+/// A wallet's position in the gate's allowlist tree and its sibling path.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct AllowlistProof {
+    pub index: u32,
+    pub path: Vec<[u8; 32]>,
+}
+
+/// Leaf of `wallet` in an allowlist tree.
+pub fn allowlist_leaf(wallet: &Pubkey) -> [u8; 32] {
+    keccak::hashv(&[wallet.as_ref()]).to_bytes()
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::MAX_GIFT_MESSAGE_LEN;
+
+/// A coupon escrowed by `send_gift` until its recipient claims it, or until
+/// `expires_at`, after which it goes back to the sender.
+#[account]
+#[derive(InitSpace)]
+pub struct Gift {
+    pub sender: Pubkey,
+    pub nft_mint: Pubkey,
+    pub recipient: Option<Pubkey>, // wallet that may claim the gift
+    pub claim_key: Option<Pubkey>, // or any claimer this key co-signs for
+    #[max_len(MAX_GIFT_MESSAGE_LEN)]
+    pub message: String,
+    pub sent_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl Gift {
+    pub const VERSION: u8 = 1;
+    
+    /// A claim key's signature covers the whole claim, claimer included, so
+    /// a claim seen in flight can't be replayed for another wallet.
+    pub fn can_claim(&self, claimer: &Pubkey, claim_signer: Option<&Pubkey>) -> bool {
+        self.recipient == Some(*claimer)
+            || (self.claim_key.is_some() && self.claim_key.as_ref() == claim_signer)
+    }
+}
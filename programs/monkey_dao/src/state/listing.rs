@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::state::LoyaltyTier;
 
 #[account]
 #[derive(InitSpace)]
@@ -13,7 +14,8 @@ pub struct Listing {
     pub deal_price_4: Option<u64>,
     pub deal_price_6: Option<u64>,
     pub is_active: bool,
-    pub is_used: bool,
+    pub is_used: bool, // true once all uses are spent
+    pub uses: CouponUses,
     pub total_sales: u64,
     #[max_len(500)]
     pub coupon_description: String,
@@ -22,4 +24,220 @@ pub struct Listing {
     pub average_rating: u8, // 0-100 (representing 0.0-5.0 stars * 20)
     pub total_reviews: u64,
     pub bump: u8,
-}
\ No newline at end of file
+    pub version: u8,
+    pub min_tier: LoyaltyTier, // buyers below it are turned away
+    pub early_access_until: i64, // until then only Silver and Gold buyers may buy
+    pub gate: Option<Pubkey>, // `ListingGate` buyers must pass
+    // `holder` and `custody` took the 22 reserved bytes of version 1 and
+    // grew the account by the other 11
+    pub holder: Pubkey, // wallet the coupon belongs to, wherever `custody` puts it
+    pub custody: Custody,
+}
+
+impl Listing {
+    pub const VERSION: u8 = 2;
+}
+
+/// Where the coupon's token sits. `seller` is only who listed it last, the
+/// one paid when it sells; `holder` and `custody` follow the token itself.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum Custody {
+    Wallet,       // in a token account of `holder`
+    ListingVault, // listed for sale by `holder`
+    StakeVault,   // staked by `holder`
+    Pool,         // bought by a filled pool, held by it from `complete_pool` until its initiator claims it; `holder` is the pool
+    GiftEscrow,   // sent as a gift by `holder`, not yet claimed
+    Redeemed,     // burned once all uses were spent, or refunded
+}
+
+/// Layout of `Listing` accounts created before custody was tracked. Only
+/// read by `migrate_listing`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct ListingV1 {
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub merchant: Pubkey,
+    pub original_price: u64,
+    pub current_price: u64,
+    pub is_group_deal: bool,
+    pub deal_price_2: Option<u64>,
+    pub deal_price_4: Option<u64>,
+    pub deal_price_6: Option<u64>,
+    pub is_active: bool,
+    pub is_used: bool,
+    pub uses: CouponUses,
+    pub total_sales: u64,
+    #[max_len(500)]
+    pub coupon_description: String,
+    pub expiry_date: i64,
+    pub created_at: i64,
+    pub average_rating: u8,
+    pub total_reviews: u64,
+    pub bump: u8,
+    pub version: u8,
+    pub min_tier: LoyaltyTier,
+    pub early_access_until: i64,
+    pub gate: Option<Pubkey>,
+    pub reserved: [u8; 22],
+}
+
+impl From<ListingV1> for Listing {
+    fn from(old: ListingV1) -> Self {
+        // `seller` was set to each buyer, so it is the holder unless the
+        // coupon has since moved by a plain transfer or into a stake vault,
+        // gift or pool; `sync_custody` records those from the token itself
+        let custody = if old.is_used {
+            Custody::Redeemed
+        } else if old.is_active {
+            Custody::ListingVault
+        } else {
+            Custody::Wallet
+        };
+        Self {
+            nft_mint: old.nft_mint,
+            seller: old.seller,
+            merchant: old.merchant,
+            original_price: old.original_price,
+            current_price: old.current_price,
+            is_group_deal: old.is_group_deal,
+            deal_price_2: old.deal_price_2,
+            deal_price_4: old.deal_price_4,
+            deal_price_6: old.deal_price_6,
+            is_active: old.is_active,
+            is_used: old.is_used,
+            uses: old.uses,
+            total_sales: old.total_sales,
+            coupon_description: old.coupon_description,
+            expiry_date: old.expiry_date,
+            created_at: old.created_at,
+            average_rating: old.average_rating,
+            total_reviews: old.total_reviews,
+            bump: old.bump,
+            version: Self::VERSION,
+            min_tier: old.min_tier,
+            early_access_until: old.early_access_until,
+            gate: old.gate,
+            holder: old.seller,
+            custody,
+        }
+    }
+}
+
+/// Layout of `Listing` accounts created before accounts carried a version,
+/// as deployed before coupons had uses. Only read by `migrate_listing`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct ListingV0 {
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub merchant: Pubkey,
+    pub original_price: u64,
+    pub current_price: u64,
+    pub is_group_deal: bool,
+    pub deal_price_2: Option<u64>,
+    pub deal_price_4: Option<u64>,
+    pub deal_price_6: Option<u64>,
+    pub is_active: bool,
+    pub is_used: bool,
+    pub total_sales: u64,
+    #[max_len(500)]
+    pub coupon_description: String,
+    pub expiry_date: i64,
+    pub created_at: i64,
+    pub average_rating: u8,
+    pub total_reviews: u64,
+    pub bump: u8,
+}
+
+impl From<ListingV0> for ListingV1 {
+    fn from(old: ListingV0) -> Self {
+        Self {
+            nft_mint: old.nft_mint,
+            seller: old.seller,
+            merchant: old.merchant,
+            original_price: old.original_price,
+            current_price: old.current_price,
+            is_group_deal: old.is_group_deal,
+            deal_price_2: old.deal_price_2,
+            deal_price_4: old.deal_price_4,
+            deal_price_6: old.deal_price_6,
+            is_active: old.is_active,
+            is_used: old.is_used,
+            // Every coupon was single use, spent once redeemed
+            uses: CouponUses {
+                use_method: UseMethod::Single,
+                remaining: if old.is_used { 0 } else { 1 },
+                total: 1,
+            },
+            total_sales: old.total_sales,
+            coupon_description: old.coupon_description,
+            expiry_date: old.expiry_date,
+            created_at: old.created_at,
+            average_rating: old.average_rating,
+            total_reviews: old.total_reviews,
+            bump: old.bump,
+            version: 1,
+            min_tier: LoyaltyTier::Bronze,
+            early_access_until: 0,
+            gate: None,
+            reserved: [0; 22],
+        }
+    }
+}
+
+impl From<ListingV0> for Listing {
+    fn from(old: ListingV0) -> Self {
+        ListingV1::from(old).into()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum UseMethod {
+    Single,      // one redemption, then burn
+    Multiple,    // punch card: each redemption spends one or more uses
+    StoredValue, // voucher: each redemption spends an arbitrary amount of value
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct CouponUses {
+    pub use_method: UseMethod,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+impl CouponUses {
+    pub fn new(use_method: UseMethod, total: u64) -> Self {
+        let total = match use_method {
+            UseMethod::Single => 1,
+            _ => total,
+        };
+        Self { use_method, remaining: total, total }
+    }
+
+    /// Uses or stored value spent so far, which also numbers the coupon's
+    /// redemption receipts.
+    pub fn spent(&self) -> u64 {
+        self.total.saturating_sub(self.remaining)
+    }
+
+    /// Scales `price` by `uses` out of the coupon's total uses.
+    pub fn share_of(&self, uses: u64, price: u64) -> Result<u64> {
+        if self.total == 0 {
+            return Ok(0);
+        }
+        let share = (price as u128)
+            .checked_mul(uses as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            / self.total as u128;
+        Ok(share as u64)
+    }
+
+    /// Scales `amount` by the fraction of uses still remaining.
+    pub fn remaining_share(&self, amount: u64) -> Result<u64> {
+        self.share_of(self.remaining, amount)
+    }
+
+    /// Remaining fraction in basis points (10000 = unused).
+    pub fn remaining_bps(&self) -> Result<u64> {
+        self.remaining_share(10_000)
+    }
+}
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::state::UserStats;
+use crate::{
+    GOLD_MIN_MONK, GOLD_MIN_NFTS_STAKED, GOLD_MIN_PURCHASES, SILVER_MIN_MONK, SILVER_MIN_PURCHASES,
+    TIER_VALIDITY,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, InitSpace, Debug)]
+pub enum LoyaltyTier {
+    Bronze, // every buyer, including ones that never refreshed a tier
+    Silver,
+    Gold,
+}
+
+impl LoyaltyTier {
+    /// The highest tier whose thresholds the user meets.
+    pub fn from_stats(stats: &UserStats) -> Self {
+        if stats.total_purchases >= GOLD_MIN_PURCHASES
+            && stats.total_monk_earned >= GOLD_MIN_MONK
+            && stats.nfts_staked >= GOLD_MIN_NFTS_STAKED
+        {
+            LoyaltyTier::Gold
+        } else if stats.total_purchases >= SILVER_MIN_PURCHASES && stats.total_monk_earned >= SILVER_MIN_MONK {
+            LoyaltyTier::Silver
+        } else {
+            LoyaltyTier::Bronze
+        }
+    }
+    
+    /// Multiplier on `PURCHASE_REWARD_BPS`, 10000 = 1x.
+    pub fn reward_multiplier_bps(&self) -> u64 {
+        match self {
+            LoyaltyTier::Bronze => 10000,
+            LoyaltyTier::Silver => 12500,
+            LoyaltyTier::Gold => 15000,
+        }
+    }
+    
+    /// Share of the platform fee waived for the buyer.
+    pub fn fee_discount_bps(&self) -> u64 {
+        match self {
+            LoyaltyTier::Bronze => 0,
+            LoyaltyTier::Silver => 1000,
+            LoyaltyTier::Gold => 2500,
+        }
+    }
+}
+
+/// A user's tier as of its last refresh.
+#[account]
+#[derive(InitSpace)]
+pub struct Loyalty {
+    pub user: Pubkey,
+    pub tier: LoyaltyTier,
+    pub refreshed_at: i64,
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl Loyalty {
+    pub const VERSION: u8 = 1;
+    
+    /// The refreshed tier, or Bronze once it is older than `TIER_VALIDITY`.
+    pub fn current_tier(&self, current_time: i64) -> LoyaltyTier {
+        if current_time.saturating_sub(self.refreshed_at) > TIER_VALIDITY {
+            LoyaltyTier::Bronze
+        } else {
+            self.tier
+        }
+    }
+}
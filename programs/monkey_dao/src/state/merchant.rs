@@ -3,6 +3,76 @@ use anchor_lang::prelude::*;
 #[account]
 #[derive(InitSpace)]
 pub struct Merchant {
+    pub authority: Pubkey,
+    #[max_len(100)]
+    pub business_name: String,
+    #[max_len(50)]
+    pub business_type: String,
+    pub kyc_hash: [u8; 32], // SHA-256 of the off-chain KYC document bundle
+    #[max_len(200)]
+    pub kyc_uri: String,
+    pub is_verified: bool,
+    pub total_listings: u64,
+    pub total_redemptions: u64,
+    pub total_value_honored: u64, // lamports, pro rata of redeemed coupons' original prices
+    pub registration_date: i64,
+    pub bump: u8,
+    pub version: u8,
+    pub total_sales_volume: u64, // lamports of primary sales, picks the merchant's fee tier
+    pub latest_expiry: i64, // latest expiry date of its listed coupons, holds back refund escrow withdrawals
+    pub reserved: [u8; 48], // zeroed, taken by fields added in later versions
+}
+
+impl Merchant {
+    pub const VERSION: u8 = 1;
+}
+
+/// Layout of `Merchant` accounts registered on a KYC commitment before
+/// accounts carried a version. Only read by `migrate_merchant`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct MerchantV0 {
+    pub authority: Pubkey,
+    #[max_len(100)]
+    pub business_name: String,
+    #[max_len(50)]
+    pub business_type: String,
+    pub kyc_hash: [u8; 32],
+    #[max_len(200)]
+    pub kyc_uri: String,
+    pub is_verified: bool,
+    pub total_listings: u64,
+    pub total_redemptions: u64,
+    pub total_value_honored: u64,
+    pub registration_date: i64,
+    pub bump: u8,
+}
+
+impl From<MerchantV0> for Merchant {
+    fn from(old: MerchantV0) -> Self {
+        Self {
+            authority: old.authority,
+            business_name: old.business_name,
+            business_type: old.business_type,
+            kyc_hash: old.kyc_hash,
+            kyc_uri: old.kyc_uri,
+            is_verified: old.is_verified,
+            total_listings: old.total_listings,
+            total_redemptions: old.total_redemptions,
+            total_value_honored: old.total_value_honored,
+            registration_date: old.registration_date,
+            bump: old.bump,
+            version: Self::VERSION,
+            total_sales_volume: 0,
+            latest_expiry: 0,
+            reserved: [0; 48],
+        }
+    }
+}
+
+/// Layout of `Merchant` accounts registered before contact details moved off-chain.
+/// Only read by `migrate_merchant`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct LegacyMerchant {
     pub authority: Pubkey,
     #[max_len(100)]
     pub business_name: String,
@@ -20,4 +90,4 @@ pub struct Merchant {
     pub total_listings: u64,
     pub registration_date: i64,
     pub bump: u8,
-}
\ No newline at end of file
+}
@@ -1,16 +1,39 @@
 use anchor_lang::prelude::*;
+use crate::PAUSE_STAKING;
 
 pub mod merchant;
 pub mod listing;
 pub mod pool;
 pub mod review;
 pub mod staking;
+pub mod verifier;
+pub mod delegate;
+pub mod receipt;
+pub mod refund;
+pub mod compressed;
+pub mod fee;
+pub mod referral;
+pub mod loyalty;
+pub mod gate;
+pub mod schedule;
+pub mod gift;
 
 pub use merchant::*;
 pub use listing::*;
 pub use pool::*;
 pub use review::*;
 pub use staking::*;
+pub use verifier::*;
+pub use delegate::*;
+pub use receipt::*;
+pub use refund::*;
+pub use compressed::*;
+pub use fee::*;
+pub use referral::*;
+pub use loyalty::*;
+pub use gate::*;
+pub use schedule::*;
+pub use gift::*;
 
 #[account]
 #[derive(InitSpace)]
@@ -18,9 +41,68 @@ pub struct PlatformConfig {
     pub authority: Pubkey,
     pub monk_mint: Pubkey,
     pub platform_wallet: Pubkey,
-    pub platform_fee_bps: u64,
+    pub platform_fee_bps: u64, // flat rate on every sale until a `FeeSchedule` is set, unused after
     pub staking_reward_rate: u64, // MONK tokens per day per NFT
     pub bump: u8,
+    pub version: u8,
+    pub guardian: Pubkey, // may pause subsystems, but only the authority unpauses
+    pub paused: u8, // PAUSE_* bits
+    pub referral_fee_share_bps: u16, // share of the platform fee paid to the referrer
+    pub referral_monk_bps: u16, // extra MONK for the referrer, as a share of the amount spent
+    pub staking_paused_at: i64, // when staking was paused, while it is
+    pub staking_paused_secs: i64, // staking time lost to earlier pauses, which earns no rewards
+    pub reserved: [u8; 11], // zeroed, taken by fields added in later versions
+}
+
+impl PlatformConfig {
+    pub const VERSION: u8 = 1;
+    
+    pub fn is_paused(&self, subsystem: u8) -> bool {
+        self.paused & subsystem != 0
+    }
+    
+    /// Seconds staking has spent paused up to `now`, the current pause included.
+    pub fn staking_paused_total(&self, now: i64) -> i64 {
+        // A pause set before its start was recorded is not counted
+        if self.is_paused(PAUSE_STAKING) && self.staking_paused_at > 0 {
+            self.staking_paused_secs.saturating_add(now.saturating_sub(self.staking_paused_at))
+        } else {
+            self.staking_paused_secs
+        }
+    }
+}
+
+/// Layout of the `PlatformConfig` account created before accounts carried a
+/// version. Only read by `migrate_config`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct PlatformConfigV0 {
+    pub authority: Pubkey,
+    pub monk_mint: Pubkey,
+    pub platform_wallet: Pubkey,
+    pub platform_fee_bps: u64,
+    pub staking_reward_rate: u64,
+    pub bump: u8,
+}
+
+impl From<PlatformConfigV0> for PlatformConfig {
+    fn from(old: PlatformConfigV0) -> Self {
+        Self {
+            authority: old.authority,
+            monk_mint: old.monk_mint,
+            platform_wallet: old.platform_wallet,
+            platform_fee_bps: old.platform_fee_bps,
+            staking_reward_rate: old.staking_reward_rate,
+            bump: old.bump,
+            version: Self::VERSION,
+            guardian: Pubkey::default(),
+            paused: 0,
+            referral_fee_share_bps: 0,
+            referral_monk_bps: 0,
+            staking_paused_at: 0,
+            staking_paused_secs: 0,
+            reserved: [0; 11],
+        }
+    }
 }
 
 #[account]
@@ -15,6 +15,58 @@ pub struct Pool {
     pub participants: Vec<Pubkey>,
     pub created_at: i64,
     pub bump: u8,
+    pub version: u8,
+    pub referrals: u8, // participants who joined with a referrer, credited when the pool settles
+    pub reserved: [u8; 63], // zeroed, taken by fields added in later versions
+}
+
+impl Pool {
+    pub const VERSION: u8 = 1;
+    
+    /// Settled, or cancelled before anyone joined; either way the escrow
+    /// owes nobody anything.
+    pub fn is_closable(&self) -> bool {
+        !self.is_active && (self.is_completed || self.current_participants == 0)
+    }
+}
+
+/// Layout of `Pool` accounts created before accounts carried a version.
+/// Only read by `migrate_pool`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct PoolV0 {
+    pub listing: Pubkey,
+    pub initiator: Pubkey,
+    pub pool_size: u8,
+    pub current_participants: u8,
+    pub price_per_person: u64,
+    pub total_deposited: u64,
+    pub is_active: bool,
+    pub is_completed: bool,
+    #[max_len(6)]
+    pub participants: Vec<Pubkey>,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl From<PoolV0> for Pool {
+    fn from(old: PoolV0) -> Self {
+        Self {
+            listing: old.listing,
+            initiator: old.initiator,
+            pool_size: old.pool_size,
+            current_participants: old.current_participants,
+            price_per_person: old.price_per_person,
+            total_deposited: old.total_deposited,
+            is_active: old.is_active,
+            is_completed: old.is_completed,
+            participants: old.participants,
+            created_at: old.created_at,
+            bump: old.bump,
+            version: Self::VERSION,
+            referrals: 0,
+            reserved: [0; 63],
+        }
+    }
 }
 
 #[account]
@@ -25,4 +77,21 @@ pub struct PoolParticipant {
     pub amount_deposited: u64,
     pub joined_at: i64,
     pub bump: u8,
-}
\ No newline at end of file
+}
+
+/// The referrer a participant joined a pool with. The referrer is only
+/// credited once the pool settles, so a pool that never does pays nothing.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolReferral {
+    pub pool: Pubkey,
+    pub participant: Pubkey,
+    pub referrer: Pubkey, // the `Referrer` account
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl PoolReferral {
+    pub const VERSION: u8 = 1;
+}
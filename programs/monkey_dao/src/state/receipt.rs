@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct RedemptionReceipt {
+    pub merchant: Pubkey,
+    pub listing: Pubkey, // `CouponTree` for a compressed coupon
+    pub nft_mint: Pubkey,
+    pub redeemer: Pubkey,
+    pub approver: Pubkey, // merchant authority or delegate; the only record of who approved, `Listing` keeps none
+    pub index: u64, // position in the merchant's redemption sequence, not part of the seeds
+    pub amount: u64, // uses or stored value spent
+    pub value: u64, // lamport value honored, pro rata of the original price
+    pub redeemed_at: i64,
+    pub bump: u8,
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+/// A wallet registered to refer buyers. Its share of platform fees is paid
+/// into this account and its MONK bonus accrues here until it claims them.
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    pub authority: Pubkey,
+    pub total_referrals: u64, // purchases and settled pool shares made with this referrer
+    pub total_referred_volume: u64, // lamports spent by referred buyers
+    pub total_fees_earned: u64, // lamports of platform fee shared, claimed or not
+    pub total_monk_earned: u64, // MONK bonus, claimed or not
+    pub pending_monk: u64, // MONK bonus not claimed yet
+    pub registered_at: i64,
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl Referrer {
+    pub const VERSION: u8 = 1;
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::state::Merchant;
+
+#[account]
+#[derive(InitSpace)]
+pub struct RefundPolicy {
+    pub merchant: Pubkey,
+    pub refund_bps: u64, // share of the coupon's remaining value refunded after expiry
+    pub claim_window: i64, // seconds after expiry during which holders can claim
+    pub total_refunded: u64,
+    pub sales_volume_at_set: u64, // merchant's primary sales volume when set; any sale after it freezes the policy
+    pub bump: u8,
+}
+
+impl RefundPolicy {
+    pub fn can_claim(&self, expiry_date: i64, current_time: i64) -> bool {
+        current_time >= expiry_date
+            && expiry_date
+                .checked_add(self.claim_window)
+                .is_some_and(|deadline| current_time <= deadline)
+    }
+    
+    /// Holders of coupons sold under the policy rely on its terms, so it
+    /// can only change until the merchant's next sale.
+    pub fn is_frozen(&self, merchant: &Merchant) -> bool {
+        merchant.total_sales_volume > self.sales_volume_at_set
+    }
+    
+    /// Escrow stays locked until every coupon the merchant listed has expired
+    /// and its claim window has closed.
+    pub fn escrow_unlocked(&self, merchant: &Merchant, current_time: i64) -> bool {
+        merchant
+            .latest_expiry
+            .checked_add(self.claim_window)
+            .is_some_and(|deadline| current_time > deadline)
+    }
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::MAX_FLASH_SALES;
+
+/// A price that holds from `starts_at` until just before `ends_at`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, PartialEq, Eq, Debug)]
+pub struct FlashSale {
+    pub starts_at: i64,
+    pub ends_at: i64,
+    pub price: u64,
+}
+
+/// When a merchant's listing goes on sale, and its flash sales. Applies only
+/// while `seller` still sells the listing, so resales are never scheduled.
+#[account]
+#[derive(InitSpace)]
+pub struct ListingSchedule {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub start_time: i64, // not for sale before
+    #[max_len(MAX_FLASH_SALES)]
+    pub flash_sales: Vec<FlashSale>, // ascending and non-overlapping
+    pub bump: u8,
+    pub version: u8,
+    pub reserved: [u8; 64], // zeroed, taken by fields added in later versions
+}
+
+impl ListingSchedule {
+    pub const VERSION: u8 = 1;
+    
+    /// Price at `current_time`: the flash-sale price while a sale runs, and
+    /// `price` otherwise. A sale never raises the price.
+    pub fn effective_price(&self, price: u64, current_time: i64) -> u64 {
+        self.flash_sales
+            .iter()
+            .find(|sale| sale.starts_at <= current_time && current_time < sale.ends_at)
+            .map_or(price, |sale| sale.price.min(price))
+    }
+}
@@ -9,13 +9,25 @@ pub struct StakeAccount {
     pub last_claim: i64,
     pub total_rewards_claimed: u64,
     pub is_active: bool,
+    pub reward_weight_bps: u64, // remaining coupon value at stake time, 10000 = unused
     pub bump: u8,
+    pub version: u8,
+    pub paused_secs_at_claim: i64, // the config's `staking_paused_total` at `last_claim`
+    pub reserved: [u8; 56], // zeroed, taken by fields added in later versions
 }
 
 impl StakeAccount {
-    pub fn calculate_rewards(&self, current_time: i64, reward_rate: u64) -> Result<u64> {
+    pub const VERSION: u8 = 1;
+    
+    /// Rewards since `last_claim`, leaving out any time staking spent paused,
+    /// `paused_total` being the config's `staking_paused_total` now.
+    pub fn calculate_rewards(&self, current_time: i64, reward_rate: u64, paused_total: i64) -> Result<u64> {
+        let paused = paused_total
+            .checked_sub(self.paused_secs_at_claim)
+            .ok_or(ProgramError::InvalidArgument)?;
         let time_elapsed = current_time
             .checked_sub(self.last_claim)
+            .and_then(|elapsed| elapsed.checked_sub(paused))
             .ok_or(ProgramError::InvalidArgument)?;
         
         if time_elapsed < 0 {
@@ -29,6 +41,45 @@ impl StakeAccount {
             .checked_mul(reward_rate)
             .ok_or(ProgramError::InvalidArgument)?;
         
-        Ok(rewards)
+        // Partially spent coupons earn in proportion to what is left
+        let weighted = (rewards as u128)
+            .checked_mul(self.reward_weight_bps as u128)
+            .ok_or(ProgramError::InvalidArgument)?
+            / 10_000;
+        
+        Ok(weighted as u64)
+    }
+}
+
+/// Layout of `StakeAccount` accounts created before accounts carried a
+/// version, as deployed before rewards were weighted. Only read by
+/// `migrate_stake_account`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct StakeAccountV0 {
+    pub nft_mint: Pubkey,
+    pub owner: Pubkey,
+    pub staked_at: i64,
+    pub last_claim: i64,
+    pub total_rewards_claimed: u64,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+impl From<StakeAccountV0> for StakeAccount {
+    fn from(old: StakeAccountV0) -> Self {
+        Self {
+            nft_mint: old.nft_mint,
+            owner: old.owner,
+            staked_at: old.staked_at,
+            last_claim: old.last_claim,
+            total_rewards_claimed: old.total_rewards_claimed,
+            is_active: old.is_active,
+            // Coupons were single use, so a staked one was always unspent
+            reward_weight_bps: 10_000,
+            bump: old.bump,
+            version: Self::VERSION,
+            paused_secs_at_claim: 0,
+            reserved: [0; 56],
+        }
     }
-}
\ No newline at end of file
+}
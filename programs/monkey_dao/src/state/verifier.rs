@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct VerifierRegistry {
+    pub authority: Pubkey,
+    #[max_len(10)]
+    pub verifiers: Vec<Pubkey>,
+    pub threshold: u8, // attestations required for a merchant to count as verified
+    pub bump: u8,
+}
+
+impl VerifierRegistry {
+    pub fn is_verifier(&self, key: &Pubkey) -> bool {
+        self.verifiers.contains(key)
+    }
+    
+    /// A threshold can only be met while at least that many verifiers are
+    /// registered; a higher one would turn away every merchant.
+    pub fn is_valid_threshold(&self, threshold: u8) -> bool {
+        threshold > 0 && threshold as usize <= self.verifiers.len()
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Attestation {
+    pub merchant: Pubkey,
+    pub verifier: Pubkey,
+    pub kyc_hash: [u8; 32], // commitment the verifier reviewed
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
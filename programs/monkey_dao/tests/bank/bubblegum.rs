@@ -0,0 +1,317 @@
+//! Stand-in for Bubblegum and the compression program, enough to run the
+//! compressed coupon instructions end to end.
+//!
+//! A Merkle tree account is `[max_depth: u32][2^max_depth leaves]`, every
+//! leaf a 32-byte hash, zero while empty. There is no changelog: `transfer`
+//! and `burn` only accept a proof against the current root, and check the
+//! signer, the leaf hash and the proof path as Bubblegum does. Leaf schema
+//! events go to the noop program, which ignores them.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    entrypoint::ProgramResult, hash, instruction::Instruction, keccak, program::invoke,
+};
+use anchor_lang::AnchorSerialize;
+use mpl_bubblegum::accounts::TreeConfig;
+use mpl_bubblegum::hash::{hash_creators, hash_metadata};
+use mpl_bubblegum::instructions::{
+    BurnInstructionArgs, CreateTreeConfigInstructionArgs, MintV1InstructionArgs, TransferInstructionArgs,
+};
+use mpl_bubblegum::programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+use mpl_bubblegum::types::{DecompressibleState, LeafSchema, MetadataArgs, Version};
+use mpl_bubblegum::utils::get_asset_id;
+use mpl_bubblegum::{get_instruction_type, InstructionName, LeafSchemaEvent};
+use monkey_dao::state::LeafArgs;
+
+use super::{Account, Bank};
+
+/// Allocates an empty tree of `max_depth`, owned by the compression program
+/// and ready for `create_coupon_tree`.
+pub fn create_tree(bank: &mut Bank, max_depth: u32) -> Pubkey {
+    let key = Pubkey::new_unique();
+    let mut data = max_depth.to_le_bytes().to_vec();
+    data.resize(4 + (32 << max_depth), 0);
+    let account = Account {
+        lamports: bank.minimum_balance(data.len()),
+        data,
+        owner: SPL_ACCOUNT_COMPRESSION_ID,
+        executable: false,
+    };
+    bank.set_account(key, account);
+    key
+}
+
+/// The tree's leaves, in index order.
+pub fn leaves(bank: &Bank, merkle_tree: &Pubkey) -> Vec<[u8; 32]> {
+    let account = bank.account(merkle_tree).expect("no such tree");
+    split_leaves(&account.data[4..])
+}
+
+/// What an indexer's `getAssetProof` returns for the leaf at `nonce`, minted
+/// with `metadata`: the leaf arguments against the current root and the
+/// sibling path.
+pub fn asset_proof(bank: &Bank, merkle_tree: &Pubkey, nonce: u64, metadata: &MetadataArgs) -> (LeafArgs, Vec<Pubkey>) {
+    let levels = levels(leaves(bank, merkle_tree));
+    let index = nonce as u32;
+    let leaf = LeafArgs {
+        root: root(&levels),
+        data_hash: hash_metadata(metadata).unwrap(),
+        creator_hash: hash_creators(&metadata.creators),
+        nonce,
+        index,
+    };
+    let proof = proof(&levels, index).into_iter().map(Pubkey::new_from_array).collect();
+    (leaf, proof)
+}
+
+/// Hash of the leaf at `nonce` owned, and delegated, by `owner`.
+pub fn leaf_hash(merkle_tree: &Pubkey, nonce: u64, owner: Pubkey, metadata: &MetadataArgs) -> [u8; 32] {
+    LeafSchema::V1 {
+        id: get_asset_id(merkle_tree, nonce),
+        owner,
+        delegate: owner,
+        nonce,
+        data_hash: hash_metadata(metadata).unwrap(),
+        creator_hash: hash_creators(&metadata.creators),
+    }
+    .hash()
+}
+
+fn split_leaves(data: &[u8]) -> Vec<[u8; 32]> {
+    data.chunks_exact(32).map(|leaf| leaf.try_into().unwrap()).collect()
+}
+
+/// Every level of the tree, from the leaves up to the root.
+fn levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| keccak::hashv(&[&pair[0], &pair[1]]).to_bytes())
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+fn root(levels: &[Vec<[u8; 32]>]) -> [u8; 32] {
+    levels.last().unwrap()[0]
+}
+
+fn proof(levels: &[Vec<[u8; 32]>], index: u32) -> Vec<[u8; 32]> {
+    let index = index as usize;
+    (0..levels.len() - 1).map(|level| levels[level][(index >> level) ^ 1]).collect()
+}
+
+fn tree_config_discriminator() -> [u8; 8] {
+    hash::hash(b"account:TreeConfig").to_bytes()[..8].try_into().unwrap()
+}
+
+fn read_config(tree_config: &AccountInfo, merkle_tree: &AccountInfo) -> std::result::Result<TreeConfig, ProgramError> {
+    if *tree_config.key != TreeConfig::find_pda(merkle_tree.key).0 || *tree_config.owner != mpl_bubblegum::ID {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    TreeConfig::from_bytes(&tree_config.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+fn write_config(tree_config: &AccountInfo, config: &TreeConfig) -> ProgramResult {
+    let bytes = config.try_to_vec()?;
+    tree_config.try_borrow_mut_data()?[..bytes.len()].copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// Replaces the leaf at `index` once `current` is shown to be there under
+/// `root`, with `proof` as its sibling path.
+fn replace_leaf(
+    merkle_tree: &AccountInfo,
+    root: [u8; 32],
+    index: u32,
+    current: [u8; 32],
+    new: [u8; 32],
+    proof: &[AccountInfo],
+) -> ProgramResult {
+    let mut data = merkle_tree.try_borrow_mut_data()?;
+    let levels = levels(split_leaves(&data[4..]));
+    let supplied: Vec<[u8; 32]> = proof.iter().map(|node| node.key.to_bytes()).collect();
+    let index_in_tree = (index as usize) < levels[0].len();
+    if !index_in_tree
+        || root != self::root(&levels)
+        || levels[0][index as usize] != current
+        || supplied != self::proof(&levels, index)
+    {
+        msg!("Invalid root recomputed from proof");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let offset = 4 + 32 * index as usize;
+    data[offset..offset + 32].copy_from_slice(&new);
+    Ok(())
+}
+
+fn log_leaf(schema: LeafSchema) -> ProgramResult {
+    let leaf_hash = schema.hash();
+    let event = LeafSchemaEvent::new(Version::V1, schema, leaf_hash);
+    invoke(
+        &Instruction {
+            program_id: SPL_NOOP_ID,
+            accounts: Vec::new(),
+            data: event.try_to_vec()?,
+        },
+        &[],
+    )
+}
+
+/// Runs a Bubblegum instruction. Only the ones the program calls are known.
+pub(super) fn process(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let args = &data[8..];
+    match get_instruction_type(data) {
+        InstructionName::CreateTree => {
+            let args = CreateTreeConfigInstructionArgs::try_from_slice(args)?;
+            create_tree_config(accounts, args)
+        }
+        InstructionName::MintV1 => {
+            let args = MintV1InstructionArgs::try_from_slice(args)?;
+            mint_v1(accounts, args.metadata)
+        }
+        InstructionName::Transfer => {
+            let TransferInstructionArgs { root, data_hash, creator_hash, nonce, index } =
+                TransferInstructionArgs::try_from_slice(args)?;
+            let [tree_config, leaf_owner, leaf_delegate, new_leaf_owner, merkle_tree, _log_wrapper, _compression_program, _system_program, proof @ ..] =
+                accounts
+            else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let leaf = LeafArgs { root, data_hash, creator_hash, nonce, index };
+            let new = LeafSchema::V1 {
+                id: get_asset_id(merkle_tree.key, nonce),
+                owner: *new_leaf_owner.key,
+                delegate: *new_leaf_owner.key,
+                nonce,
+                data_hash,
+                creator_hash,
+            };
+            update_leaf(leaf, tree_config, leaf_owner, leaf_delegate, merkle_tree, proof, new.hash())?;
+            log_leaf(new)
+        }
+        InstructionName::Burn => {
+            let BurnInstructionArgs { root, data_hash, creator_hash, nonce, index } =
+                BurnInstructionArgs::try_from_slice(args)?;
+            let [tree_config, leaf_owner, leaf_delegate, merkle_tree, _log_wrapper, _compression_program, _system_program, proof @ ..] =
+                accounts
+            else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let leaf = LeafArgs { root, data_hash, creator_hash, nonce, index };
+            update_leaf(leaf, tree_config, leaf_owner, leaf_delegate, merkle_tree, proof, [0; 32])
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn create_tree_config(accounts: &[AccountInfo], args: CreateTreeConfigInstructionArgs) -> ProgramResult {
+    let [tree_config, merkle_tree, payer, tree_creator, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if !payer.is_signer || !tree_creator.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *tree_config.key != TreeConfig::find_pda(merkle_tree.key).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if tree_config.lamports() > 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let data = merkle_tree.try_borrow_data()?;
+    if *merkle_tree.owner != SPL_ACCOUNT_COMPRESSION_ID
+        || data.len() < 4
+        || data[..4] != args.max_depth.to_le_bytes()
+        || data[4..].iter().any(|byte| *byte != 0)
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    drop(data);
+
+    let lamports = Rent::get()?.minimum_balance(TreeConfig::LEN);
+    **payer.try_borrow_mut_lamports()? = payer
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **tree_config.try_borrow_mut_lamports()? += lamports;
+    tree_config.realloc(TreeConfig::LEN, true)?;
+    tree_config.assign(&mpl_bubblegum::ID);
+    write_config(
+        tree_config,
+        &TreeConfig {
+            discriminator: tree_config_discriminator(),
+            tree_creator: *tree_creator.key,
+            tree_delegate: *tree_creator.key,
+            total_mint_capacity: 1 << args.max_depth,
+            num_minted: 0,
+            is_public: args.public.unwrap_or(false),
+            is_decompressible: DecompressibleState::Disabled,
+        },
+    )
+}
+
+fn mint_v1(accounts: &[AccountInfo], metadata: MetadataArgs) -> ProgramResult {
+    let [tree_config, leaf_owner, leaf_delegate, merkle_tree, payer, tree_creator_or_delegate, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let mut config = read_config(tree_config, merkle_tree)?;
+    if !payer.is_signer || !tree_creator_or_delegate.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let is_minter = [config.tree_creator, config.tree_delegate].contains(tree_creator_or_delegate.key);
+    if !config.is_public && !is_minter {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if config.num_minted >= config.total_mint_capacity {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    let nonce = config.num_minted;
+    let schema = LeafSchema::V1 {
+        id: get_asset_id(merkle_tree.key, nonce),
+        owner: *leaf_owner.key,
+        delegate: *leaf_delegate.key,
+        nonce,
+        data_hash: hash_metadata(&metadata)?,
+        creator_hash: hash_creators(&metadata.creators),
+    };
+    let offset = 4 + 32 * nonce as usize;
+    merkle_tree.try_borrow_mut_data()?[offset..offset + 32].copy_from_slice(&schema.hash());
+    config.num_minted += 1;
+    write_config(tree_config, &config)?;
+    log_leaf(schema)
+}
+
+/// Swaps the leaf described by `leaf` for `new`, signed by its owner or
+/// delegate.
+fn update_leaf(
+    leaf: LeafArgs,
+    tree_config: &AccountInfo,
+    leaf_owner: &AccountInfo,
+    leaf_delegate: &AccountInfo,
+    merkle_tree: &AccountInfo,
+    proof: &[AccountInfo],
+    new: [u8; 32],
+) -> ProgramResult {
+    read_config(tree_config, merkle_tree)?;
+    if !leaf_owner.is_signer && !leaf_delegate.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let current = LeafSchema::V1 {
+        id: get_asset_id(merkle_tree.key, leaf.nonce),
+        owner: *leaf_owner.key,
+        delegate: *leaf_delegate.key,
+        nonce: leaf.nonce,
+        data_hash: leaf.data_hash,
+        creator_hash: leaf.creator_hash,
+    }
+    .hash();
+    replace_leaf(merkle_tree, leaf.root, leaf.index, current, new, proof)
+}
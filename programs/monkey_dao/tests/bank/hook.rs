@@ -0,0 +1,131 @@
+//! A transfer hook program for Token-2022 coupons, counting every transfer
+//! of a hooked mint.
+//!
+//! Each hooked mint's validation account lists one extra account, the
+//! mint's counter, so the hook only runs if the caller forwarded it. The
+//! validation account and the counter are written directly instead of
+//! through `InitializeExtraAccountMetaList`.
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, system_instruction};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+use spl_token_2022::extension::{transfer_hook, ExtensionType};
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address,
+    instruction::{ExecuteInstruction, TransferHookInstruction},
+    offchain::add_extra_account_metas_for_execute,
+};
+
+use super::{Account, Bank};
+
+pub const HOOK_PROGRAM_ID: Pubkey = Pubkey::new_from_array([0x48; 32]);
+
+/// A fresh 0-decimal Token-2022 mint whose transfers call the hook, with
+/// `authority` as mint authority.
+pub fn create_mint(bank: &mut Bank, authority: Pubkey) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferHook,
+    ])
+    .unwrap();
+    for instruction in [
+        system_instruction::create_account(
+            &authority,
+            &mint,
+            bank.minimum_balance(space),
+            space as u64,
+            &spl_token_2022::ID,
+        ),
+        transfer_hook::instruction::initialize(&spl_token_2022::ID, &mint, Some(authority), Some(HOOK_PROGRAM_ID))
+            .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(&spl_token_2022::ID, &mint, &authority, None, 0).unwrap(),
+    ] {
+        bank.process(instruction).unwrap();
+    }
+
+    let counter = counter(&mint);
+    let extra_metas = [ExtraAccountMeta::new_with_pubkey(&counter, false, true).unwrap()];
+    let mut validation = vec![0; ExtraAccountMetaList::size_of(extra_metas.len()).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut validation, &extra_metas).unwrap();
+    set_hook_account(bank, get_extra_account_metas_address(&mint, &HOOK_PROGRAM_ID), validation);
+    set_hook_account(bank, counter, vec![0; 8]);
+    mint
+}
+
+/// The accounts a transfer of `mint` has to forward, resolved from its
+/// validation account as a wallet would before sending the transfer.
+pub fn accounts(bank: &Bank, mint: &Pubkey) -> Vec<AccountMeta> {
+    // The counter does not depend on the transfer's own accounts
+    let (source, destination, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut instruction =
+        spl_token_2022::instruction::transfer_checked(&spl_token_2022::ID, &source, mint, &destination, &owner, &[], 1, 0)
+            .unwrap();
+    let base_accounts = instruction.accounts.len();
+    let resolved = add_extra_account_metas_for_execute(
+        &mut instruction,
+        &HOOK_PROGRAM_ID,
+        &source,
+        mint,
+        &destination,
+        &owner,
+        1,
+        |address| std::future::ready(Ok(bank.account(&address).map(|account| account.data.clone()))),
+    );
+    ready(resolved).unwrap();
+    instruction.accounts.split_off(base_accounts)
+}
+
+/// How many transfers of `mint` the hook has seen.
+pub fn transfers(bank: &Bank, mint: &Pubkey) -> u64 {
+    let data = &bank.account(&counter(mint)).expect("not a hooked mint").data;
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+fn counter(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"counter", mint.as_ref()], &HOOK_PROGRAM_ID).0
+}
+
+fn set_hook_account(bank: &mut Bank, key: Pubkey, data: Vec<u8>) {
+    let account = Account {
+        lamports: bank.minimum_balance(data.len()),
+        data,
+        owner: HOOK_PROGRAM_ID,
+        executable: false,
+    };
+    bank.set_account(key, account);
+}
+
+/// Output of a future that never waits, as the bank's account fetches don't.
+fn ready<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("bank account fetches are immediate"),
+    }
+}
+
+pub(super) fn process(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let TransferHookInstruction::Execute { .. } = TransferHookInstruction::unpack(data)? else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    let [_source, mint, _destination, _owner, validation, counter_info, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if *validation.key != get_extra_account_metas_address(mint.key, &HOOK_PROGRAM_ID) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+        accounts,
+        data,
+        &HOOK_PROGRAM_ID,
+        &validation.try_borrow_data()?,
+    )?;
+
+    let mut counter = counter_info.try_borrow_mut_data()?;
+    let transfers = u64::from_le_bytes(counter[..8].try_into().unwrap()) + 1;
+    counter[..8].copy_from_slice(&transfers.to_le_bytes());
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! The platform every test starts from: a Token-2022 MONK mint, no fee
+//! schedule, so sales pay the flat `PLATFORM_FEE_BPS`, and a verifier
+//! registry with one accredited verifier at threshold 1.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction, native_token::LAMPORTS_PER_SOL, program_pack::Pack, system_instruction,
+};
+use anchor_spl::token_interface::TokenAccount;
+use monkey_dao::state::{Listing, Merchant, UseMethod};
+use monkey_dao_client::{pda, Client, Coupon, ListNftArgs};
+
+use super::{Bank, TransactionMeta, TransactionResult};
+
+pub const SOL: u64 = LAMPORTS_PER_SOL;
+pub const DAY: i64 = 86_400;
+pub const KYC_HASH: [u8; 32] = [7; 32];
+
+pub struct Marketplace {
+    pub bank: Bank,
+    pub client: Client,
+    pub authority: Pubkey,
+    pub verifier: Pubkey,
+}
+
+impl Marketplace {
+    pub fn new() -> Self {
+        let mut bank = Bank::new();
+        let authority = bank.funded_key(100 * SOL);
+        let platform_wallet = bank.funded_key(SOL);
+        let verifier = bank.funded_key(10 * SOL);
+        let client = Client::new(platform_wallet, spl_token_2022::ID);
+        let mut market = Self { bank, client, authority, verifier };
+
+        market.run(market.client.initialize_monk_mint_2022(
+            authority,
+            "Monkey DAO".to_string(),
+            "MONK".to_string(),
+            "https://monkeydao.example/monk.json".to_string(),
+        ));
+        market.run(market.client.initialize_verifier_registry(authority, vec![verifier], 1));
+        market
+    }
+
+    pub fn send(&mut self, instruction: Instruction) -> TransactionResult {
+        self.bank.process(instruction)
+    }
+
+    #[track_caller]
+    pub fn run(&mut self, instruction: Instruction) -> TransactionMeta {
+        self.bank.process(instruction).unwrap()
+    }
+
+    pub fn user(&mut self) -> Pubkey {
+        self.bank.funded_key(100 * SOL)
+    }
+
+    pub fn platform_wallet(&self) -> Pubkey {
+        self.client.platform_wallet
+    }
+
+    /// A registered merchant without any attestations.
+    pub fn merchant(&mut self) -> Pubkey {
+        let authority = self.user();
+        self.run(self.client.register_merchant(
+            authority,
+            "Banana Ramen".to_string(),
+            "Restaurant".to_string(),
+            KYC_HASH,
+            "https://kyc.example/banana-ramen".to_string(),
+        ));
+        authority
+    }
+
+    /// A merchant attested by the platform verifier and marked verified.
+    pub fn verified_merchant(&mut self) -> Pubkey {
+        let authority = self.merchant();
+        let expires_at = self.bank.now() + 365 * DAY;
+        self.run(self.client.issue_attestation(self.verifier, authority, KYC_HASH, expires_at));
+        self.run(self.client.verify_merchant(authority, &[self.verifier]));
+        authority
+    }
+
+    /// A fresh 0-decimal mint under `token_program` with one token held by
+    /// `owner`, who is also the mint authority.
+    pub fn coupon(&mut self, owner: Pubkey, token_program: Pubkey) -> Coupon {
+        let mint = Pubkey::new_unique();
+        let space = spl_token_2022::state::Mint::LEN;
+        self.run(system_instruction::create_account(
+            &owner,
+            &mint,
+            self.bank.minimum_balance(space),
+            space as u64,
+            &token_program,
+        ));
+        self.run(
+            spl_token_2022::instruction::initialize_mint2(&token_program, &mint, &owner, None, 0).unwrap(),
+        );
+        let coupon = Coupon::new(mint, token_program);
+        self.mint_to(coupon, owner, owner);
+        coupon
+    }
+
+    /// Mints one more token of `coupon` to `recipient`, creating their token
+    /// account if needed. `authority` is the merchant that created the mint.
+    pub fn mint_to(&mut self, coupon: Coupon, authority: Pubkey, recipient: Pubkey) {
+        let account = self.token_account(&recipient, &coupon);
+        if !self.bank.exists(&account) {
+            self.run(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &recipient,
+                    &recipient,
+                    &coupon.mint,
+                    &coupon.token_program,
+                ),
+            );
+        }
+        self.run(
+            spl_token_2022::instruction::mint_to(
+                &coupon.token_program,
+                &coupon.mint,
+                &account,
+                &authority,
+                &[],
+                1,
+            )
+            .unwrap(),
+        );
+    }
+
+    /// Single-use listing terms valid for 30 days.
+    pub fn list_args(&self, price: u64) -> ListNftArgs {
+        ListNftArgs {
+            price,
+            is_group_deal: false,
+            deal_price_2: None,
+            deal_price_4: None,
+            deal_price_6: None,
+            coupon_description: "Two bowls for the price of one".to_string(),
+            expiry_date: self.bank.now() + 30 * DAY,
+            use_method: UseMethod::Single,
+            total_uses: 1,
+        }
+    }
+
+    pub fn list(&mut self, merchant: Pubkey, coupon: Coupon, args: ListNftArgs) -> TransactionResult {
+        let verifiers = [self.verifier];
+        self.send(self.client.list_nft(merchant, coupon, args, &verifiers))
+    }
+
+    /// A new legacy SPL Token coupon minted and listed by `merchant`.
+    pub fn listed_coupon(&mut self, merchant: Pubkey, args: ListNftArgs) -> Coupon {
+        let coupon = self.coupon(merchant, spl_token::ID);
+        self.list(merchant, coupon, args).unwrap();
+        coupon
+    }
+
+    pub fn buy(&mut self, buyer: Pubkey, coupon: Coupon) -> TransactionResult {
+        let listing = self.listing(&coupon);
+        let merchant: Merchant = self.bank.get(&listing.merchant);
+        self.send(self.client.buy_nft(buyer, listing.seller, merchant.authority, coupon, None))
+    }
+
+    pub fn listing(&self, coupon: &Coupon) -> Listing {
+        self.bank.get(&pda::listing(&coupon.mint).0)
+    }
+
+    pub fn token_account(&self, owner: &Pubkey, coupon: &Coupon) -> Pubkey {
+        pda::associated_token(owner, &coupon.mint, &coupon.token_program)
+    }
+
+    pub fn coupon_balance(&self, owner: &Pubkey, coupon: &Coupon) -> u64 {
+        self.bank
+            .try_get::<TokenAccount>(&self.token_account(owner, coupon))
+            .map_or(0, |account| account.amount)
+    }
+
+    pub fn monk_balance(&self, owner: &Pubkey) -> u64 {
+        let account = pda::associated_token(owner, &self.client.monk_mint, &self.client.monk_token_program);
+        self.bank.try_get::<TokenAccount>(&account).map_or(0, |account| account.amount)
+    }
+}
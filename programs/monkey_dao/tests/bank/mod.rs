@@ -0,0 +1,605 @@
+//! In-process bank the integration tests run the program against.
+//!
+//! Instructions execute natively through the program's own entrypoint, on an
+//! input buffer laid out exactly as the SBF loader serializes it, so Anchor's
+//! account checks, `init`, `realloc` and `close` behave as they do on chain.
+//! CPIs into the system, SPL Token, Token-2022 and associated token programs
+//! are routed to those programs' processors through the `solana-program`
+//! syscall stubs, and PDA signer seeds are checked against the caller.
+//! Bubblegum and the noop program run as the stand-ins in `bubblegum`, and
+//! `hook` provides a transfer hook program for hooked Token-2022 mints.
+//!
+//! Not modelled: compute limits, transaction signatures (an account marked
+//! `is_signer` counts as signed) and the runtime's per-program rules on who
+//! may debit lamports or write data. Token Metadata is not available
+//! natively, so CPIs into it fail with `IncorrectProgramId`.
+
+#![allow(dead_code)]
+
+pub mod bubblegum;
+pub mod hook;
+pub mod marketplace;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Once;
+
+use anchor_lang::__private::base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader,
+    entrypoint::{self, ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+    instruction::Instruction,
+    program_stubs::{self, SyscallStubs},
+    program_utils::limited_deserialize,
+    system_instruction::{SystemError, SystemInstruction},
+    system_program,
+};
+use anchor_lang::{AccountDeserialize, AccountSerialize, Event};
+use anchor_spl::metadata::mpl_token_metadata;
+use mpl_bubblegum::programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+
+/// Start of the bank's clock, a fixed point so expiry dates are reproducible.
+pub const GENESIS_TIMESTAMP: i64 = 1_700_000_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Why a transaction was rolled back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    Program(ProgramError),
+    ReadonlyAccountModified(Pubkey),
+    UnbalancedLamports,
+    InsufficientFundsForRent(Pubkey),
+}
+
+pub struct Failure {
+    pub error: TransactionError,
+    pub logs: Vec<String>,
+}
+
+impl fmt::Debug for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?}", self.error)?;
+        for line in &self.logs {
+            writeln!(f, "  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TransactionMeta {
+    pub logs: Vec<String>,
+}
+
+impl TransactionMeta {
+    /// Every `E` emitted by the transaction, in order.
+    pub fn events<E: Event>(&self) -> Vec<E> {
+        self.logs
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program data: "))
+            .filter_map(|encoded| BASE64.decode(encoded).ok())
+            .filter(|data| data.starts_with(&E::DISCRIMINATOR))
+            .map(|data| E::try_from_slice(&data[8..]).expect("malformed event"))
+            .collect()
+    }
+
+    /// The single `E` emitted by the transaction.
+    pub fn event<E: Event>(&self) -> E {
+        let mut events = self.events::<E>();
+        assert_eq!(events.len(), 1, "expected exactly one event");
+        events.remove(0)
+    }
+}
+
+pub type TransactionResult = std::result::Result<TransactionMeta, Failure>;
+
+/// Asserts that `result` failed with the program error `code`, e.g. a
+/// `monkey_dao::error::ErrorCode` or an Anchor framework error.
+#[track_caller]
+pub fn assert_error(result: TransactionResult, code: impl Into<u32>) {
+    let expected = TransactionError::Program(ProgramError::Custom(code.into()));
+    match result {
+        Ok(meta) => panic!("expected {expected:?}, transaction succeeded: {:#?}", meta.logs),
+        Err(failure) => assert_eq!(failure.error, expected, "{failure:?}"),
+    }
+}
+
+pub struct Bank {
+    accounts: HashMap<Pubkey, Account>,
+    clock: Clock,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut bank = Self {
+            accounts: HashMap::new(),
+            clock: Clock {
+                slot: 1,
+                unix_timestamp: GENESIS_TIMESTAMP,
+                ..Clock::default()
+            },
+        };
+        for program in [
+            monkey_dao::ID,
+            system_program::ID,
+            spl_token::ID,
+            spl_token_2022::ID,
+            spl_associated_token_account::ID,
+            mpl_token_metadata::ID,
+            mpl_bubblegum::ID,
+            SPL_NOOP_ID,
+            SPL_ACCOUNT_COMPRESSION_ID,
+            hook::HOOK_PROGRAM_ID,
+        ] {
+            bank.set_account(
+                program,
+                Account {
+                    lamports: 1,
+                    data: Vec::new(),
+                    owner: bpf_loader::ID,
+                    executable: true,
+                },
+            );
+        }
+        bank
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.accounts.insert(key, account);
+    }
+
+    /// Writes `data` as a rent-exempt account owned by the program, for state
+    /// that cannot be reached through the current instructions.
+    pub fn set_program_data(&mut self, key: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: self.minimum_balance(data.len()),
+            data,
+            owner: monkey_dao::ID,
+            executable: false,
+        };
+        self.set_account(key, account);
+    }
+
+    pub fn set_program_account<T: AccountSerialize>(&mut self, key: Pubkey, state: &T) {
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        self.set_program_data(key, data);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&Account> {
+        self.accounts.get(key)
+    }
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.contains_key(key)
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let account = self.accounts.entry(*key).or_insert_with(|| Account {
+            owner: system_program::ID,
+            ..Account::default()
+        });
+        account.lamports += lamports;
+    }
+
+    /// A fresh system account holding `lamports`.
+    pub fn funded_key(&mut self, lamports: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.airdrop(&key, lamports);
+        key
+    }
+
+    /// Deserializes an Anchor or SPL account, `None` if it does not exist.
+    pub fn try_get<T: AccountDeserialize>(&self, key: &Pubkey) -> Option<T> {
+        let account = self.accounts.get(key)?;
+        Some(T::try_deserialize(&mut account.data.as_slice()).expect("account does not deserialize"))
+    }
+
+    #[track_caller]
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        self.try_get(key).unwrap_or_else(|| panic!("account {key} does not exist"))
+    }
+
+    pub fn minimum_balance(&self, data_len: usize) -> u64 {
+        Rent::default().minimum_balance(data_len)
+    }
+
+    pub fn now(&self) -> i64 {
+        self.clock.unix_timestamp
+    }
+
+    pub fn warp_to(&mut self, unix_timestamp: i64) {
+        self.clock.unix_timestamp = unix_timestamp;
+        self.clock.slot += 1;
+    }
+
+    pub fn warp_forward(&mut self, seconds: i64) {
+        self.warp_to(self.clock.unix_timestamp + seconds);
+    }
+
+    /// Runs `instruction` as a transaction of its own. Accounts are only
+    /// written back when it succeeds.
+    pub fn process(&mut self, instruction: Instruction) -> TransactionResult {
+        CONTEXT.with(|context| {
+            let mut context = context.borrow_mut();
+            context.clock = self.clock.clone();
+            context.logs.clear();
+            context.return_data = None;
+        });
+
+        let input = Input::serialize(&instruction, &self.accounts);
+        let mut buffer = input.buffer.clone();
+
+        let result = {
+            // SAFETY: `buffer` is 8-byte aligned, laid out as the loader does and
+            // outlives every `AccountInfo` deserialized from it
+            let (program_id, accounts, data) =
+                unsafe { entrypoint::deserialize(buffer.as_mut_ptr() as *mut u8) };
+            log(format!("Program {program_id} invoke [1]"));
+            CONTEXT.with(|context| context.borrow_mut().stack.push(*program_id));
+            let result = if *program_id == monkey_dao::ID {
+                monkey_dao::entry(program_id, &accounts, data)
+            } else {
+                invoke_builtin(program_id, &accounts, data)
+            };
+            CONTEXT.with(|context| context.borrow_mut().stack.pop());
+            result
+        };
+
+        let result = result
+            .map_err(TransactionError::Program)
+            .and_then(|()| input.check(&buffer, &self.accounts));
+        match &result {
+            Ok(()) => log(format!("Program {} success", instruction.program_id)),
+            Err(error) => log(format!("Program {} failed: {error:?}", instruction.program_id)),
+        }
+        let logs = CONTEXT.with(|context| std::mem::take(&mut context.borrow_mut().logs));
+
+        match result {
+            Ok(()) => {
+                for (key, account) in input.read_back(&buffer) {
+                    if account.lamports == 0 {
+                        self.accounts.remove(&key);
+                    } else {
+                        self.accounts.insert(key, account);
+                    }
+                }
+                Ok(TransactionMeta { logs })
+            }
+            Err(error) => Err(Failure { error, logs }),
+        }
+    }
+}
+
+/// Per-thread execution state read by the syscall stubs; tests run in parallel.
+#[derive(Default)]
+struct Context {
+    clock: Clock,
+    logs: Vec<String>,
+    stack: Vec<Pubkey>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+fn log(line: String) {
+    CONTEXT.with(|context| context.borrow_mut().logs.push(line));
+}
+
+fn current_program() -> Pubkey {
+    CONTEXT.with(|context| *context.borrow().stack.last().expect("no program running"))
+}
+
+/// Serialized loader input for one instruction, with the offsets needed to
+/// read the accounts back after execution.
+struct Input {
+    buffer: Vec<u64>,
+    accounts: Vec<Slot>,
+}
+
+struct Slot {
+    key: Pubkey,
+    is_writable: bool,
+    executable: bool,
+    data_offset: usize,
+}
+
+impl Input {
+    fn serialize(instruction: &Instruction, accounts: &HashMap<Pubkey, Account>) -> Self {
+        // Flags are merged across duplicate metas as a message would
+        let mut first_position: HashMap<Pubkey, usize> = HashMap::new();
+        let mut is_signer: HashMap<Pubkey, bool> = HashMap::new();
+        let mut is_writable: HashMap<Pubkey, bool> = HashMap::new();
+        for (position, meta) in instruction.accounts.iter().enumerate() {
+            first_position.entry(meta.pubkey).or_insert(position);
+            *is_signer.entry(meta.pubkey).or_default() |= meta.is_signer;
+            *is_writable.entry(meta.pubkey).or_default() |= meta.is_writable;
+        }
+
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut slots = Vec::new();
+        bytes.extend_from_slice(&(instruction.accounts.len() as u64).to_le_bytes());
+        for (position, meta) in instruction.accounts.iter().enumerate() {
+            let first = first_position[&meta.pubkey];
+            if first != position {
+                bytes.push(first as u8);
+                bytes.extend_from_slice(&[0u8; 7]);
+                continue;
+            }
+            let account = accounts.get(&meta.pubkey).cloned().unwrap_or_else(|| Account {
+                owner: system_program::ID,
+                ..Account::default()
+            });
+            bytes.push(NON_DUP_MARKER);
+            bytes.push(is_signer[&meta.pubkey] as u8);
+            bytes.push(is_writable[&meta.pubkey] as u8);
+            bytes.push(account.executable as u8);
+            bytes.extend_from_slice(&[0u8; 4]);
+            bytes.extend_from_slice(meta.pubkey.as_ref());
+            bytes.extend_from_slice(account.owner.as_ref());
+            bytes.extend_from_slice(&account.lamports.to_le_bytes());
+            bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            slots.push(Slot {
+                key: meta.pubkey,
+                is_writable: is_writable[&meta.pubkey],
+                executable: account.executable,
+                data_offset: bytes.len(),
+            });
+            bytes.extend_from_slice(&account.data);
+            bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+            bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&instruction.data);
+        bytes.extend_from_slice(instruction.program_id.as_ref());
+
+        let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+        as_bytes_mut(&mut buffer)[..bytes.len()].copy_from_slice(&bytes);
+        Self { buffer, accounts: slots }
+    }
+
+    fn read_back(&self, buffer: &[u64]) -> Vec<(Pubkey, Account)> {
+        let bytes = as_bytes(buffer);
+        let u64_at = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        self.accounts
+            .iter()
+            .map(|slot| {
+                let data_len = u64_at(slot.data_offset - 8) as usize;
+                let account = Account {
+                    lamports: u64_at(slot.data_offset - 16),
+                    data: bytes[slot.data_offset..slot.data_offset + data_len].to_vec(),
+                    owner: Pubkey::try_from(&bytes[slot.data_offset - 48..slot.data_offset - 16]).unwrap(),
+                    executable: slot.executable,
+                };
+                (slot.key, account)
+            })
+            .collect()
+    }
+
+    /// The runtime's post-transaction checks.
+    fn check(&self, buffer: &[u64], before: &HashMap<Pubkey, Account>) -> std::result::Result<(), TransactionError> {
+        let rent = Rent::default();
+        let mut lamports_before: u128 = 0;
+        let mut lamports_after: u128 = 0;
+        for (slot, (key, after)) in self.accounts.iter().zip(self.read_back(buffer)) {
+            let before = before.get(&key).cloned().unwrap_or_else(|| Account {
+                owner: system_program::ID,
+                ..Account::default()
+            });
+            lamports_before += before.lamports as u128;
+            lamports_after += after.lamports as u128;
+            if !slot.is_writable && after != before {
+                return Err(TransactionError::ReadonlyAccountModified(key));
+            }
+            if slot.is_writable && after.lamports > 0 && !rent.is_exempt(after.lamports, after.data.len()) {
+                return Err(TransactionError::InsufficientFundsForRent(key));
+            }
+        }
+        if lamports_before != lamports_after {
+            return Err(TransactionError::UnbalancedLamports);
+        }
+        Ok(())
+    }
+}
+
+fn as_bytes(buffer: &[u64]) -> &[u8] {
+    // SAFETY: u8 has no alignment requirement and every bit pattern is valid
+    unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 8) }
+}
+
+fn as_bytes_mut(buffer: &mut [u64]) -> &mut [u8] {
+    // SAFETY: as above, and the borrow is exclusive
+    unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8) }
+}
+
+/// Programs the bank can run as CPI targets.
+fn invoke_builtin(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if *program_id == system_program::ID {
+        process_system(accounts, data)
+    } else if *program_id == spl_token::ID {
+        spl_token::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == spl_token_2022::ID {
+        spl_token_2022::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == spl_associated_token_account::ID {
+        spl_associated_token_account::processor::process_instruction(program_id, accounts, data)
+    } else if *program_id == mpl_bubblegum::ID {
+        bubblegum::process(accounts, data)
+    } else if *program_id == SPL_NOOP_ID {
+        Ok(())
+    } else if *program_id == hook::HOOK_PROGRAM_ID {
+        hook::process(accounts, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+/// The subset of the system program Anchor and the SPL programs use.
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction =
+        limited_deserialize(data, 1232).map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            if to.lamports() > 0 {
+                return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+            }
+            allocate(to, space)?;
+            assign(to, &owner)?;
+            transfer(from, to, lamports)
+        }
+        SystemInstruction::Transfer { lamports } => transfer(&accounts[0], &accounts[1], lamports),
+        SystemInstruction::Allocate { space } => allocate(&accounts[0], space),
+        SystemInstruction::Assign { owner } => assign(&accounts[0], &owner),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+    }
+    account.realloc(space as usize, true)
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner == owner {
+        return Ok(());
+    }
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !from.data_is_empty() || *from.owner != system_program::ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if from.lamports() < lamports {
+        return Err(ProgramError::Custom(SystemError::ResultWithNegativeLamports as u32));
+    }
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        log(format!("Program log: {message}"));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let encoded: Vec<String> = fields.iter().map(|field| BASE64.encode(field)).collect();
+        log(format!("Program data: {}", encoded.join(" ")));
+    }
+
+    fn sol_remaining_compute_units(&self) -> u64 {
+        u64::MAX
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = CONTEXT.with(|context| context.borrow().clock.clone());
+        // SAFETY: the caller passes a pointer to a `Clock`
+        unsafe { *(var_addr as *mut Clock) = clock };
+        entrypoint::SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: the caller passes a pointer to a `Rent`
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        entrypoint::SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        CONTEXT.with(|context| context.borrow().return_data.clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program = current_program();
+        CONTEXT.with(|context| {
+            context.borrow_mut().return_data = (!data.is_empty()).then(|| (program, data.to_vec()));
+        });
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        CONTEXT.with(|context| context.borrow().stack.len() as u64)
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = current_program();
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        // The callee sees the caller's accounts with the privileges it was granted
+        let mut callee_infos = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_signer && !info.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let mut callee = info.clone();
+            callee.is_signer = meta.is_signer;
+            callee.is_writable = meta.is_writable;
+            callee_infos.push(callee);
+        }
+
+        let depth = CONTEXT.with(|context| {
+            let mut context = context.borrow_mut();
+            context.stack.push(instruction.program_id);
+            context.return_data = None;
+            context.stack.len()
+        });
+        log(format!("Program {} invoke [{depth}]", instruction.program_id));
+        let result = invoke_builtin(&instruction.program_id, &callee_infos, &instruction.data);
+        CONTEXT.with(|context| context.borrow_mut().stack.pop());
+        match &result {
+            Ok(()) => log(format!("Program {} success", instruction.program_id)),
+            Err(error) => log(format!("Program {} failed: {error}", instruction.program_id)),
+        }
+        result
+    }
+}
@@ -0,0 +1,165 @@
+//! Cart checkout: several listings bought in one instruction, with one
+//! platform fee transfer and one MONK mint, and nothing bought if any fails.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CartCheckedOut, CouponPurchased};
+use monkey_dao::state::{Merchant, UserStats};
+use monkey_dao::{MAX_CART_ITEMS, PLATFORM_FEE_BPS, PURCHASE_REWARD_BPS};
+use monkey_dao_client::{pda, CartItem, Coupon};
+
+fn item(market: &Marketplace, coupon: Coupon) -> CartItem {
+    let listing = market.listing(&coupon);
+    let merchant: Merchant = market.bank.get(&listing.merchant);
+    CartItem { coupon, seller: listing.seller, merchant: merchant.authority }
+}
+
+fn fee(price: u64) -> u64 {
+    price * PLATFORM_FEE_BPS / 10_000
+}
+
+#[test]
+fn cart_buys_from_several_merchants_at_once() {
+    let mut market = Marketplace::new();
+    let ramen = market.verified_merchant();
+    let tacos = market.verified_merchant();
+    let coupons = [
+        market.listed_coupon(ramen, market.list_args(SOL)),
+        market.listed_coupon(ramen, market.list_args(2 * SOL)),
+        market.listed_coupon(tacos, market.list_args(SOL / 2)),
+    ];
+    let items = coupons.map(|coupon| item(&market, coupon));
+    let buyer = market.user();
+    let (ramen_before, tacos_before) = (market.bank.lamports(&ramen), market.bank.lamports(&tacos));
+    let wallet_before = market.bank.lamports(&market.platform_wallet());
+
+    let meta = market.run(market.client.checkout_cart(buyer, &items));
+
+    let total = SOL + 2 * SOL + SOL / 2;
+    let total_fee = fee(SOL) + fee(2 * SOL) + fee(SOL / 2);
+    let event = meta.event::<CartCheckedOut>();
+    assert_eq!(event.buyer, buyer);
+    assert_eq!(event.items, 3);
+    assert_eq!(event.total_price, total);
+    assert_eq!(event.platform_fee, total_fee);
+    assert_eq!(event.monk_reward, total * PURCHASE_REWARD_BPS / 10_000);
+    assert_eq!(meta.events::<CouponPurchased>().len(), 3);
+
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), wallet_before + total_fee);
+    // Sellers also get the rent of the closed vaults back
+    let vault_rent = market.bank.minimum_balance(165);
+    assert_eq!(
+        market.bank.lamports(&ramen),
+        ramen_before + 3 * SOL - fee(SOL) - fee(2 * SOL) + 2 * vault_rent
+    );
+    assert_eq!(market.bank.lamports(&tacos), tacos_before + SOL / 2 - fee(SOL / 2) + vault_rent);
+    assert_eq!(market.monk_balance(&buyer), event.monk_reward);
+    for coupon in &coupons {
+        assert_eq!(market.coupon_balance(&buyer, coupon), 1);
+        let listing = market.listing(coupon);
+        assert!(!listing.is_active);
+        assert_eq!(listing.holder, buyer);
+    }
+    let stats: UserStats = market.bank.get(&pda::user_stats(&buyer).0);
+    assert_eq!(stats.total_purchases, 3);
+    let merchant: Merchant = market.bank.get(&pda::merchant(&ramen).0);
+    assert_eq!(merchant.total_sales_volume, 3 * SOL);
+}
+
+#[test]
+fn one_bad_listing_fails_the_whole_cart() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let good = market.listed_coupon(merchant, market.list_args(SOL));
+    let sold = market.listed_coupon(merchant, market.list_args(SOL));
+    let first_buyer = market.user();
+    market.buy(first_buyer, sold).unwrap();
+    let buyer = market.user();
+    let before = market.bank.lamports(&buyer);
+
+    let other = market.listed_coupon(merchant, market.list_args(SOL));
+    let mut misdirected = item(&market, other);
+    misdirected.seller = first_buyer;
+    let items = [item(&market, good), item(&market, sold)];
+    assert_error(market.send(market.client.checkout_cart(buyer, &items)), ErrorCode::ListingNotActive);
+    assert_error(
+        market.send(market.client.checkout_cart(buyer, &[item(&market, good), misdirected])),
+        ErrorCode::Unauthorized,
+    );
+
+    assert!(market.listing(&good).is_active);
+    assert_eq!(market.coupon_balance(&buyer, &good), 0);
+    assert_eq!(market.bank.lamports(&buyer), before);
+}
+
+#[test]
+fn a_listing_is_bought_only_once_per_cart() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+
+    let twice = [item(&market, coupon), item(&market, coupon)];
+    assert_error(market.send(market.client.checkout_cart(buyer, &twice)), ErrorCode::ListingNotActive);
+}
+
+#[test]
+fn carts_follow_schedules_and_gates() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let buyer = market.user();
+    let later = market.listed_coupon(merchant, market.list_args(SOL));
+    let start = market.bank.now() + DAY;
+    market.run(market.client.set_listing_schedule(merchant, later.mint, start, vec![]));
+    let gated = market.listed_coupon(merchant, market.list_args(SOL));
+    market.run(market.client.set_gate(merchant, 0, None, None, 0));
+    let gate = pda::listing_gate(&pda::merchant(&merchant).0, 0).0;
+    market.run(market.client.set_listing_gate(merchant, gated.mint, Some(gate)));
+
+    assert_error(
+        market.send(market.client.checkout_cart(buyer, &[item(&market, later)])),
+        ErrorCode::ListingNotStarted,
+    );
+    assert_error(
+        market.send(market.client.checkout_cart(buyer, &[item(&market, gated)])),
+        ErrorCode::GateRequired,
+    );
+
+    market.bank.warp_to(start);
+    market.run(market.client.checkout_cart(buyer, &[item(&market, later)]));
+}
+
+#[test]
+fn carts_hold_one_to_max_items() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let buyer = market.user();
+    let items: Vec<CartItem> = (0..=MAX_CART_ITEMS)
+        .map(|_| {
+            let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+            item(&market, coupon)
+        })
+        .collect();
+
+    assert_error(market.send(market.client.checkout_cart(buyer, &[])), ErrorCode::InvalidCart);
+    assert_error(market.send(market.client.checkout_cart(buyer, &items)), ErrorCode::InvalidCart);
+    market.run(market.client.checkout_cart(buyer, &items[..MAX_CART_ITEMS]));
+}
+
+#[test]
+fn cart_accounts_must_match_the_listing() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let buyer = market.user();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let mut ix = market.client.checkout_cart(buyer, &[item(&market, coupon)]);
+    // The buyer's token account is the second to last of the item
+    let at = ix.accounts.len() - 2;
+    ix.accounts[at].pubkey = Pubkey::new_unique();
+
+    assert_error(market.send(ix), ErrorCode::InvalidCart);
+}
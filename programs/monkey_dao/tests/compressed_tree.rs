@@ -0,0 +1,340 @@
+//! Compressed coupons: the leaf and proof helpers against a small in-memory
+//! Merkle tree built the same way spl-account-compression does, and the
+//! instructions end to end against the bank's Bubblegum stand-in.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{keccak, program_error::ProgramError};
+use bank::bubblegum::{asset_proof, create_tree, leaf_hash, leaves};
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use bank::TransactionError;
+use monkey_dao::constants::{COMPRESSED_COUPON_SYMBOL, PAUSE_LISTING, PLATFORM_FEE_BPS, PURCHASE_REWARD_BPS};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CompressedCouponMinted, CompressedCouponPurchased};
+use monkey_dao::state::{
+    compute_merkle_root, verify_merkle_proof, CouponTree, LeafArgs, Merchant, RedemptionReceipt, UserStats,
+};
+use monkey_dao_client::pda;
+use mpl_bubblegum::types::{MetadataArgs, TokenProgramVersion, TokenStandard};
+use mpl_bubblegum::utils::get_asset_id;
+
+const DEPTH: usize = 3;
+
+struct LocalTree {
+    leaves: Vec<[u8; 32]>,
+}
+
+impl LocalTree {
+    fn new() -> Self {
+        Self { leaves: vec![[0u8; 32]; 1 << DEPTH] }
+    }
+
+    fn levels(&self) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![self.leaves.clone()];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| keccak::hashv(&[&pair[0], &pair[1]]).to_bytes())
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    fn root(&self) -> [u8; 32] {
+        self.levels().last().unwrap()[0]
+    }
+
+    fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        let levels = self.levels();
+        (0..DEPTH).map(|level| levels[level][(index >> level) ^ 1]).collect()
+    }
+}
+
+fn leaf_args(tree: &LocalTree, index: u32) -> LeafArgs {
+    LeafArgs {
+        root: tree.root(),
+        data_hash: [7u8; 32],
+        creator_hash: [9u8; 32],
+        nonce: index as u64,
+        index,
+    }
+}
+
+#[test]
+fn proof_verifies_every_minted_leaf() {
+    let merkle_tree = Pubkey::new_unique();
+    let owners: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let mut tree = LocalTree::new();
+
+    for (i, owner) in owners.iter().enumerate() {
+        let args = leaf_args(&tree, i as u32);
+        tree.leaves[i] = args.leaf_hash(get_asset_id(&merkle_tree, i as u64), *owner, *owner);
+    }
+
+    for (i, owner) in owners.iter().enumerate() {
+        let args = leaf_args(&tree, i as u32);
+        let leaf = args.leaf_hash(get_asset_id(&merkle_tree, args.nonce), *owner, *owner);
+        let proof = tree.proof(i);
+        assert_eq!(compute_merkle_root(leaf, args.index, &proof), tree.root());
+        assert!(verify_merkle_proof(args.root, leaf, args.index, &proof));
+    }
+}
+
+#[test]
+fn transferred_leaf_rejects_previous_owner() {
+    let merkle_tree = Pubkey::new_unique();
+    let asset_id = get_asset_id(&merkle_tree, 2);
+    let seller = Pubkey::new_unique();
+    let buyer = Pubkey::new_unique();
+    let mut tree = LocalTree::new();
+
+    let args = leaf_args(&tree, 2);
+    tree.leaves[2] = args.leaf_hash(asset_id, seller, seller);
+    let seller_leaf = tree.leaves[2];
+
+    // Transfer replaces the leaf with one owned by the buyer
+    tree.leaves[2] = args.leaf_hash(asset_id, buyer, buyer);
+    let root = tree.root();
+    let proof = tree.proof(2);
+
+    assert!(verify_merkle_proof(root, tree.leaves[2], 2, &proof));
+    assert!(!verify_merkle_proof(root, seller_leaf, 2, &proof));
+}
+
+#[test]
+fn proof_rejects_wrong_index() {
+    let merkle_tree = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut tree = LocalTree::new();
+
+    let args = leaf_args(&tree, 1);
+    tree.leaves[1] = args.leaf_hash(get_asset_id(&merkle_tree, 1), owner, owner);
+    let proof = tree.proof(1);
+
+    assert!(verify_merkle_proof(tree.root(), tree.leaves[1], 1, &proof));
+    assert!(!verify_merkle_proof(tree.root(), tree.leaves[1], 0, &proof));
+}
+
+#[test]
+fn asset_ids_are_unique_per_tree_and_nonce() {
+    let tree_a = Pubkey::new_unique();
+    let tree_b = Pubkey::new_unique();
+
+    assert_eq!(get_asset_id(&tree_a, 0), get_asset_id(&tree_a, 0));
+    assert_ne!(get_asset_id(&tree_a, 0), get_asset_id(&tree_a, 1));
+    assert_ne!(get_asset_id(&tree_a, 0), get_asset_id(&tree_b, 0));
+}
+
+// ==================== INSTRUCTIONS ====================
+
+const TREE_DEPTH: u32 = 3;
+const COUPON_NAME: &str = "Ramen Night";
+const COUPON_URI: &str = "https://banana-ramen.example/ramen-night.json";
+
+/// The metadata `mint_compressed_coupon` gives every coupon.
+fn coupon_metadata() -> MetadataArgs {
+    MetadataArgs {
+        name: COUPON_NAME.to_string(),
+        symbol: COMPRESSED_COUPON_SYMBOL.to_string(),
+        uri: COUPON_URI.to_string(),
+        seller_fee_basis_points: 0,
+        primary_sale_happened: false,
+        is_mutable: false,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        token_program_version: TokenProgramVersion::Original,
+        creators: vec![],
+    }
+}
+
+/// A verified merchant and the Merkle tree of its coupon tree.
+fn coupon_tree(market: &mut Marketplace) -> (Pubkey, Pubkey) {
+    let merchant = market.verified_merchant();
+    let merkle_tree = create_tree(&mut market.bank, TREE_DEPTH);
+    market.run(market.client.create_coupon_tree(
+        merchant,
+        merkle_tree,
+        TREE_DEPTH,
+        8,
+        SOL,
+        market.bank.now() + 30 * DAY,
+        &[market.verifier],
+    ));
+    (merchant, merkle_tree)
+}
+
+/// Mints a coupon to `recipient` and returns its nonce.
+fn mint(market: &mut Marketplace, merchant: Pubkey, merkle_tree: Pubkey, recipient: Pubkey) -> u64 {
+    let meta = market.run(market.client.mint_compressed_coupon(
+        merchant,
+        merkle_tree,
+        recipient,
+        COUPON_NAME.to_string(),
+        COUPON_URI.to_string(),
+        &[market.verifier],
+    ));
+    meta.event::<CompressedCouponMinted>().nonce
+}
+
+fn leaf_owned_by(market: &Marketplace, merkle_tree: &Pubkey, nonce: u64, owner: Pubkey) -> bool {
+    leaves(&market.bank, merkle_tree)[nonce as usize] == leaf_hash(merkle_tree, nonce, owner, &coupon_metadata())
+}
+
+#[test]
+fn compressed_coupon_is_minted_to_the_recipient() {
+    let mut market = Marketplace::new();
+    let (merchant, merkle_tree) = coupon_tree(&mut market);
+    let holder = market.user();
+
+    let meta = market.run(market.client.mint_compressed_coupon(
+        merchant,
+        merkle_tree,
+        holder,
+        COUPON_NAME.to_string(),
+        COUPON_URI.to_string(),
+        &[market.verifier],
+    ));
+
+    let minted = meta.event::<CompressedCouponMinted>();
+    assert_eq!(minted.nonce, 0);
+    assert_eq!(minted.asset_id, get_asset_id(&merkle_tree, 0));
+    assert_eq!(minted.recipient, holder);
+    assert!(leaf_owned_by(&market, &merkle_tree, 0, holder));
+    let tree = market.bank.get::<CouponTree>(&pda::coupon_tree(&merkle_tree).0);
+    assert_eq!(tree.num_minted, 1);
+    assert_eq!(market.bank.account(&pda::tree_config(&merkle_tree).0).unwrap().owner, mpl_bubblegum::ID);
+}
+
+#[test]
+fn minting_needs_current_attestations_and_open_listing() {
+    let mut market = Marketplace::new();
+    let (merchant, merkle_tree) = coupon_tree(&mut market);
+    let holder = market.user();
+    let mint_ix = |market: &Marketplace, verifiers: &[Pubkey]| {
+        market.client.mint_compressed_coupon(
+            merchant,
+            merkle_tree,
+            holder,
+            COUPON_NAME.to_string(),
+            COUPON_URI.to_string(),
+            verifiers,
+        )
+    };
+
+    assert_error(market.send(mint_ix(&market, &[])), ErrorCode::MerchantNotVerified);
+    market.run(market.client.set_paused(market.authority, PAUSE_LISTING));
+    assert_error(market.send(mint_ix(&market, &[market.verifier])), ErrorCode::Paused);
+}
+
+#[test]
+fn compressed_coupon_sells_through_its_listing() {
+    let mut market = Marketplace::new();
+    let (merchant, merkle_tree) = coupon_tree(&mut market);
+    let buyer = market.user();
+    let nonce = mint(&mut market, merchant, merkle_tree, merchant);
+    let listing = pda::compressed_listing(&merkle_tree, nonce).0;
+
+    let (leaf, proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+    market.run(market.client.list_compressed_coupon(merchant, merkle_tree, SOL, leaf, &proof));
+    assert!(leaf_owned_by(&market, &merkle_tree, nonce, listing));
+
+    let platform_before = market.bank.lamports(&market.platform_wallet());
+    let (leaf, proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+    let meta = market.run(market.client.buy_compressed_coupon(
+        buyer,
+        merchant,
+        merchant,
+        merkle_tree,
+        leaf,
+        &proof,
+        None,
+    ));
+
+    let purchase = meta.event::<CompressedCouponPurchased>();
+    assert_eq!(purchase.platform_fee, SOL * PLATFORM_FEE_BPS / 10_000);
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), platform_before + purchase.platform_fee);
+    assert!(leaf_owned_by(&market, &merkle_tree, nonce, buyer));
+    assert!(!market.bank.exists(&listing));
+    assert_eq!(market.bank.get::<Merchant>(&pda::merchant(&merchant).0).total_sales_volume, SOL);
+    // Compressed purchases earn MONK like any other
+    assert_eq!(market.monk_balance(&buyer), SOL * PURCHASE_REWARD_BPS / 10_000);
+    let stats = market.bank.get::<UserStats>(&pda::user_stats(&buyer).0);
+    assert_eq!(stats.total_purchases, 1);
+    assert_eq!(stats.total_monk_earned, SOL * PURCHASE_REWARD_BPS / 10_000);
+
+    // The merchant's old leaf is gone, so it cannot list the coupon again
+    let (leaf, proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+    let failure = market
+        .send(market.client.list_compressed_coupon(merchant, merkle_tree, SOL, leaf, &proof))
+        .unwrap_err();
+    assert_eq!(failure.error, TransactionError::Program(ProgramError::InvalidArgument));
+}
+
+#[test]
+fn delisted_compressed_coupon_returns_to_its_seller() {
+    let mut market = Marketplace::new();
+    let (merchant, merkle_tree) = coupon_tree(&mut market);
+    let holder = market.user();
+    let nonce = mint(&mut market, merchant, merkle_tree, holder);
+    let listing = pda::compressed_listing(&merkle_tree, nonce).0;
+
+    let (leaf, proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+    market.run(market.client.list_compressed_coupon(holder, merkle_tree, 2 * SOL, leaf, &proof));
+    let (leaf, proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+    market.run(market.client.delist_compressed_coupon(holder, merkle_tree, leaf, &proof));
+
+    assert!(leaf_owned_by(&market, &merkle_tree, nonce, holder));
+    assert!(!market.bank.exists(&listing));
+}
+
+#[test]
+fn redeemed_compressed_coupon_is_burned() {
+    let mut market = Marketplace::new();
+    let (merchant, merkle_tree) = coupon_tree(&mut market);
+    let holder = market.user();
+    let nonce = mint(&mut market, merchant, merkle_tree, holder);
+
+    let (leaf, proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+    market.run(market.client.redeem_compressed_coupon(
+        holder,
+        merchant,
+        merchant,
+        merkle_tree,
+        vec![1; 64],
+        leaf,
+        &proof,
+    ));
+
+    assert_eq!(leaves(&market.bank, &merkle_tree)[nonce as usize], [0; 32]);
+    let tree = market.bank.get::<CouponTree>(&pda::coupon_tree(&merkle_tree).0);
+    assert_eq!(tree.num_redeemed, 1);
+    let receipt = market.bank.get::<RedemptionReceipt>(&pda::receipt(&pda::coupon_tree(&merkle_tree).0, nonce).0);
+    assert_eq!(receipt.nft_mint, get_asset_id(&merkle_tree, nonce));
+    assert_eq!(receipt.redeemer, holder);
+    assert_eq!(receipt.value, SOL);
+}
+
+#[test]
+fn stale_proof_is_rejected() {
+    let mut market = Marketplace::new();
+    let (merchant, merkle_tree) = coupon_tree(&mut market);
+    let holder = market.user();
+    let nonce = mint(&mut market, merchant, merkle_tree, holder);
+    let (stale_leaf, stale_proof) = asset_proof(&market.bank, &merkle_tree, nonce, &coupon_metadata());
+
+    // Minting next to it changes the root and the leaf's sibling
+    mint(&mut market, merchant, merkle_tree, holder);
+
+    let failure = market
+        .send(market.client.list_compressed_coupon(holder, merkle_tree, SOL, stale_leaf, &stale_proof))
+        .unwrap_err();
+    assert_eq!(failure.error, TransactionError::Program(ProgramError::InvalidArgument));
+    assert!(leaf_owned_by(&market, &merkle_tree, nonce, holder));
+}
@@ -0,0 +1,173 @@
+//! Custody tracking: every instruction that moves a coupon records who holds
+//! it and where it sits, and actions are authorized on the token itself.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::CustodySynced;
+use monkey_dao::state::Custody;
+use monkey_dao_client::{pda, Coupon, ListNftArgs};
+
+fn custody(market: &Marketplace, coupon: &Coupon) -> (Pubkey, Custody) {
+    let listing = market.listing(coupon);
+    (listing.holder, listing.custody)
+}
+
+/// Moves `coupon` from `from` to `to` with a plain token transfer, outside
+/// the program.
+fn transfer(market: &mut Marketplace, coupon: Coupon, from: Pubkey, to: Pubkey) {
+    market.run(spl_associated_token_account::instruction::create_associated_token_account(
+        &to,
+        &to,
+        &coupon.mint,
+        &coupon.token_program,
+    ));
+    market.run(
+        spl_token_2022::instruction::transfer_checked(
+            &coupon.token_program,
+            &market.token_account(&from, &coupon),
+            &coupon.mint,
+            &market.token_account(&to, &coupon),
+            &from,
+            &[],
+            1,
+            0,
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn custody_follows_the_coupon_through_the_program() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let listing = pda::listing(&coupon.mint).0;
+    assert_eq!(custody(&market, &coupon), (merchant, Custody::ListingVault));
+
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap();
+    assert_eq!(custody(&market, &coupon), (buyer, Custody::Wallet));
+    // The merchant stays the seller of record
+    assert_eq!(market.listing(&coupon).seller, merchant);
+
+    market.run(market.client.stake_nft(buyer, coupon));
+    assert_eq!(custody(&market, &coupon), (buyer, Custody::StakeVault));
+    market.run(market.client.unstake_nft(buyer, coupon));
+    assert_eq!(custody(&market, &coupon), (buyer, Custody::Wallet));
+
+    let friend = market.user();
+    market.run(market.client.send_gift(buyer, coupon, Some(friend), None, ""));
+    assert_eq!(custody(&market, &coupon), (buyer, Custody::GiftEscrow));
+    market.run(market.client.claim_gift(friend, buyer, coupon, None));
+    assert_eq!(custody(&market, &coupon), (friend, Custody::Wallet));
+
+    market.run(market.client.relist_nft(friend, coupon, SOL / 2));
+    assert_eq!(custody(&market, &coupon), (friend, Custody::ListingVault));
+    assert_eq!(market.coupon_balance(&listing, &coupon), 1);
+    market.run(market.client.delist_nft(friend, coupon));
+    assert_eq!(custody(&market, &coupon), (friend, Custody::Wallet));
+
+    market.run(market.client.redeem_nft(friend, merchant, merchant, 0, coupon, vec![1; 64], 1));
+    assert_eq!(custody(&market, &coupon), (friend, Custody::Redeemed));
+}
+
+#[test]
+fn coupon_moved_outside_the_program_is_relisted_by_its_new_holder() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap();
+    let friend = market.user();
+    transfer(&mut market, coupon, buyer, friend);
+
+    // The stale holder no longer has the token to stake or relist
+    assert!(market.send(market.client.stake_nft(buyer, coupon)).is_err());
+    assert!(market.send(market.client.relist_nft(buyer, coupon, SOL)).is_err());
+
+    // Anyone can point the listing at the wallet now holding the coupon
+    let event = market
+        .run(market.client.sync_custody(coupon, Some(friend)))
+        .event::<CustodySynced>();
+    assert_eq!(event.holder, friend);
+    assert_eq!(custody(&market, &coupon), (friend, Custody::Wallet));
+    // Syncing again changes nothing
+    let meta = market.run(market.client.sync_custody(coupon, Some(friend)));
+    assert!(meta.events::<CustodySynced>().is_empty());
+    assert_eq!(custody(&market, &coupon), (friend, Custody::Wallet));
+    // The old holder's emptied token account proves nothing
+    assert_error(
+        market.send(market.client.sync_custody(coupon, Some(buyer))),
+        ErrorCode::InvalidCustody,
+    );
+
+    market.run(market.client.relist_nft(friend, coupon, SOL / 2));
+    let listing = market.listing(&coupon);
+    assert_eq!(listing.seller, friend);
+    assert_eq!(listing.holder, friend);
+    assert_eq!(listing.custody, Custody::ListingVault);
+    assert_error(
+        market.send(market.client.sync_custody(coupon, Some(friend))),
+        ErrorCode::InvalidCustody,
+    );
+}
+
+#[test]
+fn filled_pool_takes_the_coupon_off_the_market() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let args = ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        deal_price_4: None,
+        deal_price_6: None,
+        ..market.list_args(SOL)
+    };
+    let coupon = market.listed_coupon(merchant, args);
+    let initiator = market.user();
+    let friend = market.user();
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+    market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+
+    let pool = pda::pool(&pda::listing(&coupon.mint).0, &initiator).0;
+    assert_eq!(custody(&market, &coupon), (pool, Custody::Pool));
+    let buyer = market.user();
+    assert_error(market.buy(buyer, coupon), ErrorCode::InvalidCustody);
+    assert_error(market.send(market.client.delist_nft(merchant, coupon)), ErrorCode::Unauthorized);
+    assert_error(
+        market.send(market.client.create_pool(buyer, coupon.mint, 2)),
+        ErrorCode::InvalidCustody,
+    );
+}
+
+#[test]
+fn coupon_burned_outside_the_program_is_synced_as_redeemed() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap();
+
+    // Not burned while it is still in the buyer's wallet
+    assert_error(market.send(market.client.sync_custody(coupon, None)), ErrorCode::InvalidCustody);
+    market.run(
+        spl_token_2022::instruction::burn(
+            &coupon.token_program,
+            &market.token_account(&buyer, &coupon),
+            &coupon.mint,
+            &buyer,
+            &[],
+            1,
+        )
+        .unwrap(),
+    );
+
+    let event = market.run(market.client.sync_custody(coupon, None)).event::<CustodySynced>();
+    assert_eq!(event.custody, Custody::Redeemed);
+    assert_eq!(custody(&market, &coupon), (buyer, Custody::Redeemed));
+}
@@ -0,0 +1,795 @@
+//! Every `ErrorCode` the program returns, each triggered through an
+//! instruction that guards it. `every_error_code_is_covered` keeps this file
+//! in step with `src/error.rs`.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, KYC_HASH, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::state::{CouponTree, FeeTier, LeafArgs, LoyaltyTier, StakeAccount, UseMethod};
+use monkey_dao::{MAX_FEE_BPS, MAX_REFERRAL_MONK_BPS, MAX_VERIFIERS, PAUSE_TRADING};
+use monkey_dao_client::{pda, Coupon, GatePass, ListNftArgs};
+
+/// Codes no instruction can return in the current program, and why.
+const UNREACHABLE: &[(&str, &str)] = &[
+    ("NotPoolInitiator", "pool seeds include the initiator, so another signer fails the seeds check first"),
+    ("InsufficientAttestations", "a missed threshold is reported as MerchantNotVerified"),
+];
+
+fn group_deal(market: &Marketplace) -> ListNftArgs {
+    ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        deal_price_4: None,
+        deal_price_6: None,
+        ..market.list_args(SOL)
+    }
+}
+
+/// A coupon listed by a fresh merchant and bought by a fresh user.
+fn held_coupon(market: &mut Marketplace, args: ListNftArgs) -> (Pubkey, Pubkey, Coupon) {
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, args);
+    let holder = market.user();
+    market.buy(holder, coupon).unwrap();
+    (merchant, holder, coupon)
+}
+
+/// A single-use coupon that `holder` has already redeemed in full.
+fn redeemed_coupon(market: &mut Marketplace) -> (Pubkey, Pubkey, Coupon) {
+    let args = market.list_args(SOL);
+    let (merchant, holder, coupon) = held_coupon(market, args);
+    market.run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 1));
+    (merchant, holder, coupon)
+}
+
+// ==================== LISTING ERRORS ====================
+
+#[test]
+fn listing_rejects_invalid_terms() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.coupon(merchant, spl_token::ID);
+
+    assert_error(market.list(merchant, coupon, market.list_args(0)), ErrorCode::InvalidPrice);
+
+    let args = ListNftArgs { total_uses: 0, ..market.list_args(SOL) };
+    assert_error(market.list(merchant, coupon, args), ErrorCode::InvalidUses);
+
+    let args = ListNftArgs { expiry_date: market.bank.now(), ..market.list_args(SOL) };
+    assert_error(market.list(merchant, coupon, args), ErrorCode::CouponExpired);
+}
+
+#[test]
+fn unverified_merchant_cannot_list() {
+    let mut market = Marketplace::new();
+    let merchant = market.merchant();
+    let coupon = market.coupon(merchant, spl_token::ID);
+
+    assert_error(
+        market.send(market.client.list_nft(merchant, coupon, market.list_args(SOL), &[])),
+        ErrorCode::MerchantNotVerified,
+    );
+}
+
+#[test]
+fn coupon_tree_needs_current_attestations() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let verifier = market.verifier;
+    // The attestation lapses, though the merchant's stored flag still says verified
+    market.bank.warp_forward(366 * DAY);
+
+    assert_error(
+        market.send(market.client.create_coupon_tree(
+            merchant,
+            Pubkey::new_unique(),
+            14,
+            64,
+            SOL,
+            market.bank.now() + 30 * DAY,
+            &[verifier],
+        )),
+        ErrorCode::MerchantNotVerified,
+    );
+}
+
+#[test]
+fn listed_coupon_cannot_be_listed_again() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    market.mint_to(coupon, merchant, merchant);
+
+    assert_error(
+        market.list(merchant, coupon, market.list_args(SOL)),
+        ErrorCode::ListingStillActive,
+    );
+}
+
+#[test]
+fn used_coupon_cannot_be_listed_again() {
+    let mut market = Marketplace::new();
+    let (merchant, _, coupon) = redeemed_coupon(&mut market);
+    market.mint_to(coupon, merchant, merchant);
+
+    assert_error(
+        market.list(merchant, coupon, market.list_args(SOL)),
+        ErrorCode::CouponAlreadyUsed,
+    );
+}
+
+#[test]
+fn relisted_coupon_keeps_its_uses() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    market.run(market.client.delist_nft(merchant, coupon));
+
+    let args = ListNftArgs { use_method: UseMethod::Multiple, total_uses: 3, ..market.list_args(SOL) };
+    assert_error(market.list(merchant, coupon, args), ErrorCode::InvalidUses);
+    market.list(merchant, coupon, market.list_args(2 * SOL)).unwrap();
+    assert_eq!(market.listing(&coupon).uses.total, 1);
+}
+
+#[test]
+fn resale_must_undercut_the_original_price() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (_, holder, coupon) = held_coupon(&mut market, args);
+
+    assert_error(
+        market.send(market.client.relist_nft(holder, coupon, SOL)),
+        ErrorCode::PriceTooHigh,
+    );
+}
+
+#[test]
+fn unused_listing_cannot_be_closed() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    assert_error(
+        market.send(market.client.close_listing(merchant, coupon.mint)),
+        ErrorCode::CouponNotUsed,
+    );
+}
+
+// ==================== TRADING ERRORS ====================
+
+#[test]
+fn purchase_must_name_the_listing_seller() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    let impostor = market.user();
+
+    assert_error(
+        market.send(market.client.buy_nft(buyer, impostor, merchant, coupon, None)),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn expired_coupon_cannot_be_bought() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let args = ListNftArgs { expiry_date: market.bank.now() + DAY, ..market.list_args(SOL) };
+    let coupon = market.listed_coupon(merchant, args);
+    let buyer = market.user();
+    market.bank.warp_forward(DAY);
+
+    assert_error(market.buy(buyer, coupon), ErrorCode::CouponExpired);
+}
+
+// ==================== POOL ERRORS ====================
+
+#[test]
+fn pools_need_an_active_group_deal() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let initiator = market.user();
+
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    assert_error(
+        market.send(market.client.create_pool(initiator, coupon.mint, 2)),
+        ErrorCode::NotGroupDeal,
+    );
+
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    assert_error(
+        market.send(market.client.create_pool(initiator, coupon.mint, 3)),
+        ErrorCode::InvalidPoolSize,
+    );
+    assert_error(
+        market.send(market.client.create_pool(initiator, coupon.mint, 6)),
+        ErrorCode::DealNotAvailable,
+    );
+
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap();
+    assert_error(
+        market.send(market.client.create_pool(initiator, coupon.mint, 2)),
+        ErrorCode::ListingNotActive,
+    );
+}
+
+#[test]
+fn pool_joins_stop_when_full_or_cancelled() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let late = market.user();
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+
+    // An active pool has to be cancelled before it can be closed
+    assert_error(
+        market.send(market.client.close_pool(initiator, coupon)),
+        ErrorCode::PoolStillActive,
+    );
+    // Nor settled before it fills
+    assert_error(
+        market.send(market.client.complete_pool(initiator, merchant, merchant, coupon)),
+        ErrorCode::PoolNotComplete,
+    );
+
+    let other = market.user();
+    market.run(market.client.create_pool(other, coupon.mint, 2));
+    market.run(market.client.cancel_pool(other, coupon.mint));
+    assert_error(
+        market.send(market.client.join_pool(late, coupon.mint, other, None)),
+        ErrorCode::PoolNotActive,
+    );
+
+    market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    let participant = market.user();
+    market.run(market.client.join_pool(participant, coupon.mint, initiator, None));
+    assert_error(
+        market.send(market.client.join_pool(late, coupon.mint, initiator, None)),
+        ErrorCode::PoolFull,
+    );
+
+    // A filled pool settles instead of refunding
+    assert_error(
+        market.send(market.client.refund_pool_deposit(initiator, coupon.mint, initiator)),
+        ErrorCode::PoolStillOpen,
+    );
+    // A settled pool closes only once its coupon has been claimed
+    market.run(market.client.complete_pool(initiator, merchant, merchant, coupon));
+    assert_error(
+        market.send(market.client.close_pool(initiator, coupon)),
+        ErrorCode::PoolHoldsCoupon,
+    );
+}
+
+// ==================== REVIEW ERRORS ====================
+
+#[test]
+fn rating_must_be_one_to_five_stars() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let reviewer = market.user();
+
+    assert_error(
+        market.send(market.client.add_review(reviewer, coupon.mint, 0, String::new())),
+        ErrorCode::InvalidRating,
+    );
+}
+
+// ==================== STAKING ERRORS ====================
+
+#[test]
+fn rewards_need_a_full_day_and_an_active_stake() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (_, owner, coupon) = held_coupon(&mut market, args);
+    market.run(market.client.stake_nft(owner, coupon));
+
+    assert_error(
+        market.send(market.client.claim_staking_rewards(owner, coupon.mint)),
+        ErrorCode::InsufficientTimeElapsed,
+    );
+    assert_error(
+        market.send(market.client.close_stake_account(owner, coupon.mint)),
+        ErrorCode::AlreadyStaked,
+    );
+
+    // A leftover inactive stake account from an earlier program version
+    let stake = pda::stake(&coupon.mint);
+    let mut state: StakeAccount = market.bank.get(&stake.0);
+    state.is_active = false;
+    market.bank.set_program_account(stake.0, &state);
+    assert_error(
+        market.send(market.client.claim_staking_rewards(owner, coupon.mint)),
+        ErrorCode::NotStaked,
+    );
+}
+
+#[test]
+fn used_coupon_cannot_be_staked() {
+    let mut market = Marketplace::new();
+    let (merchant, holder, coupon) = redeemed_coupon(&mut market);
+    market.mint_to(coupon, merchant, holder);
+
+    assert_error(
+        market.send(market.client.stake_nft(holder, coupon)),
+        ErrorCode::CannotStakeUsedCoupon,
+    );
+}
+
+// ==================== REDEMPTION ERRORS ====================
+
+#[test]
+fn redemption_checks_signature_and_uses() {
+    let mut market = Marketplace::new();
+    let args = ListNftArgs {
+        use_method: UseMethod::Multiple,
+        total_uses: 2,
+        ..market.list_args(SOL)
+    };
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+
+    assert_error(
+        market.send(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 32], 1)),
+        ErrorCode::InvalidSignature,
+    );
+    assert_error(
+        market.send(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 3)),
+        ErrorCode::InsufficientUses,
+    );
+}
+
+#[test]
+fn delegates_need_a_future_expiry_and_expire() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+    let cashier = market.user();
+
+    assert_error(
+        market.send(market.client.add_delegate(merchant, cashier, market.bank.now())),
+        ErrorCode::InvalidExpiry,
+    );
+
+    market.run(market.client.add_delegate(merchant, cashier, market.bank.now() + DAY));
+    market.bank.warp_forward(2 * DAY);
+    assert_error(
+        market.send(market.client.redeem_nft(holder, merchant, cashier, 0, coupon, vec![1; 64], 1)),
+        ErrorCode::DelegateNotAuthorized,
+    );
+}
+
+#[test]
+fn honored_value_cannot_overflow() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+
+    // The merchant redeems two of its own coupons priced at u64::MAX
+    for index in 0..2 {
+        let coupon = market.listed_coupon(merchant, market.list_args(u64::MAX));
+        market.run(market.client.delist_nft(merchant, coupon));
+        let result = market.send(market.client.redeem_nft(merchant, merchant, merchant, 0, coupon, vec![1; 64], 1));
+        if index == 0 {
+            result.unwrap();
+        } else {
+            assert_error(result, ErrorCode::ArithmeticOverflow);
+        }
+    }
+}
+
+#[test]
+fn compressed_redemption_checks_the_merkle_proof() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let redeemer = market.user();
+    let merkle_tree = Pubkey::new_unique();
+    let (coupon_tree, bump) = pda::coupon_tree(&merkle_tree);
+    // The proof is rejected before any CPI, so the tree record is written directly
+    let state = CouponTree {
+        merchant: pda::merchant(&merchant).0,
+        merkle_tree,
+        original_price: SOL,
+        expiry_date: market.bank.now() + 30 * DAY,
+        num_minted: 1,
+        num_redeemed: 0,
+        created_at: market.bank.now(),
+        bump,
+    };
+    market.bank.set_program_account(coupon_tree, &state);
+    let leaf = LeafArgs {
+        root: [1; 32],
+        data_hash: [2; 32],
+        creator_hash: [3; 32],
+        nonce: 0,
+        index: 0,
+    };
+
+    assert_error(
+        market.send(market.client.redeem_compressed_coupon(
+            redeemer,
+            merchant,
+            merchant,
+            merkle_tree,
+            vec![1; 64],
+            leaf,
+            &[Pubkey::new_unique()],
+        )),
+        ErrorCode::InvalidMerkleProof,
+    );
+}
+
+// ==================== EXPIRY ERRORS ====================
+
+#[test]
+fn unexpired_listing_cannot_be_reclaimed() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let crank = market.user();
+
+    assert_error(
+        market.send(market.client.reclaim_expired_listing(crank, merchant, coupon)),
+        ErrorCode::CouponNotExpired,
+    );
+}
+
+#[test]
+fn refunds_follow_the_policy_and_escrow() {
+    let mut market = Marketplace::new();
+    let args = ListNftArgs { expiry_date: market.bank.now() + DAY, ..market.list_args(SOL) };
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+
+    assert_error(
+        market.send(market.client.set_refund_policy(merchant, 10_001, DAY)),
+        ErrorCode::InvalidRefundPolicy,
+    );
+    market.run(market.client.set_refund_policy(merchant, 5_000, DAY));
+    market.run(market.client.set_refund_policy(merchant, 5_000, 2 * DAY));
+
+    // Coupons sold under the policy freeze its terms
+    let covered = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    market.buy(buyer, covered).unwrap();
+    assert_error(
+        market.send(market.client.set_refund_policy(merchant, 0, DAY)),
+        ErrorCode::RefundPolicyFrozen,
+    );
+
+    // Half a SOL is owed, but the escrow's rent reserve is not refundable
+    market.bank.warp_forward(DAY);
+    market.run(market.client.fund_refund_escrow(merchant, SOL / 2));
+    assert_error(
+        market.send(market.client.claim_expired_refund(holder, merchant, coupon)),
+        ErrorCode::InsufficientRefundEscrow,
+    );
+    // Nor can the merchant take it back while coupons may still claim
+    assert_error(
+        market.send(market.client.withdraw_refund_escrow(merchant, SOL / 2)),
+        ErrorCode::RefundEscrowLocked,
+    );
+
+    market.run(market.client.fund_refund_escrow(merchant, SOL));
+    market.bank.warp_forward(3 * DAY);
+    assert_error(
+        market.send(market.client.claim_expired_refund(holder, merchant, coupon)),
+        ErrorCode::RefundWindowClosed,
+    );
+}
+
+// ==================== MERCHANT AND VERIFIER ERRORS ====================
+
+#[test]
+fn current_merchant_cannot_be_migrated() {
+    let mut market = Marketplace::new();
+    let merchant = market.merchant();
+
+    assert_error(
+        market.send(market.client.migrate_merchant(merchant, KYC_HASH, String::new())),
+        ErrorCode::AccountAlreadyMigrated,
+    );
+}
+
+#[test]
+fn attestations_come_from_accredited_verifiers_for_the_current_kyc() {
+    let mut market = Marketplace::new();
+    let merchant = market.merchant();
+    let expires_at = market.bank.now() + 90 * DAY;
+    let stranger = market.user();
+
+    assert_error(
+        market.send(market.client.issue_attestation(stranger, merchant, KYC_HASH, expires_at)),
+        ErrorCode::NotAccreditedVerifier,
+    );
+    assert_error(
+        market.send(market.client.issue_attestation(market.verifier, merchant, [0; 32], expires_at)),
+        ErrorCode::KycHashMismatch,
+    );
+}
+
+#[test]
+fn registry_is_managed_by_the_platform_authority() {
+    let mut market = Marketplace::new();
+    let stranger = market.user();
+
+    assert_error(
+        market.send(market.client.add_verifier(stranger, stranger)),
+        ErrorCode::NotPlatformAuthority,
+    );
+    assert_error(
+        market.send(market.client.add_verifier(market.authority, market.verifier)),
+        ErrorCode::VerifierAlreadyRegistered,
+    );
+    assert_error(
+        market.send(market.client.set_attestation_threshold(market.authority, 2)),
+        ErrorCode::InvalidThreshold,
+    );
+
+    for _ in 1..MAX_VERIFIERS {
+        market.run(market.client.add_verifier(market.authority, Pubkey::new_unique()));
+    }
+    assert_error(
+        market.send(market.client.add_verifier(market.authority, stranger)),
+        ErrorCode::VerifierRegistryFull,
+    );
+}
+
+// ==================== PAUSE ERRORS ====================
+
+#[test]
+fn paused_subsystems_reject_new_activity() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+
+    assert_error(
+        market.send(market.client.set_paused(market.authority, 1 << 7)),
+        ErrorCode::InvalidPauseFlags,
+    );
+    market.run(market.client.set_paused(market.authority, PAUSE_TRADING));
+    assert_error(market.buy(buyer, coupon), ErrorCode::Paused);
+}
+
+// ==================== FEE ERRORS ====================
+
+#[test]
+fn fee_schedule_is_validated() {
+    let mut market = Marketplace::new();
+    let tier = |min_volume, fee_bps| FeeTier { min_volume, fee_bps };
+    let invalid = [
+        (vec![], 0, 0),
+        (vec![tier(SOL, 100)], 0, 0),
+        (vec![tier(0, 300), tier(SOL, 200), tier(SOL, 100)], 0, 0),
+        (vec![tier(0, MAX_FEE_BPS + 1)], 0, 0),
+        (vec![tier(0, 100)], MAX_FEE_BPS + 1, 0),
+        (vec![tier(0, 100)], 0, 10_001),
+    ];
+    for (tiers, secondary_fee_bps, staker_discount_bps) in invalid {
+        assert_error(
+            market.send(market.client.set_fee_schedule(
+                market.authority,
+                tiers,
+                secondary_fee_bps,
+                staker_discount_bps,
+                false,
+            )),
+            ErrorCode::InvalidFeeSchedule,
+        );
+    }
+
+    let stranger = market.user();
+    assert_error(
+        market.send(market.client.set_fee_schedule(stranger, vec![tier(0, 0)], 0, 0, false)),
+        ErrorCode::NotPlatformAuthority,
+    );
+}
+
+// ==================== REFERRAL ERRORS ====================
+
+#[test]
+fn referrals_are_checked() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    market.run(market.client.register_referrer(buyer));
+
+    assert_error(
+        market.send(market.client.buy_nft(buyer, merchant, merchant, coupon, Some(buyer))),
+        ErrorCode::SelfReferral,
+    );
+    assert_error(
+        market.send(market.client.claim_referral_rewards(buyer)),
+        ErrorCode::NoReferralRewards,
+    );
+    for (fee_share_bps, monk_bps) in [(10_001, 0), (0, MAX_REFERRAL_MONK_BPS + 1)] {
+        assert_error(
+            market.send(market.client.set_referral_rewards(market.authority, fee_share_bps, monk_bps)),
+            ErrorCode::InvalidReferralRewards,
+        );
+    }
+
+    // A pool joined with a referrer settles only with its referral records
+    let group_coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    market.run(market.client.create_pool(initiator, group_coupon.mint, 2));
+    market.run(market.client.join_pool(initiator, group_coupon.mint, initiator, Some(buyer)));
+    let participant = market.user();
+    market.run(market.client.join_pool(participant, group_coupon.mint, initiator, None));
+    assert_error(
+        market.send(market.client.complete_pool(initiator, merchant, merchant, group_coupon)),
+        ErrorCode::PoolReferralsMissing,
+    );
+    market.run(market.client.complete_referred_pool(
+        initiator,
+        merchant,
+        merchant,
+        group_coupon,
+        &[(initiator, buyer)],
+    ));
+}
+
+// ==================== LOYALTY ERRORS ====================
+
+#[test]
+fn tier_gates_turn_away_lower_tiers() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let gated = market.listed_coupon(merchant, market.list_args(SOL));
+    let early = market.listed_coupon(merchant, market.list_args(SOL));
+    let opens = market.bank.now() + DAY;
+    market.run(market.client.set_listing_access(merchant, gated.mint, LoyaltyTier::Gold, 0));
+    market.run(market.client.set_listing_access(merchant, early.mint, LoyaltyTier::Bronze, opens));
+    let buyer = market.user();
+
+    assert_error(market.buy(buyer, gated), ErrorCode::TierTooLow);
+    assert_error(market.buy(buyer, early), ErrorCode::EarlyAccessOnly);
+}
+
+// ==================== GATE ERRORS ====================
+
+#[test]
+fn gates_turn_away_wallets_that_do_not_pass() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let merchant_pda = pda::merchant(&merchant).0;
+    let buyer = market.user();
+    let gated = |market: &mut Marketplace, gate_id: u64, collection, allowlist_root, max_per_wallet| {
+        let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+        let gate = pda::listing_gate(&merchant_pda, gate_id).0;
+        market.run(market.client.set_gate(merchant, gate_id, collection, allowlist_root, max_per_wallet));
+        market.run(market.client.set_listing_gate(merchant, coupon.mint, Some(gate)));
+        (coupon, GatePass::new(gate))
+    };
+    let (allowlisted, allowlist_pass) = gated(&mut market, 0, None, Some([1; 32]), 0);
+    let (collection, collection_pass) = gated(&mut market, 1, Some(Pubkey::new_unique()), None, 0);
+    let (limited, limit_pass) = gated(&mut market, 2, None, None, 1);
+    let buy = |market: &mut Marketplace, coupon: Coupon, pass: &GatePass| {
+        market.send(market.client.buy_gated_nft(buyer, merchant, merchant, coupon, None, Some(pass)))
+    };
+
+    assert_error(market.buy(buyer, allowlisted), ErrorCode::GateRequired);
+    assert_error(buy(&mut market, allowlisted, &allowlist_pass), ErrorCode::NotOnAllowlist);
+    assert_error(buy(&mut market, collection, &collection_pass), ErrorCode::CollectionNotHeld);
+    buy(&mut market, limited, &limit_pass).unwrap();
+    market.run(market.client.set_listing_gate(merchant, allowlisted.mint, Some(limit_pass.gate)));
+    assert_error(buy(&mut market, allowlisted, &limit_pass), ErrorCode::WalletLimitReached);
+}
+
+// ==================== SCHEDULE ERRORS ====================
+
+#[test]
+fn schedules_hold_back_and_reject() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let expiry = market.listing(&coupon).expiry_date;
+    let buyer = market.user();
+
+    assert_error(
+        market.send(market.client.set_listing_schedule(merchant, coupon.mint, expiry, vec![])),
+        ErrorCode::InvalidSchedule,
+    );
+    market.run(market.client.set_listing_schedule(merchant, coupon.mint, market.bank.now() + DAY, vec![]));
+    assert_error(market.buy(buyer, coupon), ErrorCode::ListingNotStarted);
+}
+
+// ==================== CART ERRORS ====================
+
+#[test]
+fn empty_cart_is_rejected() {
+    let mut market = Marketplace::new();
+    let buyer = market.user();
+
+    assert_error(market.send(market.client.checkout_cart(buyer, &[])), ErrorCode::InvalidCart);
+}
+
+// ==================== GIFT ERRORS ====================
+
+#[test]
+fn gifts_are_claimed_in_time_by_their_recipient() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let sender = market.user();
+    market.buy(sender, coupon).unwrap();
+    let friend = market.user();
+    let stranger = market.user();
+
+    assert_error(market.send(market.client.send_gift(sender, coupon, None, None, "")), ErrorCode::InvalidGift);
+    market.run(market.client.send_gift(sender, coupon, Some(friend), None, ""));
+    assert_error(
+        market.send(market.client.claim_gift(stranger, sender, coupon, None)),
+        ErrorCode::NotGiftRecipient,
+    );
+    assert_error(market.send(market.client.return_gift(sender, sender, coupon)), ErrorCode::GiftNotExpired);
+    market.bank.warp_forward(monkey_dao::GIFT_CLAIM_PERIOD);
+    assert_error(market.send(market.client.claim_gift(friend, sender, coupon, None)), ErrorCode::GiftExpired);
+}
+
+// ==================== CUSTODY ERRORS ====================
+
+#[test]
+fn staked_coupon_cannot_be_moved() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let holder = market.user();
+    market.buy(holder, coupon).unwrap();
+    market.run(market.client.stake_nft(holder, coupon));
+    let friend = market.user();
+
+    assert_error(market.send(market.client.gift_coupon(holder, coupon, friend, "")), ErrorCode::InvalidCustody);
+    assert_error(market.send(market.client.stake_nft(holder, coupon)), ErrorCode::InvalidCustody);
+}
+
+// ==================== TRANSFER HOOK ERRORS ====================
+
+#[test]
+fn hooked_coupon_needs_its_hook_accounts() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = Coupon::token_2022(bank::hook::create_mint(&mut market.bank, merchant));
+    market.mint_to(coupon, merchant, merchant);
+    let args = market.list_args(SOL);
+
+    assert_error(market.list(merchant, coupon, args), ErrorCode::TransferHookAccountsMissing);
+}
+
+// ==================== COVERAGE ====================
+
+#[test]
+fn every_error_code_is_covered() {
+    let variants: Vec<&str> = include_str!("../src/error.rs")
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_suffix(','))
+        .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()))
+        .collect();
+    assert!(!variants.is_empty(), "no variants parsed from error.rs");
+
+    let tests = include_str!("errors.rs");
+    let is_tested = |name: &str| {
+        let path = format!("ErrorCode::{name}");
+        tests.match_indices(&path).any(|(at, _)| {
+            !tests[at + path.len()..].starts_with(|c: char| c.is_ascii_alphanumeric())
+        })
+    };
+
+    for name in &variants {
+        let unreachable = UNREACHABLE.iter().any(|(code, _)| code == name);
+        assert!(
+            is_tested(name) != unreachable,
+            "ErrorCode::{name} must be triggered here or listed in UNREACHABLE, not both"
+        );
+    }
+    for (code, _) in UNREACHABLE {
+        assert!(variants.contains(code), "UNREACHABLE lists unknown ErrorCode::{code}");
+    }
+}
@@ -0,0 +1,142 @@
+//! The fee schedule: primary tiers by merchant volume, the secondary-sale
+//! fee, the staker discount and buyer-paid fees.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::marketplace::{Marketplace, SOL};
+use monkey_dao::events::{CouponPurchased, FeeScheduleUpdated};
+use monkey_dao::state::{FeeSchedule, FeeTier, Merchant};
+use monkey_dao::PLATFORM_FEE_BPS;
+use monkey_dao_client::{pda, Coupon};
+
+/// 3% until the merchant has sold 2 SOL, 1% after; 5% on resales.
+fn tiered(market: &mut Marketplace, staker_discount_bps: u16, buyer_pays_fee: bool) {
+    let tiers = vec![
+        FeeTier { min_volume: 0, fee_bps: 300 },
+        FeeTier { min_volume: 2 * SOL, fee_bps: 100 },
+    ];
+    market.run(market.client.set_fee_schedule(market.authority, tiers, 500, staker_discount_bps, buyer_pays_fee));
+}
+
+/// Buys `coupon` for a fresh user, checking where the money went.
+fn buy(market: &mut Marketplace, coupon: Coupon) -> (Pubkey, CouponPurchased) {
+    let buyer = market.user();
+    let listing = market.listing(&coupon);
+    let vault_rent = market.bank.lamports(&market.token_account(&pda::listing(&coupon.mint).0, &coupon));
+    let seller_before = market.bank.lamports(&listing.seller);
+    let wallet_before = market.bank.lamports(&market.platform_wallet());
+
+    let event = market.buy(buyer, coupon).unwrap().event::<CouponPurchased>();
+
+    assert_eq!(market.bank.lamports(&listing.seller), seller_before + event.seller_amount + vault_rent);
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), wallet_before + event.platform_fee);
+    (buyer, event)
+}
+
+fn volume(market: &Marketplace, merchant: &Pubkey) -> u64 {
+    market.bank.get::<Merchant>(&pda::merchant(merchant).0).total_sales_volume
+}
+
+#[test]
+fn sales_pay_the_flat_platform_fee_until_a_schedule_is_set() {
+    let mut market = Marketplace::new();
+    assert!(!market.bank.exists(&pda::fee_schedule().0));
+    let merchant = market.verified_merchant();
+
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let (_, event) = buy(&mut market, coupon);
+    assert_eq!(event.platform_fee, SOL * PLATFORM_FEE_BPS / 10_000);
+    assert_eq!(event.seller_amount, SOL - event.platform_fee);
+
+    tiered(&mut market, 0, false);
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let (_, event) = buy(&mut market, coupon);
+    assert_eq!(event.platform_fee, 3 * SOL / 100);
+}
+
+#[test]
+fn schedule_is_set_by_the_platform_authority() {
+    let mut market = Marketplace::new();
+    let tiers = vec![FeeTier { min_volume: 0, fee_bps: 300 }];
+
+    let event = market
+        .run(market.client.set_fee_schedule(market.authority, tiers.clone(), 500, 2_000, true))
+        .event::<FeeScheduleUpdated>();
+
+    assert_eq!(event.primary_tiers, tiers);
+    let schedule: FeeSchedule = market.bank.get(&pda::fee_schedule().0);
+    assert_eq!(schedule.primary_tiers, tiers);
+    assert_eq!(schedule.secondary_fee_bps, 500);
+    assert_eq!(schedule.staker_discount_bps, 2_000);
+    assert!(schedule.buyer_pays_fee);
+    assert_eq!(schedule.version, FeeSchedule::VERSION);
+}
+
+#[test]
+fn primary_fee_drops_once_the_merchant_reaches_the_next_tier() {
+    let mut market = Marketplace::new();
+    tiered(&mut market, 0, false);
+    let merchant = market.verified_merchant();
+
+    let mut fees = Vec::new();
+    for _ in 0..3 {
+        let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+        let (_, event) = buy(&mut market, coupon);
+        assert_eq!(event.seller_amount, SOL - event.platform_fee);
+        fees.push(event.platform_fee);
+    }
+
+    // The tier is picked from the volume before each sale
+    assert_eq!(fees, vec![3 * SOL / 100, 3 * SOL / 100, SOL / 100]);
+    assert_eq!(volume(&market, &merchant), 3 * SOL);
+}
+
+#[test]
+fn resales_pay_the_secondary_fee_and_do_not_count_as_volume() {
+    let mut market = Marketplace::new();
+    tiered(&mut market, 0, false);
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(2 * SOL));
+    let (holder, _) = buy(&mut market, coupon);
+    market.run(market.client.relist_nft(holder, coupon, SOL));
+
+    let (_, event) = buy(&mut market, coupon);
+
+    assert_eq!(event.seller, holder);
+    assert_eq!(event.platform_fee, SOL * 5 / 100);
+    assert_eq!(event.seller_amount, SOL - SOL * 5 / 100);
+    assert_eq!(volume(&market, &merchant), 2 * SOL);
+}
+
+#[test]
+fn stakers_get_the_staker_discount() {
+    let mut market = Marketplace::new();
+    tiered(&mut market, 5_000, false);
+    let merchant = market.verified_merchant();
+    let staked = market.listed_coupon(merchant, market.list_args(SOL));
+    let (staker, _) = buy(&mut market, staked);
+    market.run(market.client.stake_nft(staker, staked));
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let listing = market.listing(&coupon);
+    let before = market.bank.lamports(&market.platform_wallet());
+
+    market.run(market.client.buy_nft(staker, listing.seller, merchant, coupon, None));
+
+    // Half of the 3% primary fee
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), before + 15 * SOL / 1_000);
+}
+
+#[test]
+fn buyer_paid_fee_comes_on_top_of_the_price() {
+    let mut market = Marketplace::new();
+    tiered(&mut market, 0, true);
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    let (_, event) = buy(&mut market, coupon);
+
+    assert_eq!(event.price, SOL);
+    assert_eq!(event.platform_fee, 3 * SOL / 100);
+    assert_eq!(event.seller_amount, SOL);
+}
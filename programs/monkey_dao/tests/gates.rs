@@ -0,0 +1,251 @@
+//! Listing gates: allowlist proofs, collection holdings read from remaining
+//! accounts and the per-wallet purchase limit, on purchases and pool joins.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::AnchorSerialize;
+use anchor_spl::metadata::mpl_token_metadata::{
+    accounts::Metadata,
+    types::{Collection, Key},
+};
+use bank::marketplace::{Marketplace, SOL};
+use bank::{assert_error, Account};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{GateUpdated, ListingGateUpdated};
+use monkey_dao::state::{allowlist_leaf, GatePurchases};
+use monkey_dao_client::{pda, AllowlistProof, Coupon, GatePass, ListNftArgs};
+
+/// Root of an allowlist tree of four `wallets`, with each wallet's proof.
+fn allowlist(wallets: [Pubkey; 4]) -> ([u8; 32], Vec<AllowlistProof>) {
+    let leaves = wallets.map(|wallet| allowlist_leaf(&wallet));
+    let nodes = [
+        keccak::hashv(&[&leaves[0], &leaves[1]]).to_bytes(),
+        keccak::hashv(&[&leaves[2], &leaves[3]]).to_bytes(),
+    ];
+    let root = keccak::hashv(&[&nodes[0], &nodes[1]]).to_bytes();
+    let proofs = (0..4)
+        .map(|index| AllowlistProof {
+            index: index as u32,
+            path: vec![leaves[index ^ 1], nodes[(index >> 1) ^ 1]],
+        })
+        .collect();
+    (root, proofs)
+}
+
+/// Creates gate 0 of `merchant` and puts `coupon` behind it.
+fn gate(
+    market: &mut Marketplace,
+    merchant: Pubkey,
+    coupon: Coupon,
+    collection: Option<Pubkey>,
+    allowlist_root: Option<[u8; 32]>,
+    max_per_wallet: u16,
+) -> Pubkey {
+    let gate = pda::listing_gate(&pda::merchant(&merchant).0, 0).0;
+    let event = market
+        .run(market.client.set_gate(merchant, 0, collection, allowlist_root, max_per_wallet))
+        .event::<GateUpdated>();
+    assert_eq!(event.gate, gate);
+    market.run(market.client.set_listing_gate(merchant, coupon.mint, Some(gate)));
+    gate
+}
+
+fn buy_gated(market: &mut Marketplace, buyer: Pubkey, coupon: Coupon, pass: &GatePass) -> bank::TransactionResult {
+    let listing = market.listing(&coupon);
+    let merchant: monkey_dao::state::Merchant = market.bank.get(&listing.merchant);
+    market.send(market.client.buy_gated_nft(buyer, listing.seller, merchant.authority, coupon, None, Some(pass)))
+}
+
+/// Metaplex metadata for `mint`, naming `collection` as its collection.
+fn set_metadata(market: &mut Marketplace, mint: Pubkey, collection: Pubkey, verified: bool) {
+    let metadata = Metadata {
+        key: Key::MetadataV1,
+        update_authority: Pubkey::new_unique(),
+        mint,
+        name: "Monkey".to_string(),
+        symbol: "MONKE".to_string(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        primary_sale_happened: true,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: Some(Collection { verified, key: collection }),
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    };
+    let data = metadata.try_to_vec().unwrap();
+    let account = Account {
+        lamports: market.bank.minimum_balance(data.len()),
+        data,
+        owner: anchor_spl::metadata::ID,
+        executable: false,
+    };
+    market.bank.set_account(pda::metadata(&mint).0, account);
+}
+
+#[test]
+fn allowlisted_wallets_buy_with_a_proof() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let wallets = [market.user(), market.user(), market.user(), market.user()];
+    let (root, proofs) = allowlist(wallets);
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let gate = gate(&mut market, merchant, coupon, None, Some(root), 0);
+
+    let stranger = market.user();
+    let forged = GatePass { allowlist_proof: Some(proofs[2].clone()), ..GatePass::new(gate) };
+    assert_error(buy_gated(&mut market, stranger, coupon, &forged), ErrorCode::NotOnAllowlist);
+    assert_error(buy_gated(&mut market, wallets[2], coupon, &GatePass::new(gate)), ErrorCode::NotOnAllowlist);
+
+    let pass = GatePass { allowlist_proof: Some(proofs[2].clone()), ..GatePass::new(gate) };
+    buy_gated(&mut market, wallets[2], coupon, &pass).unwrap();
+    assert_eq!(market.coupon_balance(&wallets[2], &coupon), 1);
+    let purchases: GatePurchases = market.bank.get(&pda::gate_purchases(&gate, &wallets[2]).0);
+    assert_eq!(purchases.gate, gate);
+    assert_eq!(purchases.wallet, wallets[2]);
+    assert_eq!(purchases.purchases, 1);
+}
+
+#[test]
+fn gated_listing_needs_its_gate() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    gate(&mut market, merchant, coupon, None, None, 0);
+    let buyer = market.user();
+
+    assert_error(market.buy(buyer, coupon), ErrorCode::GateRequired);
+
+    // A gate of another merchant does not stand in for it
+    let other = market.verified_merchant();
+    let other_coupon = market.listed_coupon(other, market.list_args(SOL));
+    let other_gate = gate(&mut market, other, other_coupon, None, None, 0);
+    assert_error(buy_gated(&mut market, buyer, coupon, &GatePass::new(other_gate)), ErrorCode::GateRequired);
+}
+
+#[test]
+fn wallet_limit_spans_the_listings_of_a_gate() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let first = market.listed_coupon(merchant, market.list_args(SOL));
+    let second = market.listed_coupon(merchant, market.list_args(SOL));
+    let gate = gate(&mut market, merchant, first, None, None, 1);
+    market.run(market.client.set_listing_gate(merchant, second.mint, Some(gate)));
+    let pass = GatePass::new(gate);
+    let buyer = market.user();
+
+    buy_gated(&mut market, buyer, first, &pass).unwrap();
+    assert_error(buy_gated(&mut market, buyer, second, &pass), ErrorCode::WalletLimitReached);
+
+    let other = market.user();
+    buy_gated(&mut market, other, second, &pass).unwrap();
+}
+
+#[test]
+fn collection_holders_buy_with_their_nft() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let collection = Pubkey::new_unique();
+    let holder = market.user();
+    let held = market.listed_coupon(merchant, market.list_args(SOL));
+    market.buy(holder, held).unwrap();
+    let unverified = market.listed_coupon(merchant, market.list_args(SOL));
+    market.buy(holder, unverified).unwrap();
+    set_metadata(&mut market, held.mint, collection, true);
+    set_metadata(&mut market, unverified.mint, collection, false);
+
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let gate = gate(&mut market, merchant, coupon, Some(collection), None, 0);
+    let holding = |coupon: Coupon| GatePass {
+        holding: Some((market.token_account(&holder, &coupon), coupon.mint)),
+        ..GatePass::new(gate)
+    };
+    let (with_held, with_unverified) = (holding(held), holding(unverified));
+
+    assert_error(buy_gated(&mut market, holder, coupon, &GatePass::new(gate)), ErrorCode::CollectionNotHeld);
+    assert_error(buy_gated(&mut market, holder, coupon, &with_unverified), ErrorCode::CollectionNotHeld);
+    // Someone else's NFT does not count
+    let stranger = market.user();
+    assert_error(buy_gated(&mut market, stranger, coupon, &with_held), ErrorCode::CollectionNotHeld);
+
+    buy_gated(&mut market, holder, coupon, &with_held).unwrap();
+    assert_eq!(market.coupon_balance(&holder, &coupon), 1);
+}
+
+#[test]
+fn pool_joins_pass_the_gate() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let group_deal = ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        ..market.list_args(SOL)
+    };
+    let coupon = market.listed_coupon(merchant, group_deal);
+    let wallets = [market.user(), market.user(), market.user(), market.user()];
+    let (root, proofs) = allowlist(wallets);
+    let gate = gate(&mut market, merchant, coupon, None, Some(root), 1);
+    let initiator = wallets[0];
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+
+    assert_error(
+        market.send(market.client.join_pool(initiator, coupon.mint, initiator, None)),
+        ErrorCode::GateRequired,
+    );
+    let stranger = market.user();
+    assert_error(
+        market.send(market.client.join_gated_pool(stranger, coupon.mint, initiator, None, Some(&GatePass::new(gate)))),
+        ErrorCode::NotOnAllowlist,
+    );
+
+    let pass = GatePass { allowlist_proof: Some(proofs[0].clone()), ..GatePass::new(gate) };
+    market.run(market.client.join_gated_pool(initiator, coupon.mint, initiator, None, Some(&pass)));
+    let purchases: GatePurchases = market.bank.get(&pda::gate_purchases(&gate, &initiator).0);
+    assert_eq!(purchases.purchases, 1);
+}
+
+#[test]
+fn resales_are_ungated() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let gate = gate(&mut market, merchant, coupon, None, None, 1);
+    let buyer = market.user();
+    buy_gated(&mut market, buyer, coupon, &GatePass::new(gate)).unwrap();
+
+    assert_eq!(market.listing(&coupon).gate, None);
+    market.run(market.client.relist_nft(buyer, coupon, SOL / 2));
+    let stranger = market.user();
+    market.buy(stranger, coupon).unwrap();
+}
+
+#[test]
+fn only_the_listing_merchant_gates_it() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let other = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let other_coupon = market.listed_coupon(other, market.list_args(SOL));
+    let other_gate = gate(&mut market, other, other_coupon, None, None, 0);
+
+    assert_error(
+        market.send(market.client.set_listing_gate(other, coupon.mint, Some(other_gate))),
+        ErrorCode::Unauthorized,
+    );
+    // Nor may a merchant point its listing at another merchant's gate
+    market.run(market.client.set_gate(merchant, 0, None, None, 0));
+    assert_error(
+        market.send(market.client.set_listing_gate(merchant, coupon.mint, Some(other_gate))),
+        ErrorCode::Unauthorized,
+    );
+
+    let event = market
+        .run(market.client.set_listing_gate(merchant, coupon.mint, None))
+        .event::<ListingGateUpdated>();
+    assert_eq!(event.gate, None);
+}
@@ -0,0 +1,166 @@
+//! Gifting: direct transfers that hand the listing over, and escrowed gifts
+//! claimed by their recipient or with a claim key, returned once they expire.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CouponGifted, GiftClaimed, GiftReturned, GiftSent};
+use monkey_dao::state::Gift;
+use monkey_dao::{GIFT_CLAIM_PERIOD, MAX_GIFT_MESSAGE_LEN};
+use monkey_dao_client::{pda, Coupon};
+use spl_associated_token_account::instruction::create_associated_token_account;
+
+/// A coupon bought by a fresh user, who is returned with it.
+fn held_coupon(market: &mut Marketplace) -> (Pubkey, Coupon) {
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let holder = market.user();
+    market.buy(holder, coupon).unwrap();
+    (holder, coupon)
+}
+
+#[test]
+fn direct_gift_hands_the_coupon_over() {
+    let mut market = Marketplace::new();
+    let (sender, coupon) = held_coupon(&mut market);
+    let friend = market.user();
+
+    let event = market
+        .run(market.client.gift_coupon(sender, coupon, friend, "Happy birthday!"))
+        .event::<CouponGifted>();
+    assert_eq!(event.listing, pda::listing(&coupon.mint).0);
+    assert_eq!(event.sender, sender);
+    assert_eq!(event.recipient, friend);
+    assert_eq!(event.message, "Happy birthday!");
+
+    assert_eq!(market.coupon_balance(&sender, &coupon), 0);
+    assert_eq!(market.coupon_balance(&friend, &coupon), 1);
+    assert_eq!(market.listing(&coupon).holder, friend);
+    // The recipient now acts as the holder
+    market.run(market.client.relist_nft(friend, coupon, SOL / 2));
+}
+
+#[test]
+fn escrowed_gift_is_claimed_by_its_recipient() {
+    let mut market = Marketplace::new();
+    let (sender, coupon) = held_coupon(&mut market);
+    let friend = market.user();
+    let gift = pda::gift(&coupon.mint).0;
+
+    let event = market
+        .run(market.client.send_gift(sender, coupon, Some(friend), None, "Enjoy"))
+        .event::<GiftSent>();
+    assert_eq!(event.gift, gift);
+    assert_eq!(event.recipient, Some(friend));
+    assert_eq!(event.expires_at, market.bank.now() + GIFT_CLAIM_PERIOD);
+    assert_eq!(market.coupon_balance(&gift, &coupon), 1);
+    let escrow: Gift = market.bank.get(&gift);
+    assert_eq!(escrow.sender, sender);
+    assert_eq!(escrow.message, "Enjoy");
+    assert_eq!(escrow.version, Gift::VERSION);
+
+    let stranger = market.user();
+    assert_error(
+        market.send(market.client.claim_gift(stranger, sender, coupon, None)),
+        ErrorCode::NotGiftRecipient,
+    );
+
+    let sender_before = market.bank.lamports(&sender);
+    let (gift_rent, vault_rent) = (market.bank.lamports(&gift), market.bank.lamports(&market.token_account(&gift, &coupon)));
+    let event = market
+        .run(market.client.claim_gift(friend, sender, coupon, None))
+        .event::<GiftClaimed>();
+    assert_eq!(event.recipient, friend);
+    assert_eq!(market.coupon_balance(&friend, &coupon), 1);
+    assert_eq!(market.listing(&coupon).holder, friend);
+    assert_eq!(market.bank.lamports(&gift), 0);
+    assert_eq!(market.bank.lamports(&sender), sender_before + gift_rent + vault_rent);
+}
+
+#[test]
+fn gift_with_a_claim_key_goes_to_whoever_it_signs_for() {
+    let mut market = Marketplace::new();
+    let (sender, coupon) = held_coupon(&mut market);
+    let claim_key = Pubkey::new_unique();
+    market.run(market.client.send_gift(sender, coupon, None, Some(claim_key), ""));
+    let friend = market.user();
+
+    assert_error(
+        market.send(market.client.claim_gift(friend, sender, coupon, Some(Pubkey::new_unique()))),
+        ErrorCode::NotGiftRecipient,
+    );
+    assert_error(
+        market.send(market.client.claim_gift(friend, sender, coupon, None)),
+        ErrorCode::NotGiftRecipient,
+    );
+
+    market.run(market.client.claim_gift(friend, sender, coupon, Some(claim_key)));
+    assert_eq!(market.coupon_balance(&friend, &coupon), 1);
+    assert_eq!(market.listing(&coupon).holder, friend);
+}
+
+#[test]
+fn gift_vault_opened_ahead_of_the_gift_is_reused() {
+    let mut market = Marketplace::new();
+    let (sender, coupon) = held_coupon(&mut market);
+    let gift = pda::gift(&coupon.mint).0;
+    let griefer = market.user();
+    market.run(create_associated_token_account(&griefer, &gift, &coupon.mint, &coupon.token_program));
+    let friend = market.user();
+
+    market.run(market.client.send_gift(sender, coupon, Some(friend), None, ""));
+
+    assert_eq!(market.coupon_balance(&gift, &coupon), 1);
+}
+
+#[test]
+fn unclaimed_gift_returns_to_the_sender() {
+    let mut market = Marketplace::new();
+    let (sender, coupon) = held_coupon(&mut market);
+    let friend = market.user();
+    market.run(market.client.send_gift(sender, coupon, Some(friend), None, "Too late"));
+    let expires_at = market.bank.now() + GIFT_CLAIM_PERIOD;
+    let crank = market.user();
+
+    assert_error(market.send(market.client.return_gift(crank, sender, coupon)), ErrorCode::GiftNotExpired);
+    market.bank.warp_to(expires_at);
+    assert_error(
+        market.send(market.client.claim_gift(friend, sender, coupon, None)),
+        ErrorCode::GiftExpired,
+    );
+
+    let event = market.run(market.client.return_gift(crank, sender, coupon)).event::<GiftReturned>();
+    assert_eq!(event.sender, sender);
+    assert_eq!(market.coupon_balance(&sender, &coupon), 1);
+    assert_eq!(market.listing(&coupon).holder, sender);
+    assert_eq!(market.bank.lamports(&pda::gift(&coupon.mint).0), 0);
+    // The coupon can be gifted again
+    market.run(market.client.send_gift(sender, coupon, Some(friend), None, "Second try"));
+}
+
+#[test]
+fn only_the_holder_gifts_an_unlisted_coupon() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let listed = market.listed_coupon(merchant, market.list_args(SOL));
+    let (holder, coupon) = held_coupon(&mut market);
+    let friend = market.user();
+
+    assert_error(
+        market.send(market.client.gift_coupon(merchant, listed, friend, "")),
+        ErrorCode::ListingStillActive,
+    );
+    let long = "a".repeat(MAX_GIFT_MESSAGE_LEN + 1);
+    assert_error(market.send(market.client.gift_coupon(holder, coupon, friend, &long)), ErrorCode::InvalidGift);
+    assert_error(market.send(market.client.send_gift(holder, coupon, None, None, "")), ErrorCode::InvalidGift);
+
+    // Once given away, the coupon is no longer the sender's to gift
+    market.run(market.client.gift_coupon(holder, coupon, friend, ""));
+    assert_error(
+        market.send(market.client.send_gift(holder, coupon, Some(holder), None, "")),
+        ErrorCode::Unauthorized,
+    );
+}
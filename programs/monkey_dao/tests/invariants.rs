@@ -0,0 +1,434 @@
+//! Property tests: random instruction sequences against a small marketplace,
+//! with the economic invariants checked after every step. Failed instructions
+//! are expected along the way and leave the bank untouched.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token_interface::Mint;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::events::{CouponPurchased, ReviewAdded};
+use monkey_dao::state::{Custody, Gift, Pool, PoolReferral, Referrer, StakeAccount, UseMethod, UserStats};
+use monkey_dao::{PLATFORM_FEE_BPS, PURCHASE_REWARD_BPS};
+use monkey_dao_client::{pda, CartItem, Coupon, ListNftArgs};
+use proptest::prelude::*;
+
+const USERS: usize = 4;
+const COUPONS: usize = 3;
+/// The group deal, the only coupon pools can form around.
+const GROUP_DEAL: usize = 1;
+/// The user registered as the referrer every referred action names.
+const REFERRER: usize = 0;
+
+#[derive(Debug, Clone)]
+enum Action {
+    Buy { coupon: usize, buyer: usize, referred: bool },
+    Checkout { buyer: usize, first: usize, second: usize },
+    Relist { coupon: usize, price_bps: u64 },
+    Delist { coupon: usize },
+    Stake { coupon: usize },
+    Unstake { coupon: usize },
+    Claim { coupon: usize },
+    Redeem { coupon: usize, amount: u64 },
+    CreatePool { initiator: usize, size: u8 },
+    JoinPool { initiator: usize, participant: usize, referred: bool },
+    CompletePool { initiator: usize },
+    ClaimPoolCoupon { initiator: usize },
+    CancelPool { initiator: usize },
+    RefundPoolDeposit { initiator: usize, participant: usize },
+    ClaimReferralRewards,
+    Gift { coupon: usize, recipient: usize },
+    SendGift { coupon: usize, recipient: usize },
+    ClaimGift { coupon: usize },
+    ReturnGift { coupon: usize },
+    ReclaimExpiredListing { coupon: usize },
+    ClaimExpiredRefund { coupon: usize },
+    Review { coupon: usize, reviewer: usize, rating: u8 },
+    Warp { seconds: i64 },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    let coupon = 0..COUPONS;
+    let user = 0..USERS;
+    // Pools come from two of the users, so joins mostly find one
+    let initiator = 0..2usize;
+    prop_oneof![
+        3 => (coupon.clone(), user.clone(), any::<bool>())
+            .prop_map(|(coupon, buyer, referred)| Action::Buy { coupon, buyer, referred }),
+        1 => (user.clone(), coupon.clone(), coupon.clone())
+            .prop_map(|(buyer, first, second)| Action::Checkout { buyer, first, second }),
+        2 => (coupon.clone(), 0..=10_000u64).prop_map(|(coupon, price_bps)| Action::Relist { coupon, price_bps }),
+        1 => coupon.clone().prop_map(|coupon| Action::Delist { coupon }),
+        2 => coupon.clone().prop_map(|coupon| Action::Stake { coupon }),
+        1 => coupon.clone().prop_map(|coupon| Action::Unstake { coupon }),
+        1 => coupon.clone().prop_map(|coupon| Action::Claim { coupon }),
+        2 => (coupon.clone(), 0..1_200u64).prop_map(|(coupon, amount)| Action::Redeem { coupon, amount }),
+        3 => (initiator.clone(), prop::sample::select(vec![2u8, 2, 4]))
+            .prop_map(|(initiator, size)| Action::CreatePool { initiator, size }),
+        5 => (initiator.clone(), user.clone(), any::<bool>())
+            .prop_map(|(initiator, participant, referred)| Action::JoinPool { initiator, participant, referred }),
+        4 => initiator.clone().prop_map(|initiator| Action::CompletePool { initiator }),
+        3 => initiator.clone().prop_map(|initiator| Action::ClaimPoolCoupon { initiator }),
+        1 => initiator.clone().prop_map(|initiator| Action::CancelPool { initiator }),
+        1 => (initiator, user.clone())
+            .prop_map(|(initiator, participant)| Action::RefundPoolDeposit { initiator, participant }),
+        1 => Just(Action::ClaimReferralRewards),
+        1 => (coupon.clone(), user.clone()).prop_map(|(coupon, recipient)| Action::Gift { coupon, recipient }),
+        1 => (coupon.clone(), user.clone()).prop_map(|(coupon, recipient)| Action::SendGift { coupon, recipient }),
+        2 => coupon.clone().prop_map(|coupon| Action::ClaimGift { coupon }),
+        1 => coupon.clone().prop_map(|coupon| Action::ReturnGift { coupon }),
+        1 => coupon.clone().prop_map(|coupon| Action::ReclaimExpiredListing { coupon }),
+        1 => coupon.clone().prop_map(|coupon| Action::ClaimExpiredRefund { coupon }),
+        1 => (coupon, user, 0..=6u8).prop_map(|(coupon, reviewer, rating)| Action::Review { coupon, reviewer, rating }),
+        2 => (0..3 * DAY).prop_map(|seconds| Action::Warp { seconds }),
+        1 => (3 * DAY..20 * DAY).prop_map(|seconds| Action::Warp { seconds }),
+    ]
+}
+
+/// One verified merchant with a single-use coupon expiring within the week,
+/// a four-use group deal with a pool of two open on it and a Token-2022
+/// stored-value voucher, plus a few funded users, one of them a referrer.
+/// The merchant refunds half of what expires unused.
+struct World {
+    market: Marketplace,
+    merchant: Pubkey,
+    users: Vec<Pubkey>,
+    coupons: Vec<Coupon>,
+}
+
+impl World {
+    fn new() -> Self {
+        let mut market = Marketplace::new();
+        let merchant = market.verified_merchant();
+        let users: Vec<Pubkey> = (0..USERS).map(|_| market.user()).collect();
+        market.run(market.client.set_referral_rewards(market.authority, 4_000, 500));
+        market.run(market.client.register_referrer(users[REFERRER]));
+        market.run(market.client.set_refund_policy(merchant, 5_000, 7 * DAY));
+        market.run(market.client.fund_refund_escrow(merchant, 3 * SOL));
+
+        let single = ListNftArgs {
+            expiry_date: market.bank.now() + 5 * DAY,
+            ..market.list_args(SOL)
+        };
+        let single = market.listed_coupon(merchant, single);
+        let group_deal = ListNftArgs {
+            is_group_deal: true,
+            deal_price_2: Some(SOL),
+            deal_price_4: Some(SOL / 2),
+            use_method: UseMethod::Multiple,
+            total_uses: 4,
+            ..market.list_args(2 * SOL)
+        };
+        let group_deal = market.listed_coupon(merchant, group_deal);
+        market.run(market.client.create_pool(users[1], group_deal.mint, 2));
+        let voucher = market.coupon(merchant, spl_token_2022::ID);
+        let args = ListNftArgs {
+            use_method: UseMethod::StoredValue,
+            total_uses: 1_000,
+            ..market.list_args(SOL / 2)
+        };
+        market.list(merchant, voucher, args).unwrap();
+
+        Self { market, merchant, users, coupons: vec![single, group_deal, voucher] }
+    }
+
+    /// Whoever the listing says owns the coupon, which is the wallet that
+    /// has to sign for anything done with it.
+    fn holder(&self, coupon: &Coupon) -> Pubkey {
+        self.market.listing(coupon).holder
+    }
+
+    fn referrer(&self, referred: bool) -> Option<Pubkey> {
+        referred.then_some(self.users[REFERRER])
+    }
+
+    fn pool_key(&self, coupon: &Coupon, initiator: usize) -> Pubkey {
+        pda::pool(&pda::listing(&coupon.mint).0, &self.users[initiator]).0
+    }
+
+    fn apply(&mut self, action: Action) {
+        match action {
+            Action::Warp { seconds } => self.market.bank.warp_forward(seconds),
+            action => {
+                let instruction = self.instruction(action);
+                let _ = self.market.send(instruction);
+            }
+        }
+    }
+
+    fn instruction(&self, action: Action) -> Instruction {
+        let client = &self.market.client;
+        match action {
+            Action::Buy { coupon, buyer, referred } => {
+                let coupon = self.coupons[coupon];
+                let seller = self.market.listing(&coupon).seller;
+                client.buy_nft(self.users[buyer], seller, self.merchant, coupon, self.referrer(referred))
+            }
+            Action::Checkout { buyer, first, second } => {
+                let items = [first, second].map(|coupon| {
+                    let coupon = self.coupons[coupon];
+                    let seller = self.market.listing(&coupon).seller;
+                    CartItem { coupon, seller, merchant: self.merchant }
+                });
+                client.checkout_cart(self.users[buyer], &items)
+            }
+            Action::Relist { coupon, price_bps } => {
+                let coupon = self.coupons[coupon];
+                let original_price = self.market.listing(&coupon).original_price;
+                let price = (original_price as u128 * price_bps as u128 / 10_000) as u64;
+                client.relist_nft(self.holder(&coupon), coupon, price)
+            }
+            Action::Delist { coupon } => {
+                let coupon = self.coupons[coupon];
+                client.delist_nft(self.holder(&coupon), coupon)
+            }
+            Action::Stake { coupon } => {
+                let coupon = self.coupons[coupon];
+                client.stake_nft(self.holder(&coupon), coupon)
+            }
+            Action::Unstake { coupon } => {
+                let coupon = self.coupons[coupon];
+                client.unstake_nft(self.holder(&coupon), coupon)
+            }
+            Action::Claim { coupon } => {
+                let coupon = self.coupons[coupon];
+                client.claim_staking_rewards(self.holder(&coupon), coupon.mint)
+            }
+            Action::Redeem { coupon, amount } => {
+                let coupon = self.coupons[coupon];
+                let listing = self.market.listing(&coupon);
+                client.redeem_nft(
+                    self.holder(&coupon),
+                    self.merchant,
+                    self.merchant,
+                    listing.uses.spent(),
+                    coupon,
+                    vec![1; 64],
+                    amount,
+                )
+            }
+            Action::CreatePool { initiator, size } => {
+                client.create_pool(self.users[initiator], self.coupons[GROUP_DEAL].mint, size)
+            }
+            Action::JoinPool { initiator, participant, referred } => client.join_pool(
+                self.users[participant],
+                self.coupons[GROUP_DEAL].mint,
+                self.users[initiator],
+                self.referrer(referred),
+            ),
+            Action::CompletePool { initiator } => {
+                let coupon = self.coupons[GROUP_DEAL];
+                let seller = self.market.listing(&coupon).seller;
+                // Every referred participant's record, with its referrer's authority
+                let pool_key = self.pool_key(&coupon, initiator);
+                let referrals: Vec<(Pubkey, Pubkey)> = self
+                    .market
+                    .bank
+                    .try_get::<Pool>(&pool_key)
+                    .map_or_else(Vec::new, |pool| pool.participants)
+                    .into_iter()
+                    .filter_map(|participant| {
+                        let record = self
+                            .market
+                            .bank
+                            .try_get::<PoolReferral>(&pda::pool_referral(&pool_key, &participant).0)?;
+                        let referrer: Referrer = self.market.bank.get(&record.referrer);
+                        Some((participant, referrer.authority))
+                    })
+                    .collect();
+                client.complete_referred_pool(self.users[initiator], seller, self.merchant, coupon, &referrals)
+            }
+            Action::ClaimPoolCoupon { initiator } => {
+                client.claim_pool_coupon(self.users[initiator], self.coupons[GROUP_DEAL])
+            }
+            Action::CancelPool { initiator } => {
+                client.cancel_pool(self.users[initiator], self.coupons[GROUP_DEAL].mint)
+            }
+            Action::RefundPoolDeposit { initiator, participant } => {
+                let mint = self.coupons[GROUP_DEAL].mint;
+                client.refund_pool_deposit(self.users[participant], mint, self.users[initiator])
+            }
+            Action::ClaimReferralRewards => client.claim_referral_rewards(self.users[REFERRER]),
+            Action::Gift { coupon, recipient } => {
+                let coupon = self.coupons[coupon];
+                client.gift_coupon(self.holder(&coupon), coupon, self.users[recipient], "")
+            }
+            Action::SendGift { coupon, recipient } => {
+                let coupon = self.coupons[coupon];
+                client.send_gift(self.holder(&coupon), coupon, Some(self.users[recipient]), None, "")
+            }
+            Action::ClaimGift { coupon } => {
+                let coupon = self.coupons[coupon];
+                let gift = self.market.bank.try_get::<Gift>(&pda::gift(&coupon.mint).0);
+                let claimer = gift.as_ref().and_then(|gift| gift.recipient).unwrap_or(self.users[0]);
+                client.claim_gift(claimer, self.holder(&coupon), coupon, None)
+            }
+            Action::ReturnGift { coupon } => {
+                let coupon = self.coupons[coupon];
+                client.return_gift(self.users[0], self.holder(&coupon), coupon)
+            }
+            Action::ReclaimExpiredListing { coupon } => {
+                let coupon = self.coupons[coupon];
+                let seller = self.market.listing(&coupon).seller;
+                client.reclaim_expired_listing(self.users[0], seller, coupon)
+            }
+            Action::ClaimExpiredRefund { coupon } => {
+                let coupon = self.coupons[coupon];
+                client.claim_expired_refund(self.holder(&coupon), self.merchant, coupon)
+            }
+            Action::Review { coupon, reviewer, rating } => {
+                client.add_review(self.users[reviewer], self.coupons[coupon].mint, rating, String::new())
+            }
+            Action::Warp { .. } => unreachable!("applied directly"),
+        }
+    }
+
+    fn check_invariants(&self) {
+        let market = &self.market;
+        let wallets: Vec<Pubkey> = self.users.iter().copied().chain([self.merchant]).collect();
+
+        // Every MONK minted, for purchases, stakes or a referrer's claimed
+        // bonus, is recorded in its earner's stats, and stays in their wallet
+        let monk: Mint = market.bank.get(&market.client.monk_mint);
+        let earned: u64 = wallets
+            .iter()
+            .filter_map(|wallet| market.bank.try_get::<UserStats>(&pda::user_stats(wallet).0))
+            .map(|stats| stats.total_monk_earned)
+            .sum();
+        assert_eq!(monk.supply, earned, "MONK supply differs from total earned");
+        let held: u64 = wallets.iter().map(|wallet| market.monk_balance(wallet)).sum();
+        assert_eq!(held, monk.supply, "MONK held outside the wallets that earned it");
+        let referrer: Referrer = market.bank.get(&pda::referrer(&self.users[REFERRER]).0);
+        assert!(referrer.pending_monk <= referrer.total_monk_earned);
+
+        for coupon in &self.coupons {
+            let listing_key = pda::listing(&coupon.mint).0;
+            let stake_key = pda::stake(&coupon.mint).0;
+            let gift_key = pda::gift(&coupon.mint).0;
+            let pool_keys: Vec<Pubkey> = (0..USERS).map(|initiator| self.pool_key(coupon, initiator)).collect();
+            let listing = market.listing(coupon);
+            let mint: Mint = market.bank.get(&coupon.mint);
+            let holders: Vec<Pubkey> = wallets
+                .iter()
+                .copied()
+                .chain([listing_key, stake_key, gift_key])
+                .chain(pool_keys.iter().copied())
+                .filter(|owner| market.coupon_balance(owner, coupon) > 0)
+                .collect();
+
+            assert!(listing.uses.remaining <= listing.uses.total);
+            assert_eq!(market.bank.exists(&gift_key), listing.custody == Custody::GiftEscrow);
+            if listing.is_used {
+                // Fully redeemed or refunded coupons are burned
+                assert_eq!(listing.uses.remaining, 0);
+                assert_eq!(listing.custody, Custody::Redeemed);
+                assert_eq!(mint.supply, 0);
+                assert!(holders.is_empty(), "burned coupon still held by {holders:?}");
+            } else {
+                let staked = market
+                    .bank
+                    .try_get::<StakeAccount>(&stake_key)
+                    .is_some_and(|stake| stake.is_active);
+                assert_eq!(staked, listing.custody == Custody::StakeVault);
+                // A filled pool's coupon waits in the vault until the pool
+                // settles, then sits in the pool's own account until claimed
+                let settled = listing.custody == Custody::Pool
+                    && market.bank.get::<Pool>(&listing.holder).is_completed;
+                assert_eq!(
+                    listing.is_active,
+                    matches!(listing.custody, Custody::ListingVault | Custody::Pool) && !settled
+                );
+                let expected = match listing.custody {
+                    Custody::ListingVault => listing_key,
+                    Custody::Pool if settled => listing.holder,
+                    Custody::Pool => listing_key,
+                    Custody::StakeVault => stake_key,
+                    Custody::GiftEscrow => gift_key,
+                    Custody::Wallet | Custody::Redeemed => listing.holder,
+                };
+                assert_eq!(mint.supply, 1);
+                assert_eq!(holders, vec![expected], "coupon {} is not held only by {expected}", coupon.mint);
+            }
+
+            if listing.total_reviews > 0 {
+                assert!((20..=100).contains(&listing.average_rating));
+            }
+
+            for pool_key in &pool_keys {
+                let Some(pool) = market.bank.try_get::<Pool>(pool_key) else {
+                    continue;
+                };
+                // Settling pays the whole escrow to the seller
+                let escrowed = if pool.is_completed { 0 } else { pool.total_deposited };
+                assert_eq!(
+                    market.bank.lamports(&pda::escrow(pool_key).0),
+                    escrowed,
+                    "escrow of pool {pool_key} differs from its deposits"
+                );
+                assert_eq!(pool.total_deposited, pool.price_per_person * pool.current_participants as u64);
+                assert_eq!(pool.participants.len(), pool.current_participants as usize);
+                assert!(pool.current_participants <= pool.pool_size);
+                if !pool.is_completed {
+                    let referred = pool
+                        .participants
+                        .iter()
+                        .filter(|participant| market.bank.exists(&pda::pool_referral(pool_key, participant).0))
+                        .count();
+                    assert_eq!(referred, pool.referrals as usize, "pool {pool_key} lost track of its referrals");
+                }
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn invariants_hold_for_random_instruction_sequences(actions in prop::collection::vec(action(), 1..40)) {
+        let mut world = World::new();
+        world.check_invariants();
+        for action in actions {
+            world.apply(action);
+            world.check_invariants();
+        }
+    }
+
+    #[test]
+    fn purchase_math_holds_for_any_price(price in 1..=u64::MAX / 4) {
+        let mut market = Marketplace::new();
+        let merchant = market.verified_merchant();
+        let coupon = market.listed_coupon(merchant, market.list_args(price));
+        let buyer = market.bank.funded_key(price.saturating_add(SOL));
+        let vault_rent = market.bank.lamports(&market.token_account(&pda::listing(&coupon.mint).0, &coupon));
+        let seller_before = market.bank.lamports(&merchant);
+
+        let event = market.buy(buyer, coupon).unwrap().event::<CouponPurchased>();
+
+        let platform_fee = (price as u128 * PLATFORM_FEE_BPS as u128 / 10_000) as u64;
+        let monk_reward = (price as u128 * PURCHASE_REWARD_BPS as u128 / 10_000) as u64;
+        prop_assert_eq!(event.platform_fee, platform_fee);
+        prop_assert_eq!(event.monk_reward, monk_reward);
+        prop_assert_eq!(market.bank.lamports(&merchant), seller_before + price - platform_fee + vault_rent);
+        prop_assert_eq!(market.monk_balance(&buyer), monk_reward);
+    }
+
+    #[test]
+    fn review_average_stays_within_the_given_ratings(ratings in prop::collection::vec(1..=5u8, 1..20)) {
+        let mut market = Marketplace::new();
+        let merchant = market.verified_merchant();
+        let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+        for (count, rating) in ratings.iter().enumerate() {
+            let reviewer = market.user();
+            let event = market
+                .run(market.client.add_review(reviewer, coupon.mint, *rating, String::new()))
+                .event::<ReviewAdded>();
+            let seen = &ratings[..=count];
+            prop_assert_eq!(event.total_reviews, count as u64 + 1);
+            prop_assert!(event.average_rating >= seen.iter().min().unwrap() * 20);
+            prop_assert!(event.average_rating <= seen.iter().max().unwrap() * 20);
+        }
+    }
+}
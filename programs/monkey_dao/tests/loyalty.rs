@@ -0,0 +1,181 @@
+//! Loyalty tiers: refreshing a tier from a user's stats and earned MONK, the bigger
+//! purchase rewards and lower fees it brings, and tier-gated listings.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CouponPurchased, ListingAccessUpdated, TierRefreshed};
+use monkey_dao::state::{Loyalty, LoyaltyTier};
+use monkey_dao::{MONK_DECIMALS, PLATFORM_FEE_BPS, PURCHASE_REWARD_BPS, SILVER_MIN_MONK, TIER_VALIDITY};
+use monkey_dao_client::pda;
+
+/// A buyer that made `purchases` purchases from `merchant`, the first at
+/// `spend` and the rest at 1 SOL, earning a tenth of that in MONK.
+fn shopper(market: &mut Marketplace, merchant: Pubkey, purchases: usize, spend: u64) -> Pubkey {
+    let buyer = market.bank.funded_key(spend + (purchases as u64 + 1) * SOL);
+    for price in std::iter::once(spend).chain(std::iter::repeat(SOL)).take(purchases) {
+        let coupon = market.listed_coupon(merchant, market.list_args(price));
+        market.buy(buyer, coupon).unwrap();
+    }
+    buyer
+}
+
+fn refresh(market: &mut Marketplace, user: Pubkey) -> TierRefreshed {
+    market.run(market.client.refresh_tier(user)).event::<TierRefreshed>()
+}
+
+#[test]
+fn tier_follows_purchases_monk_and_staking() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+
+    // 100 MONK, but one purchase short of Silver
+    let buyer = shopper(&mut market, merchant, 4, 1_000 * SOL);
+    assert_eq!(refresh(&mut market, buyer).tier, LoyaltyTier::Bronze);
+
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    market.buy(buyer, coupon).unwrap();
+    let event = refresh(&mut market, buyer);
+    assert_eq!(event.previous, LoyaltyTier::Bronze);
+    assert_eq!(event.tier, LoyaltyTier::Silver);
+
+    let loyalty: Loyalty = market.bank.get(&pda::loyalty(&buyer).0);
+    assert_eq!(loyalty.user, buyer);
+    assert_eq!(loyalty.tier, LoyaltyTier::Silver);
+    assert_eq!(loyalty.refreshed_at, market.bank.now());
+    assert_eq!(loyalty.version, Loyalty::VERSION);
+}
+
+#[test]
+fn monk_passed_between_wallets_does_not_count() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let whale = shopper(&mut market, merchant, 5, 1_000 * SOL);
+    assert_eq!(refresh(&mut market, whale).tier, LoyaltyTier::Silver);
+
+    // Five purchases but not the MONK, until the whale hands theirs over
+    let buyer = shopper(&mut market, merchant, 5, SOL);
+    let (mint, token_program) = (market.client.monk_mint, market.client.monk_token_program);
+    let transfer = spl_token_2022::instruction::transfer_checked(
+        &token_program,
+        &pda::associated_token(&whale, &mint, &token_program),
+        &mint,
+        &pda::associated_token(&buyer, &mint, &token_program),
+        &whale,
+        &[],
+        SILVER_MIN_MONK,
+        MONK_DECIMALS,
+    )
+    .unwrap();
+    market.run(transfer);
+    assert!(market.monk_balance(&buyer) >= SILVER_MIN_MONK);
+
+    assert_eq!(refresh(&mut market, buyer).tier, LoyaltyTier::Bronze);
+}
+
+#[test]
+fn gold_also_takes_a_staked_coupon() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let buyer = shopper(&mut market, merchant, 20, 10_000 * SOL);
+    assert_eq!(refresh(&mut market, buyer).tier, LoyaltyTier::Silver);
+
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    market.buy(buyer, coupon).unwrap();
+    market.run(market.client.stake_nft(buyer, coupon));
+
+    let event = refresh(&mut market, buyer);
+    assert_eq!(event.previous, LoyaltyTier::Silver);
+    assert_eq!(event.tier, LoyaltyTier::Gold);
+}
+
+#[test]
+fn loyal_buyers_earn_more_monk_and_pay_lower_fees() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let buyer = shopper(&mut market, merchant, 5, 1_000 * SOL);
+    refresh(&mut market, buyer);
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    let event = market.buy(buyer, coupon).unwrap().event::<CouponPurchased>();
+
+    // 1.25x the purchase reward, 10% off the fee
+    assert_eq!(event.monk_reward, SOL * PURCHASE_REWARD_BPS / 10_000 * 5 / 4);
+    assert_eq!(event.platform_fee, SOL * PLATFORM_FEE_BPS / 10_000 * 9 / 10);
+}
+
+#[test]
+fn tier_lapses_to_bronze_unless_refreshed() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let buyer = shopper(&mut market, merchant, 5, 1_000 * SOL);
+    refresh(&mut market, buyer);
+    market.bank.warp_forward(TIER_VALIDITY + 1);
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    let event = market.buy(buyer, coupon).unwrap().event::<CouponPurchased>();
+
+    assert_eq!(event.monk_reward, SOL * PURCHASE_REWARD_BPS / 10_000);
+    assert_eq!(event.platform_fee, SOL * PLATFORM_FEE_BPS / 10_000);
+    assert_eq!(refresh(&mut market, buyer).previous, LoyaltyTier::Bronze);
+}
+
+#[test]
+fn tier_gated_listing_sells_only_to_the_tier() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let member = shopper(&mut market, merchant, 5, 1_000 * SOL);
+    refresh(&mut market, member);
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let event = market
+        .run(market.client.set_listing_access(merchant, coupon.mint, LoyaltyTier::Silver, 0))
+        .event::<ListingAccessUpdated>();
+    assert_eq!(event.min_tier, LoyaltyTier::Silver);
+
+    let stranger = market.user();
+    assert_error(market.buy(stranger, coupon), ErrorCode::TierTooLow);
+    market.buy(member, coupon).unwrap();
+
+    // The resale is open to everyone
+    let listing = market.listing(&coupon);
+    assert_eq!(listing.min_tier, LoyaltyTier::Bronze);
+    market.run(market.client.relist_nft(member, coupon, SOL / 2));
+    market.buy(stranger, coupon).unwrap();
+}
+
+#[test]
+fn early_access_opens_to_everyone_later() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let member = shopper(&mut market, merchant, 5, 1_000 * SOL);
+    refresh(&mut market, member);
+    let stranger = market.user();
+    let opens = market.bank.now() + DAY;
+    let first = market.listed_coupon(merchant, market.list_args(SOL));
+    let second = market.listed_coupon(merchant, market.list_args(SOL));
+    for coupon in [first, second] {
+        market.run(market.client.set_listing_access(merchant, coupon.mint, LoyaltyTier::Bronze, opens));
+    }
+
+    assert_error(market.buy(stranger, first), ErrorCode::EarlyAccessOnly);
+    market.buy(member, first).unwrap();
+
+    market.bank.warp_to(opens);
+    market.buy(stranger, second).unwrap();
+}
+
+#[test]
+fn only_the_listing_merchant_sets_access() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let other = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    assert_error(
+        market.send(market.client.set_listing_access(other, coupon.mint, LoyaltyTier::Gold, 0)),
+        ErrorCode::Unauthorized,
+    );
+}
@@ -0,0 +1,259 @@
+//! Merchant registration, KYC attestations from accredited verifiers,
+//! delegates and the migration from the legacy merchant layout.
+
+mod bank;
+
+use anchor_lang::{AnchorSerialize, Discriminator, Space};
+use bank::marketplace::{Marketplace, DAY, KYC_HASH, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{
+    AttestationIssued, AttestationRevoked, MerchantKycUpdated, MerchantRegistered,
+    MerchantVerificationUpdated,
+};
+use monkey_dao::state::{Attestation, LegacyMerchant, Merchant, MerchantDelegate, VerifierRegistry};
+use monkey_dao::ANCHOR_DISCRIMINATOR;
+use monkey_dao_client::pda;
+
+const NEW_KYC_HASH: [u8; 32] = [9; 32];
+
+#[test]
+fn registration_starts_unverified() {
+    let mut market = Marketplace::new();
+    let authority = market.user();
+
+    let meta = market.run(market.client.register_merchant(
+        authority,
+        "Banana Ramen".to_string(),
+        "Restaurant".to_string(),
+        KYC_HASH,
+        "https://kyc.example/banana-ramen".to_string(),
+    ));
+
+    let merchant: Merchant = market.bank.get(&pda::merchant(&authority).0);
+    assert_eq!(merchant.authority, authority);
+    assert_eq!(merchant.business_name, "Banana Ramen");
+    assert_eq!(merchant.kyc_hash, KYC_HASH);
+    assert!(!merchant.is_verified);
+    assert_eq!(merchant.registration_date, market.bank.now());
+
+    let event = meta.event::<MerchantRegistered>();
+    assert_eq!(event.merchant, pda::merchant(&authority).0);
+    assert_eq!(event.kyc_hash, KYC_HASH);
+}
+
+#[test]
+fn attestation_from_an_accredited_verifier_verifies_the_merchant() {
+    let mut market = Marketplace::new();
+    let authority = market.merchant();
+    let merchant = pda::merchant(&authority).0;
+    let attestation = pda::attestation(&merchant, &market.verifier).0;
+    let expires_at = market.bank.now() + 90 * DAY;
+
+    let meta = market.run(market.client.issue_attestation(market.verifier, authority, KYC_HASH, expires_at));
+    assert_eq!(meta.event::<AttestationIssued>().attestation, attestation);
+    let record: Attestation = market.bank.get(&attestation);
+    assert_eq!(record.merchant, merchant);
+    assert_eq!(record.verifier, market.verifier);
+    assert_eq!(record.kyc_hash, KYC_HASH);
+    assert_eq!(record.expires_at, expires_at);
+
+    let event = market
+        .run(market.client.verify_merchant(authority, &[market.verifier]))
+        .event::<MerchantVerificationUpdated>();
+    assert_eq!(event.valid_attestations, 1);
+    assert_eq!(event.threshold, 1);
+    assert!(event.is_verified);
+    let state: Merchant = market.bank.get(&merchant);
+    assert!(state.is_verified);
+}
+
+#[test]
+fn kyc_update_invalidates_existing_attestations() {
+    let mut market = Marketplace::new();
+    let authority = market.verified_merchant();
+
+    let meta = market.run(market.client.update_merchant_kyc(
+        authority,
+        NEW_KYC_HASH,
+        "https://kyc.example/banana-ramen-2".to_string(),
+    ));
+    assert_eq!(meta.event::<MerchantKycUpdated>().kyc_hash, NEW_KYC_HASH);
+    let state: Merchant = market.bank.get(&pda::merchant(&authority).0);
+    assert!(!state.is_verified);
+
+    // The old attestation covers the old documents only
+    let coupon = market.coupon(authority, spl_token::ID);
+    bank::assert_error(
+        market.list(authority, coupon, market.list_args(SOL)),
+        ErrorCode::MerchantNotVerified,
+    );
+    let event = market
+        .run(market.client.verify_merchant(authority, &[market.verifier]))
+        .event::<MerchantVerificationUpdated>();
+    assert_eq!(event.valid_attestations, 0);
+
+    // Re-attesting the new bundle overwrites the attestation in place
+    let expires_at = market.bank.now() + 90 * DAY;
+    market.run(market.client.issue_attestation(market.verifier, authority, NEW_KYC_HASH, expires_at));
+    market.list(authority, coupon, market.list_args(SOL)).unwrap();
+}
+
+#[test]
+fn threshold_requires_distinct_verifiers() {
+    let mut market = Marketplace::new();
+    let second = market.user();
+    market.run(market.client.add_verifier(market.authority, second));
+    market.run(market.client.set_attestation_threshold(market.authority, 2));
+    let registry: VerifierRegistry = market.bank.get(&pda::verifier_registry().0);
+    assert_eq!(registry.verifiers, vec![market.verifier, second]);
+    assert_eq!(registry.threshold, 2);
+
+    let authority = market.merchant();
+    let expires_at = market.bank.now() + 90 * DAY;
+    market.run(market.client.issue_attestation(market.verifier, authority, KYC_HASH, expires_at));
+
+    // Passing the same attestation twice still counts once
+    let event = market
+        .run(market.client.verify_merchant(authority, &[market.verifier, market.verifier]))
+        .event::<MerchantVerificationUpdated>();
+    assert_eq!(event.valid_attestations, 1);
+    assert!(!event.is_verified);
+
+    market.run(market.client.issue_attestation(second, authority, KYC_HASH, expires_at));
+    let event = market
+        .run(market.client.verify_merchant(authority, &[market.verifier, second]))
+        .event::<MerchantVerificationUpdated>();
+    assert_eq!(event.valid_attestations, 2);
+    assert!(event.is_verified);
+
+    // Removing a verifier drops their attestation from the count, once the
+    // threshold no longer needs them
+    bank::assert_error(
+        market.send(market.client.remove_verifier(market.authority, second)),
+        ErrorCode::InvalidThreshold,
+    );
+    market.run(market.client.set_attestation_threshold(market.authority, 1));
+    market.run(market.client.remove_verifier(market.authority, second));
+    let event = market
+        .run(market.client.verify_merchant(authority, &[market.verifier, second]))
+        .event::<MerchantVerificationUpdated>();
+    assert_eq!(event.valid_attestations, 1);
+}
+
+#[test]
+fn expired_attestations_stop_counting() {
+    let mut market = Marketplace::new();
+    let authority = market.merchant();
+    let expires_at = market.bank.now() + 10 * DAY;
+    market.run(market.client.issue_attestation(market.verifier, authority, KYC_HASH, expires_at));
+    market.run(market.client.verify_merchant(authority, &[market.verifier]));
+
+    market.bank.warp_to(expires_at);
+
+    let coupon = market.coupon(authority, spl_token::ID);
+    bank::assert_error(
+        market.list(authority, coupon, market.list_args(SOL)),
+        ErrorCode::MerchantNotVerified,
+    );
+    let event = market
+        .run(market.client.verify_merchant(authority, &[market.verifier]))
+        .event::<MerchantVerificationUpdated>();
+    assert!(!event.is_verified);
+}
+
+#[test]
+fn revoked_attestation_is_closed() {
+    let mut market = Marketplace::new();
+    let authority = market.verified_merchant();
+    let attestation = pda::attestation(&pda::merchant(&authority).0, &market.verifier).0;
+    let rent = market.bank.lamports(&attestation);
+    let before = market.bank.lamports(&market.verifier);
+
+    let meta = market.run(market.client.revoke_attestation(market.verifier, authority));
+
+    assert_eq!(meta.event::<AttestationRevoked>().attestation, attestation);
+    assert!(!market.bank.exists(&attestation));
+    assert_eq!(market.bank.lamports(&market.verifier), before + rent);
+    // The closed account is no longer owned by the program
+    let coupon = market.coupon(authority, spl_token::ID);
+    bank::assert_error(
+        market.list(authority, coupon, market.list_args(SOL)),
+        ErrorCode::Unauthorized,
+    );
+}
+
+#[test]
+fn delegates_are_recorded_until_revoked() {
+    let mut market = Marketplace::new();
+    let authority = market.verified_merchant();
+    let cashier = market.user();
+    let merchant = pda::merchant(&authority).0;
+    let delegate = pda::merchant_delegate(&merchant, &cashier).0;
+    let valid_until = market.bank.now() + 7 * DAY;
+
+    market.run(market.client.add_delegate(authority, cashier, valid_until));
+
+    let state: MerchantDelegate = market.bank.get(&delegate);
+    assert_eq!(state.merchant, merchant);
+    assert_eq!(state.delegate, cashier);
+    assert_eq!(state.valid_until, valid_until);
+    assert_eq!(state.total_redemptions, 0);
+
+    let rent = market.bank.lamports(&delegate);
+    let before = market.bank.lamports(&authority);
+    market.run(market.client.revoke_delegate(authority, cashier));
+    assert!(!market.bank.exists(&delegate));
+    assert_eq!(market.bank.lamports(&authority), before + rent);
+}
+
+#[test]
+fn legacy_merchant_is_migrated_to_the_kyc_layout() {
+    let mut market = Marketplace::new();
+    let authority = market.user();
+    let (merchant, bump) = pda::merchant(&authority);
+    let legacy = LegacyMerchant {
+        authority,
+        business_name: "Banana Ramen".to_string(),
+        business_type: "Restaurant".to_string(),
+        contact_email: "owner@banana-ramen.example".to_string(),
+        phone: "+1 555 0100".to_string(),
+        business_address: "1 Noodle Lane".to_string(),
+        tax_id: "12-3456789".to_string(),
+        is_verified: true,
+        total_listings: 3,
+        registration_date: market.bank.now() - 100 * DAY,
+        bump,
+    };
+    // Legacy accounts were allocated at their maximum size
+    let mut data = Merchant::DISCRIMINATOR.to_vec();
+    legacy.serialize(&mut data).unwrap();
+    data.resize(ANCHOR_DISCRIMINATOR + LegacyMerchant::INIT_SPACE, 0);
+    market.bank.set_program_data(merchant, data);
+    let new_len = ANCHOR_DISCRIMINATOR + Merchant::INIT_SPACE;
+    let freed = market.bank.lamports(&merchant) - market.bank.minimum_balance(new_len);
+    let before = market.bank.lamports(&authority);
+
+    market.run(market.client.migrate_merchant(
+        authority,
+        KYC_HASH,
+        "https://kyc.example/banana-ramen".to_string(),
+    ));
+
+    let account = market.bank.account(&merchant).unwrap();
+    assert_eq!(account.data.len(), new_len);
+    assert_eq!(market.bank.lamports(&authority), before + freed);
+    let state: Merchant = market.bank.get(&merchant);
+    assert_eq!(state.authority, authority);
+    assert_eq!(state.business_name, "Banana Ramen");
+    assert_eq!(state.kyc_hash, KYC_HASH);
+    assert!(!state.is_verified);
+    assert_eq!(state.total_listings, 3);
+    assert_eq!(state.registration_date, legacy.registration_date);
+    assert_eq!(state.bump, bump);
+
+    // Only once
+    bank::assert_error(
+        market.send(market.client.migrate_merchant(authority, KYC_HASH, String::new())),
+        ErrorCode::AccountAlreadyMigrated,
+    );
+}
@@ -0,0 +1,389 @@
+//! Migrating accounts written by the program before accounts carried a
+//! version, or in an older versioned layout. The fixtures are account data
+//! dumped from the deployed, unversioned program.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator, Space};
+use bank::marketplace::{Marketplace, DAY, KYC_HASH, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::CustodySynced;
+use monkey_dao::state::*;
+use monkey_dao::{ANCHOR_DISCRIMINATOR, STAKING_REWARD_RATE};
+use monkey_dao_client::{pda, Coupon};
+
+const CONFIG_V0: &[u8] = include_bytes!("fixtures/platform_config_v0.bin");
+const MERCHANT_V0: &[u8] = include_bytes!("fixtures/merchant_v0.bin");
+const LISTING_V0: &[u8] = include_bytes!("fixtures/listing_v0.bin");
+const POOL_V0: &[u8] = include_bytes!("fixtures/pool_v0.bin");
+const STAKE_ACCOUNT_V0: &[u8] = include_bytes!("fixtures/stake_account_v0.bin");
+
+fn decode<Old: AnchorDeserialize + Space>(fixture: &[u8]) -> Old {
+    assert_eq!(fixture.len(), ANCHOR_DISCRIMINATOR + Old::INIT_SPACE);
+    Old::deserialize(&mut &fixture[ANCHOR_DISCRIMINATOR..]).unwrap()
+}
+
+/// Places `fixture` at `key` and returns a funded payer together with the
+/// rent the migration to `A`'s current layout is expected to cost it.
+fn place<A: Discriminator + Space>(market: &mut Marketplace, key: Pubkey, fixture: &[u8]) -> (Pubkey, u64) {
+    assert_eq!(fixture[..ANCHOR_DISCRIMINATOR], A::DISCRIMINATOR);
+    market.bank.set_program_data(key, fixture.to_vec());
+    let cost = market.bank.minimum_balance(ANCHOR_DISCRIMINATOR + A::INIT_SPACE)
+        - market.bank.minimum_balance(fixture.len());
+    (market.user(), cost)
+}
+
+/// The account at `key` is in `A`'s current layout and is rent exempt.
+fn assert_migrated<A: AccountDeserialize + Space>(market: &Marketplace, key: &Pubkey) -> A {
+    let len = ANCHOR_DISCRIMINATOR + A::INIT_SPACE;
+    assert_eq!(market.bank.account(key).unwrap().data.len(), len);
+    assert_eq!(market.bank.lamports(key), market.bank.minimum_balance(len));
+    market.bank.get(key)
+}
+
+/// Rewrites `key` as `old`, padded to the size it was allocated at.
+fn downgrade<A: Discriminator, Old: AnchorSerialize + Space>(market: &mut Marketplace, key: Pubkey, old: Old) {
+    let mut data = A::DISCRIMINATOR.to_vec();
+    old.serialize(&mut data).unwrap();
+    data.resize(ANCHOR_DISCRIMINATOR + Old::INIT_SPACE, 0);
+    market.bank.set_program_data(key, data);
+}
+
+/// A listed coupon bought by a fresh user, returned with the merchant and
+/// the buyer.
+fn bought_coupon(market: &mut Marketplace) -> (Pubkey, Pubkey, Coupon) {
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap();
+    (merchant, buyer, coupon)
+}
+
+#[test]
+fn unversioned_config_is_migrated() {
+    let mut market = Marketplace::new();
+    let old: PlatformConfigV0 = decode(CONFIG_V0);
+    let config = pda::config().0;
+    let (payer, cost) = place::<PlatformConfig>(&mut market, config, CONFIG_V0);
+    let before = market.bank.lamports(&payer);
+
+    market.run(market.client.migrate_config(payer));
+
+    assert_eq!(market.bank.lamports(&payer), before - cost);
+    let state: PlatformConfig = assert_migrated(&market, &config);
+    assert_eq!(state.version, PlatformConfig::VERSION);
+    assert_eq!(state.authority, old.authority);
+    assert_eq!(state.monk_mint, old.monk_mint);
+    assert_eq!(state.platform_wallet, old.platform_wallet);
+    assert_eq!(state.platform_fee_bps, old.platform_fee_bps);
+    assert_eq!(state.staking_reward_rate, old.staking_reward_rate);
+    assert_eq!(state.bump, old.bump);
+    assert_eq!(state.guardian, Pubkey::default());
+    assert_eq!(state.paused, 0);
+    assert_eq!(state.referral_fee_share_bps, 0);
+    assert_eq!(state.referral_monk_bps, 0);
+
+    bank::assert_error(market.send(market.client.migrate_config(payer)), ErrorCode::AccountAlreadyMigrated);
+}
+
+#[test]
+fn unversioned_merchant_keeps_its_verification_for_the_same_kyc_bundle() {
+    let mut market = Marketplace::new();
+    let old: MerchantV0 = decode(MERCHANT_V0);
+    assert!(old.is_verified);
+    assert_eq!(old.kyc_hash, KYC_HASH);
+    let merchant = pda::merchant(&old.authority).0;
+    let (_, cost) = place::<Merchant>(&mut market, merchant, MERCHANT_V0);
+    market.bank.airdrop(&old.authority, SOL);
+    let before = market.bank.lamports(&old.authority);
+
+    market.run(market.client.migrate_merchant(old.authority, KYC_HASH, old.kyc_uri.clone()));
+
+    assert_eq!(market.bank.lamports(&old.authority), before - cost);
+    let state: Merchant = assert_migrated(&market, &merchant);
+    assert_eq!(state.version, Merchant::VERSION);
+    assert_eq!(state.authority, old.authority);
+    assert_eq!(state.business_name, old.business_name);
+    assert_eq!(state.business_type, old.business_type);
+    assert_eq!(state.kyc_uri, old.kyc_uri);
+    assert!(state.is_verified);
+    assert_eq!(state.total_listings, old.total_listings);
+    assert_eq!(state.total_redemptions, old.total_redemptions);
+    assert_eq!(state.total_value_honored, old.total_value_honored);
+    assert_eq!(state.registration_date, old.registration_date);
+    assert_eq!(state.bump, old.bump);
+
+    bank::assert_error(
+        market.send(market.client.migrate_merchant(old.authority, KYC_HASH, String::new())),
+        ErrorCode::AccountAlreadyMigrated,
+    );
+
+    // The migrated account is usable by the current instructions
+    market.run(market.client.update_merchant_kyc(old.authority, [9; 32], "https://kyc.example/2".to_string()));
+    let state: Merchant = market.bank.get(&merchant);
+    assert_eq!(state.kyc_hash, [9; 32]);
+    assert!(!state.is_verified);
+}
+
+#[test]
+fn unversioned_merchant_loses_its_verification_for_a_new_kyc_bundle() {
+    let mut market = Marketplace::new();
+    let old: MerchantV0 = decode(MERCHANT_V0);
+    let merchant = pda::merchant(&old.authority).0;
+    place::<Merchant>(&mut market, merchant, MERCHANT_V0);
+    market.bank.airdrop(&old.authority, SOL);
+
+    market.run(market.client.migrate_merchant(old.authority, [9; 32], "https://kyc.example/2".to_string()));
+
+    let state: Merchant = market.bank.get(&merchant);
+    assert_eq!(state.kyc_hash, [9; 32]);
+    assert_eq!(state.kyc_uri, "https://kyc.example/2");
+    assert!(!state.is_verified);
+}
+
+#[test]
+fn unversioned_merchant_is_migrated_by_its_authority_only() {
+    let mut market = Marketplace::new();
+    let old: MerchantV0 = decode(MERCHANT_V0);
+    place::<Merchant>(&mut market, pda::merchant(&old.authority).0, MERCHANT_V0);
+    let other = market.user();
+
+    // Another signer derives another merchant address, which holds nothing
+    bank::assert_error(
+        market.send(market.client.migrate_merchant(other, KYC_HASH, String::new())),
+        anchor_lang::error::ErrorCode::ConstraintOwner,
+    );
+}
+
+#[test]
+fn unversioned_listing_is_migrated() {
+    let mut market = Marketplace::new();
+    // The size the deployed program allocated every listing at
+    assert_eq!(LISTING_V0.len(), 688);
+    let old: ListingV0 = decode(LISTING_V0);
+    let listing = pda::listing(&old.nft_mint).0;
+    let (payer, cost) = place::<Listing>(&mut market, listing, LISTING_V0);
+    let before = market.bank.lamports(&payer);
+
+    market.run(market.client.migrate_listing(payer, listing));
+
+    assert_eq!(market.bank.lamports(&payer), before - cost);
+    let state: Listing = assert_migrated(&market, &listing);
+    assert_eq!(state.version, Listing::VERSION);
+    assert_eq!(state.nft_mint, old.nft_mint);
+    assert_eq!(state.seller, old.seller);
+    assert_eq!(state.merchant, old.merchant);
+    assert_eq!(state.current_price, old.current_price);
+    assert!(state.is_group_deal);
+    assert_eq!(state.deal_price_2, old.deal_price_2);
+    assert_eq!(state.deal_price_4, old.deal_price_4);
+    assert!(state.uses.use_method == UseMethod::Single);
+    assert_eq!(state.uses.total, 1);
+    assert_eq!(state.uses.remaining, 1);
+    assert_eq!(state.coupon_description, old.coupon_description);
+    assert_eq!(state.expiry_date, old.expiry_date);
+    assert_eq!(state.average_rating, old.average_rating);
+    assert_eq!(state.total_reviews, 1);
+    assert_eq!(state.bump, old.bump);
+    assert_eq!(state.min_tier, LoyaltyTier::Bronze);
+    assert_eq!(state.early_access_until, 0);
+    assert_eq!(state.gate, None);
+    assert_eq!(state.holder, old.seller);
+    assert_eq!(state.custody, if old.is_active { Custody::ListingVault } else { Custody::Wallet });
+
+    bank::assert_error(
+        market.send(market.client.migrate_listing(payer, listing)),
+        ErrorCode::AccountAlreadyMigrated,
+    );
+
+    // Reviews keep averaging over the ones given before the migration
+    let reviewer = market.user();
+    market.run(market.client.add_review(reviewer, old.nft_mint, 2, String::new()));
+    let state: Listing = market.bank.get(&listing);
+    assert_eq!(state.total_reviews, 2);
+    assert_eq!(state.average_rating, 60);
+}
+
+#[test]
+fn first_versioned_listing_is_migrated() {
+    let mut market = Marketplace::new();
+    let mut old: ListingV1 = decode::<ListingV0>(LISTING_V0).into();
+    old.is_active = false;
+    old.min_tier = LoyaltyTier::Gold;
+    let mut fixture = Listing::DISCRIMINATOR.to_vec();
+    old.serialize(&mut fixture).unwrap();
+    fixture.resize(ANCHOR_DISCRIMINATOR + ListingV1::INIT_SPACE, 0);
+    let listing = pda::listing(&old.nft_mint).0;
+    let (payer, cost) = place::<Listing>(&mut market, listing, &fixture);
+    let before = market.bank.lamports(&payer);
+
+    market.run(market.client.migrate_listing(payer, listing));
+
+    assert_eq!(market.bank.lamports(&payer), before - cost);
+    let state: Listing = assert_migrated(&market, &listing);
+    assert_eq!(state.version, Listing::VERSION);
+    assert_eq!(state.nft_mint, old.nft_mint);
+    assert_eq!(state.seller, old.seller);
+    assert_eq!(state.current_price, old.current_price);
+    assert_eq!(state.total_reviews, old.total_reviews);
+    assert_eq!(state.min_tier, LoyaltyTier::Gold);
+    // A delisted or sold coupon is taken to still be in the last seller's wallet
+    assert_eq!(state.holder, old.seller);
+    assert_eq!(state.custody, Custody::Wallet);
+
+    bank::assert_error(
+        market.send(market.client.migrate_listing(payer, listing)),
+        ErrorCode::AccountAlreadyMigrated,
+    );
+}
+
+#[test]
+fn listing_migrated_without_custody_is_synced_to_its_vault() {
+    let mut market = Marketplace::new();
+    let (_, buyer, coupon) = bought_coupon(&mut market);
+    market.run(market.client.stake_nft(buyer, coupon));
+    let listing = pda::listing(&coupon.mint).0;
+    let data = market.bank.account(&listing).unwrap().data.clone();
+    let mut old = ListingV1::deserialize(&mut &data[ANCHOR_DISCRIMINATOR..]).unwrap();
+    old.reserved = [0; 22];
+    downgrade::<Listing, ListingV1>(&mut market, listing, old);
+    market.run(market.client.migrate_listing(buyer, listing));
+    // Migration only knows the coupon is off the market
+    assert_eq!(market.listing(&coupon).custody, Custody::Wallet);
+
+    let event = market
+        .run(market.client.sync_custody(coupon, Some(pda::stake(&coupon.mint).0)))
+        .event::<CustodySynced>();
+    assert_eq!((event.holder, event.custody), (buyer, Custody::StakeVault));
+    let state = market.listing(&coupon);
+    assert_eq!((state.holder, state.custody), (buyer, Custody::StakeVault));
+    market.run(market.client.unstake_nft(buyer, coupon));
+}
+
+#[test]
+fn unversioned_pool_is_migrated() {
+    let mut market = Marketplace::new();
+    let old: PoolV0 = decode(POOL_V0);
+    let pool = pda::pool(&old.listing, &old.initiator).0;
+    let (payer, cost) = place::<Pool>(&mut market, pool, POOL_V0);
+    let before = market.bank.lamports(&payer);
+
+    market.run(market.client.migrate_pool(payer, pool));
+
+    assert_eq!(market.bank.lamports(&payer), before - cost);
+    let state: Pool = assert_migrated(&market, &pool);
+    assert_eq!(state.version, Pool::VERSION);
+    assert_eq!(state.listing, old.listing);
+    assert_eq!(state.initiator, old.initiator);
+    assert_eq!(state.pool_size, old.pool_size);
+    assert_eq!(state.current_participants, old.current_participants);
+    assert_eq!(state.price_per_person, old.price_per_person);
+    assert_eq!(state.total_deposited, old.total_deposited);
+    assert!(state.is_active);
+    assert_eq!(state.participants, old.participants);
+    assert_eq!(state.bump, old.bump);
+}
+
+#[test]
+fn unversioned_stake_account_is_migrated() {
+    let mut market = Marketplace::new();
+    // The size the deployed program allocated every stake account at
+    assert_eq!(STAKE_ACCOUNT_V0.len(), 98);
+    let old: StakeAccountV0 = decode(STAKE_ACCOUNT_V0);
+    let stake = pda::stake(&old.nft_mint).0;
+    let (payer, cost) = place::<StakeAccount>(&mut market, stake, STAKE_ACCOUNT_V0);
+    let before = market.bank.lamports(&payer);
+
+    market.run(market.client.migrate_stake_account(payer, stake));
+
+    assert_eq!(market.bank.lamports(&payer), before - cost);
+    let state: StakeAccount = assert_migrated(&market, &stake);
+    assert_eq!(state.version, StakeAccount::VERSION);
+    assert_eq!(state.nft_mint, old.nft_mint);
+    assert_eq!(state.owner, old.owner);
+    assert_eq!(state.staked_at, old.staked_at);
+    assert_eq!(state.last_claim, old.last_claim);
+    assert!(state.is_active);
+    assert_eq!(state.reward_weight_bps, 10_000);
+    assert_eq!(state.bump, old.bump);
+
+    bank::assert_error(
+        market.send(market.client.migrate_stake_account(payer, stake)),
+        ErrorCode::AccountAlreadyMigrated,
+    );
+}
+
+#[test]
+fn coupon_listed_before_uses_redeems_once() {
+    let mut market = Marketplace::new();
+    let (merchant, holder, coupon) = bought_coupon(&mut market);
+    let listing = pda::listing(&coupon.mint).0;
+    let state = market.listing(&coupon);
+    downgrade::<Listing, _>(&mut market, listing, ListingV0 {
+        nft_mint: state.nft_mint,
+        seller: state.seller,
+        merchant: state.merchant,
+        original_price: state.original_price,
+        current_price: state.current_price,
+        is_group_deal: state.is_group_deal,
+        deal_price_2: state.deal_price_2,
+        deal_price_4: state.deal_price_4,
+        deal_price_6: state.deal_price_6,
+        is_active: state.is_active,
+        is_used: state.is_used,
+        total_sales: state.total_sales,
+        coupon_description: state.coupon_description,
+        expiry_date: state.expiry_date,
+        created_at: state.created_at,
+        average_rating: state.average_rating,
+        total_reviews: state.total_reviews,
+        bump: state.bump,
+    });
+    let payer = market.user();
+    market.run(market.client.migrate_listing(payer, listing));
+
+    // Asking for more than one use still spends the only one
+    market.run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 3));
+    let state = market.listing(&coupon);
+    assert!(state.is_used);
+    assert_eq!(state.uses.remaining, 0);
+    assert_eq!(market.coupon_balance(&holder, &coupon), 0);
+}
+
+#[test]
+fn coupon_staked_before_weighting_earns_full_rewards() {
+    let mut market = Marketplace::new();
+    let (_, owner, coupon) = bought_coupon(&mut market);
+    market.run(market.client.stake_nft(owner, coupon));
+    let stake = pda::stake(&coupon.mint).0;
+    let state: StakeAccount = market.bank.get(&stake);
+    downgrade::<StakeAccount, _>(&mut market, stake, StakeAccountV0 {
+        nft_mint: state.nft_mint,
+        owner: state.owner,
+        staked_at: state.staked_at,
+        last_claim: state.last_claim,
+        total_rewards_claimed: state.total_rewards_claimed,
+        is_active: state.is_active,
+        bump: state.bump,
+    });
+    let payer = market.user();
+    market.run(market.client.migrate_stake_account(payer, stake));
+
+    market.bank.warp_forward(2 * DAY);
+    market.run(market.client.claim_staking_rewards(owner, coupon.mint));
+    let state: StakeAccount = market.bank.get(&stake);
+    assert_eq!(state.total_rewards_claimed, 2 * STAKING_REWARD_RATE);
+}
+
+#[test]
+fn only_program_accounts_can_be_migrated() {
+    let mut market = Marketplace::new();
+    let payer = market.user();
+    let wallet = market.user();
+
+    bank::assert_error(
+        market.send(market.client.migrate_listing(payer, wallet)),
+        anchor_lang::error::ErrorCode::ConstraintOwner,
+    );
+}
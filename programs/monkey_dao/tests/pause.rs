@@ -0,0 +1,172 @@
+//! Pausing subsystems: who may pause and unpause, what each flag stops and
+//! the withdrawal paths that stay open while paused.
+
+mod bank;
+
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CouponUnstaked, GuardianUpdated, PauseUpdated, StakingRewardsClaimed};
+use monkey_dao::state::PlatformConfig;
+use monkey_dao::{
+    PAUSE_ALL, PAUSE_LISTING, PAUSE_POOLS, PAUSE_REDEMPTION, PAUSE_REVIEWS, PAUSE_STAKING, PAUSE_TRADING,
+    STAKING_REWARD_RATE,
+};
+use monkey_dao_client::{pda, ListNftArgs};
+
+fn paused(market: &Marketplace) -> u8 {
+    market.bank.get::<PlatformConfig>(&pda::config().0).paused
+}
+
+#[test]
+fn each_flag_stops_its_subsystem() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let group_deal = ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        ..market.list_args(SOL)
+    };
+    let listed = market.listed_coupon(merchant, group_deal);
+    let held = market.listed_coupon(merchant, market.list_args(SOL));
+    let holder = market.user();
+    market.buy(holder, held).unwrap();
+    let unlisted = market.coupon(merchant, spl_token::ID);
+    let user = market.user();
+
+    let cases = [
+        (PAUSE_LISTING, market.client.list_nft(merchant, unlisted, market.list_args(SOL), &[market.verifier])),
+        (PAUSE_LISTING, market.client.relist_nft(holder, held, SOL)),
+        (PAUSE_TRADING, market.client.buy_nft(user, merchant, merchant, listed, None)),
+        (PAUSE_POOLS, market.client.create_pool(user, listed.mint, 2)),
+        (PAUSE_STAKING, market.client.stake_nft(holder, held)),
+        (PAUSE_REDEMPTION, market.client.redeem_nft(holder, merchant, merchant, 0, held, vec![1; 64], 1)),
+        (PAUSE_REVIEWS, market.client.add_review(user, listed.mint, 5, String::new())),
+    ];
+    for (flag, instruction) in cases {
+        market.run(market.client.set_paused(market.authority, flag));
+        assert_eq!(paused(&market), flag);
+        assert_error(market.send(instruction), ErrorCode::Paused);
+    }
+}
+
+#[test]
+fn withdrawals_stay_open_while_everything_is_paused() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let group_deal = ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        ..market.list_args(SOL)
+    };
+    let listed = market.listed_coupon(merchant, group_deal);
+    let initiator = market.user();
+    market.run(market.client.create_pool(initiator, listed.mint, 2));
+    let friend = market.user();
+    market.run(market.client.join_pool(friend, listed.mint, initiator, None));
+    let staked = market.listed_coupon(merchant, market.list_args(SOL));
+    let owner = market.user();
+    market.buy(owner, staked).unwrap();
+    market.run(market.client.stake_nft(owner, staked));
+    market.bank.warp_forward(3 * DAY);
+    let monk_before = market.monk_balance(&owner);
+
+    market.run(market.client.set_paused(market.authority, PAUSE_ALL));
+    assert_error(
+        market.send(market.client.claim_staking_rewards(owner, staked.mint)),
+        ErrorCode::Paused,
+    );
+
+    // Unstaking returns the coupon with what it earned before the pause
+    market.bank.warp_forward(2 * DAY);
+    let event = market.run(market.client.unstake_nft(owner, staked)).event::<CouponUnstaked>();
+    assert_eq!(event.rewards, 3 * STAKING_REWARD_RATE);
+    assert_eq!(market.monk_balance(&owner), monk_before + 3 * STAKING_REWARD_RATE);
+    assert_eq!(market.coupon_balance(&owner, &staked), 1);
+
+    market.run(market.client.cancel_pool(initiator, listed.mint));
+    let before = market.bank.lamports(&friend);
+    market.run(market.client.refund_pool_deposit(friend, listed.mint, initiator));
+    assert!(market.bank.lamports(&friend) > before + 6 * SOL / 10);
+    market.run(market.client.close_pool(initiator, listed));
+    market.run(market.client.delist_nft(merchant, listed));
+    assert_eq!(market.coupon_balance(&merchant, &listed), 1);
+}
+
+#[test]
+fn stakes_earn_nothing_while_staking_is_paused() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let owner = market.user();
+    let [claimed, unstaked] = [(); 2].map(|_| {
+        let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+        market.buy(owner, coupon).unwrap();
+        market.run(market.client.stake_nft(owner, coupon));
+        coupon
+    });
+
+    market.bank.warp_forward(2 * DAY);
+    market.run(market.client.set_paused(market.authority, PAUSE_STAKING));
+    market.bank.warp_forward(3 * DAY);
+    market.run(market.client.set_paused(market.authority, 0));
+    market.bank.warp_forward(DAY);
+
+    // Claimed or unstaked, only the two days before and one after count
+    let event = market
+        .run(market.client.claim_staking_rewards(owner, claimed.mint))
+        .event::<StakingRewardsClaimed>();
+    assert_eq!(event.rewards, 3 * STAKING_REWARD_RATE);
+    let event = market.run(market.client.unstake_nft(owner, unstaked)).event::<CouponUnstaked>();
+    assert_eq!(event.rewards, 3 * STAKING_REWARD_RATE);
+}
+
+#[test]
+fn guardian_can_pause_but_only_the_authority_unpauses() {
+    let mut market = Marketplace::new();
+    let guardian = market.user();
+    let stranger = market.user();
+
+    assert_error(
+        market.send(market.client.set_guardian(guardian, guardian)),
+        ErrorCode::NotPlatformAuthority,
+    );
+    let event = market
+        .run(market.client.set_guardian(market.authority, guardian))
+        .event::<GuardianUpdated>();
+    assert_eq!(event.guardian, guardian);
+
+    let event = market
+        .run(market.client.set_paused(guardian, PAUSE_TRADING))
+        .event::<PauseUpdated>();
+    assert_eq!(event.paused, PAUSE_TRADING);
+    assert_eq!(event.updated_by, guardian);
+    market.run(market.client.set_paused(guardian, PAUSE_TRADING | PAUSE_STAKING));
+    assert_eq!(paused(&market), PAUSE_TRADING | PAUSE_STAKING);
+
+    assert_error(
+        market.send(market.client.set_paused(guardian, PAUSE_STAKING)),
+        ErrorCode::NotPlatformAuthority,
+    );
+    assert_error(
+        market.send(market.client.set_paused(stranger, PAUSE_ALL)),
+        ErrorCode::NotPlatformAuthority,
+    );
+
+    market.run(market.client.set_paused(market.authority, 0));
+    assert_eq!(paused(&market), 0);
+}
+
+#[test]
+fn trading_resumes_once_unpaused() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+
+    market.run(market.client.set_paused(market.authority, PAUSE_TRADING));
+    assert_error(market.buy(buyer, coupon), ErrorCode::Paused);
+
+    market.run(market.client.set_paused(market.authority, 0));
+    market.buy(buyer, coupon).unwrap();
+    assert_eq!(market.coupon_balance(&buyer, &coupon), 1);
+}
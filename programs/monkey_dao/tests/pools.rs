@@ -0,0 +1,324 @@
+//! Group-buy pools: creation at the deal price, escrowed joins, settlement
+//! and the cancel and close paths.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{
+    PoolCancelled, PoolCompleted, PoolCouponClaimed, PoolCreated, PoolDepositRefunded, PoolJoined, PoolSettled,
+};
+use monkey_dao::state::{Custody, Merchant, Pool, PoolParticipant, UseMethod};
+use monkey_dao_client::{pda, Coupon, ListNftArgs};
+
+fn group_deal(market: &Marketplace) -> ListNftArgs {
+    ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        deal_price_4: Some(35 * SOL / 100),
+        deal_price_6: None,
+        ..market.list_args(SOL)
+    }
+}
+
+fn pool_key(coupon: &Coupon, initiator: &Pubkey) -> Pubkey {
+    pda::pool(&pda::listing(&coupon.mint).0, initiator).0
+}
+
+#[test]
+fn pool_fills_up_with_escrowed_deposits() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let friend = market.user();
+    let pool = pool_key(&coupon, &initiator);
+    let escrow = pda::escrow(&pool).0;
+
+    let event = market
+        .run(market.client.create_pool(initiator, coupon.mint, 2))
+        .event::<PoolCreated>();
+    assert_eq!(event.pool, pool);
+    assert_eq!(event.pool_size, 2);
+    assert_eq!(event.price_per_person, 6 * SOL / 10);
+
+    let meta = market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    assert_eq!(meta.event::<PoolJoined>().current_participants, 1);
+    assert!(meta.events::<PoolCompleted>().is_empty());
+
+    let friend_before = market.bank.lamports(&friend);
+    let meta = market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+    let joined = meta.event::<PoolJoined>();
+    assert_eq!(joined.participant, friend);
+    assert_eq!(joined.amount, 6 * SOL / 10);
+    assert_eq!(joined.current_participants, 2);
+    assert_eq!(meta.event::<PoolCompleted>().total_deposited, 12 * SOL / 10);
+
+    let participant_rent = market.bank.lamports(&pda::pool_participant(&pool, &friend).0);
+    assert_eq!(market.bank.lamports(&friend), friend_before - 6 * SOL / 10 - participant_rent);
+    assert_eq!(market.bank.lamports(&escrow), 12 * SOL / 10);
+
+    let state: Pool = market.bank.get(&pool);
+    assert!(state.is_active);
+    assert_eq!(state.current_participants, 2);
+    assert_eq!(state.total_deposited, 12 * SOL / 10);
+    assert_eq!(state.participants, vec![initiator, friend]);
+
+    let record: PoolParticipant = market.bank.get(&pda::pool_participant(&pool, &friend).0);
+    assert_eq!(record.pool, pool);
+    assert_eq!(record.amount_deposited, 6 * SOL / 10);
+    assert_eq!(record.joined_at, market.bank.now());
+}
+
+#[test]
+fn filled_pool_pays_the_seller_and_takes_the_coupon() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let friend = market.user();
+    let pool = pool_key(&coupon, &initiator);
+    let listing = pda::listing(&coupon.mint).0;
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+    market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+    let vault_rent = market.bank.lamports(&market.token_account(&listing, &coupon));
+    let before = market.bank.lamports(&merchant);
+
+    let event = market
+        .run(market.client.complete_pool(initiator, merchant, merchant, coupon))
+        .event::<PoolSettled>();
+
+    assert_eq!(event.pool, pool);
+    assert_eq!(event.seller, merchant);
+    assert_eq!(event.amount, 12 * SOL / 10);
+    assert_eq!(market.bank.lamports(&merchant), before + 12 * SOL / 10 + vault_rent);
+    assert_eq!(market.bank.lamports(&pda::escrow(&pool).0), 0);
+    assert_eq!(market.coupon_balance(&pool, &coupon), 1);
+    assert!(!market.bank.exists(&market.token_account(&listing, &coupon)));
+
+    let state: Pool = market.bank.get(&pool);
+    assert!(!state.is_active);
+    assert!(state.is_completed);
+    let listed = market.listing(&coupon);
+    assert!(!listed.is_active);
+    assert_eq!((listed.holder, listed.custody), (pool, Custody::Pool));
+    assert_eq!(listed.total_sales, 1);
+    let record: Merchant = market.bank.get(&pda::merchant(&merchant).0);
+    assert_eq!(record.total_sales_volume, 12 * SOL / 10);
+}
+
+#[test]
+fn initiator_claims_the_settled_pools_coupon() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let friend = market.user();
+    let pool = pool_key(&coupon, &initiator);
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+    market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+    market.run(market.client.complete_pool(initiator, merchant, merchant, coupon));
+    let pool_account = market.token_account(&pool, &coupon);
+    let account_rent = market.bank.lamports(&pool_account);
+    let before = market.bank.lamports(&initiator);
+
+    let event = market
+        .run(market.client.claim_pool_coupon(initiator, coupon))
+        .event::<PoolCouponClaimed>();
+
+    assert_eq!(event.pool, pool);
+    assert_eq!(event.initiator, initiator);
+    assert_eq!(market.coupon_balance(&initiator, &coupon), 1);
+    assert!(!market.bank.exists(&pool_account));
+    let initiator_account_rent = market.bank.lamports(&market.token_account(&initiator, &coupon));
+    assert_eq!(market.bank.lamports(&initiator), before + account_rent - initiator_account_rent);
+    let listed = market.listing(&coupon);
+    assert_eq!((listed.holder, listed.custody), (initiator, Custody::Wallet));
+}
+
+#[test]
+fn each_pool_size_uses_its_own_deal_price() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+
+    market.run(market.client.create_pool(initiator, coupon.mint, 4));
+
+    let state: Pool = market.bank.get(&pool_key(&coupon, &initiator));
+    assert_eq!(state.pool_size, 4);
+    assert_eq!(state.price_per_person, 35 * SOL / 100);
+}
+
+#[test]
+fn partly_redeemed_coupons_pool_at_their_remaining_share() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let args = ListNftArgs {
+        use_method: UseMethod::Multiple,
+        total_uses: 4,
+        ..group_deal(&market)
+    };
+    let coupon = market.listed_coupon(merchant, args);
+    let holder = market.user();
+    market.buy(holder, coupon).unwrap();
+    market.run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 1));
+    market.run(market.client.relist_nft(holder, coupon, SOL / 2));
+    let initiator = market.user();
+
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+
+    // Three of four uses are left, so the deal price is scaled to 3/4
+    let state: Pool = market.bank.get(&pool_key(&coupon, &initiator));
+    assert_eq!(state.price_per_person, 6 * SOL / 10 * 3 / 4);
+}
+
+#[test]
+fn empty_pool_can_be_cancelled_and_closed() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let pool = pool_key(&coupon, &initiator);
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+
+    let meta = market.run(market.client.cancel_pool(initiator, coupon.mint));
+    assert_eq!(meta.event::<PoolCancelled>().pool, pool);
+    let state: Pool = market.bank.get(&pool);
+    assert!(!state.is_active);
+
+    let pool_rent = market.bank.lamports(&pool);
+    let before = market.bank.lamports(&initiator);
+    market.run(market.client.close_pool(initiator, coupon));
+    assert!(!market.bank.exists(&pool));
+    assert_eq!(market.bank.lamports(&initiator), before + pool_rent);
+}
+
+#[test]
+fn cancelled_pool_refunds_its_participants() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let friend = market.user();
+    let pool = pool_key(&coupon, &initiator);
+    market.run(market.client.create_pool(initiator, coupon.mint, 4));
+    market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+
+    // Still open, nobody gets their deposit back
+    assert_error(
+        market.send(market.client.refund_pool_deposit(friend, coupon.mint, initiator)),
+        ErrorCode::PoolStillOpen,
+    );
+    market.run(market.client.cancel_pool(initiator, coupon.mint));
+    // Until everyone is refunded the pool stays open
+    assert_error(
+        market.send(market.client.close_pool(initiator, coupon)),
+        ErrorCode::PoolStillActive,
+    );
+
+    let record = pda::pool_participant(&pool, &friend).0;
+    let before = market.bank.lamports(&friend) + market.bank.lamports(&record);
+    let event = market
+        .run(market.client.refund_pool_deposit(friend, coupon.mint, initiator))
+        .event::<PoolDepositRefunded>();
+    assert_eq!(event.participant, friend);
+    assert_eq!(event.amount, 35 * SOL / 100);
+    assert_eq!(event.current_participants, 1);
+    assert_eq!(market.bank.lamports(&friend), before + 35 * SOL / 100);
+    assert!(!market.bank.exists(&record));
+    let state: Pool = market.bank.get(&pool);
+    assert_eq!(state.participants, vec![initiator]);
+    assert_eq!(state.total_deposited, 35 * SOL / 100);
+
+    market.run(market.client.refund_pool_deposit(initiator, coupon.mint, initiator));
+    assert_eq!(market.bank.lamports(&pda::escrow(&pool).0), 0);
+    market.run(market.client.close_pool(initiator, coupon));
+    assert!(!market.bank.exists(&pool));
+}
+
+#[test]
+fn pool_deposits_are_refunded_once_its_listing_is_gone() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let args = ListNftArgs {
+        expiry_date: market.bank.now() + DAY,
+        ..group_deal(&market)
+    };
+    let bought = market.listed_coupon(merchant, group_deal(&market));
+    let expiring = market.listed_coupon(merchant, args);
+    let initiator = market.user();
+    let friend = market.user();
+    for coupon in [bought, expiring] {
+        market.run(market.client.create_pool(initiator, coupon.mint, 2));
+        market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    }
+
+    // Bought outright before the pool filled
+    let buyer = market.user();
+    market.buy(buyer, bought).unwrap();
+    market.run(market.client.refund_pool_deposit(initiator, bought.mint, initiator));
+    let state: Pool = market.bank.get(&pool_key(&bought, &initiator));
+    assert!(!state.is_active);
+    assert_eq!(state.current_participants, 0);
+
+    // Expired before the pool filled, so nobody can join any more
+    market.bank.warp_forward(DAY);
+    assert_error(
+        market.send(market.client.join_pool(friend, expiring.mint, initiator, None)),
+        ErrorCode::CouponExpired,
+    );
+    market.run(market.client.refund_pool_deposit(initiator, expiring.mint, initiator));
+    market.run(market.client.close_pool(initiator, expiring));
+}
+
+#[test]
+fn settled_pool_and_its_participants_close() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let friend = market.user();
+    let pool = pool_key(&coupon, &initiator);
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+    market.run(market.client.join_pool(initiator, coupon.mint, initiator, None));
+    market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+
+    // Filled but unsettled, the escrow still holds the deposits
+    assert_error(
+        market.send(market.client.close_pool(initiator, coupon)),
+        ErrorCode::PoolStillActive,
+    );
+    assert_error(
+        market.send(market.client.close_pool_participant(friend, coupon.mint, initiator)),
+        ErrorCode::PoolStillActive,
+    );
+    market.run(market.client.complete_pool(initiator, merchant, merchant, coupon));
+
+    // Settled, the pool can't close while its token account holds the coupon
+    assert_error(
+        market.send(market.client.close_pool(initiator, coupon)),
+        ErrorCode::PoolHoldsCoupon,
+    );
+    market.run(market.client.claim_pool_coupon(initiator, coupon));
+
+    let record = pda::pool_participant(&pool, &initiator).0;
+    let record_rent = market.bank.lamports(&record);
+    let before = market.bank.lamports(&initiator);
+    market.run(market.client.close_pool_participant(initiator, coupon.mint, initiator));
+    assert!(!market.bank.exists(&record));
+    assert_eq!(market.bank.lamports(&initiator), before + record_rent);
+
+    market.run(market.client.close_pool(initiator, coupon));
+    assert!(!market.bank.exists(&pool));
+
+    // Records outlive the pool and can still be closed
+    let record = pda::pool_participant(&pool, &friend).0;
+    market.run(market.client.close_pool_participant(friend, coupon.mint, initiator));
+    assert!(!market.bank.exists(&record));
+}
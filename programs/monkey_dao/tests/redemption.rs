@@ -0,0 +1,270 @@
+//! Redeeming coupons, including punch cards, stored value, delegates, and
+//! what happens to coupons once they expire.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token_interface::Mint;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CouponRedeemed, ExpiredListingReclaimed, ExpiredRefundClaimed};
+use monkey_dao::state::{Custody, Merchant, MerchantDelegate, RedemptionReceipt, RefundPolicy, UseMethod};
+use monkey_dao_client::{pda, Coupon, ListNftArgs};
+
+/// A coupon listed by a fresh merchant and bought by a fresh user.
+/// Returns the merchant, the holder and the coupon.
+fn held_coupon(market: &mut Marketplace, args: ListNftArgs) -> (Pubkey, Pubkey, Coupon) {
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, args);
+    let holder = market.user();
+    market.buy(holder, coupon).unwrap();
+    (merchant, holder, coupon)
+}
+
+fn receipt(coupon: &Coupon, spent: u64) -> Pubkey {
+    pda::receipt(&pda::listing(&coupon.mint).0, spent).0
+}
+
+#[test]
+fn single_use_coupon_is_burned_on_redemption() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(2 * SOL);
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+    let holder_account = market.token_account(&holder, &coupon);
+    let account_rent = market.bank.lamports(&holder_account);
+    let before = market.bank.lamports(&holder);
+
+    // Single-use coupons ignore the amount and spend their one use
+    let meta = market.run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 5));
+
+    let listing = market.listing(&coupon);
+    assert!(listing.is_used);
+    assert_eq!(listing.uses.remaining, 0);
+    assert!(!market.bank.exists(&holder_account));
+    let mint: Mint = market.bank.get(&coupon.mint);
+    assert_eq!(mint.supply, 0);
+
+    let receipt_key = receipt(&coupon, 0);
+    let receipt_rent = market.bank.lamports(&receipt_key);
+    assert_eq!(market.bank.lamports(&holder), before + account_rent - receipt_rent);
+
+    let record: RedemptionReceipt = market.bank.get(&receipt_key);
+    assert_eq!(record.redeemer, holder);
+    assert_eq!(record.approver, merchant);
+    assert_eq!(record.index, 0);
+    assert_eq!(record.amount, 1);
+    assert_eq!(record.value, 2 * SOL);
+    assert_eq!(record.redeemed_at, market.bank.now());
+
+    let state: Merchant = market.bank.get(&pda::merchant(&merchant).0);
+    assert_eq!(state.total_redemptions, 1);
+    assert_eq!(state.total_value_honored, 2 * SOL);
+
+    let event = meta.event::<CouponRedeemed>();
+    assert_eq!(event.receipt, receipt_key);
+    assert_eq!(event.remaining, 0);
+    assert_eq!(event.value, 2 * SOL);
+}
+
+#[test]
+fn punch_card_spends_uses_until_empty() {
+    let mut market = Marketplace::new();
+    let args = ListNftArgs {
+        use_method: UseMethod::Multiple,
+        total_uses: 5,
+        ..market.list_args(SOL)
+    };
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+
+    let event = market
+        .run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 2))
+        .event::<CouponRedeemed>();
+    assert_eq!(event.amount, 2);
+    assert_eq!(event.value, 2 * SOL / 5);
+    assert_eq!(event.remaining, 3);
+    assert!(!market.listing(&coupon).is_used);
+    assert_eq!(market.coupon_balance(&holder, &coupon), 1);
+
+    let event = market
+        .run(market.client.redeem_nft(holder, merchant, merchant, 2, coupon, vec![1; 64], 3))
+        .event::<CouponRedeemed>();
+    assert_eq!(event.index, 1);
+    assert_eq!(event.receipt, receipt(&coupon, 2));
+    assert_eq!(event.value, 3 * SOL / 5);
+    assert_eq!(event.remaining, 0);
+    assert!(market.listing(&coupon).is_used);
+    assert_eq!(market.coupon_balance(&holder, &coupon), 0);
+
+    let state: Merchant = market.bank.get(&pda::merchant(&merchant).0);
+    assert_eq!(state.total_redemptions, 2);
+    assert_eq!(state.total_value_honored, SOL);
+}
+
+#[test]
+fn stored_value_voucher_honors_the_redeemed_share() {
+    let mut market = Marketplace::new();
+    // A 50.00 voucher tracked in cents, sold for 2 SOL
+    let args = ListNftArgs {
+        use_method: UseMethod::StoredValue,
+        total_uses: 5_000,
+        ..market.list_args(2 * SOL)
+    };
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+
+    let event = market
+        .run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 1_250))
+        .event::<CouponRedeemed>();
+
+    assert_eq!(event.remaining, 3_750);
+    assert_eq!(event.value, SOL / 2);
+    assert_eq!(market.listing(&coupon).uses.remaining, 3_750);
+}
+
+#[test]
+fn delegates_approve_redemptions_until_revoked() {
+    let mut market = Marketplace::new();
+    let args = ListNftArgs {
+        use_method: UseMethod::Multiple,
+        total_uses: 3,
+        ..market.list_args(SOL)
+    };
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+    let cashier = market.user();
+    let delegate = pda::merchant_delegate(&pda::merchant(&merchant).0, &cashier).0;
+    market.run(market.client.add_delegate(merchant, cashier, market.bank.now() + 7 * DAY));
+
+    let event = market
+        .run(market.client.redeem_nft(holder, merchant, cashier, 0, coupon, vec![1; 64], 1))
+        .event::<CouponRedeemed>();
+    assert_eq!(event.approver, cashier);
+
+    let record: RedemptionReceipt = market.bank.get(&receipt(&coupon, 0));
+    assert_eq!(record.approver, cashier);
+    let state: MerchantDelegate = market.bank.get(&delegate);
+    assert_eq!(state.total_redemptions, 1);
+
+    market.run(market.client.revoke_delegate(merchant, cashier));
+    assert!(!market.bank.exists(&delegate));
+    bank::assert_error(
+        market.send(market.client.redeem_nft(holder, merchant, cashier, 1, coupon, vec![1; 64], 1)),
+        anchor_lang::error::ErrorCode::AccountNotInitialized,
+    );
+
+    // The merchant can still approve in person
+    market.run(market.client.redeem_nft(holder, merchant, merchant, 1, coupon, vec![1; 64], 1));
+}
+
+#[test]
+fn terminals_redeem_different_coupons_at_once() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (merchant, first_holder, first) = held_coupon(&mut market, args);
+    let second = market.listed_coupon(merchant, market.list_args(SOL));
+    let second_holder = market.user();
+    market.buy(second_holder, second).unwrap();
+
+    // Both built before either lands, as two tills would
+    let redemptions = [(first_holder, first), (second_holder, second)]
+        .map(|(holder, coupon)| market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 1));
+    for redemption in redemptions {
+        market.run(redemption);
+    }
+
+    for coupon in [first, second] {
+        let record: RedemptionReceipt = market.bank.get(&receipt(&coupon, 0));
+        assert_eq!(record.nft_mint, coupon.mint);
+    }
+    let state: Merchant = market.bank.get(&pda::merchant(&merchant).0);
+    assert_eq!(state.total_redemptions, 2);
+}
+
+#[test]
+fn redeemed_listing_can_be_closed_by_the_merchant() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+    market.run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 1));
+    let listing = pda::listing(&coupon.mint).0;
+    let rent = market.bank.lamports(&listing);
+    let before = market.bank.lamports(&merchant);
+
+    market.run(market.client.close_listing(merchant, coupon.mint));
+
+    assert!(!market.bank.exists(&listing));
+    assert_eq!(market.bank.lamports(&merchant), before + rent);
+}
+
+#[test]
+fn expired_listing_is_returned_to_the_seller_by_any_crank() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let args = ListNftArgs {
+        expiry_date: market.bank.now() + DAY,
+        ..market.list_args(SOL)
+    };
+    let coupon = market.listed_coupon(merchant, args);
+    let listing = pda::listing(&coupon.mint).0;
+    let vault = market.token_account(&listing, &coupon);
+    let rent = market.bank.lamports(&vault);
+    let crank = market.user();
+    market.bank.warp_forward(DAY);
+    let before = market.bank.lamports(&merchant);
+
+    let meta = market.run(market.client.reclaim_expired_listing(crank, merchant, coupon));
+
+    assert_eq!(meta.event::<ExpiredListingReclaimed>().seller, merchant);
+    assert_eq!(market.coupon_balance(&merchant, &coupon), 1);
+    assert!(!market.bank.exists(&vault));
+    assert_eq!(market.bank.lamports(&merchant), before + rent);
+    // The listing stays for the coupon's refund claim and history
+    let listed = market.listing(&coupon);
+    assert!(!listed.is_active);
+    assert_eq!((listed.holder, listed.custody), (merchant, Custody::Wallet));
+}
+
+#[test]
+fn expired_coupon_is_refunded_from_the_escrow() {
+    let mut market = Marketplace::new();
+    let args = ListNftArgs {
+        use_method: UseMethod::Multiple,
+        total_uses: 4,
+        expiry_date: market.bank.now() + 10 * DAY,
+        ..market.list_args(2 * SOL)
+    };
+    let (merchant, holder, coupon) = held_coupon(&mut market, args);
+    let policy = pda::refund_policy(&pda::merchant(&merchant).0).0;
+    let escrow = pda::refund_escrow(&pda::merchant(&merchant).0).0;
+    market.run(market.client.set_refund_policy(merchant, 5_000, 7 * DAY));
+    market.run(market.client.fund_refund_escrow(merchant, 3 * SOL));
+    market.run(market.client.redeem_nft(holder, merchant, merchant, 0, coupon, vec![1; 64], 1));
+    market.bank.warp_forward(12 * DAY);
+    let holder_account = market.token_account(&holder, &coupon);
+    let before = market.bank.lamports(&holder) + market.bank.lamports(&holder_account);
+
+    let event = market
+        .run(market.client.claim_expired_refund(holder, merchant, coupon))
+        .event::<ExpiredRefundClaimed>();
+
+    // Half of the three uses still left on a 2 SOL coupon
+    assert_eq!(event.refund, 3 * SOL / 4);
+    assert_eq!(market.bank.lamports(&holder), before + 3 * SOL / 4);
+    assert_eq!(market.bank.lamports(&escrow), 3 * SOL - 3 * SOL / 4);
+    assert!(!market.bank.exists(&holder_account));
+    let listing = market.listing(&coupon);
+    assert!(listing.is_used);
+    assert_eq!(listing.uses.remaining, 0);
+    let state: RefundPolicy = market.bank.get(&policy);
+    assert_eq!(state.total_refunded, 3 * SOL / 4);
+
+    // The merchant takes back what was not claimed once the window closes
+    assert_error(
+        market.send(market.client.withdraw_refund_escrow(merchant, 3 * SOL - 3 * SOL / 4)),
+        ErrorCode::RefundEscrowLocked,
+    );
+    market.bank.warp_forward(6 * DAY);
+    let before = market.bank.lamports(&merchant);
+    market.run(market.client.withdraw_refund_escrow(merchant, 3 * SOL - 3 * SOL / 4));
+    assert!(!market.bank.exists(&escrow));
+    assert_eq!(market.bank.lamports(&merchant), before + 3 * SOL - 3 * SOL / 4);
+}
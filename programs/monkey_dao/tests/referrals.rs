@@ -0,0 +1,193 @@
+//! Referrals: the referrer's share of the platform fee and its MONK bonus on
+//! purchases and settled pool shares, per-referrer stats and claiming.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CouponPurchased, ReferralRewarded, ReferralRewardsClaimed, ReferrerRegistered};
+use monkey_dao::state::{PlatformConfig, Pool, PoolReferral, Referrer, UserStats};
+use monkey_dao::PLATFORM_FEE_BPS;
+use monkey_dao_client::{pda, ListNftArgs};
+
+/// 40% of the platform fee and 5% of the amount in MONK.
+fn with_referrer(market: &mut Marketplace) -> Pubkey {
+    market.run(market.client.set_referral_rewards(market.authority, 4_000, 500));
+    let referrer = market.user();
+    let event = market
+        .run(market.client.register_referrer(referrer))
+        .event::<ReferrerRegistered>();
+    assert_eq!(event.referrer, pda::referrer(&referrer).0);
+    referrer
+}
+
+fn group_deal(market: &Marketplace) -> ListNftArgs {
+    ListNftArgs {
+        is_group_deal: true,
+        deal_price_2: Some(6 * SOL / 10),
+        ..market.list_args(SOL)
+    }
+}
+
+fn referrer_state(market: &Marketplace, authority: &Pubkey) -> Referrer {
+    market.bank.get(&pda::referrer(authority).0)
+}
+
+#[test]
+fn referred_purchase_shares_the_platform_fee() {
+    let mut market = Marketplace::new();
+    let referrer = with_referrer(&mut market);
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    let referrer_account = pda::referrer(&referrer).0;
+    let account_before = market.bank.lamports(&referrer_account);
+    let wallet_before = market.bank.lamports(&market.platform_wallet());
+
+    let meta = market.run(market.client.buy_nft(buyer, merchant, merchant, coupon, Some(referrer)));
+
+    let platform_fee = SOL * PLATFORM_FEE_BPS / 10_000;
+    let fee_share = platform_fee * 4 / 10;
+    let rewarded = meta.event::<ReferralRewarded>();
+    assert_eq!(rewarded.buyer, buyer);
+    assert_eq!(rewarded.amount, SOL);
+    assert_eq!(rewarded.fee_share, fee_share);
+    assert_eq!(rewarded.monk_reward, SOL * 5 / 100);
+    assert_eq!(meta.event::<CouponPurchased>().platform_fee, platform_fee);
+    assert_eq!(market.bank.lamports(&referrer_account), account_before + fee_share);
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), wallet_before + platform_fee - fee_share);
+
+    let state = referrer_state(&market, &referrer);
+    assert_eq!(state.authority, referrer);
+    assert_eq!(state.total_referrals, 1);
+    assert_eq!(state.total_referred_volume, SOL);
+    assert_eq!(state.total_fees_earned, fee_share);
+    assert_eq!(state.total_monk_earned, SOL * 5 / 100);
+    assert_eq!(state.pending_monk, SOL * 5 / 100);
+    assert_eq!(state.version, Referrer::VERSION);
+}
+
+#[test]
+fn referred_pool_share_earns_the_monk_bonus_once_the_pool_settles() {
+    let mut market = Marketplace::new();
+    let referrer = with_referrer(&mut market);
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, group_deal(&market));
+    let initiator = market.user();
+    let friend = market.user();
+    market.run(market.client.create_pool(initiator, coupon.mint, 2));
+
+    // Joining only records the referral
+    let meta = market.run(market.client.join_pool(initiator, coupon.mint, initiator, Some(referrer)));
+    assert!(meta.events::<ReferralRewarded>().is_empty());
+    assert_eq!(referrer_state(&market, &referrer).total_referrals, 0);
+    let pool = pda::pool(&pda::listing(&coupon.mint).0, &initiator).0;
+    let record: PoolReferral = market.bank.get(&pda::pool_referral(&pool, &initiator).0);
+    assert_eq!(record.referrer, pda::referrer(&referrer).0);
+    market.run(market.client.join_pool(friend, coupon.mint, initiator, None));
+
+    let rewarded = market
+        .run(market.client.complete_referred_pool(
+            initiator,
+            merchant,
+            merchant,
+            coupon,
+            &[(initiator, referrer)],
+        ))
+        .event::<ReferralRewarded>();
+
+    assert_eq!(rewarded.buyer, initiator);
+    assert_eq!(rewarded.amount, 6 * SOL / 10);
+    assert_eq!(rewarded.fee_share, 0);
+    assert_eq!(rewarded.monk_reward, 3 * SOL / 100);
+    let state = referrer_state(&market, &referrer);
+    assert_eq!(state.total_referrals, 1);
+    assert_eq!(state.total_referred_volume, 6 * SOL / 10);
+    assert_eq!(state.pending_monk, 3 * SOL / 100);
+
+    // A participant refunded before settlement takes their referral record along
+    let other = market.listed_coupon(merchant, group_deal(&market));
+    market.run(market.client.create_pool(initiator, other.mint, 2));
+    market.run(market.client.join_pool(friend, other.mint, initiator, Some(referrer)));
+    market.run(market.client.cancel_pool(initiator, other.mint));
+    market.run(market.client.refund_pool_deposit(friend, other.mint, initiator));
+    let other_pool = pda::pool(&pda::listing(&other.mint).0, &initiator).0;
+    assert!(!market.bank.exists(&pda::pool_referral(&other_pool, &friend).0));
+    assert_eq!(market.bank.get::<Pool>(&other_pool).referrals, 0);
+}
+
+#[test]
+fn referrer_claims_fee_shares_and_monk() {
+    let mut market = Marketplace::new();
+    let referrer = with_referrer(&mut market);
+    let merchant = market.verified_merchant();
+    for _ in 0..2 {
+        let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+        let buyer = market.user();
+        market.run(market.client.buy_nft(buyer, merchant, merchant, coupon, Some(referrer)));
+    }
+    let referrer_account = pda::referrer(&referrer).0;
+    let before = market.bank.lamports(&referrer);
+
+    let event = market
+        .run(market.client.claim_referral_rewards(referrer))
+        .event::<ReferralRewardsClaimed>();
+
+    let fee_share = 2 * (SOL * PLATFORM_FEE_BPS / 10_000) * 4 / 10;
+    assert_eq!(event.lamports, fee_share);
+    assert_eq!(event.monk, 2 * SOL * 5 / 100);
+    assert_eq!(market.monk_balance(&referrer), 2 * SOL * 5 / 100);
+    let monk_account = pda::associated_token(&referrer, &market.client.monk_mint, &market.client.monk_token_program);
+    let stats_account = pda::user_stats(&referrer).0;
+    assert_eq!(
+        market.bank.lamports(&referrer),
+        before + fee_share - market.bank.lamports(&monk_account) - market.bank.lamports(&stats_account)
+    );
+    let len = market.bank.account(&referrer_account).unwrap().data.len();
+    assert_eq!(market.bank.lamports(&referrer_account), market.bank.minimum_balance(len));
+
+    // Lifetime stats survive the claim
+    let state = referrer_state(&market, &referrer);
+    assert_eq!(state.pending_monk, 0);
+    assert_eq!(state.total_fees_earned, fee_share);
+    assert_eq!(state.total_monk_earned, 2 * SOL * 5 / 100);
+    assert_eq!(state.total_referrals, 2);
+    let stats: UserStats = market.bank.get(&stats_account);
+    assert_eq!(stats.total_monk_earned, 2 * SOL * 5 / 100);
+}
+
+#[test]
+fn rewards_are_set_by_the_platform_authority() {
+    let mut market = Marketplace::new();
+    let stranger = market.user();
+
+    assert_error(
+        market.send(market.client.set_referral_rewards(stranger, 4_000, 500)),
+        ErrorCode::NotPlatformAuthority,
+    );
+    market.run(market.client.set_referral_rewards(market.authority, 2_500, 100));
+
+    let config: PlatformConfig = market.bank.get(&pda::config().0);
+    assert_eq!(config.referral_fee_share_bps, 2_500);
+    assert_eq!(config.referral_monk_bps, 100);
+}
+
+#[test]
+fn purchases_without_a_referrer_pay_the_platform_in_full() {
+    let mut market = Marketplace::new();
+    with_referrer(&mut market);
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let buyer = market.user();
+    let wallet_before = market.bank.lamports(&market.platform_wallet());
+
+    let meta = market.buy(buyer, coupon).unwrap();
+
+    assert!(meta.events::<ReferralRewarded>().is_empty());
+    assert_eq!(
+        market.bank.lamports(&market.platform_wallet()),
+        wallet_before + SOL * PLATFORM_FEE_BPS / 10_000
+    );
+}
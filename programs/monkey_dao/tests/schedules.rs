@@ -0,0 +1,140 @@
+//! Scheduled listings: a start time before which nothing sells, and flash
+//! sales whose price holds only within their window.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::assert_error;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::events::{CouponPurchased, ListingScheduled};
+use monkey_dao::state::ListingSchedule;
+use monkey_dao::PLATFORM_FEE_BPS;
+use monkey_dao_client::{pda, Coupon, FlashSale};
+
+/// A 1 SOL listing of `merchant` with the given schedule.
+fn scheduled(market: &mut Marketplace, merchant: Pubkey, start_time: i64, flash_sales: Vec<FlashSale>) -> Coupon {
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    market.run(market.client.set_listing_schedule(merchant, coupon.mint, start_time, flash_sales));
+    coupon
+}
+
+fn buy_price(market: &mut Marketplace, coupon: Coupon) -> u64 {
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap().event::<CouponPurchased>().price
+}
+
+#[test]
+fn listing_sells_from_its_start_time() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let start = market.bank.now() + DAY;
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    let event = market
+        .run(market.client.set_listing_schedule(merchant, coupon.mint, start, vec![]))
+        .event::<ListingScheduled>();
+    assert_eq!(event.listing, pda::listing(&coupon.mint).0);
+    assert_eq!(event.start_time, start);
+
+    let buyer = market.user();
+    assert_error(market.buy(buyer, coupon), ErrorCode::ListingNotStarted);
+    market.bank.warp_to(start);
+    market.buy(buyer, coupon).unwrap();
+}
+
+#[test]
+fn flash_sale_price_holds_within_its_window() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let now = market.bank.now();
+    let sales = vec![
+        FlashSale { starts_at: now + DAY, ends_at: now + 2 * DAY, price: SOL / 2 },
+        FlashSale { starts_at: now + 3 * DAY, ends_at: now + 4 * DAY, price: 3 * SOL / 4 },
+    ];
+    let coupons: Vec<Coupon> = (0..4).map(|_| scheduled(&mut market, merchant, 0, sales.clone())).collect();
+
+    assert_eq!(buy_price(&mut market, coupons[0]), SOL);
+    market.bank.warp_to(now + DAY);
+    let buyer = market.user();
+    let event = market.buy(buyer, coupons[1]).unwrap().event::<CouponPurchased>();
+    assert_eq!(event.price, SOL / 2);
+    assert_eq!(event.platform_fee, SOL / 2 * PLATFORM_FEE_BPS / 10_000);
+    // The first sale ends, the next has not begun
+    market.bank.warp_to(now + 2 * DAY);
+    assert_eq!(buy_price(&mut market, coupons[2]), SOL);
+    market.bank.warp_to(now + 3 * DAY + 1);
+    assert_eq!(buy_price(&mut market, coupons[3]), 3 * SOL / 4);
+
+    let schedule: ListingSchedule = market.bank.get(&pda::listing_schedule(&pda::listing(&coupons[3].mint).0).0);
+    assert_eq!(schedule.seller, merchant);
+    assert_eq!(schedule.flash_sales, sales);
+    assert_eq!(schedule.version, ListingSchedule::VERSION);
+}
+
+#[test]
+fn resales_ignore_the_merchants_schedule() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let now = market.bank.now();
+    let sale = FlashSale { starts_at: now, ends_at: now + DAY, price: SOL / 2 };
+    let coupon = scheduled(&mut market, merchant, now, vec![sale]);
+    let buyer = market.user();
+    market.buy(buyer, coupon).unwrap();
+
+    market.run(market.client.relist_nft(buyer, coupon, 9 * SOL / 10));
+
+    assert_eq!(buy_price(&mut market, coupon), 9 * SOL / 10);
+}
+
+#[test]
+fn rescheduling_replaces_the_schedule() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let start = market.bank.now() + DAY;
+    let coupon = scheduled(&mut market, merchant, start, vec![]);
+
+    market.run(market.client.set_listing_schedule(merchant, coupon.mint, 0, vec![]));
+
+    assert_eq!(buy_price(&mut market, coupon), SOL);
+}
+
+#[test]
+fn schedules_are_validated() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let now = market.bank.now();
+    let expiry = market.listing(&coupon).expiry_date;
+    let sale = |starts_at, ends_at, price| FlashSale { starts_at, ends_at, price };
+    let invalid = [
+        (expiry, vec![]),
+        (now, vec![sale(now + DAY, now + DAY, SOL / 2)]),
+        (now, vec![sale(now, now + DAY, SOL)]),
+        (now, vec![sale(now, now + DAY, 0)]),
+        (now, vec![sale(now, expiry + 1, SOL / 2)]),
+        (now + DAY, vec![sale(now, now + 2 * DAY, SOL / 2)]),
+        (now, vec![sale(now, now + 2 * DAY, SOL / 2), sale(now + DAY, now + 3 * DAY, SOL / 2)]),
+        (now, (0..5).map(|day| sale(now + day * DAY, now + (day + 1) * DAY, SOL / 2)).collect()),
+    ];
+
+    for (start_time, flash_sales) in invalid {
+        assert_error(
+            market.send(market.client.set_listing_schedule(merchant, coupon.mint, start_time, flash_sales)),
+            ErrorCode::InvalidSchedule,
+        );
+    }
+}
+
+#[test]
+fn only_the_listing_merchant_schedules_it() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let other = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+
+    assert_error(
+        market.send(market.client.set_listing_schedule(other, coupon.mint, 0, vec![])),
+        ErrorCode::Unauthorized,
+    );
+}
@@ -0,0 +1,174 @@
+//! Staking coupons for MONK, with the clock warped to accrue rewards.
+
+mod bank;
+
+use anchor_lang::prelude::Pubkey;
+use bank::marketplace::{Marketplace, DAY, SOL};
+use monkey_dao::events::{CouponStaked, CouponUnstaked, StakingRewardsClaimed};
+use monkey_dao::state::{StakeAccount, UseMethod, UserStats};
+use monkey_dao::{PURCHASE_REWARD_BPS, STAKING_REWARD_RATE};
+use monkey_dao_client::{pda, Coupon, ListNftArgs};
+
+/// A coupon bought by a fresh user, who then has `UserStats` and a MONK
+/// account. Returns the merchant, the owner and the coupon.
+fn bought_coupon(market: &mut Marketplace, args: ListNftArgs) -> (Pubkey, Pubkey, Coupon) {
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, args);
+    let owner = market.user();
+    market.buy(owner, coupon).unwrap();
+    (merchant, owner, coupon)
+}
+
+#[test]
+fn staking_moves_the_coupon_into_the_stake_vault() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (_, owner, coupon) = bought_coupon(&mut market, args);
+    let stake = pda::stake(&coupon.mint).0;
+
+    let event = market.run(market.client.stake_nft(owner, coupon)).event::<CouponStaked>();
+
+    assert_eq!(event.stake_account, stake);
+    assert_eq!(event.reward_weight_bps, 10_000);
+    assert_eq!(market.coupon_balance(&owner, &coupon), 0);
+    assert_eq!(market.coupon_balance(&stake, &coupon), 1);
+
+    let state: StakeAccount = market.bank.get(&stake);
+    assert!(state.is_active);
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.staked_at, market.bank.now());
+    assert_eq!(state.last_claim, market.bank.now());
+
+    let stats: UserStats = market.bank.get(&pda::user_stats(&owner).0);
+    assert_eq!(stats.nfts_staked, 1);
+}
+
+#[test]
+fn rewards_accrue_per_whole_day() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (_, owner, coupon) = bought_coupon(&mut market, args);
+    let purchase_reward = SOL * PURCHASE_REWARD_BPS / 10_000;
+    market.run(market.client.stake_nft(owner, coupon));
+
+    // A day and a half only pays out the completed day
+    market.bank.warp_forward(DAY + DAY / 2);
+    let event = market
+        .run(market.client.claim_staking_rewards(owner, coupon.mint))
+        .event::<StakingRewardsClaimed>();
+    assert_eq!(event.rewards, STAKING_REWARD_RATE);
+    assert_eq!(market.monk_balance(&owner), purchase_reward + STAKING_REWARD_RATE);
+
+    // The clock restarts at the claim, so the leftover half day is not carried over
+    let state: StakeAccount = market.bank.get(&pda::stake(&coupon.mint).0);
+    assert_eq!(state.last_claim, market.bank.now());
+
+    market.bank.warp_forward(3 * DAY);
+    let event = market
+        .run(market.client.claim_staking_rewards(owner, coupon.mint))
+        .event::<StakingRewardsClaimed>();
+    assert_eq!(event.rewards, 3 * STAKING_REWARD_RATE);
+    assert_eq!(event.total_rewards_claimed, 4 * STAKING_REWARD_RATE);
+
+    let stats: UserStats = market.bank.get(&pda::user_stats(&owner).0);
+    assert_eq!(stats.total_monk_earned, purchase_reward + 4 * STAKING_REWARD_RATE);
+}
+
+#[test]
+fn unstaking_pays_pending_rewards_and_closes_the_stake() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (_, owner, coupon) = bought_coupon(&mut market, args);
+    let stake = pda::stake(&coupon.mint).0;
+    let stake_vault = market.token_account(&stake, &coupon);
+    market.run(market.client.stake_nft(owner, coupon));
+    market.bank.warp_forward(2 * DAY);
+    market.run(market.client.claim_staking_rewards(owner, coupon.mint));
+    market.bank.warp_forward(5 * DAY + 10);
+    let rent = market.bank.lamports(&stake) + market.bank.lamports(&stake_vault);
+    let before = market.bank.lamports(&owner);
+
+    let event = market.run(market.client.unstake_nft(owner, coupon)).event::<CouponUnstaked>();
+
+    assert_eq!(event.rewards, 5 * STAKING_REWARD_RATE);
+    assert_eq!(event.total_rewards_claimed, 7 * STAKING_REWARD_RATE);
+    assert_eq!(market.coupon_balance(&owner, &coupon), 1);
+    assert!(!market.bank.exists(&stake));
+    assert!(!market.bank.exists(&stake_vault));
+    assert_eq!(market.bank.lamports(&owner), before + rent);
+
+    let stats: UserStats = market.bank.get(&pda::user_stats(&owner).0);
+    assert_eq!(stats.nfts_staked, 0);
+
+    // The coupon can be staked again from scratch
+    market.run(market.client.stake_nft(owner, coupon));
+    let state: StakeAccount = market.bank.get(&stake);
+    assert_eq!(state.total_rewards_claimed, 0);
+}
+
+#[test]
+fn unstaking_early_returns_the_coupon_without_rewards() {
+    let mut market = Marketplace::new();
+    let args = market.list_args(SOL);
+    let (_, owner, coupon) = bought_coupon(&mut market, args);
+    let monk_before = market.monk_balance(&owner);
+    market.run(market.client.stake_nft(owner, coupon));
+    market.bank.warp_forward(DAY - 1);
+
+    let event = market.run(market.client.unstake_nft(owner, coupon)).event::<CouponUnstaked>();
+
+    assert_eq!(event.rewards, 0);
+    assert_eq!(market.monk_balance(&owner), monk_before);
+    assert_eq!(market.coupon_balance(&owner, &coupon), 1);
+}
+
+#[test]
+fn partly_redeemed_coupons_earn_in_proportion() {
+    let mut market = Marketplace::new();
+    let args = ListNftArgs {
+        use_method: UseMethod::Multiple,
+        total_uses: 4,
+        ..market.list_args(SOL)
+    };
+    let (merchant, owner, coupon) = bought_coupon(&mut market, args);
+    market.run(market.client.redeem_nft(owner, merchant, merchant, 0, coupon, vec![1; 64], 1));
+
+    let event = market.run(market.client.stake_nft(owner, coupon)).event::<CouponStaked>();
+    assert_eq!(event.reward_weight_bps, 7_500);
+
+    market.bank.warp_forward(2 * DAY);
+    let event = market
+        .run(market.client.claim_staking_rewards(owner, coupon.mint))
+        .event::<StakingRewardsClaimed>();
+    assert_eq!(event.rewards, 2 * STAKING_REWARD_RATE * 3 / 4);
+}
+
+#[test]
+fn leftover_inactive_stake_accounts_can_be_closed() {
+    let mut market = Marketplace::new();
+    let owner = market.user();
+    let nft_mint = Pubkey::new_unique();
+    let (stake, bump) = pda::stake(&nft_mint);
+    // Earlier program versions left the stake account open after unstaking
+    let leftover = StakeAccount {
+        nft_mint,
+        owner,
+        staked_at: market.bank.now() - 10 * DAY,
+        last_claim: market.bank.now() - 5 * DAY,
+        total_rewards_claimed: 5 * STAKING_REWARD_RATE,
+        is_active: false,
+        reward_weight_bps: 10_000,
+        bump,
+        version: StakeAccount::VERSION,
+        paused_secs_at_claim: 0,
+        reserved: [0; 56],
+    };
+    market.bank.set_program_account(stake, &leftover);
+    let rent = market.bank.lamports(&stake);
+    let before = market.bank.lamports(&owner);
+
+    market.run(market.client.close_stake_account(owner, nft_mint));
+
+    assert!(!market.bank.exists(&stake));
+    assert_eq!(market.bank.lamports(&owner), before + rent);
+}
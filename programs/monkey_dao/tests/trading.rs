@@ -0,0 +1,207 @@
+//! Listing, buying and reviewing coupons, with the fee and MONK reward math
+//! checked against balances.
+
+mod bank;
+
+use anchor_spl::token_interface::Mint;
+use bank::marketplace::{Marketplace, SOL};
+use monkey_dao::events::{CouponDelisted, CouponListed, CouponPurchased, CouponRelisted, ReviewAdded};
+use monkey_dao::state::{Custody, Merchant, Review, UserStats};
+use monkey_dao::{PLATFORM_FEE_BPS, PURCHASE_REWARD_BPS};
+use monkey_dao_client::{pda, Coupon};
+
+#[test]
+fn listing_moves_the_coupon_into_the_vault() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.coupon(merchant, spl_token::ID);
+    let args = market.list_args(2 * SOL);
+    let expiry_date = args.expiry_date;
+
+    let meta = market.list(merchant, coupon, args).unwrap();
+
+    let listing_key = pda::listing(&coupon.mint).0;
+    let listing = market.listing(&coupon);
+    assert!(listing.is_active);
+    assert_eq!(listing.seller, merchant);
+    assert_eq!(listing.merchant, pda::merchant(&merchant).0);
+    assert_eq!(listing.original_price, 2 * SOL);
+    assert_eq!(listing.current_price, 2 * SOL);
+    assert_eq!(listing.expiry_date, expiry_date);
+    assert_eq!(market.coupon_balance(&merchant, &coupon), 0);
+    assert_eq!(market.coupon_balance(&listing_key, &coupon), 1);
+
+    let merchant_account: Merchant = market.bank.get(&pda::merchant(&merchant).0);
+    assert_eq!(merchant_account.total_listings, 1);
+
+    let event = meta.event::<CouponListed>();
+    assert_eq!(event.listing, listing_key);
+    assert_eq!(event.price, 2 * SOL);
+    assert_eq!(event.remaining_uses, 1);
+}
+
+#[test]
+fn purchase_splits_the_price_and_mints_the_reward() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(2 * SOL));
+    let buyer = market.user();
+    let listing_key = pda::listing(&coupon.mint).0;
+    let vault = market.token_account(&listing_key, &coupon);
+    let vault_rent = market.bank.lamports(&vault);
+    let seller_before = market.bank.lamports(&merchant);
+    let platform_before = market.bank.lamports(&market.platform_wallet());
+
+    let meta = market.buy(buyer, coupon).unwrap();
+
+    let platform_fee = 2 * SOL * PLATFORM_FEE_BPS / 10_000;
+    let monk_reward = 2 * SOL * PURCHASE_REWARD_BPS / 10_000;
+    assert_eq!(platform_fee, 50_000_000);
+    assert_eq!(monk_reward, 200_000_000);
+
+    // The seller also gets the vault rent back when it is closed
+    assert_eq!(market.bank.lamports(&merchant), seller_before + 2 * SOL - platform_fee + vault_rent);
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), platform_before + platform_fee);
+    assert!(!market.bank.exists(&vault));
+    assert_eq!(market.coupon_balance(&buyer, &coupon), 1);
+    assert_eq!(market.monk_balance(&buyer), monk_reward);
+
+    let monk_mint: Mint = market.bank.get(&market.client.monk_mint);
+    assert_eq!(monk_mint.supply, monk_reward);
+
+    let listing = market.listing(&coupon);
+    assert!(!listing.is_active);
+    assert_eq!(listing.holder, buyer);
+    assert_eq!(listing.custody, Custody::Wallet);
+    assert_eq!(listing.total_sales, 1);
+
+    let stats: UserStats = market.bank.get(&pda::user_stats(&buyer).0);
+    assert_eq!(stats.user, buyer);
+    assert_eq!(stats.total_purchases, 1);
+    assert_eq!(stats.total_monk_earned, monk_reward);
+
+    let event = meta.event::<CouponPurchased>();
+    assert_eq!(event.seller, merchant);
+    assert_eq!(event.buyer, buyer);
+    assert_eq!(event.price, 2 * SOL);
+    assert_eq!(event.platform_fee, platform_fee);
+    assert_eq!(event.monk_reward, monk_reward);
+    assert_eq!(event.purchased_at, market.bank.now());
+}
+
+#[test]
+fn fee_and_reward_round_down() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(399));
+    let buyer = market.user();
+    let platform_before = market.bank.lamports(&market.platform_wallet());
+
+    let event = market.buy(buyer, coupon).unwrap().event::<CouponPurchased>();
+
+    // 399 * 2.5% = 9.975 and 399 * 10% = 39.9
+    assert_eq!(event.platform_fee, 9);
+    assert_eq!(event.monk_reward, 39);
+    assert_eq!(market.bank.lamports(&market.platform_wallet()), platform_before + 9);
+    assert_eq!(market.monk_balance(&buyer), 39);
+}
+
+#[test]
+fn token_2022_coupons_trade_like_spl_token_ones() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.coupon(merchant, spl_token_2022::ID);
+    market.list(merchant, coupon, market.list_args(SOL)).unwrap();
+    let buyer = market.user();
+
+    market.buy(buyer, coupon).unwrap();
+
+    assert_eq!(market.coupon_balance(&buyer, &coupon), 1);
+    assert_eq!(market.monk_balance(&buyer), SOL * PURCHASE_REWARD_BPS / 10_000);
+}
+
+#[test]
+fn resale_accumulates_stats_for_each_buyer() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(2 * SOL));
+    let first = market.user();
+    let second = market.user();
+    market.buy(first, coupon).unwrap();
+
+    let meta = market.run(market.client.relist_nft(first, coupon, SOL));
+    assert_eq!(meta.event::<CouponRelisted>().price, SOL);
+    let first_before = market.bank.lamports(&first);
+    let event = market.buy(second, coupon).unwrap().event::<CouponPurchased>();
+
+    assert_eq!(event.seller, first);
+    assert_eq!(event.price, SOL);
+    let vault_rent = market.bank.minimum_balance(165);
+    assert_eq!(market.bank.lamports(&first), first_before + SOL - event.platform_fee + vault_rent);
+    assert_eq!(market.coupon_balance(&second, &coupon), 1);
+
+    let listing = market.listing(&coupon);
+    assert_eq!(listing.total_sales, 2);
+    assert_eq!(listing.original_price, 2 * SOL);
+    assert_eq!(listing.current_price, SOL);
+
+    // A second purchase by the same wallet adds to its existing stats
+    market.run(market.client.relist_nft(second, coupon, SOL / 2));
+    market.buy(first, coupon).unwrap();
+    let stats: UserStats = market.bank.get(&pda::user_stats(&first).0);
+    assert_eq!(stats.total_purchases, 2);
+    assert_eq!(
+        stats.total_monk_earned,
+        (2 * SOL + SOL / 2) * PURCHASE_REWARD_BPS / 10_000
+    );
+}
+
+#[test]
+fn delisting_returns_the_coupon_and_closes_the_vault() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let listing_key = pda::listing(&coupon.mint).0;
+    let vault = market.token_account(&listing_key, &coupon);
+
+    let meta = market.run(market.client.delist_nft(merchant, coupon));
+
+    assert_eq!(meta.event::<CouponDelisted>().seller, merchant);
+    assert!(!market.listing(&coupon).is_active);
+    assert!(!market.bank.exists(&vault));
+    assert_eq!(market.coupon_balance(&merchant, &coupon), 1);
+
+    // The merchant can put it back up on new terms, keeping its listing count
+    market.list(merchant, coupon, market.list_args(3 * SOL)).unwrap();
+    let listing = market.listing(&coupon);
+    assert!(listing.is_active);
+    assert_eq!(listing.original_price, 3 * SOL);
+    let merchant_account: Merchant = market.bank.get(&pda::merchant(&merchant).0);
+    assert_eq!(merchant_account.total_listings, 1);
+}
+
+#[test]
+fn reviews_update_the_average_rating() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let coupon: Coupon = market.listed_coupon(merchant, market.list_args(SOL));
+    let alice = market.user();
+    let bob = market.user();
+
+    let meta = market.run(market.client.add_review(alice, coupon.mint, 5, "Great noodles".to_string()));
+    assert_eq!(meta.event::<ReviewAdded>().average_rating, 100);
+    let meta = market.run(market.client.add_review(bob, coupon.mint, 2, "Cold broth".to_string()));
+
+    // Ratings are stored as 20-100, so 5 and 2 stars average to 70 (3.5 stars)
+    let event = meta.event::<ReviewAdded>();
+    assert_eq!(event.average_rating, 70);
+    assert_eq!(event.total_reviews, 2);
+    let listing = market.listing(&coupon);
+    assert_eq!(listing.average_rating, 70);
+    assert_eq!(listing.total_reviews, 2);
+
+    let review: Review = market.bank.get(&pda::review(&pda::listing(&coupon.mint).0, &bob).0);
+    assert_eq!(review.reviewer, bob);
+    assert_eq!(review.rating, 2);
+    assert_eq!(review.comment, "Cold broth");
+}
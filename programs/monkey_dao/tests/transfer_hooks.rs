@@ -0,0 +1,124 @@
+//! Coupons whose Token-2022 mint has a transfer hook: every instruction that
+//! moves one forwards the hook's accounts, so the hook runs on each transfer.
+
+mod bank;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use bank::assert_error;
+use bank::hook;
+use bank::marketplace::{Marketplace, SOL};
+use monkey_dao::error::ErrorCode;
+use monkey_dao::state::{Custody, Merchant};
+use monkey_dao_client::{pda, with_transfer_hook, CartItem, Coupon};
+
+/// A hooked coupon held by `merchant`, with the accounts its transfers need.
+fn hooked_coupon(market: &mut Marketplace, merchant: Pubkey) -> (Coupon, Vec<AccountMeta>) {
+    let coupon = Coupon::token_2022(hook::create_mint(&mut market.bank, merchant));
+    market.mint_to(coupon, merchant, merchant);
+    let hook_accounts = hook::accounts(&market.bank, &coupon.mint);
+    (coupon, hook_accounts)
+}
+
+fn list(market: &mut Marketplace, merchant: Pubkey, coupon: Coupon, hook_accounts: &[AccountMeta]) {
+    let args = market.list_args(SOL);
+    let verifiers = [market.verifier];
+    market.run(with_transfer_hook(market.client.list_nft(merchant, coupon, args, &verifiers), hook_accounts));
+}
+
+fn buy(market: &mut Marketplace, buyer: Pubkey, coupon: Coupon, hook_accounts: &[AccountMeta]) {
+    let listing = market.listing(&coupon);
+    let merchant: Merchant = market.bank.get(&listing.merchant);
+    let ix = market.client.buy_nft(buyer, listing.seller, merchant.authority, coupon, None);
+    market.run(with_transfer_hook(ix, hook_accounts));
+}
+
+#[test]
+fn hook_runs_on_every_transfer_of_a_coupon() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let (coupon, hook_accounts) = hooked_coupon(&mut market, merchant);
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 0);
+
+    list(&mut market, merchant, coupon, &hook_accounts);
+    assert_eq!(market.coupon_balance(&pda::listing(&coupon.mint).0, &coupon), 1);
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 1);
+
+    let owner = market.user();
+    buy(&mut market, owner, coupon, &hook_accounts);
+    assert_eq!(market.coupon_balance(&owner, &coupon), 1);
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 2);
+
+    market.run(with_transfer_hook(market.client.stake_nft(owner, coupon), &hook_accounts));
+    assert_eq!(market.listing(&coupon).custody, Custody::StakeVault);
+    market.run(with_transfer_hook(market.client.unstake_nft(owner, coupon), &hook_accounts));
+    assert_eq!(market.coupon_balance(&owner, &coupon), 1);
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 4);
+
+    let friend = market.user();
+    market.run(with_transfer_hook(
+        market.client.gift_coupon(owner, coupon, friend, "Enjoy"),
+        &hook_accounts,
+    ));
+    assert_eq!(market.coupon_balance(&friend, &coupon), 1);
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 5);
+}
+
+#[test]
+fn delisting_a_hooked_coupon_runs_the_hook() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let (coupon, hook_accounts) = hooked_coupon(&mut market, merchant);
+    list(&mut market, merchant, coupon, &hook_accounts);
+
+    market.run(with_transfer_hook(market.client.delist_nft(merchant, coupon), &hook_accounts));
+
+    assert_eq!(market.coupon_balance(&merchant, &coupon), 1);
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 2);
+}
+
+#[test]
+fn cart_forwards_the_hook_accounts_of_every_hooked_coupon() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let (first, first_hook) = hooked_coupon(&mut market, merchant);
+    let (second, second_hook) = hooked_coupon(&mut market, merchant);
+    list(&mut market, merchant, first, &first_hook);
+    list(&mut market, merchant, second, &second_hook);
+    let items = [first, second].map(|coupon| CartItem { coupon, seller: merchant, merchant });
+    let buyer = market.user();
+
+    let hook_accounts = [first_hook, second_hook].concat();
+    market.run(with_transfer_hook(market.client.checkout_cart(buyer, &items), &hook_accounts));
+
+    for coupon in [first, second] {
+        assert_eq!(market.coupon_balance(&buyer, &coupon), 1);
+        assert_eq!(hook::transfers(&market.bank, &coupon.mint), 2);
+    }
+}
+
+#[test]
+fn hooked_coupon_without_its_hook_accounts_is_rejected() {
+    let mut market = Marketplace::new();
+    let merchant = market.verified_merchant();
+    let (coupon, hook_accounts) = hooked_coupon(&mut market, merchant);
+    let args = market.list_args(SOL);
+    let verifiers = [market.verifier];
+    assert_error(
+        market.send(market.client.list_nft(merchant, coupon, args, &verifiers)),
+        ErrorCode::TransferHookAccountsMissing,
+    );
+
+    list(&mut market, merchant, coupon, &hook_accounts);
+    let buyer = market.user();
+    assert_error(market.buy(buyer, coupon), ErrorCode::TransferHookAccountsMissing);
+
+    // A cart only finds the accounts of the hooks it was given
+    let (other, other_hook) = hooked_coupon(&mut market, merchant);
+    list(&mut market, merchant, other, &other_hook);
+    let items = [coupon, other].map(|coupon| CartItem { coupon, seller: merchant, merchant });
+    assert_error(
+        market.send(with_transfer_hook(market.client.checkout_cart(buyer, &items), &other_hook)),
+        ErrorCode::TransferHookAccountsMissing,
+    );
+    assert_eq!(hook::transfers(&market.bank, &coupon.mint), 1);
+}